@@ -1,6 +1,13 @@
 use std::path::PathBuf;
 
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use clap::Subcommand;
+use pixles_core::db::GeoBounds;
+use pixles_core::domain::{ExportCollision, ExportPreset, MergePolicy};
+use pixles_core::import::{DEFAULT_NEAR_DUPLICATE_DISTANCE, DEFAULT_TRACK_MAX_GAP_SECS};
+use pixles_core::library::{
+    DEFAULT_BACKUP_RETENTION_SECS, DEFAULT_EXPORT_TEMPLATE, FilenameTemplate,
+};
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
@@ -12,16 +19,120 @@ pub enum Commands {
     /// Import files into a local Pixles library
     Import {
         /// Source file or directory to import
-        path: PathBuf,
+        #[arg(required_unless_present = "resume")]
+        path: Option<PathBuf>,
         /// Path to the Pixles library
         #[arg(long, value_name = "PATH")]
         library: PathBuf,
         /// Move files instead of copying them
-        #[arg(long)]
+        #[arg(long, group = "mode")]
         r#move: bool,
+        /// Clone files copy-on-write (btrfs, XFS, APFS), copying where the filesystem cannot
+        #[arg(long, group = "mode")]
+        reflink: bool,
+        /// Hard-link files into the library; they must be on the library's filesystem
+        #[arg(long, group = "mode")]
+        hardlink: bool,
+        /// Leave files where they are and track them by path and hash
+        #[arg(long, group = "mode")]
+        reference: bool,
         /// Re-import files even if they already exist (duplicate override)
         #[arg(long)]
         force: bool,
+        /// Skip photos that look like one already in the library: resized,
+        /// recompressed or lightly edited copies within BITS of 64 (default 6)
+        #[arg(
+            long,
+            value_name = "BITS",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "6",
+            value_parser = clap::value_parser!(u32).range(0..=32),
+        )]
+        skip_similar: Option<u32>,
+        /// Geotag photos without a GPS position from this GPX or KML track log
+        /// (repeatable)
+        #[arg(long = "track", value_name = "FILE")]
+        track: Vec<PathBuf>,
+        /// Longest time between a photo and the track points it is placed
+        /// from, e.g. 10m (default 30m)
+        #[arg(long, value_name = "DURATION", value_parser = parse_shift, requires = "track",
+            default_value_t = DEFAULT_TRACK_MAX_GAP_SECS, hide_default_value = true)]
+        track_max_gap: i64,
+        /// Added to capture times before matching them to the track, e.g. +2m
+        /// for a camera two minutes slow; photos without a recorded offset
+        /// also need their UTC offset negated (-9h for Tokyo time)
+        #[arg(long, value_name = "OFFSET", value_parser = parse_shift, allow_hyphen_values = true,
+            requires = "track", default_value_t = 0, hide_default_value = true)]
+        track_offset: i64,
+        /// Number of files to copy and verify concurrently (defaults to the CPU count, up to 8)
+        #[arg(long, short = 'j', value_name = "N")]
+        jobs: Option<usize>,
+        /// Add the imported assets to this album (name or ID)
+        #[arg(long, value_name = "ALBUM")]
+        album: Option<String>,
+        /// Resume an interrupted import run by its run ID
+        #[arg(long, value_name = "RUN_ID", conflicts_with_all = ["path", "mode", "force", "skip_similar", "track", "album"])]
+        resume: Option<String>,
+    },
+    /// Export assets to a folder, named by a filename template
+    Export {
+        /// Directory to export into
+        dest: PathBuf,
+        /// Path to the Pixles library
+        #[arg(long, value_name = "PATH")]
+        library: PathBuf,
+        /// Export this album (name or ID) instead of searching
+        #[arg(long, conflicts_with_all = ["text", "after", "before", "make", "model", "min_rating", "tags", "hidden"])]
+        album: Option<String>,
+        /// Only assets matching these words in filenames, tags and captions
+        #[arg(long)]
+        text: Option<String>,
+        /// Only assets captured on or after this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        after: Option<i64>,
+        /// Only assets captured before this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        before: Option<i64>,
+        /// Camera manufacturer, e.g. FUJIFILM
+        #[arg(long)]
+        make: Option<String>,
+        /// Camera model, e.g. X-T5
+        #[arg(long)]
+        model: Option<String>,
+        /// Minimum star rating
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=5))]
+        min_rating: Option<u8>,
+        /// Required tag (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Include stack members hidden behind their primary
+        #[arg(long)]
+        hidden: bool,
+        /// Filename template, e.g. {capture:%Y-%m-%d}_{camera_model}_{original_filename};
+        /// fields are original_filename, stem, ext, capture[:FORMAT], camera_make,
+        /// camera_model, rating and uuid, and / makes folders
+        #[arg(long, default_value = DEFAULT_EXPORT_TEMPLATE, value_parser = parse_template)]
+        template: FilenameTemplate,
+        /// Also write a copy rendered with this preset: web_jpeg, full_jpeg,
+        /// social_jpeg, webp, lossless_webp or png
+        #[arg(long, value_name = "PRESET", value_parser = parse_preset)]
+        preset: Option<ExportPreset>,
+        /// Write only the rendered copies, not the originals
+        #[arg(long, requires = "preset")]
+        no_originals: bool,
+        /// Write ratings, tags and captions to an .xmp sidecar beside each file
+        #[arg(long, group = "metadata")]
+        xmp: bool,
+        /// Embed ratings, tags and captions in JPEGs; other files get an .xmp sidecar
+        #[arg(long, group = "metadata")]
+        embed: bool,
+        /// Name sidecars IMG_1.CR2.xmp, as darktable does, instead of IMG_1.xmp
+        #[arg(long)]
+        darktable: bool,
+        /// When a different file already has an exported name: rename, skip or overwrite
+        #[arg(long, value_name = "POLICY", default_value = "rename", value_parser = parse_collision)]
+        on_conflict: ExportCollision,
     },
     /// Manage the local library
     Library {
@@ -90,6 +201,405 @@ pub enum LibraryCommands {
         /// Path to the library
         path: PathBuf,
     },
+    /// Re-hash originals and cross-check sidecars and the index
+    Verify {
+        /// Path to the library
+        path: PathBuf,
+        /// Re-sync the index from the sidecars and follow moved referenced originals
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Generate missing thumbnails and previews
+    Thumbnails {
+        /// Path to the library
+        path: PathBuf,
+        /// Regenerate thumbnails that already exist
+        #[arg(long)]
+        force: bool,
+    },
+    /// Correct the capture time of assets shot with a wrong camera clock
+    ShiftTime {
+        /// Path to the library
+        path: PathBuf,
+        /// Asset UUIDs to shift, instead of the filters below
+        #[arg(conflicts_with_all = ["make", "model", "after", "before"])]
+        uuids: Vec<String>,
+        /// Amount to shift by, e.g. +1h, -1d, +1h30m, -90s
+        #[arg(long, value_name = "OFFSET", value_parser = parse_shift, allow_hyphen_values = true,
+            required_unless_present = "set", conflicts_with = "set")]
+        by: Option<i64>,
+        /// Set one asset to its real local time and shift the others by as much
+        #[arg(long, value_name = "UUID=YYYY-MM-DDTHH:MM:SS", value_parser = parse_anchor)]
+        set: Option<(String, NaiveDateTime)>,
+        /// Only assets from this camera manufacturer, e.g. FUJIFILM
+        #[arg(long)]
+        make: Option<String>,
+        /// Only assets from this camera model, e.g. X-T5
+        #[arg(long)]
+        model: Option<String>,
+        /// Only assets captured on or after this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        after: Option<i64>,
+        /// Only assets captured before this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        before: Option<i64>,
+    },
+    /// Re-resolve time zones from GPS positions after a tz database update
+    Retz {
+        /// Path to the library
+        path: PathBuf,
+        /// Also re-resolve assets already resolved with the current tz database
+        #[arg(long)]
+        all: bool,
+    },
+    /// List groups of photos that look alike (resized, recompressed or edited copies)
+    Duplicates {
+        /// Path to the library
+        path: PathBuf,
+        /// Largest number of differing perceptual-hash bits, out of 64, to call alike
+        #[arg(long, value_name = "BITS", default_value_t = DEFAULT_NEAR_DUPLICATE_DISTANCE,
+            value_parser = clap::value_parser!(u32).range(0..=32))]
+        distance: u32,
+    },
+    /// Search the library index
+    Search {
+        /// Path to the library
+        path: PathBuf,
+        /// Words to match in filenames, tags and captions
+        text: Option<String>,
+        /// Only assets captured on or after this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        after: Option<i64>,
+        /// Only assets captured before this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        before: Option<i64>,
+        /// Camera manufacturer, e.g. FUJIFILM
+        #[arg(long)]
+        make: Option<String>,
+        /// Camera model, e.g. X-T5
+        #[arg(long)]
+        model: Option<String>,
+        /// GPS bounding box as south,west,north,east in degrees
+        #[arg(long, value_name = "S,W,N,E", value_parser = parse_bounds, allow_hyphen_values = true)]
+        bbox: Option<GeoBounds>,
+        /// Minimum star rating
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=5))]
+        min_rating: Option<u8>,
+        /// Asset type (photo, video)
+        #[arg(long = "type", value_name = "TYPE")]
+        asset_type: Option<String>,
+        /// Stack type (raw_jpeg, burst, live_photo, ...)
+        #[arg(long)]
+        stack: Option<String>,
+        /// Required tag (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Include stack members hidden behind their primary
+        #[arg(long)]
+        hidden: bool,
+        /// Maximum number of results
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+    },
+    /// Manage asset tags
+    Tag {
+        #[command(subcommand)]
+        command: TagCommands,
+    },
+    /// Manage albums
+    Album {
+        #[command(subcommand)]
+        command: AlbumCommands,
+    },
+    /// Export originals with .xmp sidecars for Lightroom, darktable and other editors
+    ExportXmp {
+        /// Path to the library
+        path: PathBuf,
+        /// Directory to export into
+        #[arg(required_unless_present = "stop_auto")]
+        dest: Option<PathBuf>,
+        /// Name sidecars IMG_1.CR2.xmp, as darktable does, instead of IMG_1.xmp
+        #[arg(long)]
+        darktable: bool,
+        /// Keep the exported sidecars up to date as assets are edited
+        #[arg(long)]
+        auto: bool,
+        /// Stop updating a previously exported directory
+        #[arg(long, conflicts_with_all = ["dest", "darktable", "auto"])]
+        stop_auto: bool,
+    },
+    /// Bring a backup mirror on another disk up to date, copying only what changed
+    Backup {
+        /// Path to the library
+        path: PathBuf,
+        /// Directory of the mirror; created on the first backup
+        dest: PathBuf,
+        /// How long the mirror keeps what the library deleted, e.g. 7d (default 30d)
+        #[arg(long, value_name = "DURATION", value_parser = parse_shift,
+            default_value_t = DEFAULT_BACKUP_RETENTION_SECS, hide_default_value = true)]
+        retention: i64,
+        /// Re-hash every file already in the mirror and replace damaged copies
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Restore a library from a backup mirror and rebuild its index
+    Restore {
+        /// Directory of the mirror
+        backup: PathBuf,
+        /// Directory for the restored library; must not exist or be empty
+        path: PathBuf,
+    },
+    /// Copy in the assets, curation, stacks and albums of another library
+    Merge {
+        /// Path to the library to merge into
+        path: PathBuf,
+        /// Path to the library to merge from; it is left unchanged
+        other: PathBuf,
+        /// Whose rating, tags and caption win for assets in both: ours, theirs or combine
+        #[arg(long, value_name = "POLICY", default_value = "ours", value_parser = parse_merge_policy)]
+        on_conflict: MergePolicy,
+    },
+}
+
+/// Tags are `|`-separated paths such as `Places|Japan|Kyoto`; removing,
+/// renaming or merging a tag also applies to the tags nested below it.
+#[derive(Subcommand, Debug)]
+pub enum TagCommands {
+    /// List tags in use, or the tags of one asset
+    List {
+        /// Path to the library
+        path: PathBuf,
+        /// Asset UUID
+        uuid: Option<String>,
+    },
+    /// Add tags to assets
+    Add {
+        /// Path to the library
+        path: PathBuf,
+        /// Asset UUIDs
+        #[arg(required = true)]
+        uuids: Vec<String>,
+        /// Tag to add (repeatable)
+        #[arg(long = "tag", value_name = "TAG", required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove tags from assets
+    Remove {
+        /// Path to the library
+        path: PathBuf,
+        /// Asset UUIDs
+        #[arg(required = true)]
+        uuids: Vec<String>,
+        /// Tag to remove (repeatable)
+        #[arg(long = "tag", value_name = "TAG", required = true)]
+        tags: Vec<String>,
+    },
+    /// Rename a tag across the library
+    Rename {
+        /// Path to the library
+        path: PathBuf,
+        /// Current tag
+        from: String,
+        /// New tag
+        to: String,
+    },
+    /// Merge tags into one
+    Merge {
+        /// Path to the library
+        path: PathBuf,
+        /// Tags to merge
+        #[arg(required = true)]
+        sources: Vec<String>,
+        /// Tag to merge them into
+        #[arg(long)]
+        into: String,
+    },
+}
+
+/// Albums are ordered lists of assets; an asset can be in several albums.
+/// `ALBUM` is an album's ID or its name (ignoring case).
+#[derive(Subcommand, Debug)]
+pub enum AlbumCommands {
+    /// Create an empty album
+    Create {
+        /// Path to the library
+        path: PathBuf,
+        /// Album name
+        name: String,
+        /// Album description
+        #[arg(long)]
+        description: Option<String>,
+    },
+    /// List albums
+    List {
+        /// Path to the library
+        path: PathBuf,
+    },
+    /// Show an album and its assets in order
+    Show {
+        /// Path to the library
+        path: PathBuf,
+        album: String,
+    },
+    /// Append assets to an album
+    Add {
+        /// Path to the library
+        path: PathBuf,
+        album: String,
+        /// Asset UUIDs
+        #[arg(required = true)]
+        uuids: Vec<String>,
+    },
+    /// Take assets out of an album (the assets are kept)
+    Remove {
+        /// Path to the library
+        path: PathBuf,
+        album: String,
+        /// Asset UUIDs
+        #[arg(required = true)]
+        uuids: Vec<String>,
+    },
+    /// Move an asset to another position in an album
+    Move {
+        /// Path to the library
+        path: PathBuf,
+        album: String,
+        /// Asset UUID
+        uuid: String,
+        /// New position, starting at 1
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        to: u64,
+    },
+    /// Set the album cover, or clear it when no asset is given
+    Cover {
+        /// Path to the library
+        path: PathBuf,
+        album: String,
+        /// Asset UUID
+        uuid: Option<String>,
+    },
+    /// Rename an album
+    Rename {
+        /// Path to the library
+        path: PathBuf,
+        album: String,
+        /// New name
+        name: String,
+    },
+    /// Set the album description, or clear it when no text is given
+    Describe {
+        /// Path to the library
+        path: PathBuf,
+        album: String,
+        /// New description
+        description: Option<String>,
+    },
+    /// Delete an album (the assets are kept)
+    Delete {
+        /// Path to the library
+        path: PathBuf,
+        album: String,
+    },
+}
+
+/// Midnight UTC of a `YYYY-MM-DD` date, as Unix seconds.
+fn parse_date(s: &str) -> Result<i64, String> {
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|e| e.to_string())?;
+    Ok(date.and_time(NaiveTime::MIN).and_utc().timestamp())
+}
+
+/// `+1h`, `-1d`, `+1h30m`, `-90s` → seconds.
+fn parse_shift(s: &str) -> Result<i64, String> {
+    let (sign, rest) = match s.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let mut total: i64 = 0;
+    let mut digits = String::new();
+    for c in rest.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            'd' => 86_400,
+            'h' => 3_600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(format!("unknown unit {c:?}; use d, h, m or s")),
+        };
+        let n: i64 = digits
+            .parse()
+            .map_err(|_| format!("expected a number before {c:?}"))?;
+        total = n
+            .checked_mul(unit)
+            .and_then(|n| total.checked_add(n))
+            .ok_or("offset too large")?;
+        digits.clear();
+    }
+    if !digits.is_empty() || rest.is_empty() {
+        return Err("expected an amount with a unit, e.g. +1h".to_string());
+    }
+    Ok(sign * total)
+}
+
+/// `UUID=YYYY-MM-DDTHH:MM:SS`
+fn parse_anchor(s: &str) -> Result<(String, NaiveDateTime), String> {
+    let (uuid, time) = s
+        .split_once('=')
+        .ok_or("expected UUID=YYYY-MM-DDTHH:MM:SS")?;
+    let time = NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S"))
+        .map_err(|e| format!("{time}: {e}"))?;
+    Ok((uuid.to_string(), time))
+}
+
+fn parse_template(s: &str) -> Result<FilenameTemplate, String> {
+    FilenameTemplate::parse(s).map_err(|e| e.to_string())
+}
+
+fn parse_preset(s: &str) -> Result<ExportPreset, String> {
+    ExportPreset::from_name(s).ok_or_else(|| {
+        let names: Vec<_> = ExportPreset::ALL.iter().map(|p| p.as_str()).collect();
+        format!("unknown preset; use one of {}", names.join(", "))
+    })
+}
+
+fn parse_collision(s: &str) -> Result<ExportCollision, String> {
+    match s {
+        "rename" => Ok(ExportCollision::Rename),
+        "skip" => Ok(ExportCollision::Skip),
+        "overwrite" => Ok(ExportCollision::Overwrite),
+        _ => Err("expected rename, skip or overwrite".to_string()),
+    }
+}
+
+fn parse_merge_policy(s: &str) -> Result<MergePolicy, String> {
+    match s {
+        "ours" => Ok(MergePolicy::Ours),
+        "theirs" => Ok(MergePolicy::Theirs),
+        "combine" => Ok(MergePolicy::Combine),
+        _ => Err("expected ours, theirs or combine".to_string()),
+    }
+}
+
+fn parse_bounds(s: &str) -> Result<GeoBounds, String> {
+    let parts = s
+        .split(',')
+        .map(|p| p.trim().parse::<f64>().map_err(|e| format!("{p}: {e}")))
+        .collect::<Result<Vec<_>, _>>()?;
+    let [south, west, north, east] = parts[..] else {
+        return Err("expected four comma-separated numbers".to_string());
+    };
+    if south > north {
+        return Err("south must not be greater than north".to_string());
+    }
+    Ok(GeoBounds {
+        south,
+        west,
+        north,
+        east,
+    })
 }
 
 #[derive(Subcommand, Debug)]
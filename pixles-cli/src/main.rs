@@ -1,17 +1,25 @@
 use std::path::Path;
 
 use capitalize::Capitalize;
+use chrono::DateTime;
 use clap::Parser;
-use cli::{AuthCommands, Cli, Commands, LibraryCommands};
+use cli::{AlbumCommands, AuthCommands, Cli, Commands, LibraryCommands, TagCommands};
 use colored::*;
 use dialoguer::Confirm;
 use eyre::{Result, eyre};
-use pixles_core::domain::ImportMode;
+use pixles_core::db::{AssetRow, SearchFilter};
+use pixles_core::domain::{ExportMetadata, ImportMode, XmpExportTarget, XmpNaming};
+use pixles_core::exif::TZ_DB_VERSION;
 use pixles_core::import::scanner::scan as scan_files;
 use pixles_core::import::{
-    CancellationToken, ImportConfig, ImportOutcome, ImportProgressEvent, execute, plan,
+    CancellationToken, ImportConfig, ImportJournal, ImportOutcome, ImportProgressEvent,
+    TrackLogConfig, default_max_workers, execute_journaled, plan, resume_import,
+};
+use pixles_core::library::{
+    BackupOptions, ExportOptions, ExportSelection, Library, LibraryError, TimeShift,
+    backfill_thumbnails, init_library, open_library, open_library_read_only, rebuild_index,
+    restore_library, verify_library,
 };
-use pixles_core::library::{Library, LibraryError, init_library, open_library, rebuild_index};
 use pixles_core::metadata::FileMetadata;
 use tracing::trace;
 use tracing_subscriber::prelude::*;
@@ -119,6 +127,358 @@ async fn main() -> Result<()> {
                 lib.close()
                     .map_err(|e| eyre!("Failed to close library: {e}"))?;
             }
+            LibraryCommands::Verify { path, repair } => {
+                println!("{}", format!("Verifying {}...", path.display()).yellow());
+                let lib = open_library_or_err(&path)?;
+                let report =
+                    verify_library(&lib, repair).map_err(|e| eyre!("Verify failed: {e}"))?;
+                for issue in &report.issues {
+                    println!("  {}", issue.to_string().red());
+                }
+                println!(
+                    "{}",
+                    format!(
+                        "{} originals checked, {} issues found",
+                        report.checked,
+                        report.issues.len()
+                    )
+                    .cyan()
+                );
+                if report.repaired {
+                    println!("{}", "Index re-synced from sidecars.".green());
+                }
+                lib.close()
+                    .map_err(|e| eyre!("Failed to close library: {e}"))?;
+                let unrepaired = report.unrepaired().count();
+                if unrepaired > 0 {
+                    return Err(eyre!("{unrepaired} issues need attention"));
+                }
+                println!("{}", "Library is healthy.".green());
+            }
+            LibraryCommands::Thumbnails { path, force } => {
+                println!(
+                    "{}",
+                    format!("Generating thumbnails for {}...", path.display()).yellow()
+                );
+                let lib = open_library_or_err(&path)?;
+                let report = backfill_thumbnails(&lib, force)
+                    .map_err(|e| eyre!("Thumbnail generation failed: {e}"))?;
+                println!(
+                    "{}",
+                    format!(
+                        "{} generated, {} up to date, {} unsupported",
+                        report.generated, report.skipped, report.unsupported
+                    )
+                    .green()
+                );
+                if report.failed > 0 {
+                    println!(
+                        "{}",
+                        format!("{} failed (see log for details)", report.failed).red()
+                    );
+                }
+                lib.close()
+                    .map_err(|e| eyre!("Failed to close library: {e}"))?;
+            }
+            LibraryCommands::ShiftTime {
+                path,
+                uuids,
+                by,
+                set,
+                make,
+                model,
+                after,
+                before,
+            } => {
+                let lib = open_library_or_err(&path)?;
+                let uuids = if uuids.is_empty()
+                    && (make.is_some() || model.is_some() || after.is_some() || before.is_some())
+                {
+                    let filter = SearchFilter {
+                        captured_after: after,
+                        captured_before: before,
+                        camera_make: make,
+                        camera_model: model,
+                        include_hidden: true,
+                        ..Default::default()
+                    };
+                    lib.db
+                        .search(&filter)
+                        .map_err(|e| eyre!("Search failed: {e}"))?
+                        .into_iter()
+                        .map(|row| row.uuid)
+                        .collect()
+                } else {
+                    uuids
+                };
+                if uuids.is_empty() && set.is_none() {
+                    return Err(eyre!(
+                        "No assets selected; pass UUIDs or --make, --model, --after or --before"
+                    ));
+                }
+                let shift = match (by, set) {
+                    (Some(seconds), _) => TimeShift::By(seconds),
+                    (None, Some((uuid, to))) => TimeShift::Anchor { uuid, to },
+                    (None, None) => return Err(eyre!("Pass --by or --set")),
+                };
+                let shifted = lib
+                    .shift_capture_times(&uuids, &shift)
+                    .map_err(|e| eyre!("Time shift failed: {e}"))?;
+                println!("{}", format!("{shifted} assets shifted").green());
+                lib.close()
+                    .map_err(|e| eyre!("Failed to close library: {e}"))?;
+            }
+            LibraryCommands::Retz { path, all } => {
+                println!(
+                    "{}",
+                    format!("Re-resolving time zones with tzdb {TZ_DB_VERSION}...").yellow()
+                );
+                let lib = open_library_or_err(&path)?;
+                let report = lib
+                    .retz(all)
+                    .map_err(|e| eyre!("Time zone resolution failed: {e}"))?;
+                println!(
+                    "{}",
+                    format!(
+                        "{} re-dated, {} unchanged",
+                        report.changed, report.unchanged
+                    )
+                    .green()
+                );
+                if report.failed > 0 {
+                    println!(
+                        "{}",
+                        format!("{} failed (see log for details)", report.failed).red()
+                    );
+                }
+                lib.close()
+                    .map_err(|e| eyre!("Failed to close library: {e}"))?;
+            }
+            LibraryCommands::Duplicates { path, distance } => {
+                let lib = open_library_or_err(&path)?;
+                let groups = lib
+                    .near_duplicates(distance)
+                    .map_err(|e| eyre!("Duplicate search failed: {e}"))?;
+                for (i, group) in groups.iter().enumerate() {
+                    if i > 0 {
+                        println!();
+                    }
+                    for row in group {
+                        print_asset(row);
+                    }
+                }
+                println!(
+                    "{}",
+                    format!(
+                        "{} groups of similar photos ({} assets)",
+                        groups.len(),
+                        groups.iter().map(Vec::len).sum::<usize>()
+                    )
+                    .green()
+                );
+                lib.close()
+                    .map_err(|e| eyre!("Failed to close library: {e}"))?;
+            }
+            LibraryCommands::Search {
+                path,
+                text,
+                after,
+                before,
+                make,
+                model,
+                bbox,
+                min_rating,
+                asset_type,
+                stack,
+                tags,
+                hidden,
+                limit,
+            } => {
+                let lib = open_library_or_err(&path)?;
+                let filter = SearchFilter {
+                    text,
+                    captured_after: after,
+                    captured_before: before,
+                    camera_make: make,
+                    camera_model: model,
+                    bounds: bbox,
+                    min_rating,
+                    asset_type,
+                    stack_type: stack,
+                    tags,
+                    include_hidden: hidden,
+                    limit: Some(limit),
+                    offset: 0,
+                };
+                let results = lib
+                    .db
+                    .search(&filter)
+                    .map_err(|e| eyre!("Search failed: {e}"))?;
+                for row in &results {
+                    print_asset(row);
+                }
+                println!("{}", format!("{} assets found", results.len()).green());
+                lib.close()
+                    .map_err(|e| eyre!("Failed to close library: {e}"))?;
+            }
+            LibraryCommands::Tag { command } => run_tag_command(command)?,
+            LibraryCommands::Album { command } => run_album_command(command)?,
+            LibraryCommands::ExportXmp {
+                path,
+                dest,
+                darktable,
+                auto,
+                stop_auto,
+            } => {
+                let mut lib = open_library_or_err(&path)?;
+                if stop_auto {
+                    lib.set_auto_xmp_export(None)
+                        .map_err(|e| eyre!("Failed to update config: {e}"))?;
+                    println!("{}", "Automatic XMP export turned off.".green());
+                }
+                if let Some(dest) = dest {
+                    let naming = if darktable {
+                        XmpNaming::Filename
+                    } else {
+                        XmpNaming::Stem
+                    };
+                    println!("{}", format!("Exporting to {}...", dest.display()).yellow());
+                    let report = lib
+                        .export_xmp(&dest, naming)
+                        .map_err(|e| eyre!("Export failed: {e}"))?;
+                    for (uuid, reason) in &report.skipped {
+                        println!("  {} {}", uuid.blue(), reason.red());
+                    }
+                    println!(
+                        "{}",
+                        format!(
+                            "{} sidecars written, {} originals copied, {} skipped",
+                            report.written,
+                            report.copied,
+                            report.skipped.len()
+                        )
+                        .green()
+                    );
+                    if auto {
+                        let dir = dest.canonicalize().unwrap_or(dest);
+                        lib.set_auto_xmp_export(Some(XmpExportTarget { dir, naming }))
+                            .map_err(|e| eyre!("Failed to update config: {e}"))?;
+                        println!("{}", "Sidecars will be kept up to date.".green());
+                    }
+                }
+                lib.close()
+                    .map_err(|e| eyre!("Failed to close library: {e}"))?;
+            }
+            LibraryCommands::Backup {
+                path,
+                dest,
+                retention,
+                verify,
+            } => {
+                let lib = open_library_or_err(&path)?;
+                println!(
+                    "{}",
+                    format!("Backing up to {}...", dest.display()).yellow()
+                );
+                let options = BackupOptions {
+                    retention_secs: retention,
+                    verify_existing: verify,
+                };
+                let report = lib
+                    .backup(&dest, &options)
+                    .map_err(|e| eyre!("Backup failed: {e}"))?;
+                for (file, reason) in &report.failed {
+                    println!("  {} {}", file.blue(), reason.red());
+                }
+                println!(
+                    "{}",
+                    format!(
+                        "{} copied, {} moved, {} unchanged, {} removed, {} kept for the retention window",
+                        report.copied,
+                        report.moved,
+                        report.unchanged,
+                        report.removed,
+                        report.retained
+                    )
+                    .green()
+                );
+                lib.close()
+                    .map_err(|e| eyre!("Failed to close library: {e}"))?;
+                if !report.failed.is_empty() {
+                    return Err(eyre!(
+                        "{} files could not be backed up",
+                        report.failed.len()
+                    ));
+                }
+            }
+            LibraryCommands::Restore { backup, path } => {
+                println!(
+                    "{}",
+                    format!("Restoring {} into {}...", backup.display(), path.display()).yellow()
+                );
+                let (lib, report) =
+                    restore_library(&backup, &path).map_err(|e| eyre!("Restore failed: {e}"))?;
+                for (file, reason) in &report.damaged {
+                    println!("  {} {}", file.blue(), reason.red());
+                }
+                println!(
+                    "{}",
+                    format!("{} files restored, index rebuilt", report.restored).green()
+                );
+                println!(
+                    "Run `pixles library thumbnails {}` to generate thumbnails.",
+                    path.display()
+                );
+                lib.close()
+                    .map_err(|e| eyre!("Failed to close library: {e}"))?;
+                if !report.damaged.is_empty() {
+                    return Err(eyre!(
+                        "{} damaged files were left out",
+                        report.damaged.len()
+                    ));
+                }
+            }
+            LibraryCommands::Merge {
+                path,
+                other,
+                on_conflict,
+            } => {
+                let lib = open_library_or_err(&path)?;
+                let other_lib =
+                    open_library_read_only(&other).map_err(|e| open_error(&other, e))?;
+                println!(
+                    "{}",
+                    format!("Merging {} into {}...", other.display(), path.display()).yellow()
+                );
+                let report = lib
+                    .merge_from(&other_lib, on_conflict)
+                    .map_err(|e| eyre!("Merge failed: {e}"))?;
+                for (item, reason) in &report.skipped {
+                    println!("  {} {}", item.blue(), reason.red());
+                }
+                println!(
+                    "{}",
+                    format!(
+                        "{} added, {} updated, {} unchanged, {} albums merged",
+                        report.added, report.updated, report.unchanged, report.albums
+                    )
+                    .green()
+                );
+                if report.added > 0 {
+                    println!(
+                        "Run `pixles library thumbnails {}` to generate thumbnails.",
+                        path.display()
+                    );
+                }
+                other_lib
+                    .close()
+                    .map_err(|e| eyre!("Failed to close library: {e}"))?;
+                lib.close()
+                    .map_err(|e| eyre!("Failed to close library: {e}"))?;
+                if !report.skipped.is_empty() {
+                    return Err(eyre!("{} items could not be merged", report.skipped.len()));
+                }
+            }
         },
 
         // ── Import ────────────────────────────────────────────────────────
@@ -126,108 +486,186 @@ async fn main() -> Result<()> {
             path,
             library,
             r#move,
+            reflink,
+            hardlink,
+            reference,
             force,
+            skip_similar,
+            track,
+            track_max_gap,
+            track_offset,
+            jobs,
+            album,
+            resume,
         } => {
-            println!(
-                "{}",
-                format!(
-                    "Importing {} into library {}...",
-                    path.to_string_lossy().blue(),
-                    library.to_string_lossy().blue()
-                )
-                .green()
-            );
-
             let lib = open_library_or_err(&library)?;
 
-            // Phase 1: Scan
-            println!("{}", "Scanning source files...".cyan());
-            let scan_result = scan_files(&[path]).map_err(|e| eyre!("Scan failed: {e}"))?;
+            let (plan_result, config, mut journal) = if let Some(run_id) = resume {
+                println!(
+                    "{}",
+                    format!("Resuming import run {}...", run_id.blue()).green()
+                );
+                let resumed = resume_import(&lib, &run_id).map_err(|e| eyre!("{e}"))?;
+                println!(
+                    "{}",
+                    format!(
+                        "Already done: {} imported, {} skipped",
+                        resumed
+                            .already_done
+                            .iter()
+                            .filter(|(_, o)| matches!(o, ImportOutcome::Imported))
+                            .count(),
+                        resumed
+                            .already_done
+                            .iter()
+                            .filter(|(_, o)| !matches!(o, ImportOutcome::Imported))
+                            .count(),
+                    )
+                    .cyan()
+                );
+                let config = ImportConfig {
+                    max_workers: jobs.unwrap_or(resumed.config.max_workers),
+                    ..resumed.config
+                };
+                (resumed.plan, config, resumed.journal)
+            } else {
+                let path = path.ok_or_else(|| eyre!("A source path is required"))?;
+                println!(
+                    "{}",
+                    format!(
+                        "Importing {} into library {}...",
+                        path.to_string_lossy().blue(),
+                        library.to_string_lossy().blue()
+                    )
+                    .green()
+                );
 
-            println!(
-                "{}",
-                format!(
-                    "Found {} candidates ({} files total)",
-                    scan_result.candidates.len(),
-                    scan_result.total_files()
-                )
-                .green()
-            );
+                // Phase 1: Scan
+                println!("{}", "Scanning source files...".cyan());
+                let sources = vec![path];
+                let scan_result = scan_files(&sources).map_err(|e| eyre!("Scan failed: {e}"))?;
 
-            // Phase 2: Plan
-            let config = ImportConfig {
-                import_mode: if r#move {
-                    ImportMode::Move
-                } else {
-                    ImportMode::Copy
-                },
-                force_reimport_duplicates: force,
-                target_album_id: None,
-            };
+                println!(
+                    "{}",
+                    format!(
+                        "Found {} candidates ({} files total)",
+                        scan_result.candidates.len(),
+                        scan_result.total_files()
+                    )
+                    .green()
+                );
 
-            let plan_result =
-                plan(&scan_result, &lib.db, &config).map_err(|e| eyre!("Planning failed: {e}"))?;
+                let target_album = album
+                    .map(|album| lib.resolve_album(&album))
+                    .transpose()
+                    .map_err(|e| eyre!("{e}"))?;
+                if let Some(album) = &target_album {
+                    println!(
+                        "{}",
+                        format!("Adding imported assets to album {}", album.name.blue()).cyan()
+                    );
+                }
+
+                let track_log = (!track.is_empty()).then(|| TrackLogConfig {
+                    files: track,
+                    max_gap_secs: track_max_gap,
+                    time_offset_secs: track_offset,
+                });
+                if let Some(track_log) = &track_log {
+                    println!(
+                        "{}",
+                        format!(
+                            "Geotagging photos without GPS from {} track log(s)",
+                            track_log.files.len()
+                        )
+                        .cyan()
+                    );
+                }
+
+                // Phase 2: Plan
+                let config = ImportConfig {
+                    import_mode: match (r#move, reflink, hardlink, reference) {
+                        (true, ..) => ImportMode::Move,
+                        (_, true, ..) => ImportMode::Reflink,
+                        (.., true, _) => ImportMode::Hardlink,
+                        (.., true) => ImportMode::Reference,
+                        _ => ImportMode::Copy,
+                    },
+                    force_reimport_duplicates: force,
+                    near_duplicate_distance: skip_similar,
+                    track_log,
+                    target_album_id: target_album.map(|album| album.id),
+                    max_workers: jobs.unwrap_or_else(default_max_workers),
+                    thumbnail_format: Some(lib.config().thumbnail_format),
+                };
+
+                let plan_result = plan(&scan_result, &lib.db, &config)
+                    .map_err(|e| eyre!("Planning failed: {e}"))?;
+
+                if plan_result.counts.to_import == 0 {
+                    println!(
+                        "{}",
+                        format!(
+                            "Plan: {} duplicates skipped, {} similar skipped, {} unsupported/errors",
+                            plan_result.counts.duplicates,
+                            plan_result.counts.near_duplicates,
+                            plan_result.counts.unsupported + plan_result.counts.errors,
+                        )
+                        .cyan()
+                    );
+                    println!("{}", "Nothing to import.".yellow());
+                    lib.close()
+                        .map_err(|e| eyre!("Failed to close library: {e}"))?;
+                    return Ok(());
+                }
+
+                let journal = ImportJournal::create(&lib.root, &sources, &plan_result, &config)
+                    .map_err(|e| eyre!("Failed to create import journal: {e}"))?;
+                (plan_result, config, journal)
+            };
 
             println!(
                 "{}",
                 format!(
-                    "Plan: {} to import, {} duplicates skipped, {} unsupported/errors",
+                    "Plan: {} to import, {} duplicates skipped, {} similar skipped, {} unsupported/errors",
                     plan_result.counts.to_import,
                     plan_result.counts.duplicates,
+                    plan_result.counts.near_duplicates,
                     plan_result.counts.unsupported + plan_result.counts.errors,
                 )
                 .cyan()
             );
 
-            if plan_result.counts.to_import == 0 {
-                println!("{}", "Nothing to import.".yellow());
-                lib.close()
-                    .map_err(|e| eyre!("Failed to close library: {e}"))?;
-                return Ok(());
-            }
-
             // Phase 3: Execute
-            println!("{}", "Importing...".cyan());
+            let run_id = journal.run_id().to_string();
+            println!("{}", format!("Importing (run {run_id})...").cyan());
             let token = CancellationToken::new();
 
-            let summary = execute(
+            let summary = execute_journaled(
                 &plan_result,
                 &lib,
                 &config,
-                |event| {
-                    if let ImportProgressEvent::CandidateCompleted { outcomes, .. } = event {
-                        for (path, outcome) in &outcomes {
-                            let msg = format!("  {}", path.display());
-                            match outcome {
-                                ImportOutcome::Imported => {
-                                    println!("{}", format!("✓ {msg}").green());
-                                }
-                                ImportOutcome::DuplicateSkipped { .. } => {
-                                    println!("{}", format!("= {msg} (duplicate)").yellow());
-                                }
-                                ImportOutcome::CorruptTransfer => {
-                                    println!("{}", format!("✗ {msg} (corrupt transfer)").red());
-                                }
-                                ImportOutcome::CorruptUnreadable(e) => {
-                                    println!("{}", format!("✗ {msg} (unreadable: {e})").red());
-                                }
-                                _ => {
-                                    println!("{}", format!("- {msg}").dimmed());
-                                }
-                            }
-                        }
-                    }
-                },
+                print_import_event,
                 &token,
+                &mut journal,
             )
-            .map_err(|e| eyre!("Import execution failed: {e}"))?;
+            .map_err(|e| {
+                eyre!(
+                    "Import execution failed: {e}\nResume with: pixles import --library {} --resume {run_id}",
+                    library.display()
+                )
+            })?;
+            journal
+                .finish()
+                .map_err(|e| eyre!("Failed to finish import journal: {e}"))?;
 
             println!(
                 "{}",
                 format!(
-                    "Done: {} imported, {} duplicates, {} errors",
+                    "Done: {} imported, {} duplicates, {} similar, {} errors",
                     summary.imported_count(),
                     summary.duplicate_count(),
+                    summary.near_duplicate_count(),
                     summary.error_count()
                 )
                 .green()
@@ -237,6 +675,82 @@ async fn main() -> Result<()> {
                 .map_err(|e| eyre!("Failed to close library: {e}"))?;
         }
 
+        // ── Export ────────────────────────────────────────────────────────
+        Commands::Export {
+            dest,
+            library,
+            album,
+            text,
+            after,
+            before,
+            make,
+            model,
+            min_rating,
+            tags,
+            hidden,
+            template,
+            preset,
+            no_originals,
+            xmp,
+            embed,
+            darktable,
+            on_conflict,
+        } => {
+            let lib = open_library_or_err(&library)?;
+            let selection = match album {
+                Some(album) => ExportSelection::Album(album),
+                None => ExportSelection::Search(Box::new(SearchFilter {
+                    text,
+                    captured_after: after,
+                    captured_before: before,
+                    camera_make: make,
+                    camera_model: model,
+                    min_rating,
+                    tags,
+                    include_hidden: hidden,
+                    ..Default::default()
+                })),
+            };
+            let metadata = if embed {
+                ExportMetadata::Embedded
+            } else if xmp {
+                ExportMetadata::Sidecar
+            } else {
+                ExportMetadata::None
+            };
+            let options = ExportOptions {
+                template,
+                originals: !no_originals,
+                preset,
+                metadata,
+                xmp_naming: if darktable {
+                    XmpNaming::Filename
+                } else {
+                    XmpNaming::Stem
+                },
+                collision: on_conflict,
+            };
+            println!("{}", format!("Exporting to {}...", dest.display()).yellow());
+            let report = lib
+                .export(&selection, &dest, &options)
+                .map_err(|e| eyre!("Export failed: {e}"))?;
+            for (uuid, reason) in &report.skipped {
+                println!("  {} {}", uuid.blue(), reason.red());
+            }
+            println!(
+                "{}",
+                format!(
+                    "{} files exported, {} unchanged, {} skipped",
+                    report.written,
+                    report.unchanged,
+                    report.skipped.len()
+                )
+                .green()
+            );
+            lib.close()
+                .map_err(|e| eyre!("Failed to close library: {e}"))?;
+        }
+
         // ── Sync ──────────────────────────────────────────────────────────
         Commands::Sync { force, dry_run } => {
             println!("{}", "Syncing local and remote data...".green());
@@ -382,8 +896,235 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+fn print_import_event(event: ImportProgressEvent) {
+    if let ImportProgressEvent::CandidateCompleted { outcomes, .. } = event {
+        for (path, outcome) in &outcomes {
+            let msg = format!("  {}", path.display());
+            match outcome {
+                ImportOutcome::Imported => {
+                    println!("{}", format!("✓ {msg}").green());
+                }
+                ImportOutcome::DuplicateSkipped { .. } => {
+                    println!("{}", format!("= {msg} (duplicate)").yellow());
+                }
+                ImportOutcome::NearDuplicateSkipped {
+                    existing_uuid,
+                    distance,
+                } => {
+                    println!(
+                        "{}",
+                        format!("≈ {msg} (looks like {existing_uuid}, {distance} bits apart)")
+                            .yellow()
+                    );
+                }
+                ImportOutcome::CorruptTransfer => {
+                    println!("{}", format!("✗ {msg} (corrupt transfer)").red());
+                }
+                ImportOutcome::CorruptUnreadable(e) => {
+                    println!("{}", format!("✗ {msg} (unreadable: {e})").red());
+                }
+                _ => {
+                    println!("{}", format!("- {msg}").dimmed());
+                }
+            }
+        }
+    }
+}
+
+fn run_tag_command(command: TagCommands) -> Result<()> {
+    let (lib, changed) = match command {
+        TagCommands::List { path, uuid } => {
+            let lib = open_library_or_err(&path)?;
+            match uuid {
+                Some(uuid) => {
+                    let tags = lib.tags(&uuid).map_err(|e| eyre!("{e}"))?;
+                    for tag in &tags {
+                        println!("{tag}");
+                    }
+                }
+                None => {
+                    let tags = lib.list_tags().map_err(|e| eyre!("{e}"))?;
+                    for (tag, count) in &tags {
+                        println!("{:>6}  {}", count.to_string().cyan(), tag);
+                    }
+                    println!("{}", format!("{} tags in use", tags.len()).green());
+                }
+            }
+            lib.close()
+                .map_err(|e| eyre!("Failed to close library: {e}"))?;
+            return Ok(());
+        }
+        TagCommands::Add { path, uuids, tags } => {
+            let lib = open_library_or_err(&path)?;
+            let changed = lib.add_tags(&uuids, &tags);
+            (lib, changed)
+        }
+        TagCommands::Remove { path, uuids, tags } => {
+            let lib = open_library_or_err(&path)?;
+            let changed = lib.remove_tags(&uuids, &tags);
+            (lib, changed)
+        }
+        TagCommands::Rename { path, from, to } => {
+            let lib = open_library_or_err(&path)?;
+            let changed = lib.rename_tag(&from, &to);
+            (lib, changed)
+        }
+        TagCommands::Merge {
+            path,
+            sources,
+            into,
+        } => {
+            let lib = open_library_or_err(&path)?;
+            let changed = lib.merge_tags(&sources, &into);
+            (lib, changed)
+        }
+    };
+    let changed = changed.map_err(|e| eyre!("Tagging failed: {e}"))?;
+    println!("{}", format!("{changed} assets updated").green());
+    lib.close()
+        .map_err(|e| eyre!("Failed to close library: {e}"))?;
+    Ok(())
+}
+
+fn run_album_command(command: AlbumCommands) -> Result<()> {
+    let (lib, done) = match command {
+        AlbumCommands::Create {
+            path,
+            name,
+            description,
+        } => {
+            let lib = open_library_or_err(&path)?;
+            let album = lib
+                .create_album(&name, description.as_deref())
+                .map_err(|e| eyre!("{e}"))?;
+            let done = format!("Created album {} ({})", album.name, album.id);
+            (lib, done)
+        }
+        AlbumCommands::List { path } => {
+            let lib = open_library_or_err(&path)?;
+            let albums = lib.list_albums().map_err(|e| eyre!("{e}"))?;
+            for (album, count) in &albums {
+                println!(
+                    "{}  {:>6}  {}",
+                    album.id.blue(),
+                    count.to_string().cyan(),
+                    album.name
+                );
+            }
+            let done = format!("{} albums", albums.len());
+            (lib, done)
+        }
+        AlbumCommands::Show { path, album } => {
+            let lib = open_library_or_err(&path)?;
+            let album = lib.resolve_album(&album).map_err(|e| eyre!("{e}"))?;
+            println!("{}  {}", album.name.bold(), album.id.blue());
+            if let Some(description) = &album.description {
+                println!("{description}");
+            }
+            if let Some(cover) = &album.cover_asset_id {
+                println!("Cover: {}", cover.blue());
+            }
+            let assets = lib.album_assets(&album.id).map_err(|e| eyre!("{e}"))?;
+            for row in &assets {
+                print_asset(row);
+            }
+            let done = format!("{} assets", assets.len());
+            (lib, done)
+        }
+        AlbumCommands::Add { path, album, uuids } => {
+            let lib = open_library_or_err(&path)?;
+            let album = lib.resolve_album(&album).map_err(|e| eyre!("{e}"))?;
+            let added = lib
+                .add_to_album(&album.id, &uuids)
+                .map_err(|e| eyre!("{e}"))?;
+            let done = format!("{added} assets added to {}", album.name);
+            (lib, done)
+        }
+        AlbumCommands::Remove { path, album, uuids } => {
+            let lib = open_library_or_err(&path)?;
+            let album = lib.resolve_album(&album).map_err(|e| eyre!("{e}"))?;
+            let removed = lib
+                .remove_from_album(&album.id, &uuids)
+                .map_err(|e| eyre!("{e}"))?;
+            let done = format!("{removed} assets removed from {}", album.name);
+            (lib, done)
+        }
+        AlbumCommands::Move {
+            path,
+            album,
+            uuid,
+            to,
+        } => {
+            let lib = open_library_or_err(&path)?;
+            let album = lib.resolve_album(&album).map_err(|e| eyre!("{e}"))?;
+            lib.move_in_album(&album.id, &uuid, (to - 1) as usize)
+                .map_err(|e| eyre!("{e}"))?;
+            let done = format!("Moved {uuid} to position {to} in {}", album.name);
+            (lib, done)
+        }
+        AlbumCommands::Cover { path, album, uuid } => {
+            let lib = open_library_or_err(&path)?;
+            let album = lib.resolve_album(&album).map_err(|e| eyre!("{e}"))?;
+            lib.set_album_cover(&album.id, uuid.as_deref())
+                .map_err(|e| eyre!("{e}"))?;
+            let done = format!("Cover of {} updated", album.name);
+            (lib, done)
+        }
+        AlbumCommands::Rename { path, album, name } => {
+            let lib = open_library_or_err(&path)?;
+            let album = lib.resolve_album(&album).map_err(|e| eyre!("{e}"))?;
+            lib.rename_album(&album.id, &name)
+                .map_err(|e| eyre!("{e}"))?;
+            let done = format!("Renamed {} to {}", album.name, name.trim());
+            (lib, done)
+        }
+        AlbumCommands::Describe {
+            path,
+            album,
+            description,
+        } => {
+            let lib = open_library_or_err(&path)?;
+            let album = lib.resolve_album(&album).map_err(|e| eyre!("{e}"))?;
+            lib.set_album_description(&album.id, description.as_deref())
+                .map_err(|e| eyre!("{e}"))?;
+            let done = format!("Description of {} updated", album.name);
+            (lib, done)
+        }
+        AlbumCommands::Delete { path, album } => {
+            let lib = open_library_or_err(&path)?;
+            let album = lib.resolve_album(&album).map_err(|e| eyre!("{e}"))?;
+            lib.delete_album(&album.id).map_err(|e| eyre!("{e}"))?;
+            let done = format!("Deleted album {}", album.name);
+            (lib, done)
+        }
+    };
+    println!("{}", done.green());
+    lib.close()
+        .map_err(|e| eyre!("Failed to close library: {e}"))?;
+    Ok(())
+}
+
+/// One line per asset: UUID, capture time, camera, rating and filename.
+fn print_asset(row: &AssetRow) {
+    let captured = DateTime::from_timestamp(row.capture_utc.unwrap_or(row.capture_timestamp), 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    println!(
+        "{}  {}  {:<16}  {}  {}",
+        row.uuid.blue(),
+        captured,
+        row.camera_model.as_deref().unwrap_or("-"),
+        "★".repeat(row.rating.clamp(0, 5) as usize).yellow(),
+        row.original_filename.as_deref().unwrap_or(""),
+    );
+}
+
 fn open_library_or_err(path: &Path) -> Result<Library> {
-    open_library(path).map_err(|e| match e {
+    open_library(path).map_err(|e| open_error(path, e))
+}
+
+fn open_error(path: &Path, e: LibraryError) -> eyre::Report {
+    match e {
         LibraryError::CorruptVersion(msg) => {
             eyre!(
                 "Library at {} has a corrupt version file: {}",
@@ -397,9 +1138,18 @@ fn open_library_or_err(path: &Path) -> Result<Library> {
             pid,
             hostname
         ),
-        LibraryError::VersionMismatch { found, expected } => {
-            eyre!("Library version mismatch: found {found}, expected {expected}. Upgrade required.")
-        }
+        LibraryError::VersionMismatch { found, expected } => eyre!(
+            "Library at {} uses format v{found}, but this build only supports up to v{expected}. Upgrade Pixles to open it.",
+            path.display()
+        ),
+        LibraryError::IndexTooNew { found, supported } => eyre!(
+            "Index of library at {} uses schema v{found}, but this build only supports up to v{supported}. Upgrade Pixles to open it.",
+            path.display()
+        ),
+        LibraryError::NeedsUpgrade(msg) => eyre!(
+            "Library at {} must be upgraded before it can be read ({msg}). Open it with this build first.",
+            path.display()
+        ),
         other => eyre!("Failed to open library at {}: {other}", path.display()),
-    })
+    }
 }
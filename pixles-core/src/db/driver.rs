@@ -1,33 +1,87 @@
-use crate::db::rows::{AssetRow, AssetStackRow, StackMemberRow};
+use crate::db::rows::{AlbumRow, AssetRow, AssetStackRow, StackMemberRow};
 use crate::db::schema;
-use rusqlite::{Connection, params};
+use crate::db::search::SearchFilter;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params, params_from_iter};
 use std::path::Path;
 
+/// Column list matching [`map_asset_row`].
+const ASSET_COLUMNS: &str = "uuid, asset_type, capture_timestamp, capture_utc, capture_tz_source, \
+    import_timestamp, hash_blake3, width, height, duration_ms, stack_id, is_stack_hidden, \
+    chromahash, dominant_color, album_id, rating, is_deleted, deleted_at, original_filename, \
+    camera_make, camera_model, gps_lat, gps_lon, caption, perceptual_hash";
+const ASSET_PARAMS: &str =
+    "?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16,?17,?18,?19,?20,?21,?22,?23,?24,?25";
+/// Column list matching [`map_album_row`].
+const ALBUM_COLUMNS: &str = "id, name, description, cover_asset_id, created_at, modified_at";
+
 pub struct DatabaseDriver {
     conn: Connection,
 }
 
 impl DatabaseDriver {
+    /// Opens (or creates) the index at `path` and applies pending migrations.
     pub fn open(path: &Path) -> Result<Self, rusqlite::Error> {
-        let conn = Connection::open(path)?;
-        let driver = Self { conn };
-        driver.init_schema()?;
+        let driver = Self::open_unmigrated(path)?;
+        driver.migrate()?;
         Ok(driver)
     }
 
+    /// Opens the index without touching its schema, so the caller can
+    /// inspect [`schema_version`](Self::schema_version) and back it up first.
+    pub fn open_unmigrated(path: &Path) -> Result<Self, rusqlite::Error> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Ok(Self { conn })
+    }
+
+    /// Opens an existing index for reading only; nothing is created or
+    /// migrated.
+    pub fn open_read_only(path: &Path) -> Result<Self, rusqlite::Error> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        Ok(Self { conn })
+    }
+
     pub fn open_in_memory() -> Result<Self, rusqlite::Error> {
         let conn = Connection::open_in_memory()?;
         let driver = Self { conn };
-        driver.init_schema()?;
+        driver.migrate()?;
         Ok(driver)
     }
 
-    pub fn init_schema(&self) -> Result<(), rusqlite::Error> {
-        self.conn.execute_batch(schema::DDL)?;
-        self.conn.execute_batch(&format!(
-            "PRAGMA user_version = {};",
-            schema::SCHEMA_VERSION
-        ))?;
+    /// Applies every migration newer than the stored `user_version`, each in
+    /// its own transaction. Returns the number applied.
+    ///
+    /// A database newer than [`schema::SCHEMA_VERSION`] is left untouched;
+    /// callers must refuse to use it.
+    pub fn migrate(&self) -> Result<usize, rusqlite::Error> {
+        self.migrate_with(schema::MIGRATIONS)
+    }
+
+    fn migrate_with(&self, migrations: &[schema::Migration]) -> Result<usize, rusqlite::Error> {
+        let current = self.schema_version()?;
+        let mut applied = 0;
+        for migration in migrations.iter().filter(|m| m.version > current) {
+            let tx = self.conn.unchecked_transaction()?;
+            tx.execute_batch(migration.sql)?;
+            tx.pragma_update(None, "user_version", migration.version)?;
+            tx.commit()?;
+            log::info!(
+                "index migrated to v{}: {}",
+                migration.version,
+                migration.description
+            );
+            applied += 1;
+        }
+        Ok(applied)
+    }
+
+    /// Writes a consistent copy of the database to `dest`.
+    pub fn backup_to(&self, dest: &Path) -> Result<(), rusqlite::Error> {
+        self.conn
+            .execute("VACUUM INTO ?1", params![dest.to_string_lossy()])?;
         Ok(())
     }
 
@@ -38,47 +92,83 @@ impl DatabaseDriver {
         Ok(version)
     }
 
+    /// Runs `f` in one transaction, committed if it returns `Ok` and rolled
+    /// back otherwise. Within `f`, only single-statement methods and
+    /// [`Self::set_tags_batch`] may be called; the other batch methods open a
+    /// transaction of their own.
+    pub fn transaction<T, E>(&self, f: impl FnOnce() -> Result<T, E>) -> Result<T, E>
+    where
+        E: From<rusqlite::Error>,
+    {
+        let tx = self.conn.unchecked_transaction()?;
+        let value = f()?;
+        tx.commit()?;
+        Ok(value)
+    }
+
     pub fn insert_asset(&self, row: &AssetRow) -> Result<(), rusqlite::Error> {
-        self.conn.execute(
-            "INSERT INTO assets (uuid, asset_type, capture_timestamp, capture_utc, capture_tz_source,
-             import_timestamp, hash_blake3, width, height, duration_ms, stack_id, is_stack_hidden,
-             chromahash, dominant_color, album_id, rating, is_deleted, deleted_at)
-             VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16,?17,?18)",
-            params![
-                row.uuid, row.asset_type, row.capture_timestamp, row.capture_utc,
-                row.capture_tz_source, row.import_timestamp, row.hash_blake3,
-                row.width, row.height, row.duration_ms, row.stack_id,
-                row.is_stack_hidden as i64, row.chromahash, row.dominant_color,
-                row.album_id, row.rating, row.is_deleted as i64, row.deleted_at,
-            ],
-        )?;
-        Ok(())
+        self.write_asset(
+            &format!("INSERT INTO assets ({ASSET_COLUMNS}) VALUES ({ASSET_PARAMS})"),
+            row,
+        )
     }
 
+    /// Inserts or overwrites a row in place, keeping its rowid (and with it
+    /// the row's `assets_fts` entry).
     pub fn upsert_asset(&self, row: &AssetRow) -> Result<(), rusqlite::Error> {
+        let updates = ASSET_COLUMNS
+            .split(", ")
+            .skip(1)
+            .map(|c| format!("{c} = excluded.{c}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.write_asset(
+            &format!(
+                "INSERT INTO assets ({ASSET_COLUMNS}) VALUES ({ASSET_PARAMS})
+                 ON CONFLICT(uuid) DO UPDATE SET {updates}"
+            ),
+            row,
+        )
+    }
+
+    fn write_asset(&self, sql: &str, row: &AssetRow) -> Result<(), rusqlite::Error> {
         self.conn.execute(
-            "INSERT OR REPLACE INTO assets (uuid, asset_type, capture_timestamp, capture_utc, capture_tz_source,
-             import_timestamp, hash_blake3, width, height, duration_ms, stack_id, is_stack_hidden,
-             chromahash, dominant_color, album_id, rating, is_deleted, deleted_at)
-             VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14,?15,?16,?17,?18)",
+            sql,
             params![
-                row.uuid, row.asset_type, row.capture_timestamp, row.capture_utc,
-                row.capture_tz_source, row.import_timestamp, row.hash_blake3,
-                row.width, row.height, row.duration_ms, row.stack_id,
-                row.is_stack_hidden as i64, row.chromahash, row.dominant_color,
-                row.album_id, row.rating, row.is_deleted as i64, row.deleted_at,
+                row.uuid,
+                row.asset_type,
+                row.capture_timestamp,
+                row.capture_utc,
+                row.capture_tz_source,
+                row.import_timestamp,
+                row.hash_blake3,
+                row.width,
+                row.height,
+                row.duration_ms,
+                row.stack_id,
+                row.is_stack_hidden as i64,
+                row.chromahash,
+                row.dominant_color,
+                row.album_id,
+                row.rating,
+                row.is_deleted as i64,
+                row.deleted_at,
+                row.original_filename,
+                row.camera_make,
+                row.camera_model,
+                row.gps_lat,
+                row.gps_lon,
+                row.caption,
+                row.perceptual_hash,
             ],
         )?;
         Ok(())
     }
 
     pub fn find_by_uuid(&self, uuid: &str) -> Result<Option<AssetRow>, rusqlite::Error> {
-        let mut stmt = self.conn.prepare(
-            "SELECT uuid, asset_type, capture_timestamp, capture_utc, capture_tz_source,
-             import_timestamp, hash_blake3, width, height, duration_ms, stack_id, is_stack_hidden,
-             chromahash, dominant_color, album_id, rating, is_deleted, deleted_at
-             FROM assets WHERE uuid = ?1 LIMIT 1",
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {ASSET_COLUMNS} FROM assets WHERE uuid = ?1 LIMIT 1",
+        ))?;
         let mut rows = stmt.query_map(params![uuid], map_asset_row)?;
         match rows.next() {
             Some(r) => Ok(Some(r?)),
@@ -87,12 +177,9 @@ impl DatabaseDriver {
     }
 
     pub fn find_by_hash(&self, hash: &str) -> Result<Option<AssetRow>, rusqlite::Error> {
-        let mut stmt = self.conn.prepare(
-            "SELECT uuid, asset_type, capture_timestamp, capture_utc, capture_tz_source,
-             import_timestamp, hash_blake3, width, height, duration_ms, stack_id, is_stack_hidden,
-             chromahash, dominant_color, album_id, rating, is_deleted, deleted_at
-             FROM assets WHERE hash_blake3 = ?1 LIMIT 1",
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {ASSET_COLUMNS} FROM assets WHERE hash_blake3 = ?1 LIMIT 1",
+        ))?;
         let mut rows = stmt.query_map(params![hash], map_asset_row)?;
         match rows.next() {
             Some(r) => Ok(Some(r?)),
@@ -105,19 +192,164 @@ impl DatabaseDriver {
         offset: usize,
         limit: usize,
     ) -> Result<Vec<AssetRow>, rusqlite::Error> {
-        let mut stmt = self.conn.prepare(
-            "SELECT uuid, asset_type, capture_timestamp, capture_utc, capture_tz_source,
-             import_timestamp, hash_blake3, width, height, duration_ms, stack_id, is_stack_hidden,
-             chromahash, dominant_color, album_id, rating, is_deleted, deleted_at
-             FROM assets
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {ASSET_COLUMNS} FROM assets
              WHERE is_deleted = 0 AND is_stack_hidden = 0
              ORDER BY COALESCE(capture_utc, capture_timestamp) DESC
              LIMIT ?1 OFFSET ?2",
-        )?;
+        ))?;
         let rows = stmt.query_map(params![limit as i64, offset as i64], map_asset_row)?;
         rows.collect()
     }
 
+    /// All assets that are not in the trash, oldest import first.
+    pub fn list_assets(&self) -> Result<Vec<AssetRow>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {ASSET_COLUMNS} FROM assets WHERE is_deleted = 0
+             ORDER BY import_timestamp ASC, uuid ASC",
+        ))?;
+        let rows = stmt.query_map([], map_asset_row)?;
+        rows.collect()
+    }
+
+    /// Assets outside the trash that have a perceptual hash, oldest import
+    /// first.
+    pub fn assets_with_perceptual_hash(&self) -> Result<Vec<AssetRow>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {ASSET_COLUMNS} FROM assets
+             WHERE is_deleted = 0 AND perceptual_hash IS NOT NULL
+             ORDER BY import_timestamp ASC, uuid ASC",
+        ))?;
+        let rows = stmt.query_map([], map_asset_row)?;
+        rows.collect()
+    }
+
+    /// Assets matching every criterion in `filter`, newest capture first.
+    /// Trashed assets are never returned.
+    pub fn search(&self, filter: &SearchFilter) -> Result<Vec<AssetRow>, rusqlite::Error> {
+        let (where_clause, values) = filter.where_clause();
+        // SQLite treats a negative LIMIT as "no limit".
+        let limit = filter.limit.map_or(-1, |l| l as i64);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {ASSET_COLUMNS} FROM assets WHERE {where_clause}
+             ORDER BY COALESCE(capture_utc, capture_timestamp) DESC, uuid ASC
+             LIMIT {limit} OFFSET {}",
+            filter.offset
+        ))?;
+        let rows = stmt.query_map(params_from_iter(values), map_asset_row)?;
+        rows.collect()
+    }
+
+    /// Replaces the index's copy of an asset's tags. The sidecar remains the
+    /// source of truth.
+    pub fn set_asset_tags(&self, uuid: &str, tags: &[String]) -> Result<(), rusqlite::Error> {
+        self.set_tags_batch(&[(uuid.to_string(), tags.to_vec())])
+    }
+
+    /// Replaces the tags of several assets in one transaction, or as part of
+    /// the one [`Self::transaction`] has open.
+    pub fn set_tags_batch(&self, changes: &[(String, Vec<String>)]) -> Result<(), rusqlite::Error> {
+        let tx = self
+            .conn
+            .is_autocommit()
+            .then(|| self.conn.unchecked_transaction())
+            .transpose()?;
+        for (uuid, tags) in changes {
+            self.conn
+                .execute("DELETE FROM asset_tags WHERE uuid = ?1", params![uuid])?;
+            for tag in tags {
+                self.conn.execute(
+                    "INSERT OR IGNORE INTO asset_tags (uuid, tag) VALUES (?1, ?2)",
+                    params![uuid, tag],
+                )?;
+            }
+        }
+        tx.map_or(Ok(()), |tx| tx.commit())
+    }
+
+    pub fn tags_for(&self, uuid: &str) -> Result<Vec<String>, rusqlite::Error> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT tag FROM asset_tags WHERE uuid = ?1 ORDER BY tag")?;
+        let rows = stmt.query_map(params![uuid], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Every tag in use by assets outside the trash, with its asset count.
+    pub fn list_tags(&self) -> Result<Vec<(String, i64)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.tag, COUNT(*) FROM asset_tags t JOIN assets a ON a.uuid = t.uuid
+             WHERE a.is_deleted = 0 GROUP BY t.tag ORDER BY t.tag",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// UUIDs of assets (trashed ones included) carrying `tag` or any tag
+    /// nested below it.
+    pub fn uuids_with_tag(&self, tag: &str) -> Result<Vec<String>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT uuid FROM asset_tags
+             WHERE tag = ?1 OR substr(tag, 1, length(?1) + 1) = ?1 || '|'
+             ORDER BY uuid",
+        )?;
+        let rows = stmt.query_map(params![tag], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// UUIDs of every asset row, including those in the trash.
+    pub fn list_asset_uuids(&self) -> Result<Vec<String>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare("SELECT uuid FROM assets ORDER BY uuid")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Removes an asset row together with its stack memberships and tags.
+    /// Files on disk are not touched.
+    pub fn delete_asset(&self, uuid: &str) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "DELETE FROM stack_members WHERE asset_id = ?1",
+            params![uuid],
+        )?;
+        self.conn
+            .execute("DELETE FROM asset_tags WHERE uuid = ?1", params![uuid])?;
+        self.conn
+            .execute("DELETE FROM assets WHERE uuid = ?1", params![uuid])?;
+        Ok(())
+    }
+
+    pub fn update_derivatives(
+        &self,
+        uuid: &str,
+        chromahash: &str,
+        dominant_color: &str,
+        perceptual_hash: i64,
+    ) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "UPDATE assets SET chromahash = ?1, dominant_color = ?2, perceptual_hash = ?3
+             WHERE uuid = ?4",
+            params![chromahash, dominant_color, perceptual_hash, uuid],
+        )?;
+        Ok(())
+    }
+
+    /// Sets the capture time columns of several assets in one transaction,
+    /// given as `(uuid, capture_timestamp, capture_utc, capture_tz_source)`.
+    pub fn set_capture_times_batch(
+        &self,
+        changes: &[(String, i64, Option<i64>, Option<String>)],
+    ) -> Result<(), rusqlite::Error> {
+        let tx = self.conn.unchecked_transaction()?;
+        for (uuid, timestamp, utc, tz_source) in changes {
+            tx.execute(
+                "UPDATE assets SET capture_timestamp = ?1, capture_utc = ?2, capture_tz_source = ?3
+                 WHERE uuid = ?4",
+                params![timestamp, utc, tz_source, uuid],
+            )?;
+        }
+        tx.commit()
+    }
+
     pub fn insert_stack(&self, row: &AssetStackRow) -> Result<(), rusqlite::Error> {
         self.conn.execute(
             "INSERT INTO asset_stacks (id, stack_type, primary_asset_id, cover_asset_id,
@@ -206,6 +438,18 @@ impl DatabaseDriver {
         rows.collect()
     }
 
+    /// The stack an asset is a member of, if any.
+    pub fn stack_of(&self, asset_id: &str) -> Result<Option<String>, rusqlite::Error> {
+        self.conn
+            .query_row(
+                "SELECT stack_id FROM stack_members WHERE asset_id = ?1
+                 ORDER BY created_at, id LIMIT 1",
+                params![asset_id],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
     pub fn soft_delete(&self, uuid: &str, deleted_at: i64) -> Result<(), rusqlite::Error> {
         self.conn.execute(
             "UPDATE assets SET is_deleted = 1, deleted_at = ?1 WHERE uuid = ?2",
@@ -228,14 +472,136 @@ impl DatabaseDriver {
     ) -> Result<Vec<AssetRow>, rusqlite::Error> {
         let threshold = now_secs() - older_than_secs;
         let mut stmt = self.conn.prepare(
-            "SELECT uuid, asset_type, capture_timestamp, capture_utc, capture_tz_source,
-             import_timestamp, hash_blake3, width, height, duration_ms, stack_id, is_stack_hidden,
-             chromahash, dominant_color, album_id, rating, is_deleted, deleted_at
-             FROM assets WHERE is_deleted = 1 AND deleted_at IS NOT NULL AND deleted_at < ?1",
-        )?;
+            &format!(
+            "SELECT {ASSET_COLUMNS} FROM assets WHERE is_deleted = 1 AND deleted_at IS NOT NULL AND deleted_at < ?1",
+        ))?;
         let rows = stmt.query_map(params![threshold], map_asset_row)?;
         rows.collect()
     }
+
+    /// Inserts or replaces an album together with its members, given in
+    /// album order with the time each was added, in one transaction.
+    pub fn upsert_album(
+        &self,
+        row: &AlbumRow,
+        members: &[(String, i64)],
+    ) -> Result<(), rusqlite::Error> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO albums (id, name, description, cover_asset_id, created_at, modified_at)
+             VALUES (?1,?2,?3,?4,?5,?6)
+             ON CONFLICT(id) DO UPDATE SET name = excluded.name,
+                description = excluded.description, cover_asset_id = excluded.cover_asset_id,
+                created_at = excluded.created_at, modified_at = excluded.modified_at",
+            params![
+                row.id,
+                row.name,
+                row.description,
+                row.cover_asset_id,
+                row.created_at,
+                row.modified_at,
+            ],
+        )?;
+        tx.execute(
+            "DELETE FROM album_assets WHERE album_id = ?1",
+            params![row.id],
+        )?;
+        for (position, (asset_id, added_at)) in members.iter().enumerate() {
+            tx.execute(
+                "INSERT OR IGNORE INTO album_assets (album_id, asset_id, position, added_at)
+                 VALUES (?1,?2,?3,?4)",
+                params![row.id, asset_id, position as i64, added_at],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Appends an asset to the end of an album unless it is already in it.
+    pub fn append_album_asset(
+        &self,
+        album_id: &str,
+        asset_id: &str,
+        added_at: i64,
+    ) -> Result<(), rusqlite::Error> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO album_assets (album_id, asset_id, position, added_at)
+             VALUES (?1, ?2,
+                     (SELECT COALESCE(MAX(position) + 1, 0) FROM album_assets WHERE album_id = ?1),
+                     ?3)",
+            params![album_id, asset_id, added_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_album(&self, id: &str) -> Result<(), rusqlite::Error> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM album_assets WHERE album_id = ?1", params![id])?;
+        tx.execute("DELETE FROM albums WHERE id = ?1", params![id])?;
+        tx.commit()
+    }
+
+    pub fn find_album(&self, id: &str) -> Result<Option<AlbumRow>, rusqlite::Error> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT {ALBUM_COLUMNS} FROM albums WHERE id = ?1"))?;
+        let mut rows = stmt.query_map(params![id], map_album_row)?;
+        match rows.next() {
+            Some(r) => Ok(Some(r?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Every album, by name, with the number of its assets outside the trash.
+    pub fn list_albums(&self) -> Result<Vec<(AlbumRow, i64)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {ALBUM_COLUMNS},
+                    (SELECT COUNT(*) FROM album_assets m JOIN assets a ON a.uuid = m.asset_id
+                     WHERE m.album_id = albums.id AND a.is_deleted = 0)
+             FROM albums ORDER BY name COLLATE NOCASE, id"
+        ))?;
+        let rows = stmt.query_map([], |row| Ok((map_album_row(row)?, row.get(6)?)))?;
+        rows.collect()
+    }
+
+    /// Members of an album in album order with the time each was added,
+    /// including assets in the trash.
+    pub fn album_members(&self, album_id: &str) -> Result<Vec<(String, i64)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT asset_id, added_at FROM album_assets WHERE album_id = ?1 ORDER BY position",
+        )?;
+        let rows = stmt.query_map(params![album_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// Assets of an album outside the trash, in album order.
+    pub fn album_assets(&self, album_id: &str) -> Result<Vec<AssetRow>, rusqlite::Error> {
+        let columns = ASSET_COLUMNS
+            .split(", ")
+            .map(|c| format!("a.{c}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {columns} FROM album_assets m JOIN assets a ON a.uuid = m.asset_id
+             WHERE m.album_id = ?1 AND a.is_deleted = 0 ORDER BY m.position"
+        ))?;
+        let rows = stmt.query_map(params![album_id], map_asset_row)?;
+        rows.collect()
+    }
+
+    /// Albums containing an asset, by name.
+    pub fn albums_for_asset(&self, asset_id: &str) -> Result<Vec<AlbumRow>, rusqlite::Error> {
+        let columns = ALBUM_COLUMNS
+            .split(", ")
+            .map(|c| format!("al.{c}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {columns} FROM album_assets m JOIN albums al ON al.id = m.album_id
+             WHERE m.asset_id = ?1 ORDER BY al.name COLLATE NOCASE, al.id"
+        ))?;
+        let rows = stmt.query_map(params![asset_id], map_album_row)?;
+        rows.collect()
+    }
 }
 
 fn now_secs() -> i64 {
@@ -265,13 +631,31 @@ fn map_asset_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<AssetRow> {
         rating: row.get(15)?,
         is_deleted: row.get::<_, i64>(16)? != 0,
         deleted_at: row.get(17)?,
+        original_filename: row.get(18)?,
+        camera_make: row.get(19)?,
+        camera_model: row.get(20)?,
+        gps_lat: row.get(21)?,
+        gps_lon: row.get(22)?,
+        caption: row.get(23)?,
+        perceptual_hash: row.get(24)?,
+    })
+}
+
+fn map_album_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<AlbumRow> {
+    Ok(AlbumRow {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        cover_asset_id: row.get(3)?,
+        created_at: row.get(4)?,
+        modified_at: row.get(5)?,
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::rows::{AssetRow, AssetStackRow, StackMemberRow};
+    use crate::db::rows::{AlbumRow, AssetRow, AssetStackRow, StackMemberRow};
 
     fn make_asset(uuid: &str, hash: &str) -> AssetRow {
         AssetRow {
@@ -289,18 +673,126 @@ mod tests {
             is_stack_hidden: false,
             chromahash: None,
             dominant_color: None,
+            perceptual_hash: None,
             album_id: None,
             rating: 0,
             is_deleted: false,
             deleted_at: None,
+            original_filename: Some(format!("{uuid}.jpg")),
+            camera_make: None,
+            camera_model: None,
+            gps_lat: None,
+            gps_lon: None,
+            caption: None,
         }
     }
 
     #[test]
-    fn test_init_schema_idempotent() {
+    fn test_migrate_idempotent() {
         let db = DatabaseDriver::open_in_memory().unwrap();
-        db.init_schema().unwrap(); // second call — should not fail
-        assert_eq!(db.schema_version().unwrap(), 1);
+        assert_eq!(db.migrate().unwrap(), 0); // second call — nothing pending
+        assert_eq!(db.schema_version().unwrap(), schema::SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_migrate_applies_pending_steps_in_order() {
+        let db = DatabaseDriver::open_in_memory().unwrap();
+        let next = schema::SCHEMA_VERSION + 1;
+        let steps = [
+            schema::Migration {
+                version: schema::SCHEMA_VERSION,
+                description: "already applied",
+                sql: "SELECT * FROM missing_table;",
+            },
+            schema::Migration {
+                version: next,
+                description: "add note",
+                sql: "ALTER TABLE assets ADD COLUMN note TEXT;",
+            },
+            schema::Migration {
+                version: next + 1,
+                description: "backfill note",
+                sql: "UPDATE assets SET note = '' WHERE note IS NULL;",
+            },
+        ];
+        db.insert_asset(&make_asset("uuid-1", &"a".repeat(64)))
+            .unwrap();
+
+        assert_eq!(db.migrate_with(&steps).unwrap(), 2);
+        assert_eq!(db.schema_version().unwrap(), next + 1);
+        let note: String = db
+            .conn
+            .query_row("SELECT note FROM assets", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(note, "");
+        assert_eq!(db.migrate_with(&steps).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let db = DatabaseDriver::open_in_memory().unwrap();
+        let steps = [schema::Migration {
+            version: schema::SCHEMA_VERSION + 1,
+            description: "broken",
+            sql: "ALTER TABLE assets ADD COLUMN note TEXT; SELECT * FROM missing_table;",
+        }];
+        assert!(db.migrate_with(&steps).is_err());
+        assert_eq!(db.schema_version().unwrap(), schema::SCHEMA_VERSION);
+        // The column added before the failure was rolled back with it.
+        assert!(db.conn.prepare("SELECT note FROM assets").is_err());
+    }
+
+    #[test]
+    fn test_failed_transaction_rolls_back() {
+        let db = DatabaseDriver::open_in_memory().unwrap();
+        let result: Result<(), rusqlite::Error> = db.transaction(|| {
+            db.insert_asset(&make_asset("uuid-1", &"a".repeat(64)))?;
+            db.set_asset_tags("uuid-1", &["kyoto".to_string()])?;
+            db.insert_asset(&make_asset("uuid-1", &"a".repeat(64)))
+        });
+        assert!(result.is_err());
+        assert!(db.find_by_uuid("uuid-1").unwrap().is_none());
+        assert!(db.tags_for("uuid-1").unwrap().is_empty());
+
+        db.transaction(|| {
+            db.insert_asset(&make_asset("uuid-1", &"a".repeat(64)))?;
+            db.set_asset_tags("uuid-1", &["kyoto".to_string()])
+        })
+        .unwrap();
+        assert_eq!(db.tags_for("uuid-1").unwrap(), vec!["kyoto"]);
+    }
+
+    #[test]
+    fn test_search_migration_indexes_existing_rows() {
+        let v1 = [schema::Migration {
+            version: 1,
+            description: "initial schema",
+            sql: schema::DDL,
+        }];
+        let db = DatabaseDriver {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        db.migrate_with(&v1).unwrap();
+        db.conn
+            .execute(
+                "INSERT INTO assets (uuid, asset_type, import_timestamp, hash_blake3)
+                 VALUES ('uuid-1', 'photo', 0, 'h')",
+                [],
+            )
+            .unwrap();
+        db.conn
+            .execute("INSERT INTO asset_tags VALUES ('uuid-1', 'kyoto')", [])
+            .unwrap();
+        assert_eq!(db.migrate().unwrap(), schema::MIGRATIONS.len() - 1);
+
+        let found = db
+            .search(&SearchFilter {
+                text: Some("kyo".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].original_filename, None);
     }
 
     #[test]
@@ -410,6 +902,8 @@ mod tests {
         assert_eq!(members.len(), 2);
         assert_eq!(members[0].member_role, "primary");
         assert_eq!(members[1].member_role, "raw");
+        assert_eq!(db.stack_of("uuid-2").unwrap().as_deref(), Some("stack-1"));
+        assert_eq!(db.stack_of("uuid-3").unwrap(), None);
     }
 
     #[test]
@@ -422,4 +916,276 @@ mod tests {
         let found = db.find_by_hash(&"a".repeat(64)).unwrap().unwrap();
         assert_eq!(found.rating, 5);
     }
+
+    #[test]
+    fn test_list_assets_and_update_derivatives() {
+        let db = DatabaseDriver::open_in_memory().unwrap();
+        let a1 = make_asset("uuid-1", &"a".repeat(64));
+        let mut a2 = make_asset("uuid-2", &"b".repeat(64));
+        a2.is_deleted = true;
+        db.insert_asset(&a1).unwrap();
+        db.insert_asset(&a2).unwrap();
+
+        db.update_derivatives("uuid-1", "hash", "#102030", -2)
+            .unwrap();
+        let assets = db.list_assets().unwrap();
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].chromahash.as_deref(), Some("hash"));
+        assert_eq!(assets[0].dominant_color.as_deref(), Some("#102030"));
+        assert_eq!(assets[0].perceptual_hash, Some(-2));
+    }
+
+    #[test]
+    fn test_set_capture_times_batch() {
+        let db = DatabaseDriver::open_in_memory().unwrap();
+        db.insert_asset(&make_asset("uuid-1", &"a".repeat(64)))
+            .unwrap();
+        db.insert_asset(&make_asset("uuid-2", &"b".repeat(64)))
+            .unwrap();
+
+        db.set_capture_times_batch(&[
+            (
+                "uuid-1".to_string(),
+                100,
+                Some(90),
+                Some("gps_lookup".to_string()),
+            ),
+            ("uuid-2".to_string(), 200, None, None),
+        ])
+        .unwrap();
+        let a1 = db.find_by_uuid("uuid-1").unwrap().unwrap();
+        assert_eq!(
+            (
+                a1.capture_timestamp,
+                a1.capture_utc,
+                a1.capture_tz_source.as_deref()
+            ),
+            (100, Some(90), Some("gps_lookup"))
+        );
+        let a2 = db.find_by_uuid("uuid-2").unwrap().unwrap();
+        assert_eq!((a2.capture_timestamp, a2.capture_utc), (200, None));
+    }
+
+    #[test]
+    fn test_delete_asset() {
+        let db = DatabaseDriver::open_in_memory().unwrap();
+        db.insert_asset(&make_asset("uuid-1", &"a".repeat(64)))
+            .unwrap();
+        db.insert_asset(&make_asset("uuid-2", &"b".repeat(64)))
+            .unwrap();
+        db.delete_asset("uuid-1").unwrap();
+        assert_eq!(db.list_asset_uuids().unwrap(), vec!["uuid-2".to_string()]);
+    }
+
+    #[test]
+    fn test_search_combines_filters() {
+        use crate::db::search::GeoBounds;
+
+        let db = DatabaseDriver::open_in_memory().unwrap();
+        let mut kyoto = make_asset("uuid-1", &"a".repeat(64));
+        kyoto.camera_make = Some("FUJIFILM".to_string());
+        kyoto.camera_model = Some("X-T5".to_string());
+        kyoto.gps_lat = Some(35.01);
+        kyoto.gps_lon = Some(135.77);
+        kyoto.rating = 5;
+        kyoto.caption = Some("Fushimi Inari at dawn".to_string());
+        let mut paris = kyoto.clone();
+        paris.uuid = "uuid-2".to_string();
+        paris.hash_blake3 = "b".repeat(64);
+        paris.gps_lat = Some(48.86);
+        paris.gps_lon = Some(2.35);
+        let mut unrated = kyoto.clone();
+        unrated.uuid = "uuid-3".to_string();
+        unrated.hash_blake3 = "c".repeat(64);
+        unrated.rating = 2;
+        for row in [&kyoto, &paris, &unrated] {
+            db.insert_asset(row).unwrap();
+        }
+        db.set_asset_tags("uuid-1", &["Places|Japan".to_string()])
+            .unwrap();
+
+        let japan = SearchFilter {
+            camera_model: Some("x-t5".to_string()),
+            bounds: Some(GeoBounds {
+                south: 24.0,
+                west: 122.0,
+                north: 46.0,
+                east: 146.0,
+            }),
+            min_rating: Some(5),
+            captured_after: Some(1704067200),
+            captured_before: Some(1735689600),
+            ..Default::default()
+        };
+        let uuids = |f: &SearchFilter| -> Vec<String> {
+            db.search(f).unwrap().into_iter().map(|r| r.uuid).collect()
+        };
+        assert_eq!(uuids(&japan), vec!["uuid-1"]);
+
+        let text = SearchFilter {
+            text: Some("inari".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(uuids(&text).len(), 3);
+        let tagged = SearchFilter {
+            text: Some("japan".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(uuids(&tagged), vec!["uuid-1"]);
+
+        // Edits to the row and its tags are reflected in the FTS index.
+        kyoto.caption = None;
+        db.upsert_asset(&kyoto).unwrap();
+        db.set_asset_tags("uuid-1", &[]).unwrap();
+        assert_eq!(uuids(&text).len(), 2);
+        assert!(uuids(&tagged).is_empty());
+
+        // Trashed and stack-hidden assets drop out.
+        db.soft_delete("uuid-2", 1720000100).unwrap();
+        db.update_stack_hidden("uuid-3", true).unwrap();
+        assert!(uuids(&text).is_empty());
+        let hidden = SearchFilter {
+            include_hidden: true,
+            ..text.clone()
+        };
+        assert_eq!(uuids(&hidden), vec!["uuid-3"]);
+    }
+
+    #[test]
+    fn test_search_by_stack_type_and_page() {
+        let db = DatabaseDriver::open_in_memory().unwrap();
+        for (i, hash) in ["a", "b", "c"].iter().enumerate() {
+            let mut row = make_asset(&format!("uuid-{i}"), &hash.repeat(64));
+            row.capture_utc = Some(1719997200 + i as i64);
+            db.insert_asset(&row).unwrap();
+        }
+        db.insert_stack(&AssetStackRow {
+            id: "stack-1".to_string(),
+            stack_type: "burst".to_string(),
+            primary_asset_id: "uuid-0".to_string(),
+            cover_asset_id: None,
+            is_collapsed: true,
+            is_auto_generated: true,
+            created_at: 0,
+            modified_at: 0,
+        })
+        .unwrap();
+        for (i, uuid) in ["uuid-0", "uuid-1"].iter().enumerate() {
+            db.insert_stack_member(&StackMemberRow {
+                id: format!("m-{i}"),
+                stack_id: "stack-1".to_string(),
+                asset_id: uuid.to_string(),
+                sequence_order: i as i64,
+                member_role: "primary".to_string(),
+                created_at: 0,
+            })
+            .unwrap();
+        }
+
+        let bursts = db
+            .search(&SearchFilter {
+                stack_type: Some("burst".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        let uuids: Vec<_> = bursts.iter().map(|r| r.uuid.as_str()).collect();
+        assert_eq!(uuids, vec!["uuid-1", "uuid-0"]);
+
+        let page = db
+            .search(&SearchFilter {
+                limit: Some(1),
+                offset: 1,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].uuid, "uuid-1");
+    }
+
+    #[test]
+    fn test_album_membership_is_ordered_and_many_to_many() {
+        let db = DatabaseDriver::open_in_memory().unwrap();
+        for (uuid, hash) in [("uuid-1", "a"), ("uuid-2", "b"), ("uuid-3", "c")] {
+            db.insert_asset(&make_asset(uuid, &hash.repeat(64)))
+                .unwrap();
+        }
+        let album = |id: &str, name: &str| AlbumRow {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: None,
+            cover_asset_id: None,
+            created_at: 1720000000,
+            modified_at: 1720000000,
+        };
+        let members = |uuids: &[&str]| -> Vec<(String, i64)> {
+            uuids.iter().map(|u| (u.to_string(), 1720000000)).collect()
+        };
+        db.upsert_album(&album("album-1", "Kyoto"), &members(&["uuid-3", "uuid-1"]))
+            .unwrap();
+        db.upsert_album(&album("album-2", "best of"), &members(&["uuid-1"]))
+            .unwrap();
+        db.append_album_asset("album-1", "uuid-2", 1720000100)
+            .unwrap();
+        db.append_album_asset("album-1", "uuid-1", 1720000100)
+            .unwrap();
+
+        let order: Vec<String> = db
+            .album_assets("album-1")
+            .unwrap()
+            .into_iter()
+            .map(|r| r.uuid)
+            .collect();
+        assert_eq!(order, vec!["uuid-3", "uuid-1", "uuid-2"]);
+        let names: Vec<String> = db
+            .albums_for_asset("uuid-1")
+            .unwrap()
+            .into_iter()
+            .map(|a| a.name)
+            .collect();
+        assert_eq!(names, vec!["best of", "Kyoto"]);
+
+        db.soft_delete("uuid-3", 1720000200).unwrap();
+        let counts: Vec<i64> = db
+            .list_albums()
+            .unwrap()
+            .into_iter()
+            .map(|(_, n)| n)
+            .collect();
+        assert_eq!(counts, vec![1, 2]);
+        assert_eq!(db.album_members("album-1").unwrap().len(), 3);
+
+        db.delete_album("album-1").unwrap();
+        assert!(db.find_album("album-1").unwrap().is_none());
+        assert!(db.album_members("album-1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tags_are_hierarchical() {
+        let db = DatabaseDriver::open_in_memory().unwrap();
+        for (uuid, hash) in [("uuid-1", "a"), ("uuid-2", "b"), ("uuid-3", "c")] {
+            db.insert_asset(&make_asset(uuid, &hash.repeat(64)))
+                .unwrap();
+        }
+        db.set_tags_batch(&[
+            ("uuid-1".to_string(), vec!["Places|Japan|Kyoto".to_string()]),
+            ("uuid-2".to_string(), vec!["Places|Japan".to_string()]),
+            (
+                "uuid-3".to_string(),
+                vec!["Places|Japanese food".to_string()],
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            db.uuids_with_tag("Places|Japan").unwrap(),
+            vec!["uuid-1", "uuid-2"]
+        );
+        assert_eq!(db.list_tags().unwrap().len(), 3);
+        let japan = SearchFilter {
+            tags: vec!["Places|Japan".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(db.search(&japan).unwrap().len(), 2);
+        assert_eq!(db.tags_for("uuid-3").unwrap(), vec!["Places|Japanese food"]);
+    }
 }
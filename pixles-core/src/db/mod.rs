@@ -1,6 +1,8 @@
 pub mod driver;
 pub mod rows;
 pub mod schema;
+pub mod search;
 
 pub use driver::DatabaseDriver;
-pub use rows::{AssetRow, AssetStackRow, AssetTagRow, StackMemberRow};
+pub use rows::{AlbumRow, AssetRow, AssetStackRow, AssetTagRow, StackMemberRow};
+pub use search::{GeoBounds, SearchFilter};
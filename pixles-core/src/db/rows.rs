@@ -14,10 +14,19 @@ pub struct AssetRow {
     pub is_stack_hidden: bool,
     pub chromahash: Option<String>,
     pub dominant_color: Option<String>,
+    /// dHash of the pixels (see `pixles_media::image::phash`), bit-cast to
+    /// `i64` for SQLite.
+    pub perceptual_hash: Option<i64>,
     pub album_id: Option<String>,
     pub rating: i64,
     pub is_deleted: bool,
     pub deleted_at: Option<i64>,
+    pub original_filename: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub gps_lat: Option<f64>,
+    pub gps_lon: Option<f64>,
+    pub caption: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -47,3 +56,13 @@ pub struct AssetTagRow {
     pub uuid: String,
     pub tag: String,
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlbumRow {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub cover_asset_id: Option<String>,
+    pub created_at: i64,
+    pub modified_at: i64,
+}
@@ -1,8 +1,46 @@
-pub const SCHEMA_VERSION: u32 = 1;
+/// Version of the newest migration in [`MIGRATIONS`], stored in
+/// `PRAGMA user_version`.
+pub const SCHEMA_VERSION: u32 = 4;
 
-pub const DDL: &str = r#"
-PRAGMA journal_mode = WAL;
+/// Indexes older than this lack data only the sidecars can supply, so they
+/// are rebuilt from disk once migrated.
+pub const REINDEX_BELOW: u32 = 3;
+
+/// One step of the index schema. `sql` upgrades a database at
+/// `version - 1` to `version`.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
 
+/// Every schema step, oldest first. Append new steps here (never edit an
+/// applied one) and bump [`SCHEMA_VERSION`] to match.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        sql: DDL,
+    },
+    Migration {
+        version: 2,
+        description: "searchable metadata and full-text index",
+        sql: SEARCH_DDL,
+    },
+    Migration {
+        version: 3,
+        description: "albums with ordered many-to-many membership",
+        sql: ALBUMS_DDL,
+    },
+    Migration {
+        version: 4,
+        description: "perceptual hashes for near-duplicate detection",
+        sql: PERCEPTUAL_HASH_DDL,
+    },
+];
+
+/// Baseline schema (version 1).
+pub const DDL: &str = r#"
 CREATE TABLE IF NOT EXISTS assets (
     uuid              TEXT    PRIMARY KEY,
     asset_type        TEXT    NOT NULL,
@@ -63,3 +101,108 @@ CREATE INDEX IF NOT EXISTS idx_stack_members_stack  ON stack_members(stack_id);
 CREATE INDEX IF NOT EXISTS idx_stack_members_asset  ON stack_members(asset_id);
 CREATE INDEX IF NOT EXISTS idx_tags_tag          ON asset_tags(tag);
 "#;
+
+/// Promotes the searchable sidecar fields into columns and adds `assets_fts`,
+/// an FTS5 index over filenames, tags and captions. Its rowid is the
+/// `assets` rowid; triggers keep it in step with `assets` and `asset_tags`.
+///
+/// Existing rows get NULLs here; `library::migrate` repopulates them from
+/// the sidecars.
+pub const SEARCH_DDL: &str = r#"
+ALTER TABLE assets ADD COLUMN original_filename TEXT;
+ALTER TABLE assets ADD COLUMN camera_make       TEXT;
+ALTER TABLE assets ADD COLUMN camera_model      TEXT;
+ALTER TABLE assets ADD COLUMN gps_lat           REAL;
+ALTER TABLE assets ADD COLUMN gps_lon           REAL;
+ALTER TABLE assets ADD COLUMN caption           TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_assets_camera     ON assets(camera_make COLLATE NOCASE, camera_model COLLATE NOCASE);
+CREATE INDEX IF NOT EXISTS idx_assets_model      ON assets(camera_model COLLATE NOCASE);
+CREATE INDEX IF NOT EXISTS idx_assets_gps        ON assets(gps_lat, gps_lon);
+CREATE INDEX IF NOT EXISTS idx_assets_rating     ON assets(rating);
+CREATE INDEX IF NOT EXISTS idx_assets_type       ON assets(asset_type);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS assets_fts USING fts5(filename, tags, caption);
+
+INSERT INTO assets_fts (rowid, filename, tags, caption)
+SELECT a.rowid, a.original_filename,
+       (SELECT group_concat(t.tag, ' ') FROM asset_tags t WHERE t.uuid = a.uuid),
+       a.caption
+FROM assets a;
+
+CREATE TRIGGER IF NOT EXISTS assets_fts_insert AFTER INSERT ON assets BEGIN
+    INSERT INTO assets_fts (rowid, filename, tags, caption)
+    VALUES (new.rowid, new.original_filename,
+            (SELECT group_concat(tag, ' ') FROM asset_tags WHERE uuid = new.uuid),
+            new.caption);
+END;
+
+CREATE TRIGGER IF NOT EXISTS assets_fts_update AFTER UPDATE OF original_filename, caption ON assets BEGIN
+    UPDATE assets_fts SET filename = new.original_filename, caption = new.caption
+    WHERE rowid = new.rowid;
+END;
+
+CREATE TRIGGER IF NOT EXISTS assets_fts_delete AFTER DELETE ON assets BEGIN
+    DELETE FROM assets_fts WHERE rowid = old.rowid;
+END;
+
+CREATE TRIGGER IF NOT EXISTS asset_tags_fts_insert AFTER INSERT ON asset_tags BEGIN
+    UPDATE assets_fts
+    SET tags = (SELECT group_concat(tag, ' ') FROM asset_tags WHERE uuid = new.uuid)
+    WHERE rowid = (SELECT rowid FROM assets WHERE uuid = new.uuid);
+END;
+
+CREATE TRIGGER IF NOT EXISTS asset_tags_fts_delete AFTER DELETE ON asset_tags BEGIN
+    UPDATE assets_fts
+    SET tags = (SELECT group_concat(tag, ' ') FROM asset_tags WHERE uuid = old.uuid)
+    WHERE rowid = (SELECT rowid FROM assets WHERE uuid = old.uuid);
+END;
+"#;
+
+/// Albums and their ordered membership, mirroring the `albums/*.cbor` files.
+///
+/// The single `assets.album_id` of older libraries is left in place but no
+/// longer read; the rebuild that follows this step turns the `album_id` of
+/// each sidecar into an album of its own.
+pub const ALBUMS_DDL: &str = r#"
+CREATE TABLE IF NOT EXISTS albums (
+    id                TEXT    PRIMARY KEY,
+    name              TEXT    NOT NULL,
+    description       TEXT,
+    cover_asset_id    TEXT,
+    created_at        INTEGER NOT NULL,
+    modified_at       INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS album_assets (
+    album_id          TEXT    NOT NULL,
+    asset_id          TEXT    NOT NULL,
+    position          INTEGER NOT NULL,
+    added_at          INTEGER NOT NULL,
+    PRIMARY KEY (album_id, asset_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_album_assets_order ON album_assets(album_id, position);
+CREATE INDEX IF NOT EXISTS idx_album_assets_asset ON album_assets(asset_id);
+"#;
+
+/// A 64-bit dHash of each decodable image, stored as its two's-complement
+/// `INTEGER`. Like `chromahash` it is derived from the pixels rather than
+/// the sidecar: existing rows stay NULL until `pixles library thumbnails`
+/// backfills them.
+pub const PERCEPTUAL_HASH_DDL: &str = r#"
+ALTER TABLE assets ADD COLUMN perceptual_hash INTEGER;
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_consecutive() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as u32 + 1, "{}", migration.description);
+        }
+        assert_eq!(MIGRATIONS.last().unwrap().version, SCHEMA_VERSION);
+    }
+}
@@ -0,0 +1,180 @@
+use rusqlite::types::Value;
+
+/// Latitude/longitude rectangle in decimal degrees. A `west` greater than
+/// `east` wraps across the antimeridian.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoBounds {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+/// Criteria for [`DatabaseDriver::search`](crate::db::DatabaseDriver::search).
+/// Every field that is set must match; an empty filter returns the timeline.
+///
+/// `asset_type` and `stack_type` take the values stored in the index
+/// (`"photo"`, `"raw_jpeg"`, ...).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchFilter {
+    /// Full-text query over filenames, tags and captions. Each word is
+    /// matched as a prefix; all words must match.
+    pub text: Option<String>,
+    /// Inclusive lower bound on capture time (Unix seconds).
+    pub captured_after: Option<i64>,
+    /// Exclusive upper bound on capture time (Unix seconds).
+    pub captured_before: Option<i64>,
+    /// Case-insensitive exact match.
+    pub camera_make: Option<String>,
+    /// Case-insensitive exact match.
+    pub camera_model: Option<String>,
+    pub bounds: Option<GeoBounds>,
+    pub min_rating: Option<u8>,
+    pub asset_type: Option<String>,
+    pub stack_type: Option<String>,
+    /// Tags the asset must all carry. A hierarchical tag also matches the
+    /// tags nested below it, so `Places|Japan` finds `Places|Japan|Kyoto`.
+    pub tags: Vec<String>,
+    /// Also return stack members hidden behind their primary.
+    pub include_hidden: bool,
+    pub limit: Option<usize>,
+    pub offset: usize,
+}
+
+impl SearchFilter {
+    /// Builds the `WHERE` clause (against `assets`) and its parameters.
+    pub(crate) fn where_clause(&self) -> (String, Vec<Value>) {
+        let mut clauses = vec!["is_deleted = 0".to_string()];
+        let mut params: Vec<Value> = Vec::new();
+        // Numbers each `?` in `clause` after the parameters pushed so far.
+        let mut push = |clause: &str, values: Vec<Value>| {
+            let mut parts = clause.split('?');
+            let mut numbered = parts.next().unwrap_or_default().to_string();
+            for (part, value) in parts.zip(values) {
+                params.push(value);
+                numbered.push_str(&format!("?{}{part}", params.len()));
+            }
+            clauses.push(numbered);
+        };
+
+        if !self.include_hidden {
+            push("is_stack_hidden = 0", vec![]);
+        }
+        if let Some(query) = self.text.as_deref().and_then(fts_query) {
+            push(
+                "rowid IN (SELECT rowid FROM assets_fts WHERE assets_fts MATCH ?)",
+                vec![Value::Text(query)],
+            );
+        }
+        if let Some(after) = self.captured_after {
+            push(
+                "COALESCE(capture_utc, capture_timestamp) >= ?",
+                vec![after.into()],
+            );
+        }
+        if let Some(before) = self.captured_before {
+            push(
+                "COALESCE(capture_utc, capture_timestamp) < ?",
+                vec![before.into()],
+            );
+        }
+        if let Some(make) = &self.camera_make {
+            push(
+                "camera_make = ? COLLATE NOCASE",
+                vec![Value::Text(make.clone())],
+            );
+        }
+        if let Some(model) = &self.camera_model {
+            push(
+                "camera_model = ? COLLATE NOCASE",
+                vec![Value::Text(model.clone())],
+            );
+        }
+        if let Some(b) = self.bounds {
+            push(
+                "gps_lat BETWEEN ? AND ?",
+                vec![b.south.into(), b.north.into()],
+            );
+            if b.west <= b.east {
+                push(
+                    "gps_lon BETWEEN ? AND ?",
+                    vec![b.west.into(), b.east.into()],
+                );
+            } else {
+                push(
+                    "(gps_lon >= ? OR gps_lon <= ?)",
+                    vec![b.west.into(), b.east.into()],
+                );
+            }
+        }
+        if let Some(rating) = self.min_rating {
+            push("rating >= ?", vec![i64::from(rating).into()]);
+        }
+        if let Some(asset_type) = &self.asset_type {
+            push("asset_type = ?", vec![Value::Text(asset_type.clone())]);
+        }
+        if let Some(stack_type) = &self.stack_type {
+            push(
+                "EXISTS (SELECT 1 FROM stack_members m JOIN asset_stacks s ON s.id = m.stack_id
+                 WHERE m.asset_id = assets.uuid AND s.stack_type = ?)",
+                vec![Value::Text(stack_type.clone())],
+            );
+        }
+        for tag in &self.tags {
+            push(
+                "EXISTS (SELECT 1 FROM asset_tags t WHERE t.uuid = assets.uuid
+                 AND (t.tag = ? OR substr(t.tag, 1, length(?) + 1) = ? || '|'))",
+                vec![Value::Text(tag.clone()); 3],
+            );
+        }
+
+        (clauses.join(" AND "), params)
+    }
+}
+
+/// Turns free text into an FTS5 query: every word becomes a quoted prefix
+/// term, so user input can never be parsed as query syntax.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_query_quotes_words() {
+        assert_eq!(
+            fts_query("kyoto  IMG_12").as_deref(),
+            Some("\"kyoto\"* \"IMG_12\"*")
+        );
+        assert_eq!(
+            fts_query("say \"hi\" OR").as_deref(),
+            Some("\"say\"* \"\"\"hi\"\"\"* \"OR\"*")
+        );
+        assert_eq!(fts_query("   "), None);
+    }
+
+    #[test]
+    fn test_where_clause_numbers_params_in_order() {
+        let filter = SearchFilter {
+            camera_model: Some("X-T5".to_string()),
+            min_rating: Some(5),
+            ..Default::default()
+        };
+        let (sql, params) = filter.where_clause();
+        assert_eq!(
+            sql,
+            "is_deleted = 0 AND is_stack_hidden = 0 AND camera_model = ?1 COLLATE NOCASE \
+             AND rating >= ?2"
+        );
+        assert_eq!(
+            params,
+            vec![Value::Text("X-T5".to_string()), Value::Integer(5)]
+        );
+    }
+}
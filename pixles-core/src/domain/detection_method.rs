@@ -7,6 +7,12 @@ pub enum DetectionMethod {
     ContentIdentifier,
     Timecode,
     Manual,
+    /// Shared maker-note burst identifier (e.g. Apple `BurstUUID`).
+    BurstIdentifier,
+    /// Exposure-bias sequence or auto-bracket exposure mode.
+    ExposureSequence,
+    /// Video trailer embedded in the still image (Google/Samsung Motion Photo).
+    EmbeddedVideo,
 }
 
 #[cfg(test)]
@@ -20,6 +26,9 @@ mod tests {
             DetectionMethod::ContentIdentifier,
             DetectionMethod::Timecode,
             DetectionMethod::Manual,
+            DetectionMethod::BurstIdentifier,
+            DetectionMethod::ExposureSequence,
+            DetectionMethod::EmbeddedVideo,
         ];
         for variant in variants {
             let json = serde_json::to_string(&variant).unwrap();
@@ -0,0 +1,110 @@
+use pixles_media::image::presets::ImagePresets;
+use pixles_media::image::types::ImageOutputSettings;
+use serde::{Deserialize, Serialize};
+
+/// Rendered copy written alongside, or instead of, each exported original.
+/// Each preset is one of pixles-media's [`ImagePresets`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportPreset {
+    /// JPEG at quality 80, at most 1920 px on the long side.
+    WebJpeg,
+    /// JPEG at quality 90, full size.
+    FullJpeg,
+    /// JPEG at quality 85, fitted to 1200×630 for link previews.
+    SocialJpeg,
+    /// WebP at quality 90, full size.
+    Webp,
+    /// Lossless WebP, full size.
+    LosslessWebp,
+    /// 16-bit PNG, full size.
+    Png,
+}
+
+impl ExportPreset {
+    pub const ALL: [ExportPreset; 6] = [
+        ExportPreset::WebJpeg,
+        ExportPreset::FullJpeg,
+        ExportPreset::SocialJpeg,
+        ExportPreset::Webp,
+        ExportPreset::LosslessWebp,
+        ExportPreset::Png,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportPreset::WebJpeg => "web_jpeg",
+            ExportPreset::FullJpeg => "full_jpeg",
+            ExportPreset::SocialJpeg => "social_jpeg",
+            ExportPreset::Webp => "webp",
+            ExportPreset::LosslessWebp => "lossless_webp",
+            ExportPreset::Png => "png",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ExportPreset> {
+        Self::ALL.into_iter().find(|p| p.as_str() == name)
+    }
+
+    pub fn settings(&self) -> ImageOutputSettings {
+        match self {
+            ExportPreset::WebJpeg => ImagePresets::web_streaming_jpeg(),
+            ExportPreset::FullJpeg => ImagePresets::web_high_quality_jpeg(),
+            ExportPreset::SocialJpeg => ImagePresets::social_jpeg(),
+            ExportPreset::Webp => ImagePresets::web_high_quality_webp(),
+            ExportPreset::LosslessWebp => ImagePresets::lossless_webp(),
+            ExportPreset::Png => ImagePresets::archive_png(),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportPreset::WebJpeg | ExportPreset::FullJpeg | ExportPreset::SocialJpeg => "jpg",
+            ExportPreset::Webp | ExportPreset::LosslessWebp => "webp",
+            ExportPreset::Png => "png",
+        }
+    }
+}
+
+/// How an asset's curation travels with its exported files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportMetadata {
+    /// Files only.
+    #[default]
+    None,
+    /// An `.xmp` sidecar beside each file.
+    Sidecar,
+    /// An XMP packet written into each JPEG; other files get a sidecar.
+    Embedded,
+}
+
+/// What an export does when a file is already at the templated name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportCollision {
+    /// Add `-1`, `-2`, ... before the extension.
+    #[default]
+    Rename,
+    /// Leave the existing file and skip the asset.
+    Skip,
+    /// Replace the existing file.
+    Overwrite,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_names_match_serde() {
+        for preset in ExportPreset::ALL {
+            assert_eq!(
+                serde_json::to_string(&preset).unwrap(),
+                format!("\"{}\"", preset.as_str())
+            );
+            assert_eq!(ExportPreset::from_name(preset.as_str()), Some(preset));
+        }
+        assert_eq!(ExportPreset::from_name("tiff"), None);
+    }
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// Where an asset's GPS position came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpsSource {
+    /// The file's own metadata (EXIF, or the video container).
+    Exif,
+    /// Interpolated from a GPX/KML track log by capture time.
+    TrackLog,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serde_values() {
+        assert_eq!(serde_json::to_string(&GpsSource::Exif).unwrap(), "\"exif\"");
+        assert_eq!(
+            serde_json::to_string(&GpsSource::TrackLog).unwrap(),
+            "\"track_log\""
+        );
+    }
+}
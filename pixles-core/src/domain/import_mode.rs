@@ -1,10 +1,21 @@
 use serde::{Deserialize, Serialize};
 
+/// How an import brings originals into the library.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ImportMode {
+    /// Copy into `media/`, leaving the source untouched.
     Copy,
+    /// Copy into `media/`, then delete the source once it is indexed.
     Move,
+    /// Clone into `media/` as a copy-on-write reflink (btrfs, XFS, APFS),
+    /// falling back to a copy where the filesystem cannot clone.
+    Reflink,
+    /// Hard-link into `media/`. The source must be on the library's
+    /// filesystem; the two names then share the same data.
+    Hardlink,
+    /// Leave the original where it is and track it by path and hash.
+    Reference,
 }
 
 #[cfg(test)]
@@ -13,7 +24,13 @@ mod tests {
 
     #[test]
     fn test_serde_round_trip() {
-        for variant in [ImportMode::Copy, ImportMode::Move] {
+        for variant in [
+            ImportMode::Copy,
+            ImportMode::Move,
+            ImportMode::Reflink,
+            ImportMode::Hardlink,
+            ImportMode::Reference,
+        ] {
             let json = serde_json::to_string(&variant).unwrap();
             let roundtrip: ImportMode = serde_json::from_str(&json).unwrap();
             assert_eq!(variant, roundtrip);
@@ -30,5 +47,9 @@ mod tests {
             serde_json::to_string(&ImportMode::Move).unwrap(),
             "\"move\""
         );
+        assert_eq!(
+            serde_json::to_string(&ImportMode::Hardlink).unwrap(),
+            "\"hardlink\""
+        );
     }
 }
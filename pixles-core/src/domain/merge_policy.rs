@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// Which library's curation wins when a merge finds an asset in both.
+/// Applies to rating, tags, caption, label and pick; a field only one side
+/// has set is always taken from that side.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergePolicy {
+    /// Keep this library's values.
+    #[default]
+    Ours,
+    /// Take the other library's values.
+    Theirs,
+    /// Keep the higher rating and both sets of tags, and append their
+    /// caption to ours when it says something else.
+    Combine,
+}
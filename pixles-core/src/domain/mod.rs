@@ -1,11 +1,24 @@
 pub mod capture_tz_source;
 pub mod detection_method;
+pub mod export;
+pub mod gps_source;
 pub mod import_mode;
 pub mod member_role;
+pub mod merge_policy;
+pub mod pick;
 pub mod stack_type;
+pub mod tag;
+pub mod thumbnail_format;
+pub mod xmp_export;
 
 pub use capture_tz_source::CaptureTzSource;
 pub use detection_method::DetectionMethod;
+pub use export::{ExportCollision, ExportMetadata, ExportPreset};
+pub use gps_source::GpsSource;
 pub use import_mode::ImportMode;
 pub use member_role::MemberRole;
+pub use merge_policy::MergePolicy;
+pub use pick::Pick;
 pub use stack_type::StackType;
+pub use thumbnail_format::ThumbnailFormat;
+pub use xmp_export::{XmpExportTarget, XmpNaming};
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+/// Culling flag set in an editor (Lightroom's pick/reject flags).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pick {
+    Picked,
+    Rejected,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serde_values() {
+        assert_eq!(serde_json::to_string(&Pick::Picked).unwrap(), "\"picked\"");
+        assert_eq!(
+            serde_json::to_string(&Pick::Rejected).unwrap(),
+            "\"rejected\""
+        );
+    }
+}
@@ -0,0 +1,92 @@
+/// Separator between the levels of a hierarchical keyword, matching
+/// Lightroom's `lr:hierarchicalSubject` (`Places|Japan|Kyoto`).
+pub const TAG_SEPARATOR: char = '|';
+
+/// Trims every level and drops empty ones, so ` Places | Japan||Kyoto `
+/// becomes `Places|Japan|Kyoto`. Returns `None` if nothing is left.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let levels: Vec<&str> = tag
+        .split(TAG_SEPARATOR)
+        .map(str::trim)
+        .filter(|level| !level.is_empty())
+        .collect();
+    (!levels.is_empty()).then(|| levels.join("|"))
+}
+
+/// Whether `tag` is `ancestor` itself or nested anywhere below it.
+pub fn is_within(tag: &str, ancestor: &str) -> bool {
+    tag.strip_prefix(ancestor)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(TAG_SEPARATOR))
+}
+
+/// `tag` moved from under `from` to under `to`, or `None` if it is not
+/// within `from`. `Places|Japan|Kyoto` reparented from `Places|Japan` to
+/// `Travel|Japan` is `Travel|Japan|Kyoto`.
+pub fn reparent(tag: &str, from: &str, to: &str) -> Option<String> {
+    is_within(tag, from).then(|| format!("{to}{}", &tag[from.len()..]))
+}
+
+/// Turns embedded keywords into tags. Hierarchical paths are kept whole;
+/// a flat keyword is dropped when it already names a level of one of them,
+/// since Lightroom writes every level to `dc:subject` as well.
+pub fn tags_from_keywords(hierarchical: &[String], flat: &[String]) -> Vec<String> {
+    let paths: Vec<String> = hierarchical
+        .iter()
+        .filter_map(|k| normalize_tag(k))
+        .collect();
+    let flat = flat
+        .iter()
+        .filter_map(|k| normalize_tag(&k.replace(TAG_SEPARATOR, " ")))
+        .filter(|k| !paths.iter().any(|p| p.split(TAG_SEPARATOR).any(|l| l == k)));
+    dedup(paths.iter().cloned().chain(flat))
+}
+
+/// Removes repeats, keeping the first occurrence of each tag.
+pub fn dedup(tags: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags {
+        if !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(
+            normalize_tag(" Places | Japan||Kyoto ").as_deref(),
+            Some("Places|Japan|Kyoto")
+        );
+        assert_eq!(normalize_tag(" | "), None);
+    }
+
+    #[test]
+    fn test_is_within_and_reparent() {
+        assert!(is_within("Places|Japan|Kyoto", "Places|Japan"));
+        assert!(is_within("Places|Japan", "Places|Japan"));
+        assert!(!is_within("Places|Japanese", "Places|Japan"));
+        assert_eq!(
+            reparent("Places|Japan|Kyoto", "Places|Japan", "Travel|JP").as_deref(),
+            Some("Travel|JP|Kyoto")
+        );
+        assert_eq!(reparent("People", "Places", "Travel"), None);
+    }
+
+    #[test]
+    fn test_tags_from_keywords() {
+        let tags = tags_from_keywords(
+            &strings(&["Places|Japan|Kyoto", "Places|Japan|Kyoto"]),
+            &strings(&["Places", "Japan", "Kyoto", "shrine", " ", "a|b"]),
+        );
+        assert_eq!(tags, strings(&["Places|Japan|Kyoto", "shrine", "a b"]));
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+/// Encoding used for files under `index/thumbnails/`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThumbnailFormat {
+    #[default]
+    Webp,
+    Jpeg,
+}
+
+impl ThumbnailFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ThumbnailFormat::Webp => "webp",
+            ThumbnailFormat::Jpeg => "jpg",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serde_round_trip() {
+        for variant in [ThumbnailFormat::Webp, ThumbnailFormat::Jpeg] {
+            let json = serde_json::to_string(&variant).unwrap();
+            let roundtrip: ThumbnailFormat = serde_json::from_str(&json).unwrap();
+            assert_eq!(variant, roundtrip);
+        }
+    }
+
+    #[test]
+    fn test_serde_values() {
+        assert_eq!(
+            serde_json::to_string(&ThumbnailFormat::Webp).unwrap(),
+            "\"webp\""
+        );
+        assert_eq!(
+            serde_json::to_string(&ThumbnailFormat::Jpeg).unwrap(),
+            "\"jpeg\""
+        );
+    }
+}
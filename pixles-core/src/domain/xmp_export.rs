@@ -0,0 +1,54 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// How exported `.xmp` sidecars are named next to their original.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum XmpNaming {
+    /// `IMG_1.xmp`, as Lightroom, Capture One and Bridge expect.
+    #[default]
+    Stem,
+    /// `IMG_1.CR2.xmp`, as darktable expects.
+    Filename,
+}
+
+impl XmpNaming {
+    pub fn sidecar_for(&self, original: &Path) -> PathBuf {
+        match self {
+            XmpNaming::Stem => original.with_extension("xmp"),
+            XmpNaming::Filename => {
+                let mut name = original.as_os_str().to_owned();
+                name.push(".xmp");
+                PathBuf::from(name)
+            }
+        }
+    }
+}
+
+/// Export directory kept up to date with `.xmp` sidecars as assets are
+/// curated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct XmpExportTarget {
+    pub dir: PathBuf,
+    #[serde(default)]
+    pub naming: XmpNaming,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sidecar_names() {
+        let original = Path::new("/export/IMG_1.CR2");
+        assert_eq!(
+            XmpNaming::Stem.sidecar_for(original),
+            Path::new("/export/IMG_1.xmp")
+        );
+        assert_eq!(
+            XmpNaming::Filename.sidecar_for(original),
+            Path::new("/export/IMG_1.CR2.xmp")
+        );
+    }
+}
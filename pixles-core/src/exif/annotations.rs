@@ -0,0 +1,262 @@
+//! Curation metadata (rating, color label, pick flag, caption, keywords)
+//! read from a file's embedded XMP and IPTC, or from a paired `.xmp`
+//! sidecar written by Lightroom, darktable or a similar editor.
+//!
+//! # Precedence
+//!
+//! Sources are merged field by field. The first one that sets a field wins:
+//!
+//! 1. the paired `.xmp` sidecar, where editors record culling done after
+//!    capture;
+//! 2. the XMP packet embedded in the file;
+//! 3. the embedded IPTC-IIM block.
+//!
+//! Keywords follow the same rule, except that embedded XMP and IPTC
+//! keywords are combined, as MWG asks writers to keep them in sync.
+//! A sidecar that lists any keywords replaces the embedded ones, so
+//! keywords removed in the editor stay removed.
+
+use std::fs;
+use std::path::Path;
+
+use pixles_media::image::metadata::iptc::IptcData;
+use pixles_media::metadata::xmp::XmpData;
+
+use crate::domain::Pick;
+use crate::domain::tag::tags_from_keywords;
+
+/// Color label names for darktable's `colorlabels` indices, matching the
+/// names Lightroom writes to `xmp:Label`.
+const DARKTABLE_LABELS: [&str; 5] = ["Red", "Yellow", "Green", "Blue", "Purple"];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Annotations {
+    /// 0 (unrated) to 5 stars.
+    pub rating: Option<u8>,
+    pub label: Option<String>,
+    pub pick: Option<Pick>,
+    pub caption: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl Annotations {
+    /// Reads the annotations of one XMP packet.
+    ///
+    /// `xmp:Rating` of -1 is how Lightroom and darktable store a rejected
+    /// photo; it becomes an unrated [`Pick::Rejected`].
+    pub fn from_xmp(xmp: &XmpData) -> Annotations {
+        let basic = xmp.basic.as_ref();
+        let rating = basic.and_then(|b| b.rating);
+        let pick = match xmp.dynamic_media.as_ref().and_then(|dm| dm.pick) {
+            Some(1) => Some(Pick::Picked),
+            Some(-1) => Some(Pick::Rejected),
+            _ => (rating == Some(-1)).then_some(Pick::Rejected),
+        };
+        let label = basic.and_then(|b| b.label.clone()).or_else(|| {
+            let darktable = xmp.darktable.as_ref()?;
+            let first = *darktable.color_labels.first()?;
+            DARKTABLE_LABELS
+                .get(usize::from(first))
+                .map(|l| l.to_string())
+        });
+        let caption = xmp.dublin_core.as_ref().and_then(|dc| {
+            [&dc.description, &dc.title]
+                .into_iter()
+                .flatten()
+                .find_map(|alt| {
+                    alt.get("x-default")
+                        .or_else(|| alt.values().next())
+                        .map(|text| text.trim().to_string())
+                        .filter(|text| !text.is_empty())
+                })
+        });
+
+        Annotations {
+            rating: rating.map(|r| r.clamp(0, 5) as u8),
+            label,
+            pick,
+            caption,
+            tags: keyword_tags(Some(xmp), &[]),
+        }
+    }
+
+    /// Fills every field `self` leaves unset from `fallback`.
+    pub fn or(self, fallback: Annotations) -> Annotations {
+        Annotations {
+            rating: self.rating.or(fallback.rating),
+            label: self.label.or(fallback.label),
+            pick: self.pick.or(fallback.pick),
+            caption: self.caption.or(fallback.caption),
+            tags: if self.tags.is_empty() {
+                fallback.tags
+            } else {
+                self.tags
+            },
+        }
+    }
+}
+
+/// Reads the annotations embedded in a still image. Unreadable files and
+/// malformed packets yield none.
+pub fn read_embedded(path: &Path) -> Annotations {
+    let Ok(file) = fs::File::open(path) else {
+        return Annotations::default();
+    };
+    // SAFETY: read-only map, dropped before returning.
+    let Ok(mmap) = (unsafe { memmap2::Mmap::map(&file) }) else {
+        return Annotations::default();
+    };
+    embedded_from_bytes(&mmap)
+}
+
+/// Reads a standalone `.xmp` sidecar. Returns `None`, after logging why,
+/// if it cannot be read or parsed.
+pub fn read_xmp_sidecar(path: &Path) -> Option<Annotations> {
+    let parsed = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|xml| XmpData::parse(&xml).map_err(|e| e.to_string()));
+    match parsed {
+        Ok(xmp) => Some(Annotations::from_xmp(&xmp)),
+        Err(e) => {
+            log::warn!("read_xmp_sidecar: ignoring {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+fn embedded_from_bytes(bytes: &[u8]) -> Annotations {
+    let xmp = XmpData::find_packet(bytes).and_then(|packet| {
+        XmpData::parse(packet)
+            .inspect_err(|e| log::debug!("read_embedded: {e}"))
+            .ok()
+    });
+    let iptc = IptcData::find_block(bytes)
+        .and_then(IptcData::from_iim)
+        .unwrap_or_default();
+
+    let from_iptc = Annotations {
+        caption: iptc.caption.filter(|c| !c.trim().is_empty()),
+        ..Default::default()
+    };
+    let annotations = match &xmp {
+        Some(xmp) => Annotations::from_xmp(xmp).or(from_iptc),
+        None => from_iptc,
+    };
+    Annotations {
+        tags: keyword_tags(xmp.as_ref(), &iptc.keywords),
+        ..annotations
+    }
+}
+
+/// `lr:hierarchicalSubject` paths, then `dc:subject` and `extra` keywords
+/// not already covered by a path.
+fn keyword_tags(xmp: Option<&XmpData>, extra: &[String]) -> Vec<String> {
+    let hierarchical = xmp
+        .and_then(|x| x.lightroom.as_ref())
+        .map(|lr| lr.hierarchical_subject.clone())
+        .unwrap_or_default();
+    let flat: Vec<String> = xmp
+        .and_then(|x| x.dublin_core.as_ref())
+        .map(|dc| dc.subject.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .chain(extra.iter().cloned())
+        .collect();
+    tags_from_keywords(&hierarchical, &flat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(description: &str) -> String {
+        format!(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+             <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+              <rdf:Description rdf:about=""
+                  xmlns:dc="http://purl.org/dc/elements/1.1/"
+                  xmlns:lr="http://ns.adobe.com/lightroom/1.0/"
+                  xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+                  xmlns:darktable="http://darktable.sf.net/">
+               {description}
+              </rdf:Description>
+             </rdf:RDF>
+            </x:xmpmeta>"#
+        )
+    }
+
+    fn parse(description: &str) -> Annotations {
+        Annotations::from_xmp(&XmpData::parse(&packet(description)).unwrap())
+    }
+
+    #[test]
+    fn test_embedded_keywords_from_xmp_and_iptc() {
+        let xmp = packet(
+            "<dc:subject><rdf:Bag><rdf:li>Kyoto</rdf:li><rdf:li>shrine</rdf:li></rdf:Bag></dc:subject>
+             <lr:hierarchicalSubject><rdf:Bag><rdf:li>Places|Japan|Kyoto</rdf:li></rdf:Bag></lr:hierarchicalSubject>",
+        );
+        let mut bytes = b"\xff\xd8".to_vec();
+        bytes.extend_from_slice(xmp.as_bytes());
+        // APP13 with one IPTC keyword: 8BIM 0x0404, empty name, size 10.
+        bytes.extend_from_slice(b"Photoshop 3.0\x008BIM\x04\x04\0\0\0\0\0\x0a");
+        bytes.extend_from_slice(b"\x1c\x02\x19\x00\x05torii");
+
+        assert_eq!(
+            embedded_from_bytes(&bytes).tags,
+            vec!["Places|Japan|Kyoto", "shrine", "torii"]
+        );
+        assert_eq!(
+            embedded_from_bytes(b"\xff\xd8\xff\xd9"),
+            Annotations::default()
+        );
+    }
+
+    #[test]
+    fn test_from_xmp_reads_culling() {
+        let lightroom = parse(
+            r#"<xmp:Rating>4</xmp:Rating><xmp:Label>Green</xmp:Label>
+               <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Torii</rdf:li></rdf:Alt></dc:title>
+               <dc:description><rdf:Alt><rdf:li xml:lang="x-default">Fushimi Inari at dawn</rdf:li></rdf:Alt></dc:description>"#,
+        );
+        assert_eq!(lightroom.rating, Some(4));
+        assert_eq!(lightroom.label.as_deref(), Some("Green"));
+        assert_eq!(lightroom.caption.as_deref(), Some("Fushimi Inari at dawn"));
+        assert_eq!(lightroom.pick, None);
+
+        let darktable = parse(
+            r#"<xmp:Rating>-1</xmp:Rating>
+               <darktable:colorlabels><rdf:Seq><rdf:li>3</rdf:li></rdf:Seq></darktable:colorlabels>
+               <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Torii</rdf:li></rdf:Alt></dc:title>"#,
+        );
+        assert_eq!(darktable.rating, Some(0));
+        assert_eq!(darktable.pick, Some(Pick::Rejected));
+        assert_eq!(darktable.label.as_deref(), Some("Blue"));
+        assert_eq!(darktable.caption.as_deref(), Some("Torii"));
+    }
+
+    #[test]
+    fn test_sidecar_takes_precedence_per_field() {
+        let embedded = Annotations {
+            rating: Some(2),
+            caption: Some("in-camera".to_string()),
+            tags: vec!["camera".to_string()],
+            ..Default::default()
+        };
+        let sidecar = Annotations {
+            rating: Some(5),
+            pick: Some(Pick::Picked),
+            ..Default::default()
+        };
+        let merged = sidecar.or(embedded);
+        assert_eq!(merged.rating, Some(5));
+        assert_eq!(merged.pick, Some(Pick::Picked));
+        assert_eq!(merged.caption.as_deref(), Some("in-camera"));
+        assert_eq!(merged.tags, vec!["camera"]);
+
+        let retagged = Annotations {
+            tags: vec!["edited".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(retagged.or(merged).tags, vec!["edited"]);
+    }
+}
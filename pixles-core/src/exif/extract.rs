@@ -1,5 +1,6 @@
 use chrono::NaiveDateTime;
 use exif::{In, Reader, Tag, Value};
+use pixles_media::video::VideoMetadata;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
@@ -14,8 +15,30 @@ pub struct ExifExtract {
     pub model: Option<String>,
     pub width: Option<u32>,
     pub height: Option<u32>,
-    pub duration_ms: Option<u64>, // For video; not from EXIF — always None from this extractor
+    pub duration_ms: Option<u64>, // For video; read from the container, not EXIF
     pub content_identifier: Option<String>, // Apple Live Photo UUID
+    pub sub_sec_time_original: Option<u32>, // Milliseconds past `date_time_original`
+    pub exposure_bias: Option<f64>, // EV
+    pub exposure_time: Option<f64>, // Seconds
+    pub f_number: Option<f64>,
+    pub iso: Option<u32>,
+    pub focal_length: Option<f64>,      // mm
+    pub exposure_mode: Option<u16>,     // 0 = auto, 1 = manual, 2 = auto bracket
+    pub burst_id: Option<String>,       // Maker-note burst identifier (e.g. Apple BurstUUID)
+    pub gps_img_direction: Option<f64>, // Compass heading of the camera, degrees
+    /// Capture instant as Unix seconds, for files that record it in UTC
+    /// without a local time (video container dates); `date_time_original`
+    /// is then `None`.
+    pub capture_utc: Option<i64>,
+}
+
+impl ExifExtract {
+    /// `date_time_original` with `sub_sec_time_original` applied.
+    pub fn capture_time_precise(&self) -> Option<NaiveDateTime> {
+        let dt = self.date_time_original?;
+        let ms = self.sub_sec_time_original.unwrap_or(0);
+        Some(dt + chrono::TimeDelta::milliseconds(i64::from(ms)))
+    }
 }
 
 pub fn extract_exif(path: &Path) -> Result<ExifExtract, Box<dyn std::error::Error + Send + Sync>> {
@@ -25,19 +48,11 @@ pub fn extract_exif(path: &Path) -> Result<ExifExtract, Box<dyn std::error::Erro
     let exif = match Reader::new().read_from_container(&mut reader) {
         Ok(e) => e,
         Err(_) => {
-            // Not a valid EXIF container — return all-None result
-            return Ok(ExifExtract {
-                date_time_original: None,
-                offset_time_original: None,
-                gps_lat: None,
-                gps_lon: None,
-                make: None,
-                model: None,
-                width: None,
-                height: None,
-                duration_ms: None,
-                content_identifier: None,
-            });
+            // Not a valid EXIF container — videos carry their metadata in the
+            // container itself; anything else gets an all-None result
+            return Ok(VideoMetadata::from_path(path)
+                .map(|video| from_video(&video))
+                .unwrap_or_default());
         }
     };
 
@@ -153,6 +168,42 @@ pub fn extract_exif(path: &Path) -> Result<ExifExtract, Box<dyn std::error::Erro
             _ => None,
         });
 
+    // SubSecTimeOriginal — fractional seconds as decimal digits, e.g. "12" = 0.12 s
+    let sub_sec_time_original = exif
+        .get_field(Tag::SubSecTimeOriginal, In::PRIMARY)
+        .and_then(|field| match field.value {
+            Value::Ascii(ref v) if !v.is_empty() => parse_sub_sec(&v[0]),
+            _ => None,
+        });
+
+    // Exposure parameters (used for bracket/burst/panorama detection)
+    let exposure_bias = exif
+        .get_field(Tag::ExposureBiasValue, In::PRIMARY)
+        .and_then(|field| match field.value {
+            Value::SRational(ref v) if !v.is_empty() => Some(v[0].to_f64()),
+            Value::Rational(ref v) if !v.is_empty() => Some(v[0].to_f64()),
+            _ => None,
+        });
+    let exposure_time = rational_field(&exif, Tag::ExposureTime);
+    let f_number = rational_field(&exif, Tag::FNumber);
+    let focal_length = rational_field(&exif, Tag::FocalLength);
+    let iso = exif
+        .get_field(Tag::PhotographicSensitivity, In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0));
+    let exposure_mode = exif
+        .get_field(Tag::ExposureMode, In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+        .and_then(|v| u16::try_from(v).ok());
+    let gps_img_direction = rational_field(&exif, Tag::GPSImgDirection);
+
+    // Burst identifier from the maker note, where the vendor records one
+    let burst_id = exif
+        .get_field(Tag::MakerNote, In::PRIMARY)
+        .and_then(|field| match field.value {
+            Value::Undefined(ref bytes, _) => apple_burst_uuid(bytes),
+            _ => None,
+        });
+
     // content_identifier — Apple Live Photo UUID (byte search)
     let content_identifier = extract_content_identifier(path);
 
@@ -167,9 +218,126 @@ pub fn extract_exif(path: &Path) -> Result<ExifExtract, Box<dyn std::error::Erro
         height,
         duration_ms: None,
         content_identifier,
+        sub_sec_time_original,
+        exposure_bias,
+        exposure_time,
+        f_number,
+        iso,
+        focal_length,
+        exposure_mode,
+        burst_id,
+        gps_img_direction,
+        capture_utc: None,
     })
 }
 
+/// Maps container-level video metadata onto the EXIF fields the importer uses.
+fn from_video(video: &VideoMetadata) -> ExifExtract {
+    // Only Apple's `creationdate` says which zone the clip was shot in; other
+    // container dates are plain UTC, so the zone is left to the GPS position.
+    let (local, utc) = match video.creation_time {
+        Some(t) if video.creation_time_is_local => (Some(t), None),
+        Some(t) => (None, Some(t.timestamp())),
+        None => (None, None),
+    };
+    ExifExtract {
+        date_time_original: local.map(|t| t.naive_local()),
+        offset_time_original: local.map(|t| t.offset().to_string()),
+        capture_utc: utc,
+        gps_lat: video.location.as_ref().map(|l| l.latitude),
+        gps_lon: video.location.as_ref().map(|l| l.longitude),
+        width: video.width,
+        height: video.height,
+        duration_ms: video.duration_ms,
+        content_identifier: video.content_identifier.clone(),
+        ..Default::default()
+    }
+}
+
+fn rational_field(exif: &exif::Exif, tag: Tag) -> Option<f64> {
+    exif.get_field(tag, In::PRIMARY)
+        .and_then(|field| match field.value {
+            Value::Rational(ref v) if !v.is_empty() => Some(v[0].to_f64()),
+            _ => None,
+        })
+        .filter(|v| v.is_finite())
+}
+
+/// Parses SubSecTime digits into milliseconds ("5" → 500, "123456" → 123).
+fn parse_sub_sec(raw: &[u8]) -> Option<u32> {
+    let digits: Vec<u8> = raw
+        .iter()
+        .copied()
+        .take_while(u8::is_ascii_digit)
+        .take(3)
+        .collect();
+    if digits.is_empty() {
+        return None;
+    }
+    let mut ms = 0u32;
+    for i in 0..3 {
+        ms = ms * 10 + digits.get(i).map_or(0, |d| u32::from(d - b'0'));
+    }
+    Some(ms)
+}
+
+/// Reads tag 0x000b (BurstUUID) from an Apple iOS maker note.
+///
+/// Layout: `"Apple iOS\0"`, a 2-byte version, a 2-byte byte-order mark, then a
+/// classic IFD at offset 14. Value offsets are relative to the maker note start.
+fn apple_burst_uuid(note: &[u8]) -> Option<String> {
+    const HEADER: &[u8] = b"Apple iOS\0";
+    const IFD_START: usize = 14;
+    const BURST_UUID_TAG: u16 = 0x000b;
+    const ASCII_TYPE: u16 = 2;
+
+    if !note.starts_with(HEADER) || note.len() < IFD_START + 2 {
+        return None;
+    }
+    let big_endian = match &note[12..14] {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+    let u16_at = |pos: usize| -> Option<u16> {
+        let b: [u8; 2] = note.get(pos..pos + 2)?.try_into().ok()?;
+        Some(if big_endian {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        })
+    };
+    let u32_at = |pos: usize| -> Option<u32> {
+        let b: [u8; 4] = note.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    };
+
+    let count = u16_at(IFD_START)? as usize;
+    for i in 0..count {
+        let entry = IFD_START + 2 + i * 12;
+        if u16_at(entry)? != BURST_UUID_TAG || u16_at(entry + 2)? != ASCII_TYPE {
+            continue;
+        }
+        let len = u32_at(entry + 4)? as usize;
+        let value = if len <= 4 {
+            note.get(entry + 8..entry + 8 + len)?
+        } else {
+            let offset = u32_at(entry + 8)? as usize;
+            note.get(offset..offset.checked_add(len)?)?
+        };
+        let id = String::from_utf8_lossy(value)
+            .trim_end_matches('\0')
+            .trim()
+            .to_string();
+        return (!id.is_empty()).then_some(id);
+    }
+    None
+}
+
 fn strip_quotes(s: &str) -> String {
     let s = s.trim();
     if s.starts_with('"') && s.ends_with('"') && s.len() >= 2 {
@@ -224,6 +392,36 @@ fn is_uuid_format(s: &str) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_from_video_keeps_local_offset() {
+        let video = VideoMetadata {
+            creation_time: chrono::DateTime::parse_from_rfc3339("2024-05-01T12:34:56+02:00").ok(),
+            creation_time_is_local: true,
+            duration_ms: Some(3000),
+            ..Default::default()
+        };
+        let extract = from_video(&video);
+        assert_eq!(
+            extract.date_time_original.unwrap().to_string(),
+            "2024-05-01 12:34:56"
+        );
+        assert_eq!(extract.offset_time_original.as_deref(), Some("+02:00"));
+        assert_eq!(extract.capture_utc, None);
+        assert_eq!(extract.duration_ms, Some(3000));
+    }
+
+    #[test]
+    fn test_from_video_utc_date_has_no_offset() {
+        let video = VideoMetadata {
+            creation_time: chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00+00:00").ok(),
+            ..Default::default()
+        };
+        let extract = from_video(&video);
+        assert_eq!(extract.capture_utc, Some(1_704_067_200));
+        assert_eq!(extract.date_time_original, None);
+        assert_eq!(extract.offset_time_original, None);
+    }
+
     #[test]
     fn test_is_uuid_format_valid() {
         assert!(is_uuid_format("550e8400-e29b-41d4-a716-446655440000"));
@@ -257,6 +455,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_sub_sec() {
+        assert_eq!(parse_sub_sec(b"5"), Some(500));
+        assert_eq!(parse_sub_sec(b"12"), Some(120));
+        assert_eq!(parse_sub_sec(b"123456"), Some(123));
+        assert_eq!(parse_sub_sec(b"  "), None);
+    }
+
+    #[test]
+    fn test_capture_time_precise_adds_sub_seconds() {
+        let extract = ExifExtract {
+            date_time_original: NaiveDateTime::parse_from_str(
+                "2024:07:15 10:00:00",
+                "%Y:%m:%d %H:%M:%S",
+            )
+            .ok(),
+            sub_sec_time_original: Some(250),
+            ..Default::default()
+        };
+        let precise = extract.capture_time_precise().unwrap();
+        assert_eq!(precise.format("%H:%M:%S%.3f").to_string(), "10:00:00.250");
+    }
+
+    #[test]
+    fn test_apple_burst_uuid() {
+        let burst = b"6A2B1C3D-0000-4000-8000-00000000ABCD\0";
+        let mut note = b"Apple iOS\0".to_vec();
+        note.extend_from_slice(&[0x00, 0x01]);
+        note.extend_from_slice(b"MM");
+        note.extend_from_slice(&1u16.to_be_bytes()); // one entry
+        let value_offset = (note.len() + 12 + 4) as u32;
+        note.extend_from_slice(&0x000bu16.to_be_bytes());
+        note.extend_from_slice(&2u16.to_be_bytes());
+        note.extend_from_slice(&(burst.len() as u32).to_be_bytes());
+        note.extend_from_slice(&value_offset.to_be_bytes());
+        note.extend_from_slice(&0u32.to_be_bytes()); // next IFD
+        note.extend_from_slice(burst);
+
+        assert_eq!(
+            apple_burst_uuid(&note).as_deref(),
+            Some("6A2B1C3D-0000-4000-8000-00000000ABCD")
+        );
+        assert_eq!(apple_burst_uuid(b"Nikon\0"), None);
+    }
+
     #[test]
     fn test_extract_exif_nonexistent_file_returns_io_error() {
         let result = extract_exif(Path::new("/nonexistent/path/to/file.jpg"));
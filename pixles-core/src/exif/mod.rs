@@ -1,5 +1,7 @@
+pub mod annotations;
 pub mod extract;
 pub mod timezone;
 
+pub use annotations::{Annotations, read_embedded, read_xmp_sidecar};
 pub use extract::{ExifExtract, extract_exif};
-pub use timezone::{TimezoneResolution, resolve_timezone};
+pub use timezone::{TZ_DB_VERSION, TimezoneResolution, resolve_gps_timezone, resolve_timezone};
@@ -1,6 +1,14 @@
+use std::sync::OnceLock;
+
 use crate::domain::CaptureTzSource;
 use crate::exif::ExifExtract;
-use chrono::{FixedOffset, TimeZone};
+use chrono::{DateTime, FixedOffset, MappedLocalTime, NaiveDateTime, Offset, TimeDelta, TimeZone};
+use chrono_tz::Tz;
+use tzf_rs::DefaultFinder;
+
+/// Release of the IANA time zone database whose rules are applied to
+/// GPS-derived zones, recorded as `tz_db_version`.
+pub const TZ_DB_VERSION: &str = chrono_tz::IANA_TZDB_VERSION;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct TimezoneResolution {
@@ -32,17 +40,16 @@ pub fn resolve_timezone(extract: &ExifExtract) -> TimezoneResolution {
         };
     }
 
+    // Case 1b: only a UTC instant (video container dates)
+    if let Some(utc) = extract.capture_utc {
+        return resolve_utc_capture(utc, extract.gps_lat.zip(extract.gps_lon));
+    }
+
     // Case 2: GPS coordinates present → offline timezone lookup
     if let (Some(lat), Some(lon)) = (extract.gps_lat, extract.gps_lon)
-        && let Some((tz_name, tz_db_ver)) = lookup_timezone(lat, lon)
+        && let Some(resolution) = resolve_gps_timezone(extract.date_time_original, lat, lon)
     {
-        return TimezoneResolution {
-            capture_timestamp,
-            capture_utc: None, // Would need chrono-tz to apply IANA tz; leave as None
-            capture_tz: Some(tz_name),
-            capture_tz_source: Some(CaptureTzSource::GpsLookup),
-            tz_db_version: Some(tz_db_ver),
-        };
+        return resolution;
     }
 
     // Case 3: Floating
@@ -55,6 +62,85 @@ pub fn resolve_timezone(extract: &ExifExtract) -> TimezoneResolution {
     }
 }
 
+/// Case 2 of [`resolve_timezone`] on its own: the IANA zone at a GPS
+/// position, with its rules applied to the wall-clock time `local`.
+///
+/// `None` when no zone is found for the position. A zone
+/// the bundled rules don't know yet is still recorded, without a
+/// `capture_utc`.
+pub fn resolve_gps_timezone(
+    local: Option<NaiveDateTime>,
+    lat: f64,
+    lon: f64,
+) -> Option<TimezoneResolution> {
+    let tz_name = lookup_timezone(lat, lon)?;
+    let capture_utc = match tz_name.parse::<Tz>() {
+        Ok(tz) => local.and_then(|local| utc_from_local(tz, local)),
+        Err(_) => {
+            log::debug!("no tz rules for {tz_name} in tzdb {TZ_DB_VERSION}");
+            None
+        }
+    };
+    Some(TimezoneResolution {
+        capture_timestamp: local.map(|dt| dt.and_utc().timestamp()),
+        capture_utc,
+        capture_tz: Some(tz_name),
+        capture_tz_source: Some(CaptureTzSource::GpsLookup),
+        tz_db_version: Some(TZ_DB_VERSION.to_string()),
+    })
+}
+
+/// Case 1b of [`resolve_timezone`]: a capture recorded as a UTC instant.
+///
+/// The wall-clock time is read in the zone at `position` when one is found.
+/// Otherwise the zone stays unknown and the UTC reading stands in for the
+/// wall-clock time.
+fn resolve_utc_capture(utc: i64, position: Option<(f64, f64)>) -> TimezoneResolution {
+    let zoned = position
+        .and_then(|(lat, lon)| lookup_timezone(lat, lon))
+        .and_then(|name| Some((name.parse::<Tz>().ok()?, name)))
+        .and_then(|(tz, name)| {
+            let local = DateTime::from_timestamp(utc, 0)?.with_timezone(&tz);
+            Some((local.naive_local().and_utc().timestamp(), name))
+        });
+    match zoned {
+        Some((capture_timestamp, name)) => TimezoneResolution {
+            capture_timestamp: Some(capture_timestamp),
+            capture_utc: Some(utc),
+            capture_tz: Some(name),
+            capture_tz_source: Some(CaptureTzSource::GpsLookup),
+            tz_db_version: Some(TZ_DB_VERSION.to_string()),
+        },
+        None => TimezoneResolution {
+            capture_timestamp: Some(utc),
+            capture_utc: Some(utc),
+            capture_tz: None,
+            capture_tz_source: None,
+            tz_db_version: None,
+        },
+    }
+}
+
+/// The instant at which clocks in `tz` read `local`.
+///
+/// A time that occurs twice, when clocks go back, is taken as the first
+/// occurrence. A time that never occurs, when clocks go forward, is read
+/// with the offset from before the change: a camera set by hand usually
+/// hasn't been moved forward yet.
+fn utc_from_local(tz: Tz, local: NaiveDateTime) -> Option<i64> {
+    match tz.from_local_datetime(&local) {
+        MappedLocalTime::Single(t) => Some(t.timestamp()),
+        MappedLocalTime::Ambiguous(earliest, _) => Some(earliest.timestamp()),
+        MappedLocalTime::None => {
+            let day_before = local.checked_sub_signed(TimeDelta::days(1))?;
+            let offset = tz.offset_from_utc_datetime(&day_before).fix();
+            let utc =
+                local.checked_sub_signed(TimeDelta::seconds(offset.local_minus_utc().into()))?;
+            Some(utc.and_utc().timestamp())
+        }
+    }
+}
+
 fn parse_offset(s: &str) -> Option<FixedOffset> {
     // Parse "+HH:MM" or "-HH:MM"
     let s = s.trim();
@@ -72,15 +158,15 @@ fn parse_offset(s: &str) -> Option<FixedOffset> {
     FixedOffset::east_opt(total_secs)
 }
 
-fn lookup_timezone(lat: f64, lon: f64) -> Option<(String, String)> {
-    use tzf_rs::DefaultFinder;
-    let finder = DefaultFinder::new();
+fn lookup_timezone(lat: f64, lon: f64) -> Option<String> {
+    // Loading the zone boundaries takes a while; do it once per process.
+    static FINDER: OnceLock<DefaultFinder> = OnceLock::new();
+    let finder = FINDER.get_or_init(DefaultFinder::new);
     let tz_name = finder.get_tz_name(lon, lat); // tzf-rs takes (lon, lat)
     if tz_name.is_empty() {
         return None;
     }
-    let tz_db_version = finder.data_version().to_string();
-    Some((tz_name.to_string(), tz_db_version))
+    Some(tz_name.to_string())
 }
 
 #[cfg(test)]
@@ -88,7 +174,6 @@ mod tests {
     use super::*;
     use crate::domain::CaptureTzSource;
     use crate::exif::ExifExtract;
-    use chrono::NaiveDateTime;
 
     fn extract_with_offset(dt: &str, offset: &str) -> ExifExtract {
         ExifExtract {
@@ -102,6 +187,7 @@ mod tests {
             height: None,
             duration_ms: None,
             content_identifier: None,
+            ..Default::default()
         }
     }
 
@@ -117,6 +203,7 @@ mod tests {
             height: None,
             duration_ms: None,
             content_identifier: None,
+            ..Default::default()
         }
     }
 
@@ -132,6 +219,7 @@ mod tests {
             height: None,
             duration_ms: None,
             content_identifier: None,
+            ..Default::default()
         }
     }
 
@@ -175,7 +263,81 @@ mod tests {
             tz.contains("New_York") || tz.contains("America"),
             "Expected NYC timezone, got: {tz}"
         );
-        assert!(result.tz_db_version.is_some());
+        assert_eq!(result.tz_db_version.as_deref(), Some(TZ_DB_VERSION));
+        // 10:30 EDT = 14:30 UTC
+        assert_eq!(
+            result.capture_utc.unwrap(),
+            result.capture_timestamp.unwrap() + 4 * 3600
+        );
+    }
+
+    #[test]
+    fn test_case2_applies_standard_time_in_winter() {
+        let extract = extract_with_gps("2024:01:15 10:30:00", 40.7128, -74.0060);
+        let result = resolve_timezone(&extract);
+        // 10:30 EST = 15:30 UTC
+        assert_eq!(
+            result.capture_utc.unwrap(),
+            result.capture_timestamp.unwrap() + 5 * 3600
+        );
+    }
+
+    #[test]
+    fn test_case2_time_skipped_by_dst_uses_offset_before_change() {
+        // Clocks in New York jumped from 02:00 to 03:00 EST→EDT.
+        let extract = extract_with_gps("2024:03:10 02:30:00", 40.7128, -74.0060);
+        let result = resolve_timezone(&extract);
+        assert_eq!(
+            result.capture_utc.unwrap(),
+            result.capture_timestamp.unwrap() + 5 * 3600
+        );
+    }
+
+    #[test]
+    fn test_case2_time_repeated_by_dst_uses_first_occurrence() {
+        // 01:30 happened twice in New York, first in EDT, then in EST.
+        let extract = extract_with_gps("2024:11:03 01:30:00", 40.7128, -74.0060);
+        let result = resolve_timezone(&extract);
+        assert_eq!(
+            result.capture_utc.unwrap(),
+            result.capture_timestamp.unwrap() + 4 * 3600
+        );
+    }
+
+    #[test]
+    fn test_case2_without_datetime_keeps_zone() {
+        let result = resolve_gps_timezone(None, 35.0116, 135.7681).unwrap();
+        assert_eq!(result.capture_tz.as_deref(), Some("Asia/Tokyo"));
+        assert!(result.capture_utc.is_none());
+    }
+
+    #[test]
+    fn test_case1b_utc_instant_takes_zone_from_gps() {
+        // 2024-07-15 01:30:00 UTC, in Kyoto.
+        let extract = ExifExtract {
+            capture_utc: Some(1_721_007_000),
+            gps_lat: Some(35.0116),
+            gps_lon: Some(135.7681),
+            ..Default::default()
+        };
+        let result = resolve_timezone(&extract);
+        assert_eq!(result.capture_tz_source, Some(CaptureTzSource::GpsLookup));
+        assert_eq!(result.capture_tz.as_deref(), Some("Asia/Tokyo"));
+        assert_eq!(result.capture_utc, Some(1_721_007_000));
+        assert_eq!(result.capture_timestamp, Some(1_721_007_000 + 9 * 3600));
+    }
+
+    #[test]
+    fn test_case1b_utc_instant_without_gps_has_no_zone() {
+        let extract = ExifExtract {
+            capture_utc: Some(1_721_007_000),
+            ..Default::default()
+        };
+        let result = resolve_timezone(&extract);
+        assert_eq!(result.capture_utc, Some(1_721_007_000));
+        assert_eq!(result.capture_timestamp, Some(1_721_007_000));
+        assert!(result.capture_tz.is_none());
+        assert!(result.capture_tz_source.is_none());
     }
 
     #[test]
@@ -202,6 +364,7 @@ mod tests {
             height: None,
             duration_ms: None,
             content_identifier: None,
+            ..Default::default()
         };
         let result = resolve_timezone(&extract);
         assert_eq!(result.capture_tz_source, Some(CaptureTzSource::Floating));
@@ -0,0 +1,593 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use chrono::NaiveDateTime;
+
+use crate::domain::{DetectionMethod, MemberRole, StackType};
+use crate::exif::extract::{ExifExtract, extract_exif};
+use crate::import::scan::{ImportCandidate, ScanResult};
+use crate::metadata::AssetType;
+
+/// Frames of one exposure bracket are at most this far apart.
+const BRACKET_MAX_GAP_MS: i64 = 2_000;
+/// An AEB bracket can be as short as two frames; a bracket inferred from
+/// exposure bias alone needs at least three to rule out a manual re-shoot.
+const BRACKET_MIN_FRAMES_AEB: usize = 2;
+const BRACKET_MIN_FRAMES: usize = 3;
+const BRACKET_MAX_FRAMES: usize = 9;
+/// Minimum spread of exposure bias across an inferred bracket.
+const BRACKET_MIN_SPREAD_EV: f64 = 0.9;
+
+/// Consecutive burst frames are at most this far apart.
+const BURST_MAX_GAP_MS: i64 = 1_000;
+const BURST_MIN_FRAMES: usize = 3;
+
+/// Panorama frames are shot by hand, so allow more time between them.
+const PANORAMA_MAX_GAP_MS: i64 = 8_000;
+const PANORAMA_MIN_FRAMES: usize = 3;
+/// Minimum change of compass heading between consecutive panorama frames.
+const PANORAMA_MIN_TURN_DEG: f64 = 2.0;
+
+/// EXIF `ExposureMode` values.
+const EXPOSURE_MODE_MANUAL: u16 = 1;
+const EXPOSURE_MODE_AUTO_BRACKET: u16 = 2;
+
+const EV_EPSILON: f64 = 0.01;
+
+/// Phase 1b — group standalone photos into burst, HDR-bracket and panorama
+/// stacks using EXIF.
+///
+/// Only single-shot photo candidates (no stack yet, optionally with XMP
+/// sidecars) are considered; RAW+JPEG pairs and Live Photos keep their stacks.
+/// Shots are only ever grouped with shots from the same directory and camera.
+///
+/// - Shots sharing a maker-note burst ID form a `Burst`
+///   (`DetectionMethod::BurstIdentifier`).
+/// - Shots taken in quick succession with distinct exposure biases, or in
+///   auto-bracket exposure mode, form an `HdrBracket`
+///   (`DetectionMethod::ExposureSequence`). The frame closest to 0 EV is primary.
+/// - Rapid runs with the same exposure bias form a `Burst`
+///   (`DetectionMethod::Timecode`).
+/// - Slower runs in manual exposure mode with locked exposure and focal
+///   length form a `Panorama` (`DetectionMethod::Timecode`), but only when
+///   the recorded compass heading turns the same way on every frame. Without
+///   that signal such a run is as likely to be repeated shots of one scene,
+///   so it is left ungrouped.
+///
+/// Every member gets the stack's detection key and method in its sidecar
+/// `StackHint`, which is what `rebuild_index` groups on.
+pub fn detect_sequences(scan: &mut ScanResult) {
+    let shots: Vec<Shot> = scan
+        .candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| is_eligible(c))
+        .filter_map(|(index, c)| {
+            let exif = extract_exif(c.primary_path()).ok()?;
+            let time = exif.capture_time_precise()?;
+            Some(Shot {
+                candidate: index,
+                parent: c.primary_path().parent().map(PathBuf::from),
+                time,
+                exif,
+            })
+        })
+        .collect();
+
+    let mut stacks: Vec<DetectedStack> = Vec::new();
+    let mut grouped = vec![false; shots.len()];
+
+    // Maker-note burst IDs are authoritative.
+    let mut by_burst_id: BTreeMap<(Option<PathBuf>, String), Vec<usize>> = BTreeMap::new();
+    for (i, shot) in shots.iter().enumerate() {
+        if let Some(id) = &shot.exif.burst_id {
+            by_burst_id
+                .entry((shot.parent.clone(), id.clone()))
+                .or_default()
+                .push(i);
+        }
+    }
+    for ((_, id), mut frames) in by_burst_id {
+        if frames.len() < 2 {
+            continue;
+        }
+        frames.sort_by_key(|&i| shots[i].time);
+        for &i in &frames {
+            grouped[i] = true;
+        }
+        stacks.push(DetectedStack {
+            frames,
+            stack_type: StackType::Burst,
+            method: DetectionMethod::BurstIdentifier,
+            key: id,
+        });
+    }
+
+    // Everything else: time-ordered runs per directory and camera.
+    let mut by_camera: BTreeMap<(Option<PathBuf>, String), Vec<usize>> = BTreeMap::new();
+    for (i, shot) in shots.iter().enumerate() {
+        if !grouped[i] {
+            by_camera
+                .entry((shot.parent.clone(), camera_key(&shot.exif)))
+                .or_default()
+                .push(i);
+        }
+    }
+    for ((_, camera), mut run) in by_camera {
+        run.sort_by_key(|&i| shots[i].time);
+        let mut start = 0;
+        while start < run.len() {
+            match classify(&shots, &run[start..]) {
+                Some((len, stack_type, method)) => {
+                    let frames = run[start..start + len].to_vec();
+                    let first = shots[frames[0]].time.format("%Y%m%dT%H%M%S%.3f");
+                    stacks.push(DetectedStack {
+                        frames,
+                        stack_type,
+                        method,
+                        key: format!("{camera}@{first}"),
+                    });
+                    start += len;
+                }
+                None => start += 1,
+            }
+        }
+    }
+
+    if stacks.is_empty() {
+        return;
+    }
+
+    // Replace each stack's first candidate with the merged stack, and drop
+    // the rest of its candidates.
+    let mut merged: HashMap<usize, ImportCandidate> = HashMap::new();
+    let mut absorbed = vec![false; scan.candidates.len()];
+    for stack in &stacks {
+        let first = stack
+            .frames
+            .iter()
+            .map(|&i| shots[i].candidate)
+            .min()
+            .expect("stacks have at least two frames");
+        for &i in &stack.frames {
+            absorbed[shots[i].candidate] = true;
+        }
+        merged.insert(first, build_candidate(stack, &shots, &scan.candidates));
+    }
+
+    let candidates = std::mem::take(&mut scan.candidates);
+    scan.candidates = candidates
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, c)| match merged.remove(&i) {
+            Some(stack) => Some(stack),
+            None if absorbed[i] => None,
+            None => Some(c),
+        })
+        .collect();
+}
+
+struct Shot {
+    candidate: usize,
+    parent: Option<PathBuf>,
+    time: NaiveDateTime,
+    exif: ExifExtract,
+}
+
+struct DetectedStack {
+    /// Indices into the shot list, in capture order.
+    frames: Vec<usize>,
+    stack_type: StackType,
+    method: DetectionMethod,
+    key: String,
+}
+
+fn is_eligible(candidate: &ImportCandidate) -> bool {
+    candidate.stack_type.is_none()
+        && candidate.detected_type == AssetType::Photo
+        && candidate
+            .members
+            .iter()
+            .all(|(_, role)| matches!(role, MemberRole::Primary | MemberRole::Sidecar))
+}
+
+fn camera_key(exif: &ExifExtract) -> String {
+    format!(
+        "{} {}",
+        exif.make.as_deref().unwrap_or_default().trim(),
+        exif.model.as_deref().unwrap_or_default().trim()
+    )
+    .trim()
+    .to_string()
+}
+
+/// Classifies the longest sequence of `run` (indices into `shots`, in capture
+/// order) starting at its first shot, returning its length, stack type and
+/// detection method.
+fn classify(shots: &[Shot], run: &[usize]) -> Option<(usize, StackType, DetectionMethod)> {
+    if let Some(len) = bracket_len(shots, run) {
+        return Some((
+            len,
+            StackType::HdrBracket,
+            DetectionMethod::ExposureSequence,
+        ));
+    }
+    let burst = run_len(shots, run, BURST_MAX_GAP_MS, |first, shot| {
+        same_ev(first.exif.exposure_bias, shot.exif.exposure_bias)
+    });
+    if burst >= BURST_MIN_FRAMES {
+        return Some((burst, StackType::Burst, DetectionMethod::Timecode));
+    }
+    let mut heading: Option<f64> = None;
+    let mut turn: Option<f64> = None;
+    let panorama = run_len(shots, run, PANORAMA_MAX_GAP_MS, |first, shot| {
+        if !locked_exposure(&first.exif, &shot.exif) {
+            return false;
+        }
+        let Some(current) = shot.exif.gps_img_direction else {
+            return false;
+        };
+        if let Some(previous) = heading.replace(current) {
+            // Signed change in (-180, 180].
+            let delta = 180.0 - (previous - current + 180.0).rem_euclid(360.0);
+            if delta.abs() < PANORAMA_MIN_TURN_DEG
+                || turn.is_some_and(|t| t.signum() != delta.signum())
+            {
+                return false;
+            }
+            turn = Some(delta);
+        }
+        true
+    });
+    if panorama >= PANORAMA_MIN_FRAMES {
+        return Some((panorama, StackType::Panorama, DetectionMethod::Timecode));
+    }
+    None
+}
+
+fn bracket_len(shots: &[Shot], run: &[usize]) -> Option<usize> {
+    let aeb = shots[run[0]].exif.exposure_mode == Some(EXPOSURE_MODE_AUTO_BRACKET);
+    let mut biases: Vec<f64> = Vec::new();
+    let len = run_len(shots, run, BRACKET_MAX_GAP_MS, |_, shot| {
+        if aeb && shot.exif.exposure_mode != Some(EXPOSURE_MODE_AUTO_BRACKET) {
+            return false;
+        }
+        // A repeated bias value means the next bracket has started.
+        match shot.exif.exposure_bias {
+            Some(ev) if !biases.iter().any(|&b| (b - ev).abs() < EV_EPSILON) => {
+                biases.push(ev);
+                true
+            }
+            None if aeb => true,
+            _ => false,
+        }
+    })
+    .min(BRACKET_MAX_FRAMES);
+
+    if aeb {
+        return (len >= BRACKET_MIN_FRAMES_AEB).then_some(len);
+    }
+    let spread = biases
+        .iter()
+        .take(len)
+        .fold(f64::NEG_INFINITY, |a, &b| a.max(b))
+        - biases
+            .iter()
+            .take(len)
+            .fold(f64::INFINITY, |a, &b| a.min(b));
+    (len >= BRACKET_MIN_FRAMES && spread >= BRACKET_MIN_SPREAD_EV).then_some(len)
+}
+
+/// Length of the prefix of `run` whose consecutive gaps are at most
+/// `max_gap_ms` and whose shots all satisfy `accept(first, shot)`.
+fn run_len(
+    shots: &[Shot],
+    run: &[usize],
+    max_gap_ms: i64,
+    mut accept: impl FnMut(&Shot, &Shot) -> bool,
+) -> usize {
+    let first = &shots[run[0]];
+    if !accept(first, first) {
+        return 0;
+    }
+    let mut len = 1;
+    while len < run.len() {
+        let shot = &shots[run[len]];
+        let gap = (shot.time - shots[run[len - 1]].time).num_milliseconds();
+        if gap > max_gap_ms || !accept(first, shot) {
+            break;
+        }
+        len += 1;
+    }
+    len
+}
+
+fn same_ev(a: Option<f64>, b: Option<f64>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => (a - b).abs() < EV_EPSILON,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Manual exposure with identical shutter, aperture, ISO and focal length.
+fn locked_exposure(first: &ExifExtract, shot: &ExifExtract) -> bool {
+    fn same(a: Option<f64>, b: Option<f64>) -> bool {
+        matches!((a, b), (Some(a), Some(b)) if (a - b).abs() <= f64::EPSILON * a.abs().max(1.0))
+    }
+    shot.exposure_mode == Some(EXPOSURE_MODE_MANUAL)
+        && first.exposure_mode == Some(EXPOSURE_MODE_MANUAL)
+        && same(first.exposure_time, shot.exposure_time)
+        && same(first.f_number, shot.f_number)
+        && same(first.focal_length, shot.focal_length)
+        && first.iso.is_some()
+        && first.iso == shot.iso
+        && same_ev(first.exposure_bias, shot.exposure_bias)
+}
+
+fn build_candidate(
+    stack: &DetectedStack,
+    shots: &[Shot],
+    candidates: &[ImportCandidate],
+) -> ImportCandidate {
+    let primary = match stack.stack_type {
+        // The frame closest to the metered exposure represents the bracket.
+        StackType::HdrBracket => *stack
+            .frames
+            .iter()
+            .min_by(|&&a, &&b| {
+                let ev = |i: usize| shots[i].exif.exposure_bias.unwrap_or(0.0).abs();
+                ev(a).total_cmp(&ev(b))
+            })
+            .expect("stacks have at least two frames"),
+        _ => stack.frames[0],
+    };
+    let secondary_role = match stack.stack_type {
+        StackType::Burst => MemberRole::Alternate,
+        _ => MemberRole::Source,
+    };
+
+    // The executor treats the first member as the visible one, so the
+    // primary frame leads and the rest follow in capture order.
+    let ordered =
+        std::iter::once(primary).chain(stack.frames.iter().copied().filter(|&i| i != primary));
+    let mut members = Vec::new();
+    for frame in ordered {
+        let role = if frame == primary {
+            MemberRole::Primary
+        } else {
+            secondary_role
+        };
+        for (path, member_role) in &candidates[shots[frame].candidate].members {
+            match member_role {
+                MemberRole::Primary => members.push((path.clone(), role)),
+                other => members.push((path.clone(), *other)),
+            }
+        }
+    }
+
+    ImportCandidate {
+        source_paths: members.iter().map(|(p, _)| p.clone()).collect(),
+        detected_type: AssetType::Photo,
+        stack_type: Some(stack.stack_type),
+        detection_method: Some(stack.method),
+        detection_key: Some(stack.key.clone()),
+        members,
+        embedded_members: Vec::new(),
+    }
+}
+
+// ── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shot(ms: i64, exif: ExifExtract) -> Shot {
+        let base =
+            NaiveDateTime::parse_from_str("2024:07:15 10:00:00", "%Y:%m:%d %H:%M:%S").unwrap();
+        Shot {
+            candidate: 0,
+            parent: None,
+            time: base + chrono::TimeDelta::milliseconds(ms),
+            exif,
+        }
+    }
+
+    fn ev(bias: f64) -> ExifExtract {
+        ExifExtract {
+            exposure_bias: Some(bias),
+            ..Default::default()
+        }
+    }
+
+    fn manual() -> ExifExtract {
+        ExifExtract {
+            exposure_mode: Some(EXPOSURE_MODE_MANUAL),
+            exposure_time: Some(1.0 / 250.0),
+            f_number: Some(8.0),
+            focal_length: Some(24.0),
+            iso: Some(100),
+            exposure_bias: Some(0.0),
+            ..Default::default()
+        }
+    }
+
+    fn heading(degrees: f64) -> ExifExtract {
+        ExifExtract {
+            gps_img_direction: Some(degrees),
+            ..manual()
+        }
+    }
+
+    fn classify_shots(shots: &[Shot]) -> Option<(usize, StackType, DetectionMethod)> {
+        let run: Vec<usize> = (0..shots.len()).collect();
+        classify(shots, &run)
+    }
+
+    #[test]
+    fn test_exposure_bracket() {
+        let shots = [
+            shot(0, ev(0.0)),
+            shot(300, ev(-2.0)),
+            shot(600, ev(2.0)),
+            // Next bracket starts: repeated bias ends the first one.
+            shot(900, ev(0.0)),
+        ];
+        assert_eq!(
+            classify_shots(&shots),
+            Some((3, StackType::HdrBracket, DetectionMethod::ExposureSequence))
+        );
+    }
+
+    #[test]
+    fn test_auto_bracket_mode_allows_two_frames() {
+        let aeb = |bias| ExifExtract {
+            exposure_mode: Some(EXPOSURE_MODE_AUTO_BRACKET),
+            exposure_bias: Some(bias),
+            ..Default::default()
+        };
+        let shots = [shot(0, aeb(-1.0)), shot(500, aeb(1.0))];
+        assert_eq!(
+            classify_shots(&shots),
+            Some((2, StackType::HdrBracket, DetectionMethod::ExposureSequence))
+        );
+    }
+
+    #[test]
+    fn test_burst_by_time() {
+        let shots = [
+            shot(0, ev(0.0)),
+            shot(100, ev(0.0)),
+            shot(200, ev(0.0)),
+            shot(300, ev(0.0)),
+            // Too late to belong to the burst.
+            shot(5_000, ev(0.0)),
+        ];
+        assert_eq!(
+            classify_shots(&shots),
+            Some((4, StackType::Burst, DetectionMethod::Timecode))
+        );
+    }
+
+    #[test]
+    fn test_panorama_requires_locked_manual_exposure() {
+        let shots = [
+            shot(0, heading(350.0)),
+            shot(3_000, heading(20.0)),
+            shot(6_000, heading(50.0)),
+        ];
+        assert_eq!(
+            classify_shots(&shots),
+            Some((3, StackType::Panorama, DetectionMethod::Timecode))
+        );
+
+        let mut drifting = heading(20.0);
+        drifting.exposure_time = Some(1.0 / 125.0);
+        let shots = [
+            shot(0, heading(350.0)),
+            shot(3_000, drifting),
+            shot(6_000, heading(50.0)),
+        ];
+        assert_eq!(classify_shots(&shots), None);
+    }
+
+    #[test]
+    fn test_panorama_requires_a_turning_heading() {
+        // Locked manual exposure alone: repeated shots of one scene.
+        let shots = [
+            shot(0, manual()),
+            shot(3_000, manual()),
+            shot(6_000, manual()),
+        ];
+        assert_eq!(classify_shots(&shots), None);
+
+        // A tripod that never turns.
+        let shots = [
+            shot(0, heading(90.0)),
+            shot(3_000, heading(90.5)),
+            shot(6_000, heading(90.0)),
+        ];
+        assert_eq!(classify_shots(&shots), None);
+
+        // Turning back the other way ends the run.
+        let shots = [
+            shot(0, heading(90.0)),
+            shot(3_000, heading(120.0)),
+            shot(6_000, heading(90.0)),
+        ];
+        assert_eq!(classify_shots(&shots), None);
+    }
+
+    #[test]
+    fn test_unrelated_shots_are_left_alone() {
+        let shots = [
+            shot(0, ev(0.0)),
+            shot(1_500, ev(0.0)),
+            shot(60_000, ev(0.0)),
+        ];
+        assert_eq!(classify_shots(&shots), None);
+    }
+
+    #[test]
+    fn test_build_candidate_puts_bracket_primary_first() {
+        let shots = vec![
+            Shot {
+                candidate: 0,
+                ..shot(0, ev(-2.0))
+            },
+            Shot {
+                candidate: 1,
+                ..shot(300, ev(0.0))
+            },
+            Shot {
+                candidate: 2,
+                ..shot(600, ev(2.0))
+            },
+        ];
+        let candidates: Vec<ImportCandidate> = (0..3)
+            .map(|i| {
+                let path = PathBuf::from(format!("/src/img_{i}.jpg"));
+                ImportCandidate {
+                    source_paths: vec![path.clone()],
+                    detected_type: AssetType::Photo,
+                    stack_type: None,
+                    detection_method: None,
+                    detection_key: None,
+                    members: vec![(path, MemberRole::Primary)],
+                    embedded_members: Vec::new(),
+                }
+            })
+            .collect();
+        let stack = DetectedStack {
+            frames: vec![0, 1, 2],
+            stack_type: StackType::HdrBracket,
+            method: DetectionMethod::ExposureSequence,
+            key: "cam@1".to_string(),
+        };
+
+        let candidate = build_candidate(&stack, &shots, &candidates);
+        assert_eq!(
+            candidate.members,
+            vec![
+                (PathBuf::from("/src/img_1.jpg"), MemberRole::Primary),
+                (PathBuf::from("/src/img_0.jpg"), MemberRole::Source),
+                (PathBuf::from("/src/img_2.jpg"), MemberRole::Source),
+            ]
+        );
+        assert_eq!(candidate.detection_key.as_deref(), Some("cam@1"));
+    }
+
+    #[test]
+    fn test_photos_without_exif_are_unchanged() {
+        let src = tempfile::TempDir::new().unwrap();
+        for i in 0..3 {
+            std::fs::write(src.path().join(format!("p{i}.jpg")), b"no exif").unwrap();
+        }
+        let mut scan = crate::import::scanner::scan(&[src.path().to_path_buf()]).unwrap();
+        let before = scan.candidates.len();
+        detect_sequences(&mut scan);
+        assert_eq!(scan.candidates.len(), before);
+        assert!(scan.candidates.iter().all(|c| c.stack_type.is_none()));
+    }
+}
@@ -1,39 +1,87 @@
 use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
 
+use pixles_media::video::VideoMetadata;
 use uuid::Uuid;
 
 use crate::db::rows::{AssetRow, AssetStackRow, StackMemberRow};
-use crate::domain::MemberRole;
+use crate::domain::{GpsSource, ImportMode, MemberRole, StackType};
+use crate::exif::annotations::{Annotations, read_embedded, read_xmp_sidecar};
 use crate::exif::extract::extract_exif;
 use crate::exif::timezone::resolve_timezone;
 use crate::import::executor_cancellation::CancellationToken;
+use crate::import::geotag::Geotagger;
+use crate::import::group::{is_video, is_xmp};
+use crate::import::journal::{ImportJournal, StagedMember};
 use crate::import::planner::{ImportActionPlan, ImportConfig, ImportDecision};
 use crate::import::progress::{ImportExecutionSummary, ImportOutcome, ImportProgressEvent};
-use crate::import::scan::ImportCandidate;
+use crate::import::scan::{EmbeddedMember, ImportCandidate};
 use crate::library::library::Library;
 use crate::library::paths::{media_path, sidecar_path, tmp_path};
+use crate::library::thumbnails::{generate_derivatives, perceptual_hash};
 use crate::metadata::AssetType;
 use crate::sidecar::asset_sidecar::AssetSidecar;
 use crate::sidecar::io::write_sidecar;
+use crate::sidecar::library_version::CURRENT_LIBRARY_VERSION;
 use crate::sidecar::stack_hint::StackHint;
+use crate::utils::hash::{copy_and_hash, copy_range_and_hash, get_file_hash};
+use crate::utils::reflink::reflink;
 
 const IMPORTER_VERSION: &str = env!("CARGO_PKG_VERSION");
 const RAWSHIFT_VERSION: &str = "0.0.0";
 
+type ExecuteError = Box<dyn std::error::Error + Send + Sync>;
+
 /// Phase 4 — execute the import plan.
 ///
 /// Each `ImportDecision::Import` candidate undergoes a 10-step atomic
 /// two-phase commit. Files are never partially written: every media file and
 /// its sidecar are either fully committed or cleaned up.
+///
+/// Phase A (copy, verify, sidecar) runs on a pool of up to
+/// `config.max_workers` threads. Phase B (index inserts) and every
+/// `on_event` callback run on the calling thread, so the database connection
+/// is never shared. Cancellation stops workers from picking up new
+/// candidates; candidates already in flight are finished.
 pub fn execute(
     plan: &ImportActionPlan,
     library: &Library,
     config: &ImportConfig,
     on_event: impl Fn(ImportProgressEvent),
     cancel: &CancellationToken,
-) -> Result<ImportExecutionSummary, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<ImportExecutionSummary, ExecuteError> {
+    run(plan, library, config, on_event, cancel, None)
+}
+
+/// Like [`execute`], but records every candidate's progress in `journal` so an
+/// interrupted run can be picked up again with
+/// [`resume_import`](crate::import::journal::resume_import).
+///
+/// The journal is not finished here; call [`ImportJournal::finish`] once the
+/// run is known to be complete.
+pub fn execute_journaled(
+    plan: &ImportActionPlan,
+    library: &Library,
+    config: &ImportConfig,
+    on_event: impl Fn(ImportProgressEvent),
+    cancel: &CancellationToken,
+    journal: &mut ImportJournal,
+) -> Result<ImportExecutionSummary, ExecuteError> {
+    run(plan, library, config, on_event, cancel, Some(journal))
+}
+
+fn run(
+    plan: &ImportActionPlan,
+    library: &Library,
+    config: &ImportConfig,
+    on_event: impl Fn(ImportProgressEvent),
+    cancel: &CancellationToken,
+    mut journal: Option<&mut ImportJournal>,
+) -> Result<ImportExecutionSummary, ExecuteError> {
     let total = plan.actions.len() as u64;
     let total_files: u64 = plan
         .actions
@@ -42,50 +90,143 @@ pub fn execute(
         .map(|(c, _)| c.source_paths.len() as u64)
         .sum();
 
+    // Track logs are read once for the run, before anything is written.
+    let geotagger = match &config.track_log {
+        Some(track_log) => Some(track_log.load()?),
+        None => None,
+    };
+    let geotagger = geotagger.as_ref();
+
     on_event(ImportProgressEvent::ImportStarted {
         total_candidates: total,
         total_files,
     });
 
-    let mut summary = ImportExecutionSummary::default();
-
-    for (i, (candidate, decision)) in plan.actions.iter().enumerate() {
-        if cancel.is_cancelled() {
-            break;
+    let workers = config.max_workers.clamp(1, plan.actions.len().max(1));
+    let root: &Path = &library.root;
+    let next_index = AtomicUsize::new(0);
+    // Set when Phase B fails so workers stop picking up new candidates.
+    let aborted = AtomicBool::new(false);
+
+    let mut completed: Vec<Option<Vec<(PathBuf, ImportOutcome)>>> =
+        (0..plan.actions.len()).map(|_| None).collect();
+    let mut first_error: Option<ExecuteError> = None;
+    // Primaries committed so far, for the automatic XMP export.
+    let mut committed: Vec<String> = Vec::new();
+
+    thread::scope(|s| {
+        let (tx, rx) = mpsc::channel::<WorkerMessage>();
+
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let next_index = &next_index;
+            let aborted = &aborted;
+            s.spawn(move || {
+                loop {
+                    if cancel.is_cancelled() || aborted.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let index = next_index.fetch_add(1, Ordering::SeqCst);
+                    let Some((candidate, decision)) = plan.actions.get(index) else {
+                        break;
+                    };
+                    if tx.send(WorkerMessage::Started { index }).is_err() {
+                        break;
+                    }
+                    let staged = stage_decision(candidate, decision, root, config, geotagger);
+                    if tx.send(WorkerMessage::Staged { index, staged }).is_err() {
+                        break;
+                    }
+                }
+            });
         }
-
-        let primary_path = candidate.primary_path().clone();
-        on_event(ImportProgressEvent::CandidateStarted {
-            index: i as u64,
-            total,
-            primary_path: primary_path.clone(),
-        });
-
-        let outcomes = match decision {
-            ImportDecision::Import => execute_candidate(candidate, library, config)?,
-            ImportDecision::SkipDuplicate { existing_uuid } => {
-                vec![(
-                    primary_path,
-                    ImportOutcome::DuplicateSkipped {
-                        existing_uuid: existing_uuid.clone(),
-                    },
-                )]
-            }
-            ImportDecision::SkipUnsupported => {
-                vec![(primary_path, ImportOutcome::Unsupported)]
-            }
-            ImportDecision::SkipError(msg) => {
-                vec![(primary_path, ImportOutcome::CorruptUnreadable(msg.clone()))]
+        // Only worker clones remain; the receive loop ends once they all exit.
+        drop(tx);
+
+        for message in rx {
+            match message {
+                WorkerMessage::Started { index } => {
+                    on_event(ImportProgressEvent::CandidateStarted {
+                        index: index as u64,
+                        total,
+                        primary_path: plan.actions[index].0.primary_path().clone(),
+                    });
+                }
+                WorkerMessage::Staged { index, staged } => {
+                    let candidate = &plan.actions[index].0;
+                    let outcomes = match staged {
+                        Staged::Skipped(outcomes) => outcomes,
+                        Staged::Committed(staged) if first_error.is_some() => {
+                            // Phase B already failed for another candidate;
+                            // undo this one's Phase A so nothing is orphaned.
+                            staged.roll_back();
+                            continue;
+                        }
+                        Staged::Committed(staged) => {
+                            if let Some(journal) = journal.as_deref_mut()
+                                && let Err(e) =
+                                    journal.record_staged(index, staged.journal_members())
+                            {
+                                staged.roll_back();
+                                aborted.store(true, Ordering::SeqCst);
+                                first_error = Some(Box::new(e));
+                                continue;
+                            }
+                            match commit_candidate(candidate, &staged, library, config) {
+                                Ok(outcomes) => {
+                                    committed.extend(staged.primary().map(|c| c.uuid_str.clone()));
+                                    outcomes
+                                }
+                                Err(e) => {
+                                    // With a journal, the next run recovers
+                                    // the staged files; without one they are
+                                    // orphans, as nothing reached the index.
+                                    if journal.is_none() {
+                                        staged.roll_back();
+                                    }
+                                    aborted.store(true, Ordering::SeqCst);
+                                    first_error = Some(e);
+                                    continue;
+                                }
+                            }
+                        }
+                    };
+
+                    if let Some(journal) = journal.as_deref_mut()
+                        && let Err(e) = journal.record_completed(index, &outcomes)
+                    {
+                        aborted.store(true, Ordering::SeqCst);
+                        first_error = Some(Box::new(e));
+                        continue;
+                    }
+
+                    on_event(ImportProgressEvent::CandidateCompleted {
+                        index: index as u64,
+                        outcomes: outcomes.clone(),
+                    });
+                    completed[index] = Some(outcomes);
+                }
             }
-        };
+        }
+    });
+
+    // Candidates joined the album in the index as they committed; write its
+    // file once for the run, even if the run failed part-way.
+    if let Some(album_id) = &config.target_album_id
+        && let Err(e) = library.write_album_file(album_id)
+    {
+        first_error.get_or_insert(Box::new(e));
+    }
+    library.refresh_auto_xmp(&committed);
 
-        on_event(ImportProgressEvent::CandidateCompleted {
-            index: i as u64,
-            outcomes: outcomes.clone(),
-        });
-        summary.outcomes.extend(outcomes);
+    if let Some(e) = first_error {
+        return Err(e);
     }
 
+    let summary = ImportExecutionSummary {
+        outcomes: completed.into_iter().flatten().flatten().collect(),
+    };
+
     on_event(ImportProgressEvent::ImportCompleted {
         summary: ImportExecutionSummary {
             outcomes: summary.outcomes.clone(),
@@ -95,26 +236,145 @@ pub fn execute(
     Ok(summary)
 }
 
+// ── Worker pool plumbing ─────────────────────────────────────────────────────
+
+enum WorkerMessage {
+    Started { index: usize },
+    Staged { index: usize, staged: Staged },
+}
+
+/// Result of Phase A for one candidate.
+enum Staged {
+    /// Nothing was written; these are the final outcomes.
+    Skipped(Vec<(PathBuf, ImportOutcome)>),
+    /// Every member is on disk and awaits Phase B.
+    Committed(StagedCandidate),
+}
+
+struct StagedCandidate {
+    now: i64,
+    members: Vec<MemberCommit>,
+}
+
+impl StagedCandidate {
+    fn roll_back(&self) {
+        roll_back_members(&self.members);
+    }
+
+    /// The member shown for the stack: the primary, or else the first.
+    fn primary(&self) -> Option<&MemberCommit> {
+        self.members
+            .iter()
+            .find(|c| c.role == MemberRole::Primary)
+            .or_else(|| self.members.first())
+    }
+
+    fn journal_members(&self) -> Vec<StagedMember> {
+        self.members
+            .iter()
+            .map(|m| StagedMember {
+                source_path: m.source_path.clone(),
+                uuid: m.uuid_str.clone(),
+                media_path: m.media_final.clone(),
+                sidecar_path: m.sidecar_final.clone(),
+                referenced: m.referenced,
+            })
+            .collect()
+    }
+}
+
+fn stage_decision(
+    candidate: &ImportCandidate,
+    decision: &ImportDecision,
+    root: &Path,
+    config: &ImportConfig,
+    geotagger: Option<&Geotagger>,
+) -> Staged {
+    let primary_path = candidate.primary_path().clone();
+    match decision {
+        ImportDecision::Import => match stage_candidate(candidate, root, config, geotagger) {
+            Ok(staged) => Staged::Committed(staged),
+            Err(failed) => Staged::Skipped(vec![failed]),
+        },
+        ImportDecision::SkipDuplicate { existing_uuid } => Staged::Skipped(vec![(
+            primary_path,
+            ImportOutcome::DuplicateSkipped {
+                existing_uuid: existing_uuid.clone(),
+            },
+        )]),
+        ImportDecision::NearDuplicate {
+            existing_uuid,
+            distance,
+        } => Staged::Skipped(vec![(
+            primary_path,
+            ImportOutcome::NearDuplicateSkipped {
+                existing_uuid: existing_uuid.clone(),
+                distance: *distance,
+            },
+        )]),
+        ImportDecision::SkipUnsupported => {
+            Staged::Skipped(vec![(primary_path, ImportOutcome::Unsupported)])
+        }
+        ImportDecision::SkipError(msg) => Staged::Skipped(vec![(
+            primary_path,
+            ImportOutcome::CorruptUnreadable(msg.clone()),
+        )]),
+    }
+}
+
+fn roll_back_members(members: &[MemberCommit]) {
+    for member in members {
+        if !member.referenced {
+            let _ = fs::remove_file(&member.media_final);
+        }
+        let _ = fs::remove_file(&member.sidecar_final);
+        for thumbnail in &member.thumbnails {
+            let _ = fs::remove_file(thumbnail);
+        }
+    }
+}
+
 // ── Per-candidate execution ──────────────────────────────────────────────────
 
-fn execute_candidate(
+/// Phase A: copy + verify all members. Runs on a worker thread and touches
+/// only the filesystem.
+fn stage_candidate(
     candidate: &ImportCandidate,
-    library: &Library,
+    root: &Path,
     config: &ImportConfig,
-) -> Result<Vec<(PathBuf, ImportOutcome)>, Box<dyn std::error::Error + Send + Sync>> {
+    geotagger: Option<&Geotagger>,
+) -> Result<StagedCandidate, (PathBuf, ImportOutcome)> {
     let now = now_secs();
     let mut member_commits: Vec<MemberCommit> = Vec::new();
 
-    // ── Phase A: copy + verify all members ──────────────────────────────────
-    for (source_path, role) in &candidate.members {
-        match commit_member(source_path, *role, candidate, library, config, now) {
+    // Paired `.xmp` files are read into their member's annotations rather
+    // than imported as members of their own.
+    let paired_xmp = paired_xmp(candidate);
+    let members = candidate
+        .members
+        .iter()
+        .filter(|(path, _)| !paired_xmp.contains(path))
+        .map(|(path, role)| (path, *role, None));
+    let embedded = candidate
+        .embedded_members
+        .iter()
+        .map(|e| (&e.container, e.role, Some(e)));
+
+    for (source_path, role, embedded) in members.chain(embedded) {
+        match commit_member(
+            source_path,
+            role,
+            embedded,
+            candidate,
+            root,
+            config,
+            geotagger,
+            now,
+        ) {
             Ok(commit) => member_commits.push(commit),
             Err(e) => {
                 // Roll back any already-committed members for this candidate
-                for prev in &member_commits {
-                    let _ = fs::remove_file(&prev.media_final);
-                    let _ = fs::remove_file(&prev.sidecar_final);
-                }
+                roll_back_members(&member_commits);
                 let outcome = if e.contains("corrupt_transfer") {
                     ImportOutcome::CorruptTransfer
                 } else if e.contains("permission") {
@@ -122,19 +382,62 @@ fn execute_candidate(
                 } else {
                     ImportOutcome::CorruptUnreadable(e)
                 };
-                return Ok(vec![(source_path.clone(), outcome)]);
+                return Err((source_path.clone(), outcome));
             }
         }
     }
 
-    // ── Phase B: DB inserts + stack ─────────────────────────────────────────
-    let primary_commit = member_commits
-        .iter()
-        .find(|c| c.role == MemberRole::Primary)
-        .or_else(|| member_commits.first());
+    Ok(StagedCandidate {
+        now,
+        members: member_commits,
+    })
+}
+
+/// Phase B: DB inserts + stack, in one transaction. Runs on the calling
+/// thread.
+fn commit_candidate(
+    candidate: &ImportCandidate,
+    staged: &StagedCandidate,
+    library: &Library,
+    config: &ImportConfig,
+) -> Result<Vec<(PathBuf, ImportOutcome)>, ExecuteError> {
+    library
+        .db
+        .transaction(|| insert_candidate(candidate, staged, library, config))?;
+
+    let mut outcomes = Vec::new();
+    for commit in &staged.members {
+        // Move mode: delete source file after successful commit
+        if matches!(config.import_mode, ImportMode::Move) {
+            let _ = fs::remove_file(&commit.source_path);
+        }
+        outcomes.push((commit.source_path.clone(), ImportOutcome::Imported));
+    }
+    // Their values went into the members they name. The files stay where
+    // they are, even in move mode, as they may hold more than Pixles reads.
+    outcomes.extend(
+        paired_xmp(candidate)
+            .into_iter()
+            .map(|xmp| (xmp, ImportOutcome::Imported)),
+    );
+    Ok(outcomes)
+}
+
+/// The index rows of a staged candidate: its assets, stack and album entry.
+fn insert_candidate(
+    candidate: &ImportCandidate,
+    staged: &StagedCandidate,
+    library: &Library,
+    config: &ImportConfig,
+) -> Result<(), ExecuteError> {
+    let now = staged.now;
+    let member_commits = &staged.members;
+
+    let primary_commit = staged.primary();
 
     let stack_id = if candidate.stack_type.is_some() {
-        let sid = format!("stack-{}", now);
+        // Candidates finish Phase A concurrently, so a timestamp alone is not unique.
+        let sid = format!("stack-{}", Uuid::now_v7().simple());
         // Determine primary UUID
         let primary_uuid = primary_commit
             .map(|c| c.uuid_str.clone())
@@ -142,10 +445,8 @@ fn execute_candidate(
 
         let stack_row = AssetStackRow {
             id: sid.clone(),
-            stack_type: candidate
-                .stack_type
-                .map(|st| format!("{st:?}").to_lowercase())
-                .unwrap_or_else(|| "custom".to_string()),
+            stack_type: stack_type_str(candidate.stack_type.unwrap_or(StackType::Custom))
+                .to_string(),
             primary_asset_id: primary_uuid.clone(),
             cover_asset_id: Some(primary_uuid),
             is_collapsed: true,
@@ -159,7 +460,6 @@ fn execute_candidate(
         None
     };
 
-    let mut outcomes = Vec::new();
     for (seq, commit) in member_commits.iter().enumerate() {
         let is_primary = commit.role == MemberRole::Primary || seq == 0;
 
@@ -173,17 +473,25 @@ fn execute_candidate(
             hash_blake3: commit.hash.clone(),
             width: commit.width.map(|w| w as i64),
             height: commit.height.map(|h| h as i64),
-            duration_ms: None,
+            duration_ms: commit.duration_ms.map(|d| d as i64),
             stack_id: stack_id.clone(),
             is_stack_hidden: !is_primary,
-            chromahash: None,
-            dominant_color: None,
-            album_id: config.target_album_id.clone(),
-            rating: 0,
+            chromahash: commit.chromahash.clone(),
+            dominant_color: commit.dominant_color.clone(),
+            perceptual_hash: commit.perceptual_hash.map(|h| h as i64),
+            album_id: None,
+            rating: i64::from(commit.rating),
             is_deleted: false,
             deleted_at: None,
+            original_filename: Some(commit.original_filename.clone()),
+            camera_make: commit.camera_make.clone(),
+            camera_model: commit.camera_model.clone(),
+            gps_lat: commit.gps_lat,
+            gps_lon: commit.gps_lon,
+            caption: commit.caption.clone(),
         };
         library.db.insert_asset(&row)?;
+        library.db.set_asset_tags(&row.uuid, &commit.tags)?;
 
         if let Some(ref sid) = stack_id {
             let member_row = StackMemberRow {
@@ -196,16 +504,16 @@ fn execute_candidate(
             };
             let _ = library.db.insert_stack_member(&member_row);
         }
+    }
 
-        // Move mode: delete source file after successful commit
-        if matches!(config.import_mode, crate::domain::ImportMode::Move) {
-            let _ = fs::remove_file(&commit.source_path);
-        }
-
-        outcomes.push((commit.source_path.clone(), ImportOutcome::Imported));
+    // Only the primary joins the album; the rest of a stack is behind it.
+    if let (Some(album_id), Some(primary)) = (&config.target_album_id, primary_commit) {
+        library
+            .db
+            .append_album_asset(album_id, &primary.uuid_str, now)?;
     }
 
-    Ok(outcomes)
+    Ok(())
 }
 
 // ── Per-member atomic commit ─────────────────────────────────────────────────
@@ -215,64 +523,160 @@ struct MemberCommit {
     uuid_str: String,
     role: MemberRole,
     hash: String,
+    /// The original outside the library when `referenced`.
     media_final: PathBuf,
+    referenced: bool,
     sidecar_final: PathBuf,
     capture_utc: Option<i64>,
     capture_tz_source: Option<String>,
     width: Option<u32>,
     height: Option<u32>,
+    duration_ms: Option<u64>,
+    chromahash: Option<String>,
+    dominant_color: Option<String>,
+    perceptual_hash: Option<u64>,
+    thumbnails: Vec<PathBuf>,
+    original_filename: String,
+    camera_make: Option<String>,
+    camera_model: Option<String>,
+    gps_lat: Option<f64>,
+    gps_lon: Option<f64>,
+    rating: u8,
+    caption: Option<String>,
+    tags: Vec<String>,
 }
 
+/// Commits one member. For an `embedded` member, `source` is its container
+/// and only the embedded byte range is copied.
+#[allow(clippy::too_many_arguments)]
 fn commit_member(
     source: &Path,
     role: MemberRole,
+    embedded: Option<&EmbeddedMember>,
     candidate: &ImportCandidate,
-    library: &Library,
+    root: &Path,
     config: &ImportConfig,
+    geotagger: Option<&Geotagger>,
     now: i64,
 ) -> Result<MemberCommit, String> {
     // Step 1: Generate UUID
     let uuid = Uuid::now_v7();
     let uuid_str = uuid.to_string();
 
-    // Step 2: EXIF + timezone
-    let exif = extract_exif(source).unwrap_or_default();
-    let tz = resolve_timezone(&exif);
+    // Step 2: EXIF + timezone. A file without a position of its own is
+    // placed from the track logs, which may also give it a zone.
+    let mut exif = extract_exif(source).unwrap_or_default();
+    let mut tz = resolve_timezone(&exif);
+    let mut gps_source =
+        (exif.gps_lat.is_some() && exif.gps_lon.is_some()).then_some(GpsSource::Exif);
+    if gps_source.is_none()
+        && let Some(position) = geotagger.and_then(|g| g.position_for(&tz))
+    {
+        exif.gps_lat = Some(position.latitude);
+        exif.gps_lon = Some(position.longitude);
+        tz = resolve_timezone(&exif);
+        gps_source = Some(GpsSource::TrackLog);
+    }
     let capture_utc = tz.capture_utc;
     let capture_tz_source = tz
         .capture_tz_source
         .map(|s| format!("{s:?}").to_lowercase());
-    let width = exif.width;
-    let height = exif.height;
+    // The container's dimensions describe the still, not the embedded video.
+    let (mut width, mut height, mut duration_ms) = match embedded {
+        Some(_) => (None, None, None),
+        None => (exif.width, exif.height, exif.duration_ms),
+    };
 
-    let ext = source
-        .extension()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_lowercase();
+    let ext = match embedded {
+        Some(e) => e.extension.clone(),
+        None => source
+            .extension()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase(),
+    };
+
+    // Rating, label, caption and tags come from embedded metadata, overridden
+    // by the XMP sidecars that name this member (see `exif::annotations`).
+    let mut annotations = match embedded {
+        None if role != MemberRole::Sidecar && !is_video(&ext) => read_embedded(source),
+        _ => Annotations::default(),
+    };
+    if embedded.is_none() && role != MemberRole::Sidecar {
+        for xmp in xmp_sidecars_for(candidate, source).iter().rev() {
+            if let Some(from_sidecar) = read_xmp_sidecar(xmp) {
+                annotations = from_sidecar.or(annotations);
+            }
+        }
+    }
+
+    // Only whole files can be linked or referenced; an embedded member is
+    // always extracted by copying its byte range.
+    let mode = match embedded {
+        Some(_) => ImportMode::Copy,
+        None => config.import_mode,
+    };
 
     // Step 3: Create media dir
-    let final_media = media_path(&library.root, &uuid, &ext, capture_utc);
-    fs::create_dir_all(final_media.parent().unwrap()).map_err(|e| format!("mkdir failed: {e}"))?;
-
-    // Step 4: Copy source → tmp
-    let tmp_media = tmp_path(&final_media);
-    fs::copy(source, &tmp_media).map_err(|e| {
-        if e.kind() == std::io::ErrorKind::PermissionDenied {
-            format!("permission denied: {e}")
-        } else {
-            format!("copy failed: {e}")
+    let library_media = media_path(root, &uuid, &ext, capture_utc);
+    fs::create_dir_all(library_media.parent().unwrap())
+        .map_err(|e| format!("mkdir failed: {e}"))?;
+    let reference_path = match mode {
+        ImportMode::Reference => Some(referenced_path(source)?),
+        _ => None,
+    };
+    let final_media = reference_path
+        .as_ref()
+        .map_or_else(|| library_media.clone(), PathBuf::from);
+
+    // Step 4: Transfer source → tmp, hashing the source bytes. A reference
+    // is only hashed where it is.
+    let tmp_media = tmp_path(&library_media);
+    let transferred = match (mode, embedded) {
+        (_, Some(e)) => copy_range_and_hash(source, e.offset, e.length, &tmp_media),
+        (ImportMode::Copy | ImportMode::Move, None) => copy_and_hash(source, &tmp_media),
+        (ImportMode::Reflink, None) => reflink_and_hash(source, &tmp_media),
+        (ImportMode::Hardlink, None) => {
+            fs::hard_link(source, &tmp_media).and_then(|()| hash_in_place(source))
+        }
+        (ImportMode::Reference, None) => hash_in_place(source),
+    };
+    let (source_hash, file_size) = transferred.map_err(|e| {
+        let _ = fs::remove_file(&tmp_media);
+        match e.kind() {
+            std::io::ErrorKind::PermissionDenied => format!("permission denied: {e}"),
+            std::io::ErrorKind::CrossesDevices if mode == ImportMode::Hardlink => {
+                "hard link failed: source is not on the library's filesystem".to_string()
+            }
+            _ if mode == ImportMode::Hardlink => format!("hard link failed: {e}"),
+            _ if mode == ImportMode::Reference => format!("read failed: {e}"),
+            _ => format!("copy failed: {e}"),
         }
     })?;
 
-    // Step 5: BLAKE3 verify
-    let source_bytes = fs::read(source).map_err(|e| format!("read failed: {e}"))?;
-    let source_hash = blake3::hash(&source_bytes).to_hex().to_string();
-    let tmp_bytes = fs::read(&tmp_media).map_err(|e| format!("read tmp failed: {e}"))?;
-    let tmp_hash = blake3::hash(&tmp_bytes).to_hex().to_string();
-    if source_hash != tmp_hash {
-        let _ = fs::remove_file(&tmp_media);
-        return Err("corrupt_transfer".to_string());
+    // Step 5: BLAKE3 verify. A hard link shares the source's data, so there
+    // is nothing to compare.
+    if matches!(
+        mode,
+        ImportMode::Copy | ImportMode::Move | ImportMode::Reflink
+    ) {
+        let tmp_hash = get_file_hash(&tmp_media).map_err(|e| {
+            let _ = fs::remove_file(&tmp_media);
+            format!("read tmp failed: {e}")
+        })?;
+        if source_hash != tmp_hash {
+            let _ = fs::remove_file(&tmp_media);
+            return Err("corrupt_transfer".to_string());
+        }
+    }
+
+    // An embedded video's own metadata is readable once it is extracted.
+    if embedded.is_some()
+        && let Ok(video) = VideoMetadata::from_path(&tmp_media)
+    {
+        width = video.width;
+        height = video.height;
+        duration_ms = video.duration_ms;
     }
 
     // Step 6: Build sidecar
@@ -288,25 +692,32 @@ fn commit_member(
         stack_type: st,
     });
 
-    let original_filename = source
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_string();
+    let original_filename = match embedded {
+        Some(e) => format!(
+            "{}.{}",
+            source.file_stem().unwrap_or_default().to_string_lossy(),
+            e.extension
+        ),
+        None => source
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+    };
 
     let sidecar = AssetSidecar {
-        version: 1,
+        version: CURRENT_LIBRARY_VERSION,
         uuid: uuid_str.clone(),
         asset_type: candidate.detected_type,
         original_filename,
         import_timestamp: now,
         modified_timestamp: now,
         hash_blake3: source_hash.clone(),
-        file_size: source_bytes.len() as u64,
+        file_size,
         is_deleted: false,
-        rating: 0,
-        tags: vec![],
-        import_mode: config.import_mode,
+        rating: annotations.rating.unwrap_or(0),
+        tags: annotations.tags,
+        import_mode: mode,
         importer_version: IMPORTER_VERSION.to_string(),
         rawshift_version: RAWSHIFT_VERSION.to_string(),
         capture_timestamp: tz.capture_timestamp,
@@ -314,21 +725,27 @@ fn commit_member(
         capture_tz: tz.capture_tz,
         capture_tz_source: tz.capture_tz_source,
         tz_db_version: tz.tz_db_version,
+        original_capture_timestamp: None,
         width,
         height,
-        duration_ms: None,
+        duration_ms,
         stack_hint,
-        album_id: config.target_album_id.clone(),
+        album_id: None,
         deleted_at: None,
         camera_make: exif.make,
         camera_model: exif.model,
         gps_lat: exif.gps_lat,
         gps_lon: exif.gps_lon,
+        gps_source,
+        caption: annotations.caption,
+        label: annotations.label,
+        pick: annotations.pick,
+        reference_path,
         unknown_fields: BTreeMap::new(),
     };
 
     // Step 7: Write sidecar tmp
-    let final_sidecar = sidecar_path(&library.root, &uuid, &ext, capture_utc);
+    let final_sidecar = sidecar_path(root, &uuid, &ext, capture_utc);
     let tmp_sidecar = tmp_path(&final_sidecar);
     write_sidecar(&tmp_sidecar, &sidecar).map_err(|e| {
         let _ = fs::remove_file(&tmp_media);
@@ -336,11 +753,14 @@ fn commit_member(
     })?;
 
     // Step 8: Rename media tmp → final (atomic)
-    fs::rename(&tmp_media, &final_media).map_err(|e| {
-        let _ = fs::remove_file(&tmp_media);
-        let _ = fs::remove_file(&tmp_sidecar);
-        format!("rename media failed: {e}")
-    })?;
+    let referenced = sidecar.reference_path.is_some();
+    if !referenced {
+        fs::rename(&tmp_media, &final_media).map_err(|e| {
+            let _ = fs::remove_file(&tmp_media);
+            let _ = fs::remove_file(&tmp_sidecar);
+            format!("rename media failed: {e}")
+        })?;
+    }
 
     // Step 9: Rename sidecar tmp → final (atomic)
     // Note: write_sidecar already does the tmp→final rename internally,
@@ -358,22 +778,148 @@ fn commit_member(
     }
     // If write_sidecar already placed it at the right path, we're done.
 
+    // Step 10: Thumbnails + LQIP. The media is already committed, so a
+    // failure here only leaves the asset to `pixles library thumbnails`.
+    let derivatives = config.thumbnail_format.and_then(|format| {
+        generate_derivatives(root, &uuid, &final_media, format)
+            .inspect_err(|e| log::warn!("thumbnails failed for {}: {e}", source.display()))
+            .ok()
+            .flatten()
+    });
+    let (chromahash, dominant_color, derived_hash, thumbnails) = match derivatives {
+        Some(d) => (
+            Some(d.chromahash),
+            Some(d.dominant_color),
+            Some(d.perceptual_hash),
+            d.paths,
+        ),
+        None => (None, None, None, Vec::new()),
+    };
+    // Near-duplicate detection needs the hash whether or not thumbnails
+    // were made.
+    let perceptual_hash = derived_hash.or_else(|| {
+        perceptual_hash(&final_media)
+            .inspect_err(|e| log::warn!("perceptual hash failed for {}: {e}", source.display()))
+            .ok()
+            .flatten()
+    });
+
     Ok(MemberCommit {
         source_path: source.to_path_buf(),
         uuid_str,
         role,
         hash: source_hash,
         media_final: final_media,
+        referenced,
         sidecar_final: final_sidecar,
         capture_utc,
         capture_tz_source,
         width,
         height,
+        duration_ms,
+        chromahash,
+        dominant_color,
+        perceptual_hash,
+        thumbnails,
+        original_filename: sidecar.original_filename,
+        camera_make: sidecar.camera_make,
+        camera_model: sidecar.camera_model,
+        gps_lat: sidecar.gps_lat,
+        gps_lon: sidecar.gps_lon,
+        rating: sidecar.rating,
+        caption: sidecar.caption,
+        tags: sidecar.tags,
     })
 }
 
 // ── Helpers ──────────────────────────────────────────────────────────────────
 
+/// Hashes a file that stays where it is: a hard-linked or referenced source.
+fn hash_in_place(path: &Path) -> std::io::Result<(String, u64)> {
+    Ok((get_file_hash(path)?, fs::metadata(path)?.len()))
+}
+
+/// Reflinks `source` to `dest`, or copies it where the filesystem cannot
+/// clone.
+fn reflink_and_hash(source: &Path, dest: &Path) -> std::io::Result<(String, u64)> {
+    match reflink(source, dest) {
+        Ok(()) => hash_in_place(source),
+        Err(e) => {
+            log::debug!("reflink failed for {}, copying: {e}", source.display());
+            copy_and_hash(source, dest)
+        }
+    }
+}
+
+/// The absolute path a referenced original is tracked by.
+fn referenced_path(source: &Path) -> Result<String, String> {
+    let path = fs::canonicalize(source).map_err(|e| format!("read failed: {e}"))?;
+    path.into_os_string()
+        .into_string()
+        .map_err(|p| format!("reference path is not valid UTF-8: {}", p.to_string_lossy()))
+}
+
+/// The `.xmp` sidecars paired with `member`, highest precedence first. One
+/// named after the whole file (darktable's `IMG_1.CR2.xmp`) is more specific
+/// than one named after the stem (Lightroom's `IMG_1.xmp`).
+///
+/// A stack detected from a burst or bracket holds every frame's sidecars, so
+/// each is matched to the frame it names rather than to the stack's primary.
+fn xmp_sidecars_for(candidate: &ImportCandidate, member: &Path) -> Vec<PathBuf> {
+    let name = |path: &Path| {
+        path.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase()
+    };
+    let stem = |path: &Path| {
+        path.file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase()
+    };
+    let (member_name, member_stem) = (name(member), stem(member));
+
+    let mut sidecars: Vec<(bool, PathBuf)> = candidate
+        .members
+        .iter()
+        .filter(|(path, role)| {
+            *role == MemberRole::Sidecar
+                && is_xmp(&path.extension().unwrap_or_default().to_string_lossy())
+        })
+        .filter_map(|(path, _)| {
+            let xmp_stem = stem(path);
+            if xmp_stem == member_name {
+                Some((true, path.clone()))
+            } else if xmp_stem == member_stem {
+                Some((false, path.clone()))
+            } else {
+                None
+            }
+        })
+        .collect();
+    sidecars.sort_by_key(|(names_file, path)| (!names_file, path.clone()));
+    sidecars.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Every `.xmp` sidecar paired with one of the candidate's members.
+///
+/// Empty for a standalone `.xmp`, which has nothing to be paired with.
+fn paired_xmp(candidate: &ImportCandidate) -> Vec<PathBuf> {
+    let mut paired: Vec<PathBuf> = Vec::new();
+    for (path, role) in &candidate.members {
+        if *role == MemberRole::Sidecar {
+            continue;
+        }
+        for xmp in xmp_sidecars_for(candidate, path) {
+            if !paired.contains(&xmp) {
+                paired.push(xmp);
+            }
+        }
+    }
+    paired
+}
+
 fn asset_type_str(t: AssetType) -> &'static str {
     match t {
         AssetType::Photo => "photo",
@@ -382,6 +928,24 @@ fn asset_type_str(t: AssetType) -> &'static str {
     }
 }
 
+fn stack_type_str(st: StackType) -> &'static str {
+    match st {
+        StackType::RawJpeg => "raw_jpeg",
+        StackType::Burst => "burst",
+        StackType::LivePhoto => "live_photo",
+        StackType::Portrait => "portrait",
+        StackType::SmartSelection => "smart_selection",
+        StackType::HdrBracket => "hdr_bracket",
+        StackType::FocusStack => "focus_stack",
+        StackType::PixelShift => "pixel_shift",
+        StackType::Panorama => "panorama",
+        StackType::Proxy => "proxy",
+        StackType::Chaptered => "chaptered",
+        StackType::DualAudio => "dual_audio",
+        StackType::Custom => "custom",
+    }
+}
+
 fn role_str(r: MemberRole) -> &'static str {
     match r {
         MemberRole::Primary => "primary",
@@ -410,9 +974,10 @@ fn now_secs() -> i64 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::ImportMode;
+    use crate::domain::DetectionMethod;
     use crate::import::executor_cancellation::CancellationToken;
     use crate::import::planner::{ImportConfig, plan};
+    use crate::import::scan::ScanResult;
     use crate::import::scanner::scan;
     use crate::library::init::init_library;
     use std::fs;
@@ -504,6 +1069,89 @@ mod tests {
         );
     }
 
+    /// Imports `photo.jpg` from a fresh source directory with `mode`, and
+    /// returns the source, the files under `media/` and the sidecar.
+    fn import_with_mode(
+        mode: ImportMode,
+        lib_dir: &TempDir,
+    ) -> (TempDir, Vec<PathBuf>, AssetSidecar) {
+        let src = TempDir::new().unwrap();
+        fs::write(src.path().join("photo.jpg"), b"jpeg kept in place").unwrap();
+
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let config = ImportConfig {
+            import_mode: mode,
+            thumbnail_format: None,
+            ..Default::default()
+        };
+        let plan_result = plan(
+            &scan(&[src.path().to_path_buf()]).unwrap(),
+            &lib.db,
+            &config,
+        )
+        .unwrap();
+        let summary = execute(
+            &plan_result,
+            &lib,
+            &config,
+            noop_event,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        assert_eq!(summary.imported_count(), 1);
+
+        let (mut sidecars, media): (Vec<PathBuf>, Vec<PathBuf>) =
+            walkdir::WalkDir::new(lib_dir.path().join("media"))
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_file())
+                .map(|e| e.into_path())
+                .partition(|p| p.extension().is_some_and(|x| x == "cbor"));
+        assert_eq!(sidecars.len(), 1);
+        let sidecar = crate::sidecar::io::read_sidecar(&sidecars.remove(0)).unwrap();
+        (src, media, sidecar)
+    }
+
+    #[test]
+    fn test_reflink_mode_imports_a_full_copy() {
+        let lib_dir = TempDir::new().unwrap();
+        let (src, media, sidecar) = import_with_mode(ImportMode::Reflink, &lib_dir);
+        assert_eq!(media.len(), 1);
+        assert_eq!(fs::read(&media[0]).unwrap(), b"jpeg kept in place");
+        assert!(src.path().join("photo.jpg").exists());
+        assert_eq!(sidecar.import_mode, ImportMode::Reflink);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hardlink_mode_shares_the_source() {
+        use std::os::unix::fs::MetadataExt;
+
+        let lib_dir = TempDir::new().unwrap();
+        let (src, media, _) = import_with_mode(ImportMode::Hardlink, &lib_dir);
+        let source = fs::metadata(src.path().join("photo.jpg")).unwrap();
+        let linked = fs::metadata(&media[0]).unwrap();
+        assert_eq!((source.dev(), source.ino()), (linked.dev(), linked.ino()));
+        assert_eq!(linked.nlink(), 2);
+    }
+
+    #[test]
+    fn test_reference_mode_leaves_original_in_place() {
+        let lib_dir = TempDir::new().unwrap();
+        let (src, media, sidecar) = import_with_mode(ImportMode::Reference, &lib_dir);
+        assert!(media.is_empty(), "nothing is copied under media/");
+
+        let original = fs::canonicalize(src.path().join("photo.jpg")).unwrap();
+        assert_eq!(
+            sidecar.reference_path.as_deref(),
+            Some(original.to_str().unwrap())
+        );
+        assert_eq!(
+            sidecar.hash_blake3,
+            blake3::hash(b"jpeg kept in place").to_hex().to_string()
+        );
+    }
+
     #[test]
     fn test_cancellation_stops_execution() {
         let src = TempDir::new().unwrap();
@@ -535,6 +1183,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parallel_import_preserves_plan_order() {
+        let src = TempDir::new().unwrap();
+        let lib_dir = TempDir::new().unwrap();
+
+        for i in 0..8 {
+            fs::write(
+                src.path().join(format!("photo_{i}.jpg")),
+                format!("parallel_{i}").as_bytes(),
+            )
+            .unwrap();
+        }
+
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let scan_result = scan(&[src.path().to_path_buf()]).unwrap();
+        let config = ImportConfig {
+            max_workers: 4,
+            ..Default::default()
+        };
+        let plan_result = plan(&scan_result, &lib.db, &config).unwrap();
+        let token = CancellationToken::new();
+        let summary = execute(&plan_result, &lib, &config, noop_event, &token).unwrap();
+
+        assert_eq!(summary.imported_count(), 8);
+        let expected: Vec<_> = plan_result
+            .actions
+            .iter()
+            .map(|(c, _)| c.primary_path().clone())
+            .collect();
+        let actual: Vec<_> = summary.outcomes.iter().map(|(p, _)| p.clone()).collect();
+        assert_eq!(actual, expected, "summary should follow plan order");
+
+        let timeline = lib.db.query_timeline(0, 100).unwrap();
+        assert_eq!(timeline.len(), 8);
+    }
+
     #[test]
     fn test_raw_jpeg_stack_import() {
         let src = TempDir::new().unwrap();
@@ -570,4 +1254,196 @@ mod tests {
             "only primary should be visible in timeline"
         );
     }
+
+    #[test]
+    fn test_xmp_sidecars_annotate_primary() {
+        let src = TempDir::new().unwrap();
+        let lib_dir = TempDir::new().unwrap();
+
+        let packet = |properties: &str| {
+            format!(
+                r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+                 <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+                  <rdf:Description rdf:about=""
+                      xmlns:dc="http://purl.org/dc/elements/1.1/"
+                      xmlns:xmp="http://ns.adobe.com/xap/1.0/" {properties}/>
+                 </rdf:RDF>
+                </x:xmpmeta>"#
+            )
+        };
+        // Lightroom: the sidecar's rating wins; the embedded caption is kept.
+        fs::write(
+            src.path().join("img_0001.jpg"),
+            packet(r#"xmp:Rating="2" dc:description="in-camera""#),
+        )
+        .unwrap();
+        fs::write(
+            src.path().join("img_0001.xmp"),
+            packet(r#"xmp:Rating="4" xmp:Label="Red" dc:subject="kyoto""#),
+        )
+        .unwrap();
+        // darktable names the sidecar after the whole file.
+        fs::write(src.path().join("img_0002.ARW"), b"raw content").unwrap();
+        fs::write(
+            src.path().join("img_0002.ARW.xmp"),
+            packet(r#"xmp:Rating="-1""#),
+        )
+        .unwrap();
+
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let scan_result = scan(&[src.path().to_path_buf()]).unwrap();
+        assert_eq!(scan_result.candidates.len(), 2);
+        let config = ImportConfig {
+            thumbnail_format: None,
+            ..Default::default()
+        };
+        let plan_result = plan(&scan_result, &lib.db, &config).unwrap();
+        let token = CancellationToken::new();
+        execute(&plan_result, &lib, &config, noop_event, &token).unwrap();
+
+        let sidecar_of = |name: &str| {
+            let row = lib
+                .db
+                .list_assets()
+                .unwrap()
+                .into_iter()
+                .find(|r| r.original_filename.as_deref() == Some(name))
+                .unwrap();
+            let uuid = Uuid::parse_str(&row.uuid).unwrap();
+            let path = sidecar_path(&lib.root, &uuid, "", row.capture_utc);
+            (row, crate::sidecar::io::read_sidecar(&path).unwrap())
+        };
+
+        let (row, sidecar) = sidecar_of("img_0001.jpg");
+        assert_eq!(row.rating, 4);
+        assert_eq!(row.caption.as_deref(), Some("in-camera"));
+        assert_eq!(lib.db.tags_for(&row.uuid).unwrap(), vec!["kyoto"]);
+        assert_eq!(sidecar.rating, 4);
+        assert_eq!(sidecar.label.as_deref(), Some("Red"));
+        assert_eq!(sidecar.caption.as_deref(), Some("in-camera"));
+
+        let (row, sidecar) = sidecar_of("img_0002.ARW");
+        assert_eq!(row.rating, 0);
+        assert_eq!(sidecar.pick, Some(crate::domain::Pick::Rejected));
+
+        // The sidecars themselves are not assets, and stay at the source.
+        let names: Vec<_> = lib
+            .db
+            .list_assets()
+            .unwrap()
+            .into_iter()
+            .filter_map(|r| r.original_filename)
+            .collect();
+        assert_eq!(names.len(), 2, "{names:?}");
+        assert!(names.iter().all(|n| !n.ends_with(".xmp")));
+        assert!(src.path().join("img_0001.xmp").exists());
+    }
+
+    #[test]
+    fn test_burst_frames_keep_their_own_xmp() {
+        let src = TempDir::new().unwrap();
+        let lib_dir = TempDir::new().unwrap();
+
+        let rated = |rating: u8| {
+            format!(
+                r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+                 <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+                  <rdf:Description rdf:about=""
+                      xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:Rating="{rating}"/>
+                 </rdf:RDF>
+                </x:xmpmeta>"#
+            )
+        };
+        let path = |name: &str| src.path().join(name);
+        fs::write(path("burst_0001.jpg"), b"first frame").unwrap();
+        fs::write(path("burst_0001.xmp"), rated(2)).unwrap();
+        fs::write(path("burst_0002.jpg"), b"second frame").unwrap();
+        fs::write(path("burst_0002.xmp"), rated(5)).unwrap();
+
+        // As `detect::build_candidate` merges the two frames' candidates.
+        let members = vec![
+            (path("burst_0001.jpg"), MemberRole::Primary),
+            (path("burst_0001.xmp"), MemberRole::Sidecar),
+            (path("burst_0002.jpg"), MemberRole::Alternate),
+            (path("burst_0002.xmp"), MemberRole::Sidecar),
+        ];
+        let scan_result = ScanResult {
+            candidates: vec![ImportCandidate {
+                source_paths: members.iter().map(|(p, _)| p.clone()).collect(),
+                detected_type: AssetType::Photo,
+                stack_type: Some(StackType::Burst),
+                detection_method: Some(DetectionMethod::BurstIdentifier),
+                detection_key: Some("burst-1".to_string()),
+                members,
+                embedded_members: Vec::new(),
+            }],
+        };
+
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let config = ImportConfig {
+            thumbnail_format: None,
+            ..Default::default()
+        };
+        let plan_result = plan(&scan_result, &lib.db, &config).unwrap();
+        let token = CancellationToken::new();
+        let summary = execute(&plan_result, &lib, &config, noop_event, &token).unwrap();
+        assert_eq!(summary.imported_count(), 4);
+
+        let mut ratings: Vec<_> = lib
+            .db
+            .list_assets()
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.original_filename.unwrap(), r.rating))
+            .collect();
+        ratings.sort();
+        assert_eq!(
+            ratings,
+            vec![
+                ("burst_0001.jpg".to_string(), 2),
+                ("burst_0002.jpg".to_string(), 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_motion_photo_extracts_video_member() {
+        let src = TempDir::new().unwrap();
+        let lib_dir = TempDir::new().unwrap();
+
+        let mut video = vec![0, 0, 0, 16];
+        video.extend_from_slice(b"ftypisom\0\0\0\0motion frames");
+        let mut still = b"\xFF\xD8 <x:xmpmeta GCamera:MicroVideoOffset=\"".to_vec();
+        still.extend_from_slice(format!("{}\"/>", video.len()).as_bytes());
+        still.extend_from_slice(b" \xFF\xD9");
+        still.extend_from_slice(&video);
+        fs::write(src.path().join("PXL_0001.MP.jpg"), &still).unwrap();
+
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let scan_result = scan(&[src.path().to_path_buf()]).unwrap();
+        let config = ImportConfig::default();
+        let plan_result = plan(&scan_result, &lib.db, &config).unwrap();
+        let token = CancellationToken::new();
+        let summary = execute(&plan_result, &lib, &config, noop_event, &token).unwrap();
+
+        assert_eq!(summary.imported_count(), 2);
+        let timeline = lib.db.query_timeline(0, 100).unwrap();
+        assert_eq!(timeline.len(), 1, "the video member should be hidden");
+
+        let media: Vec<_> = walkdir::WalkDir::new(lib_dir.path().join("media"))
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .map(|e| e.into_path())
+            .filter(|p| p.extension().is_some_and(|e| e == "jpg" || e == "mp4"))
+            .collect();
+        assert_eq!(media.len(), 2);
+        for path in media {
+            let bytes = fs::read(&path).unwrap();
+            if path.extension().unwrap() == "jpg" {
+                assert_eq!(bytes, still, "original must stay byte-identical");
+            } else {
+                assert_eq!(bytes, video);
+            }
+        }
+    }
 }
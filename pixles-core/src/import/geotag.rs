@@ -0,0 +1,172 @@
+use std::fs;
+use std::path::PathBuf;
+
+use pixles_media::metadata::geo::GpsLocation;
+use pixles_media::metadata::tracklog::{TrackLog, TrackLogError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::exif::TimezoneResolution;
+
+/// Default for [`TrackLogConfig::max_gap_secs`]: loggers that record every
+/// few seconds still leave gaps of minutes indoors or in tunnels.
+pub const DEFAULT_TRACK_MAX_GAP_SECS: i64 = 30 * 60;
+
+/// GPX/KML track logs that place imports carrying no GPS position of their
+/// own, by matching capture times against the logged fixes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackLogConfig {
+    /// Track log files, merged into one log. GPX and KML are told apart by
+    /// content.
+    pub files: Vec<PathBuf>,
+    /// Longest time, in seconds, between a capture and the fixes it is
+    /// placed from. Captures farther from any fix stay without a position.
+    pub max_gap_secs: i64,
+    /// Added to every capture time before it is looked up, to correct a
+    /// camera clock that was off. A capture time with no known zone is
+    /// taken as UTC, so for such cameras this also includes the clock's UTC
+    /// offset, negated (`-9h` for a camera set to Tokyo time).
+    pub time_offset_secs: i64,
+}
+
+#[derive(Debug, Error)]
+pub enum GeotagError {
+    #[error("cannot read track log {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("invalid track log {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: TrackLogError,
+    },
+}
+
+impl TrackLogConfig {
+    pub fn new(files: Vec<PathBuf>) -> Self {
+        Self {
+            files,
+            max_gap_secs: DEFAULT_TRACK_MAX_GAP_SECS,
+            time_offset_secs: 0,
+        }
+    }
+
+    /// Reads and merges every file in [`files`](Self::files).
+    pub fn load(&self) -> Result<Geotagger, GeotagError> {
+        let mut log = TrackLog::default();
+        for path in &self.files {
+            let src = fs::read_to_string(path).map_err(|source| GeotagError::Io {
+                path: path.clone(),
+                source,
+            })?;
+            let parsed = TrackLog::parse(&src).map_err(|source| GeotagError::Parse {
+                path: path.clone(),
+                source,
+            })?;
+            log.extend(parsed);
+        }
+        Ok(Geotagger {
+            log,
+            max_gap_secs: self.max_gap_secs,
+            time_offset_secs: self.time_offset_secs,
+        })
+    }
+}
+
+/// Loaded track logs, ready to place captures.
+#[derive(Debug, Clone)]
+pub struct Geotagger {
+    log: TrackLog,
+    max_gap_secs: i64,
+    time_offset_secs: i64,
+}
+
+impl Geotagger {
+    /// Where the logs place a capture resolved as `tz`: at its UTC time if
+    /// known, otherwise at its wall-clock time read as UTC.
+    pub fn position_for(&self, tz: &TimezoneResolution) -> Option<GpsLocation> {
+        let time = tz.capture_utc.or(tz.capture_timestamp)?;
+        self.log
+            .position_at(time.checked_add(self.time_offset_secs)?, self.max_gap_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::CaptureTzSource;
+    use tempfile::TempDir;
+
+    const GPX: &str = r#"<gpx version="1.1" xmlns="http://www.topografix.com/GPX/1/1">
+ <trk><trkseg>
+  <trkpt lat="35.0000" lon="135.7000"><time>2024-07-15T01:30:00Z</time></trkpt>
+  <trkpt lat="35.0100" lon="135.7100"><time>2024-07-15T01:40:00Z</time></trkpt>
+ </trkseg></trk>
+</gpx>"#;
+
+    /// 2024-07-15 01:35:00 UTC.
+    const MIDWAY: i64 = 1_721_007_300;
+
+    fn resolution(capture_timestamp: i64, capture_utc: Option<i64>) -> TimezoneResolution {
+        TimezoneResolution {
+            capture_timestamp: Some(capture_timestamp),
+            capture_utc,
+            capture_tz: capture_utc.map(|_| "+09:00".to_string()),
+            capture_tz_source: Some(match capture_utc {
+                Some(_) => CaptureTzSource::OffsetExif,
+                None => CaptureTzSource::Floating,
+            }),
+            tz_db_version: None,
+        }
+    }
+
+    #[test]
+    fn test_position_for_capture_times() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("walk.gpx");
+        fs::write(&path, GPX).unwrap();
+        let mut config = TrackLogConfig::new(vec![path]);
+        let geotagger = config.load().unwrap();
+
+        // A camera that recorded its offset is looked up by UTC time.
+        let tokyo = MIDWAY + 9 * 3600;
+        let p = geotagger
+            .position_for(&resolution(tokyo, Some(MIDWAY)))
+            .unwrap();
+        assert!((p.latitude - 35.005).abs() < 1e-9);
+
+        // A floating one only with the offset to UTC supplied.
+        assert!(geotagger.position_for(&resolution(tokyo, None)).is_none());
+        config.time_offset_secs = -9 * 3600;
+        let p = config
+            .load()
+            .unwrap()
+            .position_for(&resolution(tokyo, None))
+            .unwrap();
+        assert!((p.longitude - 135.705).abs() < 1e-9);
+
+        // No capture time, no position.
+        let undated = TimezoneResolution {
+            capture_timestamp: None,
+            ..resolution(0, None)
+        };
+        assert!(geotagger.position_for(&undated).is_none());
+    }
+
+    #[test]
+    fn test_load_names_the_bad_file() {
+        let dir = TempDir::new().unwrap();
+        let good = dir.path().join("walk.gpx");
+        let bad = dir.path().join("notes.kml");
+        fs::write(&good, GPX).unwrap();
+        fs::write(&bad, "<kml><Document/></kml>").unwrap();
+
+        let err = TrackLogConfig::new(vec![good, bad.clone()])
+            .load()
+            .unwrap_err();
+        assert!(matches!(&err, GeotagError::Parse { path, .. } if *path == bad));
+        assert!(err.to_string().contains("notes.kml"));
+    }
+}
@@ -45,7 +45,8 @@ pub fn is_supported_extension(ext: &str) -> bool {
 /// RAW+primary pairs → `RawJpeg` stack.
 /// RAW-only (no primary) → standalone (no stack_hint).
 /// Primary-only → standalone.
-/// XMP → paired with same-stem RAW or primary; standalone if no match.
+/// XMP → paired with same-stem RAW or primary (`img.xmp` or `img.ARW.xmp`);
+/// standalone if no match.
 /// Ungrouped extensions → standalone Sidecar.
 ///
 /// Files in different parent directories are never grouped together.
@@ -55,7 +56,7 @@ pub fn group_by_stem(files: &[PathBuf]) -> Vec<ImportCandidate> {
 
     for path in files {
         let parent = path.parent().map(PathBuf::from);
-        let stem = path
+        let mut stem = path
             .file_stem()
             .unwrap_or_default()
             .to_string_lossy()
@@ -65,6 +66,14 @@ pub fn group_by_stem(files: &[PathBuf]) -> Vec<ImportCandidate> {
             .unwrap_or_default()
             .to_string_lossy()
             .to_lowercase();
+        // darktable names sidecars after the whole file (`IMG_1.CR2.xmp`).
+        if is_xmp(&ext)
+            && let Some((inner, media_ext)) = stem.rsplit_once('.')
+            && is_supported_extension(media_ext)
+            && !is_xmp(media_ext)
+        {
+            stem = inner.to_string();
+        }
         by_stem
             .entry((parent, stem))
             .or_default()
@@ -120,6 +129,7 @@ pub fn group_by_stem(files: &[PathBuf]) -> Vec<ImportCandidate> {
                 detection_method: Some(DetectionMethod::FilenameStem),
                 detection_key: Some(stem.clone()),
                 members,
+                embedded_members: Vec::new(),
             });
         } else if !raws.is_empty() {
             // RAW-only (with optional same-stem XMP)
@@ -136,6 +146,7 @@ pub fn group_by_stem(files: &[PathBuf]) -> Vec<ImportCandidate> {
                     detection_method: None,
                     detection_key: None,
                     members,
+                    embedded_members: Vec::new(),
                 });
             }
         } else if !primaries.is_empty() {
@@ -153,6 +164,7 @@ pub fn group_by_stem(files: &[PathBuf]) -> Vec<ImportCandidate> {
                     detection_method: None,
                     detection_key: None,
                     members,
+                    embedded_members: Vec::new(),
                 });
             }
         } else if !videos.is_empty() {
@@ -165,6 +177,7 @@ pub fn group_by_stem(files: &[PathBuf]) -> Vec<ImportCandidate> {
                     detection_method: None,
                     detection_key: None,
                     members: vec![(video.clone(), MemberRole::Primary)],
+                    embedded_members: Vec::new(),
                 });
             }
         } else if !xmps.is_empty() {
@@ -177,6 +190,7 @@ pub fn group_by_stem(files: &[PathBuf]) -> Vec<ImportCandidate> {
                     detection_method: None,
                     detection_key: None,
                     members: vec![(xmp.clone(), MemberRole::Sidecar)],
+                    embedded_members: Vec::new(),
                 });
             }
         }
@@ -190,6 +204,7 @@ pub fn group_by_stem(files: &[PathBuf]) -> Vec<ImportCandidate> {
                 detection_method: None,
                 detection_key: None,
                 members: vec![(other.clone(), MemberRole::Sidecar)],
+                embedded_members: Vec::new(),
             });
         }
     }
@@ -205,18 +220,7 @@ mod tests {
     use crate::domain::{MemberRole, StackType};
 
     fn paths(names: &[&str]) -> Vec<PathBuf> {
-        names.iter().map(|n| PathBuf::from(n)).collect()
-    }
-
-    fn find_candidate<'a>(
-        candidates: &'a [ImportCandidate],
-        primary: &str,
-    ) -> Option<&'a ImportCandidate> {
-        candidates.iter().find(|c| {
-            c.members
-                .iter()
-                .any(|(p, r)| p.to_string_lossy() == primary && *r == MemberRole::Primary)
-        })
+        names.iter().map(PathBuf::from).collect()
     }
 
     #[test]
@@ -287,6 +291,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_xmp_named_after_whole_file() {
+        let files = paths(&[
+            "/photos/img.ARW",
+            "/photos/img.ARW.xmp",
+            "/photos/other.txt.xmp",
+        ]);
+        let candidates = group_by_stem(&files);
+        assert_eq!(candidates.len(), 2);
+        let raw = candidates
+            .iter()
+            .find(|c| c.detected_type == AssetType::Photo)
+            .unwrap();
+        assert_eq!(raw.members.len(), 2);
+        assert_eq!(raw.members[1].1, MemberRole::Sidecar);
+    }
+
     #[test]
     fn test_xmp_standalone() {
         let files = paths(&["/photos/img.xmp"]);
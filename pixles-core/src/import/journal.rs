@@ -0,0 +1,707 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::db::rows::StackMemberRow;
+use crate::domain::{ImportMode, MemberRole};
+use crate::import::geotag::TrackLogConfig;
+use crate::import::planner::{
+    ImportActionPlan, ImportConfig, ImportDecision, default_max_workers, plan,
+};
+use crate::import::progress::ImportOutcome;
+use crate::import::scan::{ImportCandidate, ScanResult};
+use crate::library::library::Library;
+use crate::library::paths::{import_journal_dir, tmp_path};
+use crate::library::rebuild::{asset_row_from_sidecar, member_role_str};
+use crate::library::thumbnails::remove_thumbnails;
+use crate::sidecar::io::read_sidecar;
+use crate::utils::hash::get_file_hash;
+
+const HEADER_FILE: &str = "plan.cbor";
+const LOG_FILE: &str = "log.cbor";
+
+#[derive(Debug, Error)]
+pub enum JournalError {
+    #[error("no import journal found for run {0}")]
+    NotFound(String),
+
+    #[error("import run {0} has already finished")]
+    AlreadyFinished(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("database error: {0}")]
+    Db(#[from] rusqlite::Error),
+
+    #[error("CBOR error: {0}")]
+    Cbor(String),
+
+    #[error("re-planning failed: {0}")]
+    Plan(String),
+}
+
+/// Written once when the run starts: what was asked for and what was planned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalHeader {
+    pub run_id: String,
+    pub created_at: i64,
+    pub source_paths: Vec<PathBuf>,
+    pub import_mode: ImportMode,
+    pub force_reimport_duplicates: bool,
+    #[serde(default)]
+    pub near_duplicate_distance: Option<u32>,
+    #[serde(default)]
+    pub track_log: Option<TrackLogConfig>,
+    pub target_album_id: Option<String>,
+    pub actions: Vec<(ImportCandidate, ImportDecision)>,
+}
+
+/// A member whose media file and sidecar are in place but whose index rows
+/// may not be.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StagedMember {
+    pub source_path: PathBuf,
+    pub uuid: String,
+    pub media_path: PathBuf,
+    pub sidecar_path: PathBuf,
+    /// `media_path` is an original imported by reference, which stays where
+    /// it is even if the member is rolled back.
+    #[serde(default)]
+    pub referenced: bool,
+}
+
+/// One entry of the append-only log. Indices refer to `JournalHeader::actions`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JournalRecord {
+    Staged {
+        index: usize,
+        members: Vec<StagedMember>,
+    },
+    Completed {
+        index: usize,
+        outcomes: Vec<(PathBuf, ImportOutcome)>,
+    },
+    Finished {
+        at: i64,
+    },
+}
+
+/// Persistent record of one import run under `.library/imports/{run_id}/`.
+///
+/// `plan.cbor` holds the [`JournalHeader`]; `log.cbor` is a sequence of CBOR
+/// records appended as candidates are staged and completed. Each record is
+/// written in a single call and synced to disk before the import moves on,
+/// so it survives the process dying or the machine losing power; a record
+/// torn part-way is discarded on resume.
+pub struct ImportJournal {
+    header: JournalHeader,
+    log: File,
+    /// Maps indices of the plan being executed to indices in the header.
+    index_map: Vec<usize>,
+}
+
+/// State recovered from a journal by [`resume_import`].
+pub struct ResumedImport {
+    pub journal: ImportJournal,
+    /// Candidates that still need to run, re-planned against the current index.
+    pub plan: ImportActionPlan,
+    /// Config of the original run. `max_workers` is reset to the default.
+    pub config: ImportConfig,
+    /// Outcomes recorded before the interruption, in plan order.
+    pub already_done: Vec<(PathBuf, ImportOutcome)>,
+}
+
+impl ImportJournal {
+    /// Start a journal for a fresh run of `plan`.
+    pub fn create(
+        library_root: &Path,
+        source_paths: &[PathBuf],
+        plan: &ImportActionPlan,
+        config: &ImportConfig,
+    ) -> Result<Self, JournalError> {
+        let run_id = Uuid::now_v7().to_string();
+        let dir = import_journal_dir(library_root, &run_id);
+        fs::create_dir_all(&dir)?;
+
+        let header = JournalHeader {
+            run_id,
+            created_at: now_secs(),
+            source_paths: source_paths.to_vec(),
+            import_mode: config.import_mode,
+            force_reimport_duplicates: config.force_reimport_duplicates,
+            near_duplicate_distance: config.near_duplicate_distance,
+            track_log: config.track_log.clone(),
+            target_album_id: config.target_album_id.clone(),
+            actions: plan.actions.clone(),
+        };
+        write_header(&dir.join(HEADER_FILE), &header)?;
+
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG_FILE))?;
+
+        Ok(Self {
+            index_map: (0..header.actions.len()).collect(),
+            header,
+            log,
+        })
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.header.run_id
+    }
+
+    pub fn header(&self) -> &JournalHeader {
+        &self.header
+    }
+
+    /// Record that candidate `index` of the executing plan has its files in
+    /// place and is about to be indexed.
+    pub(crate) fn record_staged(
+        &mut self,
+        index: usize,
+        members: Vec<StagedMember>,
+    ) -> Result<(), JournalError> {
+        self.append(&JournalRecord::Staged {
+            index: self.index_map[index],
+            members,
+        })
+    }
+
+    /// Record the final outcomes of candidate `index` of the executing plan.
+    pub(crate) fn record_completed(
+        &mut self,
+        index: usize,
+        outcomes: &[(PathBuf, ImportOutcome)],
+    ) -> Result<(), JournalError> {
+        self.append(&JournalRecord::Completed {
+            index: self.index_map[index],
+            outcomes: outcomes.to_vec(),
+        })
+    }
+
+    /// Mark the run as finished. A finished run cannot be resumed.
+    pub fn finish(mut self) -> Result<(), JournalError> {
+        self.append(&JournalRecord::Finished { at: now_secs() })
+    }
+
+    fn append(&mut self, record: &JournalRecord) -> Result<(), JournalError> {
+        let mut buf = Vec::new();
+        ciborium::ser::into_writer(record, &mut buf)
+            .map_err(|e| JournalError::Cbor(e.to_string()))?;
+        self.log.write_all(&buf)?;
+        self.log.sync_data()?;
+        Ok(())
+    }
+}
+
+/// Reopen the journal of an interrupted run.
+///
+/// Candidates that completed are reported in `already_done` and not run
+/// again. A candidate that was staged but never completed is reconciled
+/// against the index from its staged members: if none made it into the
+/// database, its files are removed and it is re-planned with everything else
+/// that had not run yet. Otherwise the members still missing are indexed
+/// from their sidecars, so a stack is never left half-imported.
+pub fn resume_import(library: &Library, run_id: &str) -> Result<ResumedImport, JournalError> {
+    // Run IDs are UUIDs; rejecting anything else also keeps `run_id` from
+    // escaping the journal directory.
+    if Uuid::parse_str(run_id).is_err() {
+        return Err(JournalError::NotFound(run_id.to_string()));
+    }
+    let dir = import_journal_dir(&library.root, run_id);
+    let header_path = dir.join(HEADER_FILE);
+    if !header_path.exists() {
+        return Err(JournalError::NotFound(run_id.to_string()));
+    }
+    let header = read_header(&header_path)?;
+
+    let log_path = dir.join(LOG_FILE);
+    let (records, valid_len) = read_log(&log_path)?;
+
+    let mut staged: BTreeMap<usize, Vec<StagedMember>> = BTreeMap::new();
+    let mut completed: BTreeMap<usize, Vec<(PathBuf, ImportOutcome)>> = BTreeMap::new();
+    for record in records {
+        match record {
+            JournalRecord::Staged { index, members } => {
+                staged.insert(index, members);
+            }
+            JournalRecord::Completed { index, outcomes } => {
+                completed.insert(index, outcomes);
+            }
+            JournalRecord::Finished { .. } => {
+                return Err(JournalError::AlreadyFinished(run_id.to_string()));
+            }
+        }
+    }
+
+    // Drop a torn trailing record before appending after it.
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)?;
+    log.set_len(valid_len)?;
+
+    let config = ImportConfig {
+        import_mode: header.import_mode,
+        target_album_id: header.target_album_id.clone(),
+        force_reimport_duplicates: header.force_reimport_duplicates,
+        near_duplicate_distance: header.near_duplicate_distance,
+        track_log: header.track_log.clone(),
+        max_workers: default_max_workers(),
+        thumbnail_format: Some(library.config().thumbnail_format),
+    };
+
+    let mut journal = ImportJournal {
+        header,
+        log,
+        index_map: Vec::new(),
+    };
+
+    for (index, members) in staged {
+        if completed.contains_key(&index) {
+            continue;
+        }
+        if let Some(outcomes) = reconcile_staged(library, &config, &members)? {
+            journal.append(&JournalRecord::Completed {
+                index,
+                outcomes: outcomes.clone(),
+            })?;
+            completed.insert(index, outcomes);
+        }
+    }
+
+    let remaining: Vec<usize> = (0..journal.header.actions.len())
+        .filter(|i| !completed.contains_key(i))
+        .collect();
+    let scan = ScanResult {
+        candidates: remaining
+            .iter()
+            .map(|&i| journal.header.actions[i].0.clone())
+            .collect(),
+    };
+    let remaining_plan =
+        plan(&scan, &library.db, &config).map_err(|e| JournalError::Plan(e.to_string()))?;
+    journal.index_map = remaining;
+
+    Ok(ResumedImport {
+        journal,
+        plan: remaining_plan,
+        config,
+        already_done: completed.into_values().flatten().collect(),
+    })
+}
+
+/// Returns the candidate's outcomes if any of its members was indexed before
+/// the interruption, indexing the rest from their sidecars. Otherwise removes
+/// the members' files and returns `None` so the candidate is planned again.
+fn reconcile_staged(
+    library: &Library,
+    config: &ImportConfig,
+    members: &[StagedMember],
+) -> Result<Option<Vec<(PathBuf, ImportOutcome)>>, JournalError> {
+    let rows = members
+        .iter()
+        .map(|m| library.db.find_by_uuid(&m.uuid))
+        .collect::<Result<Vec<_>, _>>()?;
+    if rows.iter().all(Option::is_none) {
+        for member in members {
+            remove_member_files(library, member);
+        }
+        return Ok(None);
+    }
+
+    // Phase B inserts the stack before any member, so an indexed member
+    // carries the candidate's stack.
+    let stack_id = rows.iter().flatten().find_map(|row| row.stack_id.clone());
+    let now = now_secs();
+    let mut outcomes = Vec::new();
+    for (seq, (member, row)) in members.iter().zip(&rows).enumerate() {
+        let outcome = match row {
+            Some(_) => ImportOutcome::Imported,
+            None => match index_staged(library, config, member, seq, stack_id.as_deref(), now)? {
+                Ok(()) => ImportOutcome::Imported,
+                Err(outcome) => {
+                    remove_member_files(library, member);
+                    outcome
+                }
+            },
+        };
+        if matches!(outcome, ImportOutcome::Imported)
+            && matches!(config.import_mode, ImportMode::Move)
+        {
+            let _ = fs::remove_file(&member.source_path);
+        }
+        outcomes.push((member.source_path.clone(), outcome));
+    }
+    Ok(Some(outcomes))
+}
+
+/// Indexes a staged member that Phase B did not reach, as Phase B would
+/// have. The outer error is the index failing; the inner one is the member's
+/// files no longer being usable, with the outcome to report for it.
+fn index_staged(
+    library: &Library,
+    config: &ImportConfig,
+    member: &StagedMember,
+    seq: usize,
+    stack_id: Option<&str>,
+    now: i64,
+) -> Result<Result<(), ImportOutcome>, JournalError> {
+    let sidecar = match read_sidecar(&member.sidecar_path) {
+        Ok(sidecar) => sidecar,
+        Err(e) => return Ok(Err(ImportOutcome::CorruptUnreadable(e.to_string()))),
+    };
+    match get_file_hash(&member.media_path) {
+        Ok(hash) if hash == sidecar.hash_blake3 => {}
+        Ok(_) => return Ok(Err(ImportOutcome::CorruptTransfer)),
+        Err(e) => return Ok(Err(ImportOutcome::CorruptUnreadable(e.to_string()))),
+    }
+
+    let role = sidecar
+        .stack_hint
+        .as_ref()
+        .map_or(MemberRole::Primary, |hint| hint.member_role);
+    let is_primary = role == MemberRole::Primary || seq == 0;
+    let mut row = asset_row_from_sidecar(&sidecar);
+    row.stack_id = stack_id.map(str::to_string);
+    row.is_stack_hidden = stack_id.is_some() && !is_primary;
+    library.db.insert_asset(&row)?;
+    library.db.set_asset_tags(&row.uuid, &sidecar.tags)?;
+
+    if let Some(stack_id) = stack_id {
+        let _ = library.db.insert_stack_member(&StackMemberRow {
+            id: format!("{stack_id}#{seq}"),
+            stack_id: stack_id.to_string(),
+            asset_id: row.uuid.clone(),
+            sequence_order: seq as i64,
+            member_role: member_role_str(role).to_string(),
+            created_at: now,
+        });
+    }
+    // Phase B adds the primary to the album last, so it was not reached.
+    if is_primary && let Some(album_id) = &config.target_album_id {
+        library.db.append_album_asset(album_id, &row.uuid, now)?;
+    }
+    Ok(Ok(()))
+}
+
+fn remove_member_files(library: &Library, member: &StagedMember) {
+    if !member.referenced {
+        let _ = fs::remove_file(&member.media_path);
+    }
+    let _ = fs::remove_file(&member.sidecar_path);
+    if let Ok(uuid) = Uuid::parse_str(&member.uuid) {
+        remove_thumbnails(&library.root, &uuid);
+    }
+}
+
+fn write_header(path: &Path, header: &JournalHeader) -> Result<(), JournalError> {
+    let tmp = tmp_path(path);
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    ciborium::ser::into_writer(header, &mut writer)
+        .map_err(|e| JournalError::Cbor(e.to_string()))?;
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn read_header(path: &Path) -> Result<JournalHeader, JournalError> {
+    let file = File::open(path)?;
+    ciborium::de::from_reader(BufReader::new(file)).map_err(|e| JournalError::Cbor(e.to_string()))
+}
+
+/// Reads every complete record, returning them with the byte length they span.
+fn read_log(path: &Path) -> Result<(Vec<JournalRecord>, u64), JournalError> {
+    let mut records = Vec::new();
+    let Ok(file) = File::open(path) else {
+        return Ok((records, 0));
+    };
+    let mut reader = BufReader::new(file);
+    let mut valid_len = 0;
+    while !reader.fill_buf()?.is_empty() {
+        match ciborium::de::from_reader::<JournalRecord, _>(&mut reader) {
+            Ok(record) => {
+                records.push(record);
+                valid_len = reader.stream_position()?;
+            }
+            Err(e) => {
+                log::warn!(
+                    "Ignoring torn import journal record in {}: {e}",
+                    path.display()
+                );
+                break;
+            }
+        }
+    }
+    Ok((records, valid_len))
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+// ── Tests ────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::executor::{execute, execute_journaled};
+    use crate::import::executor_cancellation::CancellationToken;
+    use crate::import::progress::ImportProgressEvent;
+    use crate::import::scanner::scan;
+    use crate::library::init::init_library;
+    use tempfile::TempDir;
+
+    fn noop_event(_: ImportProgressEvent) {}
+
+    fn write_photos(dir: &Path, count: usize) {
+        for i in 0..count {
+            fs::write(
+                dir.join(format!("photo_{i}.jpg")),
+                format!("journal_{i}").as_bytes(),
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_finished_run_cannot_be_resumed() {
+        let src = TempDir::new().unwrap();
+        let lib_dir = TempDir::new().unwrap();
+        write_photos(src.path(), 2);
+
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let sources = vec![src.path().to_path_buf()];
+        let config = ImportConfig::default();
+        let plan_result = plan(&scan(&sources).unwrap(), &lib.db, &config).unwrap();
+
+        let mut journal =
+            ImportJournal::create(&lib.root, &sources, &plan_result, &config).unwrap();
+        let run_id = journal.run_id().to_string();
+        let token = CancellationToken::new();
+        execute_journaled(
+            &plan_result,
+            &lib,
+            &config,
+            noop_event,
+            &token,
+            &mut journal,
+        )
+        .unwrap();
+        journal.finish().unwrap();
+
+        assert!(matches!(
+            resume_import(&lib, &run_id),
+            Err(JournalError::AlreadyFinished(_))
+        ));
+    }
+
+    #[test]
+    fn test_resume_continues_cancelled_run() {
+        let src = TempDir::new().unwrap();
+        let lib_dir = TempDir::new().unwrap();
+        write_photos(src.path(), 3);
+
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let sources = vec![src.path().to_path_buf()];
+        let config = ImportConfig {
+            max_workers: 1,
+            ..Default::default()
+        };
+        let plan_result = plan(&scan(&sources).unwrap(), &lib.db, &config).unwrap();
+
+        // Only the first candidate runs before the "interruption".
+        let mut journal =
+            ImportJournal::create(&lib.root, &sources, &plan_result, &config).unwrap();
+        let run_id = journal.run_id().to_string();
+        let partial = ImportActionPlan {
+            actions: plan_result.actions[..1].to_vec(),
+            counts: Default::default(),
+        };
+        let token = CancellationToken::new();
+        execute_journaled(&partial, &lib, &config, noop_event, &token, &mut journal).unwrap();
+        drop(journal);
+
+        let mut resumed = resume_import(&lib, &run_id).unwrap();
+        assert_eq!(resumed.already_done.len(), 1);
+        assert_eq!(resumed.plan.actions.len(), 2);
+
+        let token = CancellationToken::new();
+        let rest = execute_journaled(
+            &resumed.plan,
+            &lib,
+            &resumed.config,
+            noop_event,
+            &token,
+            &mut resumed.journal,
+        )
+        .unwrap();
+        resumed.journal.finish().unwrap();
+
+        assert_eq!(rest.imported_count(), 2);
+        assert_eq!(lib.db.query_timeline(0, 100).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_staged_but_unindexed_candidate_is_rolled_back_and_replanned() {
+        let src = TempDir::new().unwrap();
+        let lib_dir = TempDir::new().unwrap();
+        write_photos(src.path(), 1);
+
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let sources = vec![src.path().to_path_buf()];
+        let config = ImportConfig::default();
+        let plan_result = plan(&scan(&sources).unwrap(), &lib.db, &config).unwrap();
+
+        // Simulate a crash between Phase A and Phase B.
+        let mut journal =
+            ImportJournal::create(&lib.root, &sources, &plan_result, &config).unwrap();
+        let run_id = journal.run_id().to_string();
+        let orphan = lib.root.join("media/orphan.jpg");
+        fs::write(&orphan, b"staged").unwrap();
+        journal
+            .record_staged(
+                0,
+                vec![StagedMember {
+                    source_path: plan_result.actions[0].0.primary_path().clone(),
+                    uuid: Uuid::now_v7().to_string(),
+                    media_path: orphan.clone(),
+                    sidecar_path: lib.root.join("media/orphan.cbor"),
+                    referenced: false,
+                }],
+            )
+            .unwrap();
+        drop(journal);
+
+        let resumed = resume_import(&lib, &run_id).unwrap();
+        assert!(!orphan.exists(), "unindexed staged file should be removed");
+        assert!(resumed.already_done.is_empty());
+        assert_eq!(resumed.plan.actions.len(), 1);
+        assert!(matches!(resumed.plan.actions[0].1, ImportDecision::Import));
+    }
+
+    #[test]
+    fn test_partly_indexed_stack_is_completed_from_journal() {
+        let src = TempDir::new().unwrap();
+        let lib_dir = TempDir::new().unwrap();
+        fs::write(src.path().join("img_0001.jpg"), b"jpeg content").unwrap();
+        fs::write(src.path().join("img_0001.ARW"), b"raw content").unwrap();
+
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let sources = vec![src.path().to_path_buf()];
+        let config = ImportConfig {
+            thumbnail_format: None,
+            ..Default::default()
+        };
+        let plan_result = plan(&scan(&sources).unwrap(), &lib.db, &config).unwrap();
+        let mut journal =
+            ImportJournal::create(&lib.root, &sources, &plan_result, &config).unwrap();
+        let run_id = journal.run_id().to_string();
+        execute(
+            &plan_result,
+            &lib,
+            &config,
+            noop_event,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+
+        // Simulate a crash in Phase B after the primary was indexed.
+        // Staged members are in Phase B order: the stack's sequence order.
+        let stack_id = lib.db.list_assets().unwrap()[0].stack_id.clone().unwrap();
+        let rows: Vec<_> = lib
+            .db
+            .list_stack_members(&stack_id)
+            .unwrap()
+            .iter()
+            .map(|m| lib.db.find_by_uuid(&m.asset_id).unwrap().unwrap())
+            .collect();
+        let raw = rows.iter().position(|row| row.is_stack_hidden).unwrap();
+        let members = rows
+            .iter()
+            .map(|row| {
+                let (uuid, sidecar_path) = lib.locate_sidecar(&row.uuid).unwrap();
+                let name = row.original_filename.clone().unwrap();
+                let ext = Path::new(&name).extension().unwrap().to_ascii_lowercase();
+                StagedMember {
+                    source_path: src.path().join(&name),
+                    uuid,
+                    media_path: sidecar_path.with_extension(ext),
+                    sidecar_path,
+                    referenced: false,
+                }
+            })
+            .collect();
+        journal.record_staged(0, members).unwrap();
+        drop(journal);
+        lib.db.delete_asset(&rows[raw].uuid).unwrap();
+
+        let resumed = resume_import(&lib, &run_id).unwrap();
+        assert!(resumed.plan.actions.is_empty());
+        assert_eq!(resumed.already_done.len(), 2);
+        assert!(
+            resumed
+                .already_done
+                .iter()
+                .all(|(_, outcome)| matches!(outcome, ImportOutcome::Imported))
+        );
+        let restored = lib.db.find_by_uuid(&rows[raw].uuid).unwrap().unwrap();
+        assert_eq!(restored.stack_id.as_deref(), Some(stack_id.as_str()));
+        assert!(restored.is_stack_hidden);
+        assert_eq!(lib.db.list_stack_members(&stack_id).unwrap().len(), 2);
+        assert_eq!(lib.db.query_timeline(0, 100).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_torn_record_is_ignored() {
+        let tmp = TempDir::new().unwrap();
+        let log_path = tmp.path().join(LOG_FILE);
+
+        let mut buf = Vec::new();
+        ciborium::ser::into_writer(
+            &JournalRecord::Completed {
+                index: 0,
+                outcomes: vec![(PathBuf::from("/a.jpg"), ImportOutcome::Imported)],
+            },
+            &mut buf,
+        )
+        .unwrap();
+        let whole = buf.len() as u64;
+        ciborium::ser::into_writer(&JournalRecord::Finished { at: 1 }, &mut buf).unwrap();
+        buf.truncate(buf.len() - 1);
+        fs::write(&log_path, &buf).unwrap();
+
+        let (records, valid_len) = read_log(&log_path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(valid_len, whole);
+    }
+
+    #[test]
+    fn test_unknown_run_id() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        assert!(matches!(
+            resume_import(&lib, "../../etc"),
+            Err(JournalError::NotFound(_))
+        ));
+        assert!(matches!(
+            resume_import(&lib, &Uuid::now_v7().to_string()),
+            Err(JournalError::NotFound(_))
+        ));
+    }
+}
@@ -1,17 +1,30 @@
+pub mod detect;
 pub mod executor;
 pub mod executor_cancellation;
+pub mod geotag;
 pub mod group;
+pub mod journal;
+pub mod plan;
 pub mod planner;
 pub mod progress;
 pub mod scan;
 pub mod scanner;
 pub mod special;
+pub mod upload;
 
-pub use executor::execute;
+pub use detect::detect_sequences;
+pub use executor::{execute, execute_journaled};
 pub use executor_cancellation::CancellationToken;
+pub use geotag::{DEFAULT_TRACK_MAX_GAP_SECS, GeotagError, Geotagger, TrackLogConfig};
 pub use group::{PRIMARY_EXTS, RAW_EXTS, VIDEO_EXTS, group_by_stem, is_supported_extension};
-pub use planner::{ImportActionPlan, ImportConfig, ImportDecision, PlanCounts, plan};
+pub use journal::{ImportJournal, JournalError, ResumedImport, resume_import};
+pub use plan::{ImportExecutionPlan, ImportExecutionPlanError};
+pub use planner::{
+    DEFAULT_NEAR_DUPLICATE_DISTANCE, ImportActionPlan, ImportConfig, ImportDecision, PlanCounts,
+    default_max_workers, plan,
+};
 pub use progress::{ImportExecutionSummary, ImportOutcome, ImportProgressEvent};
 pub use scan::{ImportCandidate, ScanResult};
 pub use scanner::scan as scan_paths;
 pub use special::{SpecialDirectoryStatus, SpecialFileStatus, SpecialStatus};
+pub use upload::{UploadExecutionPlan, UploadPriorityConfig, get_upload_ordering};
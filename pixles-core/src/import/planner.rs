@@ -1,16 +1,43 @@
 use std::path::Path;
 
+use pixles_media::image::phash::hamming_distance;
+use serde::{Deserialize, Serialize};
+
 use crate::db::DatabaseDriver;
-use crate::domain::ImportMode;
+use crate::domain::{ImportMode, ThumbnailFormat};
+use crate::import::geotag::TrackLogConfig;
 use crate::import::scan::{ImportCandidate, ScanResult};
+use crate::library::error::LibraryError;
+use crate::library::thumbnails::perceptual_hash;
+use crate::utils::hash::get_file_hash;
+
+/// Largest perceptual-hash distance, in bits, at which two images are
+/// treated as the same photo by default. Resized and recompressed copies
+/// usually land within a few bits; unrelated photos are around 32 apart.
+pub const DEFAULT_NEAR_DUPLICATE_DISTANCE: u32 = 6;
 
 /// Configuration for an import run.
 #[derive(Debug, Clone)]
 pub struct ImportConfig {
     pub import_mode: ImportMode,
+    /// ID of an existing album that every imported asset is appended to
+    /// (stack primaries only; hidden members stay out of albums).
     pub target_album_id: Option<String>,
     /// If true, import even if a file with the same BLAKE3 hash already exists.
     pub force_reimport_duplicates: bool,
+    /// Flags an image whose perceptual hash is within this many bits of an
+    /// asset already in the library as [`ImportDecision::NearDuplicate`].
+    /// `None` turns the check off; it decodes every image being planned.
+    /// Ignored when `force_reimport_duplicates` is set.
+    pub near_duplicate_distance: Option<u32>,
+    /// Track logs that give a position to imports without GPS of their own.
+    pub track_log: Option<TrackLogConfig>,
+    /// Maximum number of candidates copied and verified concurrently during
+    /// execution. Values below 1 are treated as 1.
+    pub max_workers: usize,
+    /// Thumbnail encoding generated for each imported member; `None` skips
+    /// generation (see `pixles library thumbnails` to backfill later).
+    pub thumbnail_format: Option<ThumbnailFormat>,
 }
 
 impl Default for ImportConfig {
@@ -19,15 +46,38 @@ impl Default for ImportConfig {
             import_mode: ImportMode::Copy,
             target_album_id: None,
             force_reimport_duplicates: false,
+            near_duplicate_distance: None,
+            track_log: None,
+            max_workers: default_max_workers(),
+            thumbnail_format: Some(ThumbnailFormat::default()),
         }
     }
 }
 
+/// One worker per available CPU, capped so that spinning disks and card
+/// readers are not thrashed by too many concurrent streams.
+pub fn default_max_workers() -> usize {
+    const MAX_DEFAULT_WORKERS: usize = 8;
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(MAX_DEFAULT_WORKERS)
+}
+
 /// Decision for a single candidate.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ImportDecision {
     Import,
-    SkipDuplicate { existing_uuid: String },
+    SkipDuplicate {
+        existing_uuid: String,
+    },
+    /// Not byte-identical to `existing_uuid`, but looks like it (a resized
+    /// or re-exported copy): their perceptual hashes differ in `distance`
+    /// bits.
+    NearDuplicate {
+        existing_uuid: String,
+        distance: u32,
+    },
     SkipUnsupported,
     SkipError(String),
 }
@@ -36,6 +86,7 @@ pub enum ImportDecision {
 pub struct PlanCounts {
     pub to_import: usize,
     pub duplicates: usize,
+    pub near_duplicates: usize,
     pub unsupported: usize,
     pub errors: usize,
 }
@@ -50,26 +101,44 @@ pub struct ImportActionPlan {
 /// Phase 2 — decide what to do with each candidate from the scan.
 ///
 /// BLAKE3-hashes the primary member of each candidate and checks the DB for
-/// duplicates. Returns an `ImportActionPlan` with per-candidate decisions.
+/// duplicates, and for near-duplicates when
+/// [`ImportConfig::near_duplicate_distance`] is set. Returns an
+/// `ImportActionPlan` with per-candidate decisions.
 pub fn plan(
     scan: &ScanResult,
     db: &DatabaseDriver,
     config: &ImportConfig,
 ) -> Result<ImportActionPlan, Box<dyn std::error::Error + Send + Sync>> {
     // Validate target album if specified (fail fast before hashing anything).
-    if let Some(ref _album_id) = config.target_album_id {
-        // TODO: when album DB is implemented, validate here.
-        // For now, pass through.
+    if let Some(album_id) = &config.target_album_id
+        && db.find_album(album_id)?.is_none()
+    {
+        return Err(Box::new(LibraryError::AlbumNotFound(album_id.clone())));
     }
+    // Likewise for unreadable track logs; they are loaded again to execute.
+    if let Some(track_log) = &config.track_log {
+        track_log.load()?;
+    }
+
+    // Perceptual hashes of the library, loaded once for every candidate.
+    let known = match config.near_duplicate_distance {
+        Some(_) if !config.force_reimport_duplicates => db
+            .assets_with_perceptual_hash()?
+            .into_iter()
+            .filter_map(|row| Some((row.uuid, row.perceptual_hash? as u64)))
+            .collect(),
+        _ => Vec::new(),
+    };
 
     let mut actions = Vec::new();
     let mut counts = PlanCounts::default();
 
     for candidate in &scan.candidates {
-        let decision = decide(candidate, db, config)?;
+        let decision = decide(candidate, db, config, &known)?;
         match &decision {
             ImportDecision::Import => counts.to_import += 1,
             ImportDecision::SkipDuplicate { .. } => counts.duplicates += 1,
+            ImportDecision::NearDuplicate { .. } => counts.near_duplicates += 1,
             ImportDecision::SkipUnsupported => counts.unsupported += 1,
             ImportDecision::SkipError(_) => counts.errors += 1,
         }
@@ -83,11 +152,12 @@ fn decide(
     candidate: &ImportCandidate,
     db: &DatabaseDriver,
     config: &ImportConfig,
+    known: &[(String, u64)],
 ) -> Result<ImportDecision, Box<dyn std::error::Error + Send + Sync>> {
     // Hash the primary file (first member with Primary role, or source_paths[0])
     let primary_path = candidate.primary_path();
 
-    let hash = match hash_file(primary_path) {
+    let hash = match get_file_hash(primary_path) {
         Ok(h) => h,
         Err(e) => {
             return Ok(ImportDecision::SkipError(format!(
@@ -105,13 +175,33 @@ fn decide(
         });
     }
 
+    if let Some(max_distance) = config.near_duplicate_distance
+        && let Some((existing_uuid, distance)) = closest_match(primary_path, known)
+        && distance <= max_distance
+    {
+        return Ok(ImportDecision::NearDuplicate {
+            existing_uuid,
+            distance,
+        });
+    }
+
     Ok(ImportDecision::Import)
 }
 
-fn hash_file(path: &Path) -> Result<String, std::io::Error> {
-    let bytes = std::fs::read(path)?;
-    let hash = blake3::hash(&bytes);
-    Ok(hash.to_hex().to_string())
+/// The known asset that looks most like `path`, with the distance between
+/// their perceptual hashes. `None` if `path` cannot be decoded.
+fn closest_match(path: &Path, known: &[(String, u64)]) -> Option<(String, u32)> {
+    if known.is_empty() {
+        return None;
+    }
+    let hash = perceptual_hash(path)
+        .inspect_err(|e| log::debug!("perceptual hash of {}: {e}", path.display()))
+        .ok()??;
+    known
+        .iter()
+        .map(|(uuid, other)| (uuid, hamming_distance(hash, *other)))
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(uuid, distance)| (uuid.clone(), distance))
 }
 
 // ── Tests ────────────────────────────────────────────────────────────────────
@@ -123,6 +213,7 @@ mod tests {
     use crate::import::scan::ScanResult;
     use crate::import::scanner::scan;
     use std::fs;
+    use std::path::Path;
     use tempfile::TempDir;
 
     fn make_db() -> DatabaseDriver {
@@ -147,6 +238,19 @@ mod tests {
         assert!(matches!(plan.actions[0].1, ImportDecision::Import));
     }
 
+    #[test]
+    fn test_unknown_target_album_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let db = make_db();
+        let scan = make_scan(tmp.path(), &["photo.jpg"]);
+        let config = ImportConfig {
+            target_album_id: Some("01956ef3-1234-7abc-9def-123456789abc".to_string()),
+            ..Default::default()
+        };
+        let err = plan(&scan, &db, &config).unwrap_err();
+        assert!(err.to_string().starts_with("album not found"));
+    }
+
     #[test]
     fn test_duplicate_hash_skipped() {
         let tmp = TempDir::new().unwrap();
@@ -172,10 +276,17 @@ mod tests {
             is_stack_hidden: false,
             chromahash: None,
             dominant_color: None,
+            perceptual_hash: None,
             album_id: None,
             rating: 0,
             is_deleted: false,
             deleted_at: None,
+            original_filename: None,
+            camera_make: None,
+            camera_model: None,
+            gps_lat: None,
+            gps_lon: None,
+            caption: None,
         };
         db.insert_asset(&row).unwrap();
 
@@ -214,16 +325,25 @@ mod tests {
             is_stack_hidden: false,
             chromahash: None,
             dominant_color: None,
+            perceptual_hash: None,
             album_id: None,
             rating: 0,
             is_deleted: false,
             deleted_at: None,
+            original_filename: None,
+            camera_make: None,
+            camera_model: None,
+            gps_lat: None,
+            gps_lon: None,
+            caption: None,
         };
         db.insert_asset(&row).unwrap();
 
         let scan = scan(&[tmp.path().to_path_buf()]).unwrap();
-        let mut config = ImportConfig::default();
-        config.force_reimport_duplicates = true;
+        let config = ImportConfig {
+            force_reimport_duplicates: true,
+            ..Default::default()
+        };
         let plan = plan(&scan, &db, &config).unwrap();
 
         assert_eq!(
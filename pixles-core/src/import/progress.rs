@@ -1,12 +1,20 @@
 use std::path::PathBuf;
 
+use serde::{Deserialize, Serialize};
+
 /// Outcome for a single imported file.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ImportOutcome {
     Imported,
     DuplicateSkipped {
         existing_uuid: String,
     },
+    /// Looks like `existing_uuid`: their perceptual hashes differ in
+    /// `distance` bits.
+    NearDuplicateSkipped {
+        existing_uuid: String,
+        distance: u32,
+    },
     Unsupported,
     CorruptUnreadable(String),
     CorruptTransfer,
@@ -60,6 +68,13 @@ impl ImportExecutionSummary {
             .count()
     }
 
+    pub fn near_duplicate_count(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|(_, o)| matches!(o, ImportOutcome::NearDuplicateSkipped { .. }))
+            .count()
+    }
+
     pub fn error_count(&self) -> usize {
         self.outcomes
             .iter()
@@ -1,11 +1,13 @@
 use std::path::PathBuf;
 
+use serde::{Deserialize, Serialize};
+
 use crate::domain::{DetectionMethod, MemberRole, StackType};
 use crate::metadata::AssetType;
 
 /// One logical "unit" of import — either a standalone file or a set of
 /// related files (RAW+JPEG pair, Live Photo HEIC+MOV, etc.).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportCandidate {
     /// All source file paths belonging to this candidate.
     pub source_paths: Vec<PathBuf>,
@@ -19,6 +21,24 @@ pub struct ImportCandidate {
     pub detection_key: Option<String>,
     /// Ordered list of (path, member role) for every file in the candidate.
     pub members: Vec<(PathBuf, MemberRole)>,
+    /// Members stored inside another member's file, extracted at import time.
+    #[serde(default)]
+    pub embedded_members: Vec<EmbeddedMember>,
+}
+
+/// A stack member that lives inside another file — e.g. the MP4 trailer of a
+/// Google or Samsung Motion Photo — and is extracted into its own asset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddedMember {
+    /// File containing the member; also one of the candidate's `members`.
+    pub container: PathBuf,
+    /// Byte offset of the member within `container`.
+    pub offset: u64,
+    /// Length of the member in bytes.
+    pub length: u64,
+    pub role: MemberRole,
+    /// Extension for the extracted file, without the dot.
+    pub extension: String,
 }
 
 impl ImportCandidate {
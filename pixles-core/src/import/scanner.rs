@@ -1,12 +1,17 @@
 use std::collections::HashMap;
 use std::fs;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
+use pixles_media::image::metadata::motion::MotionPhotoInfo;
+use pixles_media::video::VideoMetadata;
+use uuid::Uuid;
 use walkdir::WalkDir;
 
 use crate::domain::{DetectionMethod, MemberRole, StackType};
+use crate::import::detect::detect_sequences;
 use crate::import::group::{group_by_stem, is_supported_extension, is_video};
-use crate::import::scan::{ImportCandidate, ScanResult};
+use crate::import::scan::{EmbeddedMember, ImportCandidate, ScanResult};
 use crate::import::special::SpecialDirectoryStatus;
 use crate::metadata::AssetType;
 
@@ -14,8 +19,13 @@ use crate::metadata::AssetType;
 ///
 /// Responsibilities:
 /// - Recursive walkdir traversal, skipping special directories (`.git`, DaVinci).
-/// - Live Photo pairing: HEIC with `content_identifier` XMP field + matching `.mov`.
+/// - Live Photo pairing: HEIC with `content_identifier` XMP field + `.mov` whose
+///   QuickTime metadata carries the same identifier.
+/// - Motion Photos: JPEG/HEIC with an embedded video trailer (Google, Samsung)
+///   become a Live Photo stack whose video member is extracted at import.
 /// - Filename-stem grouping for RAW/JPEG pairs (delegated to `group_by_stem`).
+/// - Burst, HDR-bracket and panorama stacks from EXIF (delegated to
+///   `detect_sequences`).
 /// - Standalone files for everything else.
 pub fn scan(
     source_paths: &[PathBuf],
@@ -49,6 +59,7 @@ pub fn scan(
                     (heic.clone(), MemberRole::Primary),
                     (mov.clone(), MemberRole::Video),
                 ],
+                embedded_members: Vec::new(),
             });
         }
     }
@@ -71,7 +82,14 @@ pub fn scan(
     let mut candidates = live_photo_pairs;
     candidates.append(&mut stem_candidates);
 
-    Ok(ScanResult { candidates })
+    for candidate in &mut candidates {
+        attach_motion_video(candidate);
+    }
+
+    let mut result = ScanResult { candidates };
+    detect_sequences(&mut result);
+
+    Ok(result)
 }
 
 fn collect_files(
@@ -122,10 +140,12 @@ fn collect_files(
             continue;
         }
 
-        // For MOV files: try to extract content_identifier for Live Photo pairing
+        // For MOV files: read content_identifier from the QuickTime metadata
         if is_video(&ext)
             && (ext == "mov" || ext == "mp4")
-            && let Some(ci) = extract_content_identifier(&path)
+            && let Some(ci) = VideoMetadata::from_path(&path)
+                .ok()
+                .and_then(|video| video.content_identifier)
         {
             mov_ci_map.insert(ci, path.clone());
             files.push(path);
@@ -136,6 +156,47 @@ fn collect_files(
     }
 }
 
+/// Turn a standalone JPEG/HEIC with an embedded motion video into a
+/// `LivePhoto` stack. The still keeps its original bytes; the video is
+/// recorded as an `EmbeddedMember` for the executor to extract.
+fn attach_motion_video(candidate: &mut ImportCandidate) {
+    if candidate.stack_type.is_some() || candidate.detected_type != AssetType::Photo {
+        return;
+    }
+    let primary = candidate.primary_path().clone();
+    let ext = primary
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    if !matches!(ext.as_str(), "jpg" | "jpeg" | "heic" | "heif") {
+        return;
+    }
+    let Some(range) = motion_video_range(&primary) else {
+        return;
+    };
+
+    candidate.stack_type = Some(StackType::LivePhoto);
+    candidate.detection_method = Some(DetectionMethod::EmbeddedVideo);
+    // Unlike Apple pairs there is no shared identifier, and the key must be
+    // unique library-wide for rebuild to regroup the stack.
+    candidate.detection_key = Some(Uuid::now_v7().to_string());
+    candidate.embedded_members.push(EmbeddedMember {
+        container: primary,
+        offset: range.start,
+        length: range.end - range.start,
+        role: MemberRole::Video,
+        extension: "mp4".to_string(),
+    });
+}
+
+fn motion_video_range(path: &Path) -> Option<Range<u64>> {
+    let file = fs::File::open(path).ok()?;
+    // SAFETY: read-only map, dropped before returning.
+    let mmap = unsafe { memmap2::Mmap::map(&file).ok()? };
+    MotionPhotoInfo::detect(&mmap)?.video_range(mmap.len() as u64)
+}
+
 /// Naive byte-scan for the Apple QuickTime content identifier.
 ///
 /// Searches for the ASCII string `com.apple.quicktime.content.identifier`
@@ -239,6 +300,32 @@ mod tests {
         assert!(c.members.iter().any(|(_, r)| *r == MemberRole::Sidecar));
     }
 
+    #[test]
+    fn test_motion_photo_becomes_live_photo_stack() {
+        let tmp = TempDir::new().unwrap();
+        let mut video = vec![0, 0, 0, 16];
+        video.extend_from_slice(b"ftypisom\0\0\0\0motion frames");
+        let mut bytes = b"\xFF\xD8 <x:xmpmeta GCamera:MicroVideoOffset=\"".to_vec();
+        bytes.extend_from_slice(format!("{}\"/>", video.len()).as_bytes());
+        bytes.extend_from_slice(b" \xFF\xD9");
+        let still_len = bytes.len() as u64;
+        bytes.extend_from_slice(&video);
+        let path = tmp.path().join("PXL_0001.MP.jpg");
+        fs::write(&path, &bytes).unwrap();
+
+        let result = scan(&[tmp.path().to_path_buf()]).unwrap();
+        assert_eq!(result.candidates.len(), 1);
+        let c = &result.candidates[0];
+        assert_eq!(c.stack_type, Some(StackType::LivePhoto));
+        assert_eq!(c.detection_method, Some(DetectionMethod::EmbeddedVideo));
+        assert_eq!(c.embedded_members.len(), 1);
+        let embedded = &c.embedded_members[0];
+        assert_eq!(embedded.container, path);
+        assert_eq!(embedded.offset, still_len);
+        assert_eq!(embedded.length, video.len() as u64);
+        assert_eq!(embedded.role, MemberRole::Video);
+    }
+
     #[test]
     fn test_empty_directory() {
         let tmp = TempDir::new().unwrap();
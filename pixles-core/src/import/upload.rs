@@ -1,12 +1,14 @@
 // Related documentations:
 // - https://pixles.justinchung.net/design/import-prioritization/
 
-use std::{
-    collections::{HashMap, HashSet},
-    path::PathBuf,
-};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 
-use crate::import::ImportExecutionPlan;
+use crate::import::plan::ImportExecutionPlan;
+
+const SECS_PER_DAY: i64 = 86_400;
 
 pub struct UploadExecutionPlan(pub Vec<PathBuf>);
 
@@ -37,34 +39,321 @@ pub fn get_upload_ordering(
     // - Lowest directory depth first
     // - Last modified times (newest first), grouped by day, associated files
     // - File size (smallest first)
+    //
+    // Associated files are the members of one import candidate (RAW+JPEG,
+    // Live Photo HEIC+MOV, bursts, brackets), so stacks stay together however
+    // their files are named.
+    //
+    // Memory: a plan can hold 1M+ paths, so this never builds per-bucket
+    // collections. Each path is copied out of the plan once and stat'ed into a
+    // compact `UploadEntry`, candidates become index ranges into that single
+    // vector, and the ranges are sorted by one composite key. Paths are moved,
+    // not copied again, into the output.
 
     let priority_config: UploadPriorityConfig = priority_config.unwrap_or_default();
 
-    let uploadable_paths: HashSet<PathBuf> = plan.get_uploadable_paths().collect();
+    let mut entries: Vec<UploadEntry> = plan
+        .candidates()
+        .iter()
+        .enumerate()
+        .flat_map(|(candidate, c)| {
+            c.source_paths
+                .iter()
+                .map(move |path| UploadEntry::new(path.clone(), candidate))
+        })
+        .collect();
 
-    // Bucket by directory depth
-    let _buckets_by_depth: Vec<Vec<PathBuf>> = {
-        if !priority_config.prioritize_lower_depth {
-            vec![uploadable_paths.into_iter().collect::<Vec<_>>()]
-        } else {
-            let mut map: HashMap<usize, Vec<PathBuf>> = HashMap::new();
-            for path in uploadable_paths.into_iter() {
-                let depth = path.components().count();
-                map.entry(depth).or_default().push(path);
-            }
+    // Drop duplicate paths, keeping the copy from the earliest candidate, then
+    // make each candidate's members adjacent.
+    entries.sort_unstable_by(|a, b| a.path.cmp(&b.path).then(a.candidate.cmp(&b.candidate)));
+    entries.dedup_by(|a, b| a.path == b.path);
+    entries.sort_unstable_by(|a, b| {
+        a.candidate
+            .cmp(&b.candidate)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    let mut groups = group_associated(&entries);
+    groups.sort_unstable_by_key(|g| g.sort_key(&entries, &priority_config));
 
-            // TODO: Convert into Vec sorted by key (depth)
-            let mut vec: Vec<_> = map.into_iter().collect();
-            vec.sort_by_key(|(depth, _)| *depth);
-            vec.into_iter().map(|(_, paths)| paths).collect()
+    let mut ordered = Vec::with_capacity(entries.len());
+    for group in &groups {
+        let members = &mut entries[group.start..group.start + group.len];
+        if priority_config.prioritize_smaller_files {
+            // Smallest member first so previews land before RAWs and videos.
+            members.sort_by_key(|e| e.size);
         }
-    };
+        ordered.extend(members.iter_mut().map(|e| std::mem::take(&mut e.path)));
+    }
+
+    UploadExecutionPlan(ordered)
+}
 
-    // For each bucket, bucket by date modified to the day
-    // TODO
-    // if priority_config.prioritize_newer_files { ... } else { ... }
+/// Per-path data needed for ordering, gathered with a single `stat`.
+struct UploadEntry {
+    path: PathBuf,
+    /// Index of the candidate the path belongs to; its members are associated.
+    candidate: usize,
+    /// Modification time in seconds since the Unix epoch. Unreadable files
+    /// sort as the oldest.
+    modified: i64,
+    /// Size in bytes. Unreadable files sort as the largest.
+    size: u64,
+}
 
-    todo!()
+impl UploadEntry {
+    fn new(path: PathBuf, candidate: usize) -> Self {
+        let (modified, size) = match fs::metadata(&path) {
+            Ok(meta) => {
+                let modified = meta
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(i64::MIN);
+                (modified, meta.len())
+            }
+            Err(_) => (i64::MIN, u64::MAX),
+        };
+        Self {
+            path,
+            candidate,
+            modified,
+            size,
+        }
+    }
 }
 
-// TODO: This function uses a lot of overhead memory by design. Need to trace entire call tree and make sure none of the data is excessively large (e.g. 1M+ file paths).
+/// The members of one candidate, as a run in the sorted entry vector.
+struct UploadGroup {
+    start: usize,
+    len: usize,
+}
+
+type UploadGroupKey<'a> = (usize, Reverse<i64>, u64, &'a Path);
+
+impl UploadGroup {
+    /// Composite ordering key: (depth, newest day, total size, path). Disabled
+    /// criteria contribute a constant so they do not affect the order.
+    fn sort_key<'a>(
+        &self,
+        entries: &'a [UploadEntry],
+        config: &UploadPriorityConfig,
+    ) -> UploadGroupKey<'a> {
+        let members = &entries[self.start..self.start + self.len];
+        let first = &members[0].path;
+
+        let depth = if config.prioritize_lower_depth {
+            first.components().count()
+        } else {
+            0
+        };
+        // A group is as new as its newest member, bucketed to the (UTC) day so
+        // that size decides the order among files from the same day.
+        let day = if config.prioritize_newer_files {
+            members
+                .iter()
+                .map(|e| e.modified)
+                .max()
+                .map(|m| m.div_euclid(SECS_PER_DAY))
+                .unwrap_or(i64::MIN)
+        } else {
+            0
+        };
+        let size = if config.prioritize_smaller_files {
+            members
+                .iter()
+                .map(|e| e.size)
+                .fold(0u64, u64::saturating_add)
+        } else {
+            0
+        };
+
+        (depth, Reverse(day), size, first)
+    }
+}
+
+/// Splits `entries` (sorted by candidate) into one run per candidate.
+fn group_associated(entries: &[UploadEntry]) -> Vec<UploadGroup> {
+    let mut groups: Vec<UploadGroup> = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        match groups.last_mut() {
+            Some(group) if entries[group.start].candidate == entry.candidate => group.len += 1,
+            _ => groups.push(UploadGroup { start: i, len: 1 }),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{DetectionMethod, MemberRole, StackType};
+    use crate::import::ImportCandidate;
+    use crate::metadata::AssetType;
+    use std::fs::File;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    fn write(path: &Path, len: usize, days_ago: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![0u8; len]).unwrap();
+        let mtime = SystemTime::now() - Duration::from_secs(days_ago * SECS_PER_DAY as u64);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+    }
+
+    fn candidate(
+        members: &[(PathBuf, MemberRole)],
+        stack: Option<(StackType, DetectionMethod)>,
+    ) -> ImportCandidate {
+        ImportCandidate {
+            source_paths: members.iter().map(|(p, _)| p.clone()).collect(),
+            detected_type: AssetType::Photo,
+            stack_type: stack.map(|(t, _)| t),
+            detection_method: stack.map(|(_, m)| m),
+            detection_key: stack.map(|_| members[0].0.display().to_string()),
+            members: members.to_vec(),
+            embedded_members: Vec::new(),
+        }
+    }
+
+    /// One single-file candidate per path.
+    fn make_plan(paths: &[PathBuf]) -> ImportExecutionPlan {
+        ImportExecutionPlan::new(
+            paths
+                .iter()
+                .map(|p| candidate(&[(p.clone(), MemberRole::Primary)], None))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_depth_then_day_then_size() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        let deep = root.join("a/b/deep.jpg");
+        let old_small = root.join("old_small.jpg");
+        let new_big = root.join("new_big.jpg");
+        let new_small = root.join("new_small.jpg");
+        write(&deep, 1, 0);
+        write(&old_small, 1, 10);
+        write(&new_big, 100, 0);
+        write(&new_small, 10, 0);
+
+        let plan = make_plan(&[
+            deep.clone(),
+            old_small.clone(),
+            new_big.clone(),
+            new_small.clone(),
+        ]);
+        let ordering = get_upload_ordering(&plan, None);
+
+        assert_eq!(ordering.0, vec![new_small, new_big, old_small, deep]);
+    }
+
+    #[test]
+    fn test_associated_files_stay_together() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        let raw = root.join("IMG_0001.ARW");
+        let jpg = root.join("img_0001.jpg");
+        let other = root.join("IMG_0002.jpg");
+        write(&raw, 500, 0);
+        write(&jpg, 50, 0);
+        // Smaller than the RAW but larger than the pair's JPEG.
+        write(&other, 100, 0);
+
+        let plan = ImportExecutionPlan::new(vec![
+            candidate(
+                &[
+                    (jpg.clone(), MemberRole::Primary),
+                    (raw.clone(), MemberRole::Raw),
+                ],
+                Some((StackType::RawJpeg, DetectionMethod::FilenameStem)),
+            ),
+            candidate(&[(other.clone(), MemberRole::Primary)], None),
+        ]);
+        let ordering = get_upload_ordering(&plan, None);
+
+        assert_eq!(ordering.0, vec![other, jpg, raw]);
+    }
+
+    #[test]
+    fn test_stack_members_stay_together_whatever_their_names() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        let burst: Vec<PathBuf> = (1..=3)
+            .map(|i| root.join(format!("DSC_000{i}.jpg")))
+            .collect();
+        let other = root.join("DSC_0004.jpg");
+        let same_stem = root.join("DSC_0001.mov");
+        write(&burst[0], 10, 0);
+        write(&burst[1], 20, 0);
+        write(&burst[2], 30, 0);
+        write(&other, 15, 0);
+        // Shares a stem with a burst member but was not stacked with it.
+        write(&same_stem, 100, 0);
+
+        let members: Vec<_> = burst
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let role = if i == 0 {
+                    MemberRole::Primary
+                } else {
+                    MemberRole::Alternate
+                };
+                (p.clone(), role)
+            })
+            .collect();
+        let plan = ImportExecutionPlan::new(vec![
+            candidate(
+                &members,
+                Some((StackType::Burst, DetectionMethod::BurstIdentifier)),
+            ),
+            candidate(&[(other.clone(), MemberRole::Primary)], None),
+            candidate(&[(same_stem.clone(), MemberRole::Primary)], None),
+        ]);
+        let ordering = get_upload_ordering(&plan, None);
+
+        let mut expected = vec![other];
+        expected.extend(burst);
+        expected.push(same_stem);
+        assert_eq!(ordering.0, expected);
+    }
+
+    #[test]
+    fn test_disabled_criteria_fall_back_to_path_order() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        let a = root.join("x/a.jpg");
+        let b = root.join("b.jpg");
+        write(&a, 1, 0);
+        write(&b, 100, 5);
+
+        let plan = make_plan(&[b.clone(), a.clone()]);
+        let config = UploadPriorityConfig {
+            prioritize_smaller_files: false,
+            prioritize_newer_files: false,
+            prioritize_lower_depth: false,
+        };
+        let ordering = get_upload_ordering(&plan, Some(config));
+
+        assert_eq!(ordering.0, vec![b, a]);
+    }
+
+    #[test]
+    fn test_duplicate_paths_are_emitted_once() {
+        let tmp = TempDir::new().unwrap();
+        let a = tmp.path().join("a.jpg");
+        write(&a, 1, 0);
+
+        let plan = make_plan(&[a.clone(), a.clone()]);
+        assert_eq!(get_upload_ordering(&plan, None).0, vec![a]);
+    }
+}
@@ -0,0 +1,553 @@
+use std::fs;
+
+use uuid::Uuid;
+
+use crate::db::rows::{AlbumRow, AssetRow};
+use crate::library::error::LibraryError;
+use crate::library::library::Library;
+use crate::library::paths::{album_path, albums_dir};
+use crate::sidecar::io::{read_album, write_album};
+use crate::sidecar::library_version::CURRENT_LIBRARY_VERSION;
+use crate::sidecar::{AlbumCbor, AlbumMember};
+
+/// Album editing. An album is an ordered list of assets with a name, an
+/// optional description and an optional cover; an asset can be in any
+/// number of albums.
+///
+/// Each album is stored in `albums/{id}.cbor`, which is rewritten before the
+/// index is updated. If the index update fails, the previous file is put
+/// back. Albums refer to assets by UUID and never touch their sidecars.
+impl Library {
+    /// Creates an empty album.
+    pub fn create_album(
+        &self,
+        name: &str,
+        description: Option<&str>,
+    ) -> Result<AlbumRow, LibraryError> {
+        let now = now_secs();
+        let album = AlbumCbor {
+            version: CURRENT_LIBRARY_VERSION,
+            id: Uuid::now_v7().to_string(),
+            name: album_name(name)?,
+            description: description_text(description),
+            cover_asset_id: None,
+            created_at: now,
+            modified_at: now,
+            assets: vec![],
+        };
+        self.store_album(&album)?;
+        Ok(album_row(&album))
+    }
+
+    /// Every album by name, with the number of its assets outside the trash.
+    pub fn list_albums(&self) -> Result<Vec<(AlbumRow, usize)>, LibraryError> {
+        Ok(self
+            .db
+            .list_albums()?
+            .into_iter()
+            .map(|(album, count)| (album, count as usize))
+            .collect())
+    }
+
+    /// Looks an album up by ID (either UUID form) or, failing that, by its
+    /// name ignoring case. A name shared by several albums is refused.
+    pub fn resolve_album(&self, id_or_name: &str) -> Result<AlbumRow, LibraryError> {
+        if let Ok(id) = Uuid::parse_str(id_or_name)
+            && let Some(album) = self.db.find_album(&id.to_string())?
+        {
+            return Ok(album);
+        }
+        let wanted = id_or_name.trim().to_lowercase();
+        let mut matches: Vec<AlbumRow> = self
+            .db
+            .list_albums()?
+            .into_iter()
+            .map(|(album, _)| album)
+            .filter(|album| album.name.to_lowercase() == wanted)
+            .collect();
+        match matches.len() {
+            0 => Err(LibraryError::AlbumNotFound(id_or_name.to_string())),
+            1 => Ok(matches.remove(0)),
+            n => Err(LibraryError::InvalidAlbum(format!(
+                "{n} albums are named {id_or_name:?}; use an album ID"
+            ))),
+        }
+    }
+
+    /// Assets of an album outside the trash, in album order.
+    pub fn album_assets(&self, id: &str) -> Result<Vec<AssetRow>, LibraryError> {
+        let album = self.load_album(id)?;
+        Ok(self.db.album_assets(&album.id)?)
+    }
+
+    /// Albums an asset belongs to, by name.
+    pub fn albums_for_asset(&self, uuid: &str) -> Result<Vec<AlbumRow>, LibraryError> {
+        let uuid = self.asset_uuid(uuid)?;
+        Ok(self.db.albums_for_asset(&uuid)?)
+    }
+
+    pub fn rename_album(&self, id: &str, name: &str) -> Result<(), LibraryError> {
+        let name = album_name(name)?;
+        self.edit_album(id, |album| {
+            album.name = name;
+            Ok(())
+        })
+    }
+
+    /// Sets or, with `None` or blank text, clears the description.
+    pub fn set_album_description(
+        &self,
+        id: &str,
+        description: Option<&str>,
+    ) -> Result<(), LibraryError> {
+        let description = description_text(description);
+        self.edit_album(id, |album| {
+            album.description = description;
+            Ok(())
+        })
+    }
+
+    /// Sets the cover to one of the album's assets, or clears it. Without a
+    /// cover, the first asset stands in.
+    pub fn set_album_cover(&self, id: &str, uuid: Option<&str>) -> Result<(), LibraryError> {
+        let cover = uuid.map(|u| self.asset_uuid(u)).transpose()?;
+        self.edit_album(id, |album| {
+            if let Some(cover) = &cover
+                && !album.assets.iter().any(|m| &m.uuid == cover)
+            {
+                return Err(LibraryError::InvalidAlbum(format!(
+                    "cover {cover} is not in album {:?}",
+                    album.name
+                )));
+            }
+            album.cover_asset_id = cover;
+            Ok(())
+        })
+    }
+
+    /// Appends assets to the end of the album, in the order given. Assets
+    /// already in it keep their place. Returns the number added.
+    pub fn add_to_album(&self, id: &str, uuids: &[String]) -> Result<usize, LibraryError> {
+        let uuids = uuids
+            .iter()
+            .map(|u| self.asset_uuid(u))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut added = 0;
+        self.edit_album(id, |album| {
+            let now = now_secs();
+            for uuid in uuids {
+                if !album.assets.iter().any(|m| m.uuid == uuid) {
+                    album.assets.push(AlbumMember {
+                        uuid,
+                        added_at: now,
+                    });
+                    added += 1;
+                }
+            }
+            Ok(())
+        })?;
+        Ok(added)
+    }
+
+    /// Takes assets out of the album, clearing the cover if it was one of
+    /// them. The assets themselves are kept. Returns the number removed.
+    pub fn remove_from_album(&self, id: &str, uuids: &[String]) -> Result<usize, LibraryError> {
+        let uuids: Vec<String> = uuids
+            .iter()
+            .map(|u| Uuid::parse_str(u).map_or_else(|_| u.clone(), |u| u.to_string()))
+            .collect();
+        let mut removed = 0;
+        self.edit_album(id, |album| {
+            let before = album.assets.len();
+            album.assets.retain(|m| !uuids.contains(&m.uuid));
+            removed = before - album.assets.len();
+            if album
+                .cover_asset_id
+                .as_ref()
+                .is_some_and(|cover| uuids.contains(cover))
+            {
+                album.cover_asset_id = None;
+            }
+            Ok(())
+        })?;
+        Ok(removed)
+    }
+
+    /// Moves an asset of the album to `position` (0-based), shifting the
+    /// others along. Positions past the end move it to the end.
+    pub fn move_in_album(&self, id: &str, uuid: &str, position: usize) -> Result<(), LibraryError> {
+        let uuid = self.asset_uuid(uuid)?;
+        self.edit_album(id, |album| {
+            let from = album
+                .assets
+                .iter()
+                .position(|m| m.uuid == uuid)
+                .ok_or_else(|| {
+                    LibraryError::InvalidAlbum(format!("{uuid} is not in album {:?}", album.name))
+                })?;
+            let member = album.assets.remove(from);
+            let to = position.min(album.assets.len());
+            album.assets.insert(to, member);
+            Ok(())
+        })
+    }
+
+    /// Deletes an album. Its assets are kept.
+    pub fn delete_album(&self, id: &str) -> Result<(), LibraryError> {
+        let album = self.load_album(id)?;
+        let path = album_path(&self.root, &parse_album_id(&album.id)?);
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        if let Err(e) = self.db.delete_album(&album.id) {
+            if let Err(restore) = write_album(&path, &album) {
+                log::error!(
+                    "delete_album: could not restore {}: {restore}",
+                    path.display()
+                );
+            }
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Rewrites `albums/{id}.cbor` from the index. The importer adds assets
+    /// to the index as each candidate commits and calls this once per run.
+    pub(crate) fn write_album_file(&self, id: &str) -> Result<(), LibraryError> {
+        let album = self.load_album(id)?;
+        let dir = albums_dir(&self.root);
+        fs::create_dir_all(&dir)?;
+        write_album(&album_path(&self.root, &parse_album_id(&album.id)?), &album)
+            .map_err(|e| LibraryError::Cbor(e.to_string()))
+    }
+
+    /// Applies `edit` to an album and saves it if anything changed.
+    fn edit_album(
+        &self,
+        id: &str,
+        edit: impl FnOnce(&mut AlbumCbor) -> Result<(), LibraryError>,
+    ) -> Result<(), LibraryError> {
+        let original = self.load_album(id)?;
+        let mut album = original.clone();
+        edit(&mut album)?;
+        if album == original {
+            return Ok(());
+        }
+        album.modified_at = now_secs();
+        self.store_album(&album)
+    }
+
+    /// Writes the album's file, then its index rows. If the index cannot be
+    /// updated, the previous file (or none) is put back.
+    fn store_album(&self, album: &AlbumCbor) -> Result<(), LibraryError> {
+        fs::create_dir_all(albums_dir(&self.root))?;
+        let path = album_path(&self.root, &parse_album_id(&album.id)?);
+        let previous = path.exists().then(|| read_album(&path)).transpose();
+        let previous = previous.map_err(|e| LibraryError::Cbor(e.to_string()))?;
+        write_album(&path, album).map_err(|e| LibraryError::Cbor(e.to_string()))?;
+
+        let members: Vec<(String, i64)> = album
+            .assets
+            .iter()
+            .map(|m| (m.uuid.clone(), m.added_at))
+            .collect();
+        if let Err(e) = self.db.upsert_album(&album_row(album), &members) {
+            let restored = match &previous {
+                Some(previous) => write_album(&path, previous).map_err(|e| e.to_string()),
+                None => fs::remove_file(&path).map_err(|e| e.to_string()),
+            };
+            if let Err(restore) = restored {
+                log::error!(
+                    "store_album: could not restore {}: {restore}",
+                    path.display()
+                );
+            }
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// The album as the index has it, in its on-disk form.
+    fn load_album(&self, id: &str) -> Result<AlbumCbor, LibraryError> {
+        let not_found = || LibraryError::AlbumNotFound(id.to_string());
+        let parsed = Uuid::parse_str(id).map_err(|_| not_found())?;
+        let row = self
+            .db
+            .find_album(&parsed.to_string())?
+            .ok_or_else(not_found)?;
+        let assets = self
+            .db
+            .album_members(&row.id)?
+            .into_iter()
+            .map(|(uuid, added_at)| AlbumMember { uuid, added_at })
+            .collect();
+        Ok(AlbumCbor {
+            version: CURRENT_LIBRARY_VERSION,
+            id: row.id,
+            name: row.name,
+            description: row.description,
+            cover_asset_id: row.cover_asset_id,
+            created_at: row.created_at,
+            modified_at: row.modified_at,
+            assets,
+        })
+    }
+
+    /// The hyphenated UUID of an asset in the index. Accepts either UUID form.
+    fn asset_uuid(&self, uuid: &str) -> Result<String, LibraryError> {
+        let not_found = || LibraryError::AssetNotFound(uuid.to_string());
+        let parsed = Uuid::parse_str(uuid).map_err(|_| not_found())?;
+        let row = self
+            .db
+            .find_by_uuid(&parsed.to_string())?
+            .ok_or_else(not_found)?;
+        Ok(row.uuid)
+    }
+}
+
+pub(crate) fn album_row(album: &AlbumCbor) -> AlbumRow {
+    AlbumRow {
+        id: album.id.clone(),
+        name: album.name.clone(),
+        description: album.description.clone(),
+        cover_asset_id: album.cover_asset_id.clone(),
+        created_at: album.created_at,
+        modified_at: album.modified_at,
+    }
+}
+
+fn parse_album_id(id: &str) -> Result<Uuid, LibraryError> {
+    Uuid::parse_str(id).map_err(|_| LibraryError::AlbumNotFound(id.to_string()))
+}
+
+fn album_name(name: &str) -> Result<String, LibraryError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(LibraryError::InvalidAlbum(
+            "album name is empty".to_string(),
+        ));
+    }
+    Ok(name.to_string())
+}
+
+fn description_text(description: Option<&str>) -> Option<String> {
+    description
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(str::to_string)
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::executor::execute;
+    use crate::import::executor_cancellation::CancellationToken;
+    use crate::import::planner::{ImportConfig, plan};
+    use crate::import::scanner::scan;
+    use crate::library::init::init_library;
+    use crate::library::paths::sidecar_path;
+    use crate::library::rebuild::rebuild_index;
+    use crate::sidecar::io::{read_sidecar, write_sidecar};
+    use tempfile::TempDir;
+
+    /// Imports `a.jpg`, `b.jpg` and `c.jpg` into `album` and returns their
+    /// UUIDs in that order.
+    fn import_into(lib: &Library, album: Option<&AlbumRow>) -> Vec<String> {
+        let src = TempDir::new().unwrap();
+        for name in ["a.jpg", "b.jpg", "c.jpg"] {
+            fs::write(src.path().join(name), name).unwrap();
+        }
+        let config = ImportConfig {
+            thumbnail_format: None,
+            target_album_id: album.map(|a| a.id.clone()),
+            max_workers: 1,
+            ..Default::default()
+        };
+        let plan_result = plan(
+            &scan(&[src.path().to_path_buf()]).unwrap(),
+            &lib.db,
+            &config,
+        )
+        .unwrap();
+        execute(
+            &plan_result,
+            lib,
+            &config,
+            |_| {},
+            &CancellationToken::new(),
+        )
+        .unwrap();
+
+        ["a.jpg", "b.jpg", "c.jpg"]
+            .iter()
+            .map(|name| {
+                lib.db
+                    .list_assets()
+                    .unwrap()
+                    .into_iter()
+                    .find(|r| r.original_filename.as_deref() == Some(name))
+                    .unwrap()
+                    .uuid
+            })
+            .collect()
+    }
+
+    fn album_order(lib: &Library, id: &str) -> Vec<String> {
+        lib.album_assets(id)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.uuid)
+            .collect()
+    }
+
+    fn album_file(lib: &Library, id: &str) -> AlbumCbor {
+        read_album(&album_path(&lib.root, &Uuid::parse_str(id).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn test_import_into_album() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let album = lib.create_album(" Kyoto ", Some("Spring trip")).unwrap();
+        assert_eq!(album.name, "Kyoto");
+
+        let uuids = import_into(&lib, Some(&album));
+        let mut order = album_order(&lib, &album.id);
+        order.sort();
+        let mut expected = uuids.clone();
+        expected.sort();
+        assert_eq!(order, expected);
+        let file = album_file(&lib, &album.id);
+        assert_eq!(file.assets.len(), 3);
+        assert_eq!(file.description.as_deref(), Some("Spring trip"));
+        // The legacy single-album field is no longer written.
+        let row = lib.db.find_by_uuid(&uuids[0]).unwrap().unwrap();
+        let uuid = Uuid::parse_str(&row.uuid).unwrap();
+        let sidecar = read_sidecar(&sidecar_path(&lib.root, &uuid, "", row.capture_utc)).unwrap();
+        assert_eq!(sidecar.album_id, None);
+    }
+
+    #[test]
+    fn test_edit_album() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let [a, b, c]: [String; 3] = import_into(&lib, None).try_into().unwrap();
+        let album = lib.create_album("Kyoto", None).unwrap();
+        let other = lib.create_album("Best of", None).unwrap();
+
+        let added = lib
+            .add_to_album(&album.id, &[c.clone(), a.clone(), b.clone(), a.clone()])
+            .unwrap();
+        assert_eq!(added, 3);
+        lib.add_to_album(&other.id, std::slice::from_ref(&a))
+            .unwrap();
+        assert_eq!(
+            album_order(&lib, &album.id),
+            vec![c.clone(), a.clone(), b.clone()]
+        );
+
+        lib.move_in_album(&album.id, &b, 0).unwrap();
+        assert_eq!(
+            album_order(&lib, &album.id),
+            vec![b.clone(), c.clone(), a.clone()]
+        );
+
+        lib.set_album_cover(&album.id, Some(&a)).unwrap();
+        lib.rename_album(&album.id, "Kyoto 2024").unwrap();
+        let file = album_file(&lib, &album.id);
+        assert_eq!(file.name, "Kyoto 2024");
+        assert_eq!(file.cover_asset_id.as_deref(), Some(a.as_str()));
+        let names: Vec<String> = lib
+            .albums_for_asset(&a)
+            .unwrap()
+            .into_iter()
+            .map(|al| al.name)
+            .collect();
+        assert_eq!(names, vec!["Best of", "Kyoto 2024"]);
+
+        // Removing the cover clears it; the asset stays in the library.
+        assert_eq!(
+            lib.remove_from_album(&album.id, std::slice::from_ref(&a))
+                .unwrap(),
+            1
+        );
+        let row = lib.resolve_album("kyoto 2024").unwrap();
+        assert_eq!(row.cover_asset_id, None);
+        assert!(lib.db.find_by_uuid(&a).unwrap().is_some());
+        assert!(matches!(
+            lib.set_album_cover(&album.id, Some(&a)),
+            Err(LibraryError::InvalidAlbum(_))
+        ));
+
+        lib.delete_album(&other.id).unwrap();
+        assert!(!album_path(&lib.root, &Uuid::parse_str(&other.id).unwrap()).exists());
+        assert!(matches!(
+            lib.resolve_album("Best of"),
+            Err(LibraryError::AlbumNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_album_by_id_or_name() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let first = lib.create_album("Trip", None).unwrap();
+        let simple = Uuid::parse_str(&first.id).unwrap().simple().to_string();
+        assert_eq!(lib.resolve_album(&simple).unwrap().id, first.id);
+        assert_eq!(lib.resolve_album("TRIP").unwrap().id, first.id);
+
+        lib.create_album("trip", None).unwrap();
+        assert!(matches!(
+            lib.resolve_album("Trip"),
+            Err(LibraryError::InvalidAlbum(_))
+        ));
+        assert_eq!(lib.resolve_album(&first.id).unwrap().id, first.id);
+        assert!(matches!(
+            lib.create_album("  ", None),
+            Err(LibraryError::InvalidAlbum(_))
+        ));
+    }
+
+    #[test]
+    fn test_rebuild_restores_albums() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let [a, b, c]: [String; 3] = import_into(&lib, None).try_into().unwrap();
+        let album = lib.create_album("Kyoto", Some("Spring")).unwrap();
+        lib.add_to_album(&album.id, &[b.clone(), a.clone()])
+            .unwrap();
+        lib.set_album_cover(&album.id, Some(&a)).unwrap();
+
+        // A sidecar from an older build that names a single album.
+        let row = lib.db.find_by_uuid(&c).unwrap().unwrap();
+        let path = sidecar_path(
+            &lib.root,
+            &Uuid::parse_str(&c).unwrap(),
+            "",
+            row.capture_utc,
+        );
+        let mut sidecar = read_sidecar(&path).unwrap();
+        sidecar.album_id = Some("Summer".to_string());
+        write_sidecar(&path, &sidecar).unwrap();
+
+        lib.db.delete_album(&album.id).unwrap();
+        rebuild_index(&lib).unwrap();
+        rebuild_index(&lib).unwrap();
+
+        let restored = lib.resolve_album("Kyoto").unwrap();
+        assert_eq!(restored.description.as_deref(), Some("Spring"));
+        assert_eq!(restored.cover_asset_id.as_deref(), Some(a.as_str()));
+        assert_eq!(album_order(&lib, &album.id), vec![b.clone(), a.clone()]);
+
+        let summer = lib.resolve_album("Summer").unwrap();
+        assert_eq!(album_order(&lib, &summer.id), vec![c.clone()]);
+        assert_eq!(lib.list_albums().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,654 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::library::error::LibraryError;
+use crate::library::init::SKELETON_DIRS;
+use crate::library::library::Library;
+use crate::library::open::open_library;
+use crate::library::paths::{backup_manifest_path, tmp_path, trash_path};
+use crate::library::rebuild::{media_file_for, rebuild_index};
+use crate::sidecar::io::{read_backup_manifest, read_sidecar, write_backup_manifest};
+use crate::sidecar::{BackupManifestCbor, MirroredFile};
+use crate::utils::hash::{copy_and_hash, get_file_hash};
+
+/// Default for [`BackupOptions::retention_secs`]: the library's trash
+/// period.
+pub const DEFAULT_BACKUP_RETENTION_SECS: i64 = 30 * 86_400;
+
+/// Library files a mirror holds besides the assets and albums. The index,
+/// thumbnails and caches are rebuilt on restore instead.
+const LIBRARY_FILES: &[&str] = &[".library/version.cbor", ".library/config.cbor"];
+
+#[derive(Debug, Clone)]
+pub struct BackupOptions {
+    /// How long, in seconds, the mirror holds on to what the library
+    /// deleted. A soft-deleted asset stays live in the mirror until its
+    /// `deleted_at` is this old, and a file gone from the library stays
+    /// until this long after a backup first missed it.
+    pub retention_secs: i64,
+    /// Re-hash the files already in the mirror and copy again those that
+    /// no longer match, instead of trusting the manifest.
+    pub verify_existing: bool,
+}
+
+impl Default for BackupOptions {
+    fn default() -> Self {
+        Self {
+            retention_secs: DEFAULT_BACKUP_RETENTION_SECS,
+            verify_existing: false,
+        }
+    }
+}
+
+/// Outcome of [`Library::backup`]. Paths are relative to the library root.
+#[derive(Debug, Default)]
+pub struct BackupReport {
+    /// Files copied and verified.
+    pub copied: usize,
+    /// Files moved within the mirror because they moved in the library:
+    /// re-filed under another month, or into the trash.
+    pub moved: usize,
+    pub unchanged: usize,
+    /// Files deleted from the mirror once their retention window passed.
+    pub removed: usize,
+    /// Files the mirror keeps although the library deleted them.
+    pub retained: usize,
+    /// Files that could not be backed up, with the reason. Their previous
+    /// copy in the mirror, if any, is kept.
+    pub failed: Vec<(String, String)>,
+}
+
+/// Outcome of [`restore_library`].
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    pub restored: usize,
+    /// Files left out because their copy in the mirror is missing, unreadable
+    /// or no longer matches its hash, with the reason.
+    pub damaged: Vec<(String, String)>,
+}
+
+/// A library file bound for the mirror.
+struct Source {
+    relative: String,
+    path: PathBuf,
+    /// Known BLAKE3 hash; `None` to hash the file when it is backed up.
+    hash: Option<String>,
+}
+
+enum Mirrored {
+    Copied,
+    Moved,
+    Unchanged,
+}
+
+impl Library {
+    /// Brings the mirror at `dest` up to date with this library: sidecars
+    /// and originals (trashed ones included), albums, and the library's
+    /// version and config. The index and thumbnails are not mirrored;
+    /// [`restore_library`] rebuilds them.
+    ///
+    /// What changed is decided by BLAKE3 hash: an original's is the
+    /// `hash_blake3` of its sidecar, other files are hashed, and the
+    /// mirror's side comes from its manifest. A file that moved in the
+    /// library is moved in the mirror rather than copied again. Every copy
+    /// is written to `{path}.tmp`, hashed again and renamed into place only
+    /// if it matches.
+    ///
+    /// Deletions reach the mirror only after `options.retention_secs`, so
+    /// a mistake in the library can still be undone from the backup.
+    /// Originals imported by reference live outside the library and are not
+    /// copied; their sidecars are.
+    pub fn backup(
+        &self,
+        dest: &Path,
+        options: &BackupOptions,
+    ) -> Result<BackupReport, LibraryError> {
+        let manifest_path = backup_manifest_path(dest);
+        let mut manifest = if manifest_path.exists() {
+            read_backup_manifest(&manifest_path).map_err(|e| LibraryError::Cbor(e.to_string()))?
+        } else {
+            if dest.exists() && fs::read_dir(dest)?.next().is_some() {
+                return Err(LibraryError::InvalidBackup(format!(
+                    "{} is not empty and holds no backup",
+                    dest.display()
+                )));
+            }
+            BackupManifestCbor::default()
+        };
+        fs::create_dir_all(dest)?;
+        if dest.canonicalize()?.starts_with(self.root.canonicalize()?) {
+            return Err(LibraryError::InvalidBackup(
+                "a backup cannot be inside the library it backs up".to_string(),
+            ));
+        }
+
+        let now = now_secs();
+        let mut report = BackupReport::default();
+        let mut held = HashSet::new();
+        let sources = self.backup_sources(&manifest, options, now, &mut held, &mut report);
+
+        // Files the library no longer has at their mirror path, by hash, so
+        // that one which only moved can be moved in the mirror too.
+        let wanted: HashSet<&str> = sources.iter().map(|s| s.relative.as_str()).collect();
+        let mut leaving: HashMap<String, Vec<String>> = HashMap::new();
+        for (relative, file) in &manifest.files {
+            if !wanted.contains(relative.as_str()) && !held.contains(relative) {
+                leaving
+                    .entry(file.hash_blake3.clone())
+                    .or_default()
+                    .push(relative.clone());
+            }
+        }
+
+        for source in &sources {
+            match mirror_file(dest, source, &mut manifest, &mut leaving, options) {
+                Ok(Mirrored::Copied) => report.copied += 1,
+                Ok(Mirrored::Moved) => report.moved += 1,
+                Ok(Mirrored::Unchanged) => report.unchanged += 1,
+                Err(reason) => report.failed.push((source.relative.clone(), reason)),
+            }
+        }
+
+        let gone: Vec<String> = manifest
+            .files
+            .keys()
+            .filter(|r| !wanted.contains(r.as_str()) && !held.contains(*r))
+            .cloned()
+            .collect();
+        for relative in gone {
+            let file = manifest.files.get_mut(&relative).expect("listed above");
+            let removed_at = *file.removed_at.get_or_insert(now);
+            if now - removed_at < options.retention_secs {
+                report.retained += 1;
+                continue;
+            }
+            match fs::remove_file(dest.join(&relative)) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    report.failed.push((relative, e.to_string()));
+                }
+                _ => {
+                    manifest.files.remove(&relative);
+                    report.removed += 1;
+                }
+            }
+        }
+
+        manifest.library_name = self.config().library_name.clone();
+        manifest.last_backup_at = Some(now);
+        write_backup_manifest(&manifest_path, &manifest)
+            .map_err(|e| LibraryError::Cbor(e.to_string()))?;
+        Ok(report)
+    }
+
+    /// Every file the mirror should hold a current copy of. Mirror paths
+    /// to leave as they are, because the library deleted them within the
+    /// retention window or they could not be read, go to `held`.
+    fn backup_sources(
+        &self,
+        manifest: &BackupManifestCbor,
+        options: &BackupOptions,
+        now: i64,
+        held: &mut HashSet<String>,
+        report: &mut BackupReport,
+    ) -> Vec<Source> {
+        let mut sources = Vec::new();
+        let mut add = |path: PathBuf, hash: Option<String>| {
+            sources.push(Source {
+                relative: relative(&self.root, &path),
+                path,
+                hash,
+            });
+        };
+
+        for name in LIBRARY_FILES {
+            add(self.root.join(name), None);
+        }
+        let albums = fs::read_dir(self.root.join("albums"))
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == "cbor"));
+        for album in albums {
+            add(album, None);
+        }
+
+        let sidecars = WalkDir::new(self.root.join("media"))
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .map(|e| e.into_path())
+            .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "cbor"));
+        for sidecar_file in sidecars {
+            let sidecar_relative = relative(&self.root, &sidecar_file);
+            let s = match read_sidecar(&sidecar_file) {
+                Ok(s) => s,
+                Err(e) => {
+                    report
+                        .failed
+                        .push((sidecar_relative.clone(), format!("sidecar: {e}")));
+                    held.insert(sidecar_relative);
+                    continue;
+                }
+            };
+            // Originals imported by reference are not the library's to back up.
+            let live_media =
+                media_file_for(&sidecar_file, &s).filter(|_| s.reference_path.is_none());
+
+            // Within the retention window, the mirror keeps a deleted asset
+            // as it was before.
+            let deleted_recently = s.is_deleted
+                && s.deleted_at
+                    .is_some_and(|at| now - at < options.retention_secs);
+            let keep = |relative: &str| deleted_recently && manifest.files.contains_key(relative);
+
+            if keep(sidecar_relative.as_str()) {
+                held.insert(sidecar_relative);
+                report.retained += 1;
+            } else {
+                add(sidecar_file, None);
+            }
+            let Some(live_media) = live_media else {
+                continue;
+            };
+            let live_relative = relative(&self.root, &live_media);
+            if keep(live_relative.as_str()) {
+                held.insert(live_relative);
+                report.retained += 1;
+                continue;
+            }
+            let media = match Uuid::parse_str(&s.uuid) {
+                Ok(uuid) if s.is_deleted => {
+                    let ext = live_media.extension().unwrap_or_default().to_string_lossy();
+                    trash_path(&self.root, &uuid, &ext)
+                }
+                _ => live_media,
+            };
+            if media.exists() {
+                add(media, Some(s.hash_blake3.clone()));
+            } else {
+                report.failed.push((
+                    relative(&self.root, &media),
+                    "original is missing".to_string(),
+                ));
+                held.insert(live_relative);
+            }
+        }
+        sources
+    }
+}
+
+/// Makes the mirror's copy of one file current.
+fn mirror_file(
+    dest: &Path,
+    source: &Source,
+    manifest: &mut BackupManifestCbor,
+    leaving: &mut HashMap<String, Vec<String>>,
+    options: &BackupOptions,
+) -> Result<Mirrored, String> {
+    let target = dest.join(&source.relative);
+    let hash = match &source.hash {
+        Some(hash) => hash.clone(),
+        None => get_file_hash(&source.path).map_err(|e| e.to_string())?,
+    };
+    let matches = |path: &Path| get_file_hash(path).is_ok_and(|h| h == hash);
+
+    match manifest.files.get_mut(&source.relative) {
+        Some(file)
+            if file.hash_blake3 == hash
+                && target.exists()
+                && (!options.verify_existing || matches(&target)) =>
+        {
+            file.removed_at = None;
+            return Ok(Mirrored::Unchanged);
+        }
+        // Copied by a run that stopped before saving the manifest.
+        None if target.exists() && matches(&target) => {
+            let size = fs::metadata(&target).map_err(|e| e.to_string())?.len();
+            manifest
+                .files
+                .insert(source.relative.clone(), mirrored(hash, size));
+            return Ok(Mirrored::Unchanged);
+        }
+        _ => {}
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    while let Some(from) = leaving.get_mut(&hash).and_then(Vec::pop) {
+        let from_path = dest.join(&from);
+        if !from_path.exists() || (options.verify_existing && !matches(&from_path)) {
+            continue;
+        }
+        fs::rename(&from_path, &target).map_err(|e| e.to_string())?;
+        let file = manifest
+            .files
+            .remove(&from)
+            .expect("leaving files are listed");
+        manifest
+            .files
+            .insert(source.relative.clone(), mirrored(hash, file.size));
+        return Ok(Mirrored::Moved);
+    }
+
+    let tmp = tmp_path(&target);
+    let copied = copy_and_hash(&source.path, &tmp).and_then(|(read, size)| {
+        let written = get_file_hash(&tmp)?;
+        Ok((read, written, size))
+    });
+    let (read, written, size) = copied.map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        e.to_string()
+    })?;
+    if read != hash || written != hash {
+        let _ = fs::remove_file(&tmp);
+        return Err(if read != hash {
+            format!("does not match its BLAKE3 hash {hash}")
+        } else {
+            "copy failed verification".to_string()
+        });
+    }
+    fs::rename(&tmp, &target).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        e.to_string()
+    })?;
+    manifest
+        .files
+        .insert(source.relative.clone(), mirrored(hash, size));
+    Ok(Mirrored::Copied)
+}
+
+/// Restores the library backed up at `mirror` into `root`, which must not
+/// exist or be empty, and builds its index with [`rebuild_index`].
+///
+/// Only files the library still had at the last backup are restored, each
+/// checked against the hash in the mirror's manifest; a damaged or missing
+/// copy is reported and left out. Thumbnails are not part of a backup and have to
+/// be generated again.
+pub fn restore_library(
+    mirror: &Path,
+    root: &Path,
+) -> Result<(Library, RestoreReport), LibraryError> {
+    let manifest_path = backup_manifest_path(mirror);
+    if !manifest_path.exists() {
+        return Err(LibraryError::InvalidBackup(format!(
+            "no backup at {}",
+            mirror.display()
+        )));
+    }
+    let manifest =
+        read_backup_manifest(&manifest_path).map_err(|e| LibraryError::Cbor(e.to_string()))?;
+
+    let root_existed = root.exists();
+    if root_existed && fs::read_dir(root)?.next().is_some() {
+        return Err(LibraryError::DirectoryNotEmpty);
+    }
+    let result = restore_inner(mirror, root, &manifest);
+    if result.is_err() && !root_existed {
+        let _ = fs::remove_dir_all(root);
+    }
+    result
+}
+
+fn restore_inner(
+    mirror: &Path,
+    root: &Path,
+    manifest: &BackupManifestCbor,
+) -> Result<(Library, RestoreReport), LibraryError> {
+    for dir in SKELETON_DIRS {
+        fs::create_dir_all(root.join(dir))?;
+    }
+
+    let mut report = RestoreReport::default();
+    let live = manifest
+        .files
+        .iter()
+        .filter(|(_, f)| f.removed_at.is_none());
+    for (relative, file) in live {
+        let target = root.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = tmp_path(&target);
+        let copied = copy_and_hash(&mirror.join(relative), &tmp)
+            .and_then(|(hash, _)| Ok((hash, get_file_hash(&tmp)?)));
+        let problem = match copied {
+            Err(e) => Some(format!("could not be copied: {e}")),
+            Ok((read, written)) if read != file.hash_blake3 || written != file.hash_blake3 => {
+                Some("does not match its BLAKE3 hash".to_string())
+            }
+            Ok(_) => None,
+        };
+        if let Some(problem) = problem {
+            let _ = fs::remove_file(&tmp);
+            report.damaged.push((relative.clone(), problem));
+            continue;
+        }
+        fs::rename(&tmp, &target)?;
+        report.restored += 1;
+    }
+
+    let library = open_library(root)?;
+    rebuild_index(&library)?;
+    Ok((library, report))
+}
+
+fn mirrored(hash_blake3: String, size: u64) -> MirroredFile {
+    MirroredFile {
+        hash_blake3,
+        size,
+        removed_at: None,
+    }
+}
+
+/// `path` relative to `root`, `/`-separated, as the manifest keys it.
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::executor::execute;
+    use crate::import::executor_cancellation::CancellationToken;
+    use crate::import::planner::{ImportConfig, plan};
+    use crate::import::scanner::scan;
+    use crate::library::init::init_library;
+    use crate::library::paths::sidecar_path;
+    use crate::library::trash::soft_delete;
+    use tempfile::TempDir;
+
+    fn import(lib: &Library, files: &[(&str, &[u8])]) {
+        let src = TempDir::new().unwrap();
+        for (name, bytes) in files {
+            fs::write(src.path().join(name), bytes).unwrap();
+        }
+        let config = ImportConfig {
+            thumbnail_format: None,
+            ..Default::default()
+        };
+        let plan_result = plan(
+            &scan(&[src.path().to_path_buf()]).unwrap(),
+            &lib.db,
+            &config,
+        )
+        .unwrap();
+        execute(
+            &plan_result,
+            lib,
+            &config,
+            |_| {},
+            &CancellationToken::new(),
+        )
+        .unwrap();
+    }
+
+    /// The only asset's uuid, sidecar and original.
+    fn only_asset(lib: &Library) -> (Uuid, PathBuf, PathBuf) {
+        let row = lib.db.list_assets().unwrap().remove(0);
+        let uuid = Uuid::parse_str(&row.uuid).unwrap();
+        let sidecar = sidecar_path(&lib.root, &uuid, "", row.capture_utc);
+        let media = sidecar.with_extension("jpg");
+        (uuid, sidecar, media)
+    }
+
+    fn in_mirror(lib: &Library, mirror: &Path, path: &Path) -> PathBuf {
+        mirror.join(path.strip_prefix(&lib.root).unwrap())
+    }
+
+    #[test]
+    fn test_backup_copies_changes_and_restores() {
+        let lib_dir = TempDir::new().unwrap();
+        let mirror = TempDir::new().unwrap();
+        let restore_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        import(&lib, &[("a.jpg", b"a"), ("b.jpg", b"b")]);
+
+        // Two originals, two sidecars, the version and the config.
+        let first = lib
+            .backup(mirror.path(), &BackupOptions::default())
+            .unwrap();
+        assert!(first.failed.is_empty());
+        assert_eq!(first.copied, 6);
+        let second = lib
+            .backup(mirror.path(), &BackupOptions::default())
+            .unwrap();
+        assert_eq!((second.copied, second.unchanged), (0, 6));
+
+        let uuid = lib.db.list_assets().unwrap()[0].uuid.clone();
+        lib.add_tags(std::slice::from_ref(&uuid), &["Kyoto".to_string()])
+            .unwrap();
+        let third = lib
+            .backup(mirror.path(), &BackupOptions::default())
+            .unwrap();
+        assert_eq!((third.copied, third.unchanged), (1, 5));
+        lib.close().unwrap();
+
+        let (restored, report) =
+            restore_library(mirror.path(), &restore_dir.path().join("lib")).unwrap();
+        assert_eq!(report.restored, 6);
+        assert!(report.damaged.is_empty());
+        assert_eq!(restored.db.list_assets().unwrap().len(), 2);
+        assert_eq!(restored.tags(&uuid).unwrap(), vec!["Kyoto"]);
+    }
+
+    #[test]
+    fn test_deletions_reach_mirror_after_retention() {
+        let lib_dir = TempDir::new().unwrap();
+        let mirror = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        import(&lib, &[("a.jpg", b"a")]);
+        lib.backup(mirror.path(), &BackupOptions::default())
+            .unwrap();
+        let (uuid, sidecar, media) = only_asset(&lib);
+        let mirrored_media = in_mirror(&lib, mirror.path(), &media);
+        let now = BackupOptions {
+            retention_secs: 0,
+            ..Default::default()
+        };
+
+        // A soft-deleted asset stays live in the mirror for the window...
+        soft_delete(&uuid.to_string(), &media, &sidecar, &lib).unwrap();
+        let held = lib
+            .backup(mirror.path(), &BackupOptions::default())
+            .unwrap();
+        assert_eq!((held.copied, held.retained), (0, 2));
+        assert!(mirrored_media.exists());
+
+        // ...and is then trashed there too, without copying the original.
+        let applied = lib.backup(mirror.path(), &now).unwrap();
+        assert_eq!((applied.copied, applied.moved), (1, 1));
+        assert!(!mirrored_media.exists());
+        let trashed = trash_path(&lib.root, &uuid, "jpg");
+        let mirrored_trash = in_mirror(&lib, mirror.path(), &trashed);
+        assert_eq!(fs::read(&mirrored_trash).unwrap(), b"a");
+
+        // Files purged from the library are removed after the window.
+        fs::remove_file(&sidecar).unwrap();
+        fs::remove_file(&trashed).unwrap();
+        let kept = lib
+            .backup(mirror.path(), &BackupOptions::default())
+            .unwrap();
+        assert_eq!((kept.retained, kept.removed), (2, 0));
+        assert!(mirrored_trash.exists());
+        let purged = lib.backup(mirror.path(), &now).unwrap();
+        assert_eq!(purged.removed, 2);
+        assert!(!mirrored_trash.exists());
+    }
+
+    #[test]
+    fn test_damaged_mirror_copies() {
+        let lib_dir = TempDir::new().unwrap();
+        let mirror = TempDir::new().unwrap();
+        let restored = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        import(&lib, &[("a.jpg", b"original")]);
+        lib.backup(mirror.path(), &BackupOptions::default())
+            .unwrap();
+        let (_, _, media) = only_asset(&lib);
+        let mirrored_media = in_mirror(&lib, mirror.path(), &media);
+
+        // The manifest is trusted unless asked to verify.
+        fs::write(&mirrored_media, b"bitrot!!").unwrap();
+        let trusting = lib
+            .backup(mirror.path(), &BackupOptions::default())
+            .unwrap();
+        assert_eq!(trusting.copied, 0);
+        let verify = BackupOptions {
+            verify_existing: true,
+            ..Default::default()
+        };
+        let verified = lib.backup(mirror.path(), &verify).unwrap();
+        assert_eq!(verified.copied, 1);
+        assert_eq!(fs::read(&mirrored_media).unwrap(), b"original");
+
+        // A restore leaves a damaged copy out.
+        fs::write(&mirrored_media, b"bitrot!!").unwrap();
+        let (_, report) = restore_library(mirror.path(), &restored.path().join("lib")).unwrap();
+        assert_eq!(report.damaged.len(), 1);
+        assert_eq!(report.damaged[0].0, relative(&lib.root, &media));
+
+        // A folder holding something else is not taken over.
+        let other = TempDir::new().unwrap();
+        fs::write(other.path().join("notes.txt"), b"mine").unwrap();
+        assert!(matches!(
+            lib.backup(other.path(), &BackupOptions::default()),
+            Err(LibraryError::InvalidBackup(_))
+        ));
+    }
+
+    #[test]
+    fn test_restore_skips_missing_mirror_copies() {
+        let lib_dir = TempDir::new().unwrap();
+        let mirror = TempDir::new().unwrap();
+        let restored = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        import(&lib, &[("a.jpg", b"original")]);
+        lib.backup(mirror.path(), &BackupOptions::default())
+            .unwrap();
+        let (_, _, media) = only_asset(&lib);
+        fs::remove_file(in_mirror(&lib, mirror.path(), &media)).unwrap();
+
+        let (_, report) = restore_library(mirror.path(), &restored.path().join("lib")).unwrap();
+        assert_eq!(report.damaged.len(), 1);
+        assert_eq!(report.damaged[0].0, relative(&lib.root, &media));
+        assert!(report.restored > 0);
+    }
+}
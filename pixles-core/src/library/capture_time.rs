@@ -0,0 +1,378 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+use crate::library::error::LibraryError;
+use crate::library::library::Library;
+use crate::library::paths::sidecar_path;
+use crate::library::rebuild::{media_file_for, tz_source_str};
+use crate::sidecar::AssetSidecar;
+use crate::sidecar::io::{read_sidecar, write_sidecar};
+
+/// How [`Library::shift_capture_times`] moves capture times.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimeShift {
+    /// Adds this many seconds; negative to go back.
+    By(i64),
+    /// Sets the asset `uuid` to the local time `to` and moves every other
+    /// asset by as much, for when one photo's real time is known.
+    Anchor { uuid: String, to: NaiveDateTime },
+}
+
+/// Capture-time correction, for cameras whose clock was wrong.
+///
+/// A shift moves the local capture time and, where known, the UTC capture
+/// time by the same amount; the time zone stays. The first
+/// `capture_timestamp` is kept in `original_capture_timestamp`. Sidecars and
+/// originals whose month changes move to the new `media/{YYYY}/{YYYY-MM}/`
+/// folder.
+///
+/// All sidecars are rewritten before the index is updated in a single
+/// transaction. If any step fails, every file is put back and the index is
+/// left as it was.
+impl Library {
+    /// Shifts the capture time of each asset in `uuids`. Assets without a
+    /// capture time are left alone. Returns the number of assets changed.
+    pub fn shift_capture_times(
+        &self,
+        uuids: &[String],
+        shift: &TimeShift,
+    ) -> Result<usize, LibraryError> {
+        let mut uuids = uuids.to_vec();
+        let delta = match shift {
+            TimeShift::By(seconds) => *seconds,
+            TimeShift::Anchor { uuid, to } => {
+                let (_, path) = self.locate_sidecar(uuid)?;
+                let anchor = read_sidecar(&path).map_err(|e| LibraryError::Cbor(e.to_string()))?;
+                let from = anchor.capture_timestamp.ok_or_else(|| {
+                    LibraryError::InvalidTimeShift(format!("{uuid} has no capture time"))
+                })?;
+                uuids.push(uuid.clone());
+                to.and_utc().timestamp() - from
+            }
+        };
+        if delta == 0 {
+            return Ok(0);
+        }
+
+        let mut refiled = Vec::new();
+        let result = self.write_shifted(&uuids, delta, &mut refiled);
+        match result {
+            Ok(()) => {
+                let changed: Vec<String> = refiled.iter().map(|r| r.uuid.to_string()).collect();
+                for r in refiled {
+                    r.finish();
+                }
+                self.refresh_auto_xmp(&changed);
+                Ok(changed.len())
+            }
+            Err(e) => {
+                for r in refiled.into_iter().rev() {
+                    r.undo();
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Rewrites each shifted sidecar, recording it in `refiled`, then
+    /// commits all index changes at once.
+    fn write_shifted(
+        &self,
+        uuids: &[String],
+        delta: i64,
+        refiled: &mut Vec<Refiled>,
+    ) -> Result<(), LibraryError> {
+        let mut seen = HashSet::new();
+        for uuid in uuids {
+            let (uuid, path) = self.locate_sidecar(uuid)?;
+            if !seen.insert(uuid.clone()) {
+                continue;
+            }
+            let original = read_sidecar(&path).map_err(|e| LibraryError::Cbor(e.to_string()))?;
+            let Some(local) = original.capture_timestamp else {
+                continue;
+            };
+
+            let overflow = || LibraryError::InvalidTimeShift(format!("{uuid}: out of range"));
+            let mut updated = original.clone();
+            updated.original_capture_timestamp =
+                original.original_capture_timestamp.or(Some(local));
+            updated.capture_timestamp = Some(local.checked_add(delta).ok_or_else(overflow)?);
+            updated.capture_utc = original
+                .capture_utc
+                .map(|utc| utc.checked_add(delta).ok_or_else(overflow))
+                .transpose()?;
+            updated.modified_timestamp = now_secs();
+
+            let parsed = Uuid::parse_str(&uuid).map_err(|_| LibraryError::AssetNotFound(uuid))?;
+            refiled.push(self.refile(parsed, &path, &original, updated)?);
+        }
+
+        let changes: Vec<_> = refiled.iter().map(Refiled::capture_columns).collect();
+        self.db.set_capture_times_batch(&changes)?;
+        Ok(())
+    }
+
+    /// Writes `updated`, the sidecar at `path` with new capture fields, to
+    /// the month folder of its `capture_utc`, moving the original along with
+    /// it. Originals in the trash or imported by reference stay where they
+    /// are. The old sidecar is removed by [`Refiled::finish`].
+    pub(crate) fn refile(
+        &self,
+        uuid: Uuid,
+        path: &Path,
+        original: &AssetSidecar,
+        updated: AssetSidecar,
+    ) -> Result<Refiled, LibraryError> {
+        let new_path = sidecar_path(&self.root, &uuid, "", updated.capture_utc);
+        let media_move = match (
+            media_file_for(path, original),
+            media_file_for(&new_path, &updated),
+        ) {
+            (Some(from), Some(to))
+                if new_path != path && original.reference_path.is_none() && from.exists() =>
+            {
+                Some((from, to))
+            }
+            _ => None,
+        };
+        let refiled = Refiled {
+            uuid,
+            old_path: path.to_path_buf(),
+            new_path,
+            original: original.clone(),
+            updated,
+            media_move,
+        };
+
+        if let Some(dir) = refiled.new_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        if let Some((from, to)) = &refiled.media_move {
+            fs::rename(from, to)?;
+        }
+        if let Err(e) = write_sidecar(&refiled.new_path, &refiled.updated) {
+            refiled.restore_media();
+            return Err(LibraryError::Cbor(e.to_string()));
+        }
+        Ok(refiled)
+    }
+}
+
+/// A sidecar rewritten by [`Library::refile`], until the index has been
+/// updated to match and it is either finished or undone.
+pub(crate) struct Refiled {
+    uuid: Uuid,
+    old_path: PathBuf,
+    new_path: PathBuf,
+    original: AssetSidecar,
+    updated: AssetSidecar,
+    media_move: Option<(PathBuf, PathBuf)>,
+}
+
+impl Refiled {
+    /// The index columns to update, as taken by
+    /// `DatabaseDriver::set_capture_times_batch`.
+    pub(crate) fn capture_columns(&self) -> (String, i64, Option<i64>, Option<String>) {
+        let s = &self.updated;
+        (
+            self.uuid.to_string(),
+            s.capture_timestamp.unwrap_or(s.import_timestamp),
+            s.capture_utc,
+            s.capture_tz_source.map(|c| tz_source_str(c).to_string()),
+        )
+    }
+
+    /// Removes the sidecar left at the old location.
+    pub(crate) fn finish(self) {
+        if self.new_path != self.old_path
+            && let Err(e) = fs::remove_file(&self.old_path)
+        {
+            log::warn!(
+                "could not remove old sidecar {}: {e}",
+                self.old_path.display()
+            );
+        }
+    }
+
+    /// Puts the sidecar and original back as they were.
+    pub(crate) fn undo(self) {
+        let restored = if self.new_path != self.old_path {
+            fs::remove_file(&self.new_path).map_err(|e| e.to_string())
+        } else {
+            write_sidecar(&self.old_path, &self.original).map_err(|e| e.to_string())
+        };
+        if let Err(e) = restored {
+            log::error!("could not restore {}: {e}", self.old_path.display());
+        }
+        self.restore_media();
+    }
+
+    fn restore_media(&self) {
+        if let Some((from, to)) = &self.media_move
+            && let Err(e) = fs::rename(to, from)
+        {
+            log::error!("could not move {} back: {e}", to.display());
+        }
+    }
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::executor::execute;
+    use crate::import::executor_cancellation::CancellationToken;
+    use crate::import::planner::{ImportConfig, plan};
+    use crate::import::scanner::scan;
+    use crate::library::init::init_library;
+    use chrono::NaiveDate;
+    use tempfile::TempDir;
+
+    /// Imports `a.jpg` and `b.jpg`, gives them capture times of 2024-06-30
+    /// 23:30 and 23:45 UTC, and returns their UUIDs in that order.
+    fn import_two(lib: &Library) -> Vec<String> {
+        let src = TempDir::new().unwrap();
+        for name in ["a.jpg", "b.jpg"] {
+            fs::write(src.path().join(name), name).unwrap();
+        }
+        let config = ImportConfig {
+            thumbnail_format: None,
+            max_workers: 1,
+            ..Default::default()
+        };
+        let plan_result = plan(
+            &scan(&[src.path().to_path_buf()]).unwrap(),
+            &lib.db,
+            &config,
+        )
+        .unwrap();
+        execute(
+            &plan_result,
+            lib,
+            &config,
+            |_| {},
+            &CancellationToken::new(),
+        )
+        .unwrap();
+
+        let mut rows = lib.db.list_assets().unwrap();
+        rows.sort_by(|a, b| a.original_filename.cmp(&b.original_filename));
+        for (row, local) in rows.iter().zip([1_719_790_200, 1_719_791_100]) {
+            let uuid = Uuid::parse_str(&row.uuid).unwrap();
+            let path = sidecar_path(&lib.root, &uuid, "", row.capture_utc);
+            let mut sidecar = read_sidecar(&path).unwrap();
+            sidecar.capture_timestamp = Some(local);
+            sidecar.capture_utc = Some(local);
+            sidecar.capture_tz = Some("+00:00".to_string());
+            let refiled = lib.refile(uuid, &path, &sidecar.clone(), sidecar).unwrap();
+            lib.db
+                .set_capture_times_batch(&[refiled.capture_columns()])
+                .unwrap();
+            refiled.finish();
+        }
+        rows.into_iter().map(|r| r.uuid).collect()
+    }
+
+    fn sidecar_of(lib: &Library, uuid: &str) -> (PathBuf, AssetSidecar) {
+        let (_, path) = lib.locate_sidecar(uuid).unwrap();
+        let sidecar = read_sidecar(&path).unwrap();
+        (path, sidecar)
+    }
+
+    #[test]
+    fn test_shift_by_offset_refiles_into_new_month() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let uuids = import_two(&lib);
+        let (old_path, _) = sidecar_of(&lib, &uuids[0]);
+
+        // The camera was an hour behind: 23:30 was really 00:30 on 1 July.
+        assert_eq!(
+            lib.shift_capture_times(&uuids, &TimeShift::By(3600))
+                .unwrap(),
+            2
+        );
+
+        let (path, a) = sidecar_of(&lib, &uuids[0]);
+        assert_eq!(a.capture_timestamp, Some(1_719_793_800));
+        assert_eq!(a.capture_utc, Some(1_719_793_800));
+        assert_eq!(a.original_capture_timestamp, Some(1_719_790_200));
+        assert!(old_path.parent().unwrap().ends_with("2024/2024-06"));
+        assert!(path.parent().unwrap().ends_with("2024/2024-07"));
+        assert!(!old_path.exists());
+        assert_eq!(
+            fs::read(media_file_for(&path, &a).unwrap()).unwrap(),
+            b"a.jpg"
+        );
+
+        let row = lib.db.find_by_uuid(&uuids[0]).unwrap().unwrap();
+        assert_eq!(row.capture_timestamp, 1_719_793_800);
+        assert_eq!(row.capture_utc, Some(1_719_793_800));
+
+        // Shifting back keeps the time first imported.
+        lib.shift_capture_times(&uuids[..1], &TimeShift::By(-3600))
+            .unwrap();
+        let (path, a) = sidecar_of(&lib, &uuids[0]);
+        assert_eq!(path, old_path);
+        assert_eq!(a.capture_timestamp, Some(1_719_790_200));
+        assert_eq!(a.original_capture_timestamp, Some(1_719_790_200));
+    }
+
+    #[test]
+    fn test_shift_to_anchor_moves_the_rest_relatively() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let uuids = import_two(&lib);
+
+        // b.jpg was really taken at 2024-07-01 09:45; a.jpg 15 minutes earlier.
+        let to = NaiveDate::from_ymd_opt(2024, 7, 1)
+            .unwrap()
+            .and_hms_opt(9, 45, 0)
+            .unwrap();
+        let shift = TimeShift::Anchor {
+            uuid: uuids[1].clone(),
+            to,
+        };
+        assert_eq!(lib.shift_capture_times(&uuids[..1], &shift).unwrap(), 2);
+
+        let (_, b) = sidecar_of(&lib, &uuids[1]);
+        assert_eq!(b.capture_timestamp, Some(to.and_utc().timestamp()));
+        let (_, a) = sidecar_of(&lib, &uuids[0]);
+        assert_eq!(
+            a.capture_timestamp,
+            Some(to.and_utc().timestamp() - 15 * 60)
+        );
+    }
+
+    #[test]
+    fn test_failed_shift_changes_nothing() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let uuids = import_two(&lib);
+        let before: Vec<_> = uuids.iter().map(|u| sidecar_of(&lib, u)).collect();
+
+        let selection = vec![uuids[0].clone(), uuids[1].clone(), "missing".to_string()];
+        assert!(
+            lib.shift_capture_times(&selection, &TimeShift::By(86_400))
+                .is_err()
+        );
+
+        for (uuid, (path, sidecar)) in uuids.iter().zip(&before) {
+            assert_eq!(&sidecar_of(&lib, uuid), &(path.clone(), sidecar.clone()));
+            assert!(fs::read(media_file_for(path, sidecar).unwrap()).is_ok());
+        }
+        let row = lib.db.find_by_uuid(&uuids[0]).unwrap().unwrap();
+        assert_eq!(row.capture_utc, before[0].1.capture_utc);
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use pixles_media::image::phash::hamming_distance;
+
+use crate::db::rows::AssetRow;
+use crate::library::error::LibraryError;
+use crate::library::library::Library;
+
+impl Library {
+    /// Groups of assets that look alike: every asset is within
+    /// `max_distance` bits of perceptual hash of another in its group.
+    ///
+    /// Only assets outside the trash with a perceptual hash are compared;
+    /// videos, RAW files and anything else pixles-media cannot decode have
+    /// none. Members of one stack are not grouped with each other, as the
+    /// stack already holds them together. Each group, and the list of
+    /// groups, is in import order.
+    pub fn near_duplicates(&self, max_distance: u32) -> Result<Vec<Vec<AssetRow>>, LibraryError> {
+        let assets = self.db.assets_with_perceptual_hash()?;
+        let hashes: Vec<u64> = assets
+            .iter()
+            .map(|a| a.perceptual_hash.unwrap_or_default() as u64)
+            .collect();
+
+        let mut sets = DisjointSets::new(assets.len());
+        for_each_candidate_pair(&hashes, max_distance, |a, b| {
+            let same_stack =
+                assets[a].stack_id.is_some() && assets[a].stack_id == assets[b].stack_id;
+            if !same_stack && hamming_distance(hashes[a], hashes[b]) <= max_distance {
+                sets.union(a, b);
+            }
+        });
+
+        let mut groups: Vec<Vec<AssetRow>> = Vec::new();
+        let mut group_of_root = HashMap::new();
+        for (i, asset) in assets.into_iter().enumerate() {
+            let group = *group_of_root.entry(sets.find(i)).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group].push(asset);
+        }
+        groups.retain(|group| group.len() > 1);
+        Ok(groups)
+    }
+}
+
+/// Calls `f` for every pair of indices whose hashes may be within
+/// `max_distance` bits, and for some that are not.
+///
+/// The 64 bits are split into `max_distance + 1` ranges. Two hashes that
+/// close can differ in at most `max_distance` of the ranges, so they agree
+/// on at least one; only hashes sharing a range's value are paired, rather
+/// than every hash with every other.
+fn for_each_candidate_pair(hashes: &[u64], max_distance: u32, mut f: impl FnMut(usize, usize)) {
+    let ranges = (max_distance as usize + 1).min(64);
+    for range in 0..ranges {
+        let (start, end) = (range * 64 / ranges, (range + 1) * 64 / ranges);
+        let mask = (u64::MAX >> (64 - (end - start))) << start;
+
+        let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (i, hash) in hashes.iter().enumerate() {
+            buckets.entry(hash & mask).or_default().push(i);
+        }
+        for bucket in buckets.values() {
+            for (n, &a) in bucket.iter().enumerate() {
+                for &b in &bucket[n + 1..] {
+                    f(a, b);
+                }
+            }
+        }
+    }
+}
+
+/// Union-find over `0..n`.
+struct DisjointSets {
+    parent: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::executor::execute;
+    use crate::import::executor_cancellation::CancellationToken;
+    use crate::import::planner::{
+        DEFAULT_NEAR_DUPLICATE_DISTANCE, ImportConfig, ImportDecision, plan,
+    };
+    use crate::import::scanner::scan;
+    use crate::library::init::init_library;
+    use pixles_media::image::buffer::{ComponentType, ImageBuffer, PixelFormat};
+    use pixles_media::image::formats::png::PngImage;
+    use pixles_media::image::metadata::ImageMetadata;
+    use pixles_media::image::{Image, ImageEncode};
+    use pixles_media::metadata::ColorSpace;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    /// A horizontal gradient with a bright square, running the other way
+    /// when `mirrored`.
+    fn write_scene(path: &Path, width: usize, height: usize, mirrored: bool) {
+        let mut data = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let mut v = (255 * x / width) as u8;
+                if x > width / 4 && x < width / 2 && y > height / 3 && y < height * 2 / 3 {
+                    v = 255;
+                }
+                if mirrored {
+                    v = 255 - v;
+                }
+                data.extend_from_slice(&[v, v / 2, 255 - v / 3]);
+            }
+        }
+        let buffer = ImageBuffer::new(
+            data,
+            width,
+            height,
+            PixelFormat::Rgb,
+            ComponentType::U8,
+            ColorSpace::Srgb,
+        )
+        .unwrap();
+        let bytes = PngImage::from_raw_parts(buffer, ImageMetadata::default())
+            .unwrap()
+            .encode_to_bytes()
+            .unwrap();
+        fs::write(path, bytes).unwrap();
+    }
+
+    fn import(lib: &Library, dir: &Path, config: &ImportConfig) {
+        let plan_result = plan(&scan(&[dir.to_path_buf()]).unwrap(), &lib.db, config).unwrap();
+        execute(&plan_result, lib, config, |_| {}, &CancellationToken::new()).unwrap();
+    }
+
+    #[test]
+    fn test_resized_copy_is_flagged_and_grouped() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+
+        let originals = TempDir::new().unwrap();
+        write_scene(&originals.path().join("kyoto.png"), 400, 300, false);
+        write_scene(&originals.path().join("osaka.png"), 400, 300, true);
+        import(&lib, originals.path(), &ImportConfig::default());
+
+        // A smaller re-export of kyoto.png is flagged when asked for...
+        let exports = TempDir::new().unwrap();
+        write_scene(&exports.path().join("kyoto-small.png"), 200, 150, false);
+        let flagging = ImportConfig {
+            near_duplicate_distance: Some(DEFAULT_NEAR_DUPLICATE_DISTANCE),
+            ..Default::default()
+        };
+        let plan_result = plan(
+            &scan(&[exports.path().to_path_buf()]).unwrap(),
+            &lib.db,
+            &flagging,
+        )
+        .unwrap();
+        assert_eq!(plan_result.counts.near_duplicates, 1);
+        assert!(matches!(
+            &plan_result.actions[0].1,
+            ImportDecision::NearDuplicate { distance, .. } if *distance <= DEFAULT_NEAR_DUPLICATE_DISTANCE
+        ));
+
+        // ...and grouped with it once imported anyway.
+        import(&lib, exports.path(), &ImportConfig::default());
+        let groups = lib
+            .near_duplicates(DEFAULT_NEAR_DUPLICATE_DISTANCE)
+            .unwrap();
+        assert_eq!(groups.len(), 1);
+        let names: Vec<_> = groups[0]
+            .iter()
+            .map(|a| a.original_filename.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, vec!["kyoto.png", "kyoto-small.png"]);
+    }
+
+    #[test]
+    fn test_hash_is_kept_without_thumbnails() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let src = TempDir::new().unwrap();
+        write_scene(&src.path().join("kyoto.png"), 400, 300, false);
+        write_scene(&src.path().join("kyoto-small.png"), 200, 150, false);
+        let config = ImportConfig {
+            thumbnail_format: None,
+            ..Default::default()
+        };
+        import(&lib, src.path(), &config);
+
+        let groups = lib
+            .near_duplicates(DEFAULT_NEAR_DUPLICATE_DISTANCE)
+            .unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].len(), 2);
+    }
+
+    #[test]
+    fn test_candidate_pairs_cover_every_close_pair() {
+        let hashes = [0u64, 0b111, u64::MAX, 0b1 << 63, 0xff00];
+        for max_distance in [0, 3, 8] {
+            let mut found = Vec::new();
+            for_each_candidate_pair(&hashes, max_distance, |a, b| found.push((a, b)));
+            for a in 0..hashes.len() {
+                for b in a + 1..hashes.len() {
+                    if hamming_distance(hashes[a], hashes[b]) <= max_distance {
+                        assert!(found.contains(&(a, b)), "{a},{b} at {max_distance}");
+                    }
+                }
+            }
+        }
+    }
+}
@@ -21,6 +21,42 @@ pub enum LibraryError {
     #[error("version mismatch: found {found}, expected {expected}")]
     VersionMismatch { found: u8, expected: u8 },
 
+    #[error("index schema v{found} is newer than supported v{supported}")]
+    IndexTooNew { found: u32, supported: u32 },
+
+    #[error("library must be upgraded before it can be read: {0}")]
+    NeedsUpgrade(String),
+
+    #[error("asset not found: {0}")]
+    AssetNotFound(String),
+
+    #[error("invalid tag: {0:?}")]
+    InvalidTag(String),
+
+    #[error("album not found: {0}")]
+    AlbumNotFound(String),
+
+    #[error("invalid album change: {0}")]
+    InvalidAlbum(String),
+
+    #[error("invalid time shift: {0}")]
+    InvalidTimeShift(String),
+
+    #[error("invalid filename template: {0}")]
+    InvalidTemplate(String),
+
+    #[error("invalid export: {0}")]
+    InvalidExport(String),
+
+    #[error("invalid backup: {0}")]
+    InvalidBackup(String),
+
+    #[error("invalid merge: {0}")]
+    InvalidMerge(String),
+
+    #[error("migration failed: {0}")]
+    Migration(String),
+
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -0,0 +1,590 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use pixles_media::image::formats::jpeg::{JpegImage, embed_xmp};
+use pixles_media::image::formats::{png::PngImage, webp::WebpImage};
+use pixles_media::image::metadata::ImageMetadata;
+use pixles_media::image::types::{ImageOutputSettings, ImageResolution};
+use pixles_media::image::{Image, ImageEncode, normalize_orientation};
+use uuid::Uuid;
+
+use crate::db::rows::AssetRow;
+use crate::db::search::SearchFilter;
+use crate::domain::{ExportCollision, ExportMetadata, ExportPreset, XmpNaming};
+use crate::library::error::LibraryError;
+use crate::library::filename_template::FilenameTemplate;
+use crate::library::library::Library;
+use crate::library::paths::{sidecar_path, tmp_path};
+use crate::library::rebuild::media_file_for;
+use crate::library::thumbnails::{DerivativeError, decode, write_atomic};
+use crate::library::xmp_export::{is_sidecar_file, xmp_for_sidecar};
+use crate::sidecar::AssetSidecar;
+use crate::sidecar::io::read_sidecar;
+use crate::utils::hash::get_file_hash;
+
+/// The assets [`Library::export`] writes out.
+#[derive(Debug, Clone)]
+pub enum ExportSelection {
+    /// Assets matching a search, in search order.
+    Search(Box<SearchFilter>),
+    /// An album's assets, in album order. Takes an album ID or name.
+    Album(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    /// Path of each exported file below the export folder.
+    pub template: FilenameTemplate,
+    /// Copy the originals. Without them only the rendered copies are
+    /// written, so a preset is required.
+    pub originals: bool,
+    /// Also write a copy rendered with this preset.
+    pub preset: Option<ExportPreset>,
+    pub metadata: ExportMetadata,
+    /// Naming of `.xmp` sidecars written for [`ExportMetadata::Sidecar`].
+    pub xmp_naming: XmpNaming,
+    pub collision: ExportCollision,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            template: FilenameTemplate::default(),
+            originals: true,
+            preset: None,
+            metadata: ExportMetadata::None,
+            xmp_naming: XmpNaming::Stem,
+            collision: ExportCollision::Rename,
+        }
+    }
+}
+
+/// Outcome of [`Library::export`].
+#[derive(Debug, Default)]
+pub struct ExportReport {
+    /// Files written, originals and rendered copies alike.
+    pub written: usize,
+    /// Files already in the export folder with the same content.
+    pub unchanged: usize,
+    /// Assets, or one of their files, left out, with the reason.
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Bytes bound for one exported file.
+enum Content<'a> {
+    /// The original, copied as it is.
+    Original {
+        path: &'a Path,
+        s: &'a AssetSidecar,
+    },
+    Bytes(Vec<u8>),
+}
+
+enum Placement {
+    Write(PathBuf),
+    Unchanged(PathBuf),
+    Taken(PathBuf),
+}
+
+impl Library {
+    /// Writes the selected assets into `dest`, each named by
+    /// `options.template`. Imported sidecar files such as `.xmp` are left
+    /// out.
+    ///
+    /// Originals keep their extension, which is appended when the template
+    /// drops it. Rendered copies take the preset's extension instead, and
+    /// go into a folder named after the preset when originals are exported
+    /// too. Only formats pixles-media decodes (JPEG, PNG, GIF, BMP, WebP)
+    /// can be rendered; other assets are reported as skipped.
+    ///
+    /// A file already at the templated name with the same content is left
+    /// as it is, so exporting again only writes what changed. Otherwise
+    /// `options.collision` decides; two assets of one export that render
+    /// to the same name are always told apart with `-1`, `-2`, ...
+    pub fn export(
+        &self,
+        selection: &ExportSelection,
+        dest: &Path,
+        options: &ExportOptions,
+    ) -> Result<ExportReport, LibraryError> {
+        if !options.originals && options.preset.is_none() {
+            return Err(LibraryError::InvalidExport(
+                "nothing to export without originals or a preset".to_string(),
+            ));
+        }
+        let rows = match selection {
+            ExportSelection::Search(filter) => self.db.search(filter)?,
+            ExportSelection::Album(id) => self.album_assets(id)?,
+        };
+        fs::create_dir_all(dest)?;
+
+        let mut report = ExportReport::default();
+        let mut claimed = HashSet::new();
+        for row in rows.iter().filter(|r| !is_sidecar_file(r)) {
+            if let Err(reason) = self.export_asset(row, dest, options, &mut claimed, &mut report) {
+                report.skipped.push((row.uuid.clone(), reason));
+            }
+        }
+        Ok(report)
+    }
+
+    fn export_asset(
+        &self,
+        row: &AssetRow,
+        dest: &Path,
+        options: &ExportOptions,
+        claimed: &mut HashSet<PathBuf>,
+        report: &mut ExportReport,
+    ) -> Result<(), String> {
+        let uuid = Uuid::parse_str(&row.uuid).map_err(|e| e.to_string())?;
+        let sidecar_file = sidecar_path(&self.root, &uuid, "", row.capture_utc);
+        let s = read_sidecar(&sidecar_file).map_err(|e| format!("sidecar: {e}"))?;
+        let media = media_file_for(&sidecar_file, &s)
+            .filter(|m| m.exists())
+            .ok_or("original is missing")?;
+        let name = options.template.render(&s);
+        let xmp = (options.metadata != ExportMetadata::None).then(|| xmp_for_sidecar(&s).to_xml());
+
+        if options.originals {
+            let ext = Path::new(&s.original_filename)
+                .extension()
+                .map(|e| e.to_string_lossy().into_owned());
+            let path = match ext {
+                Some(ext) if !has_extension(&name, &ext) => append_extension(&name, &ext),
+                _ => name.clone(),
+            };
+            let content = Content::Original {
+                path: &media,
+                s: &s,
+            };
+            write_file(
+                dest.join(path),
+                content,
+                xmp.as_deref(),
+                options,
+                claimed,
+                report,
+            )?;
+        }
+
+        if let Some(preset) = options.preset {
+            let rendered = render(&media, preset)
+                .map_err(|e| format!("rendering {}: {e}", preset.as_str()))?
+                .ok_or_else(|| format!("cannot render {}", s.original_filename))?;
+            let mut path = name.with_extension(preset.extension());
+            if options.originals {
+                path = Path::new(preset.as_str()).join(path);
+            }
+            let content = Content::Bytes(rendered);
+            write_file(
+                dest.join(path),
+                content,
+                xmp.as_deref(),
+                options,
+                claimed,
+                report,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes one file, and its `.xmp` when metadata goes in a sidecar. With
+/// [`ExportMetadata::Embedded`], a JPEG carries the packet instead.
+fn write_file(
+    path: PathBuf,
+    mut content: Content,
+    xmp: Option<&str>,
+    options: &ExportOptions,
+    claimed: &mut HashSet<PathBuf>,
+    report: &mut ExportReport,
+) -> Result<(), String> {
+    let mut sidecar_xmp = xmp;
+    if let (Some(packet), ExportMetadata::Embedded) = (xmp, options.metadata)
+        && is_jpeg(&path)
+    {
+        let bytes = match &content {
+            Content::Original { path, .. } => fs::read(path).map_err(|e| e.to_string())?,
+            Content::Bytes(bytes) => bytes.clone(),
+        };
+        if let Some(embedded) = embed_xmp(&bytes, packet) {
+            content = Content::Bytes(embedded);
+            sidecar_xmp = None;
+        }
+    }
+
+    let path = match place(path, &content, options.collision, claimed) {
+        Placement::Write(path) => {
+            content
+                .write_to(&path)
+                .map_err(|e| format!("writing {}: {e}", path.display()))?;
+            report.written += 1;
+            path
+        }
+        Placement::Unchanged(path) => {
+            report.unchanged += 1;
+            path
+        }
+        Placement::Taken(path) => return Err(format!("{} already exists", path.display())),
+    };
+
+    if let Some(packet) = sidecar_xmp {
+        let xmp_path = options.xmp_naming.sidecar_for(&path);
+        if !claimed.insert(xmp_path.clone()) {
+            return Err(format!(
+                "{} was already written for another file",
+                xmp_path.display()
+            ));
+        }
+        write_atomic(&xmp_path, packet.as_bytes())
+            .map_err(|e| format!("{}: {e}", xmp_path.display()))?;
+    }
+    Ok(())
+}
+
+/// Where `content` goes, starting from `path`: a free name, or one that
+/// already holds the same bytes.
+fn place(
+    path: PathBuf,
+    content: &Content,
+    collision: ExportCollision,
+    claimed: &mut HashSet<PathBuf>,
+) -> Placement {
+    let mut candidate = path.clone();
+    for n in 1.. {
+        if !claimed.contains(&candidate) {
+            if !candidate.exists() {
+                claimed.insert(candidate.clone());
+                return Placement::Write(candidate);
+            }
+            if content.matches(&candidate) {
+                claimed.insert(candidate.clone());
+                return Placement::Unchanged(candidate);
+            }
+            match collision {
+                ExportCollision::Rename => {}
+                ExportCollision::Skip => return Placement::Taken(candidate),
+                ExportCollision::Overwrite => {
+                    claimed.insert(candidate.clone());
+                    return Placement::Write(candidate);
+                }
+            }
+        }
+        candidate = numbered(&path, n);
+    }
+    unreachable!("ran out of names for {}", path.display())
+}
+
+impl Content<'_> {
+    fn matches(&self, existing: &Path) -> bool {
+        let Ok(meta) = fs::metadata(existing) else {
+            return false;
+        };
+        match self {
+            Content::Original { s, .. } => {
+                meta.len() == s.file_size
+                    && get_file_hash(existing).is_ok_and(|h| h == s.hash_blake3)
+            }
+            Content::Bytes(bytes) => {
+                meta.len() == bytes.len() as u64
+                    && fs::read(existing).is_ok_and(|existing| existing == *bytes)
+            }
+        }
+    }
+
+    fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        match self {
+            Content::Original { path: media, .. } => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let tmp = tmp_path(path);
+                fs::copy(media, &tmp)
+                    .and_then(|_| fs::rename(&tmp, path))
+                    .inspect_err(|_| {
+                        let _ = fs::remove_file(&tmp);
+                    })
+            }
+            Content::Bytes(bytes) => write_atomic(path, bytes),
+        }
+    }
+}
+
+/// Renders `media` with `preset`: rotated upright and fitted within the
+/// preset's size, never enlarged or cropped. `Ok(None)` for media
+/// pixles-media cannot decode.
+fn render(media: &Path, preset: ExportPreset) -> Result<Option<Vec<u8>>, DerivativeError> {
+    let Some((buffer, mut metadata)) = decode(media)? else {
+        return Ok(None);
+    };
+    let settings = preset.settings();
+    let mut image = normalize_orientation(&buffer, &mut metadata);
+    // The JPEG and WebP encoders take 8-bit input; PNG keeps 16 bits.
+    if !matches!(settings, ImageOutputSettings::Png(_)) {
+        image = image.into_u8()?;
+    }
+    let resolution = match &settings {
+        ImageOutputSettings::Jpeg(s) => s.resolution,
+        ImageOutputSettings::WebP(s) => s.resolution,
+        ImageOutputSettings::Png(s) => s.resolution,
+        _ => None,
+    };
+    if let Some(resolution) = resolution {
+        let (width, height) = fit_within(image.width, image.height, resolution);
+        if (width, height) != (image.width, image.height) {
+            image = image.resize(width, height)?;
+        }
+    }
+
+    let bytes = match settings {
+        ImageOutputSettings::Jpeg(s) => JpegImage::from_raw_parts(image, ImageMetadata::default())?
+            .with_settings(s)
+            .encode_to_bytes()?,
+        ImageOutputSettings::WebP(s) => WebpImage::from_raw_parts(image, ImageMetadata::default())?
+            .with_settings(s)
+            .encode_to_bytes()?,
+        ImageOutputSettings::Png(s) => PngImage::from_raw_parts(image, ImageMetadata::default())?
+            .with_settings(s)
+            .encode_to_bytes()?,
+        _ => unreachable!("export presets are JPEG, WebP or PNG"),
+    };
+    Ok(Some(bytes))
+}
+
+/// The largest size with the aspect ratio of `width`×`height` that fits the
+/// box `resolution` describes, capped at the original size.
+fn fit_within(width: usize, height: usize, resolution: ImageResolution) -> (usize, usize) {
+    let (max_width, max_height) = match resolution {
+        ImageResolution::Exact { width, height } => (width, height),
+        ImageResolution::MaxDimension(max) | ImageResolution::Square(max) => (max, max),
+        ImageResolution::ScaleToWidth(width) => (width, u32::MAX),
+        ImageResolution::ScaleToHeight(height) => (u32::MAX, height),
+        ImageResolution::Standard(size) => {
+            let (width, height, _) = size.dimensions();
+            (width, height)
+        }
+    };
+    let scale = (max_width as f64 / width as f64)
+        .min(max_height as f64 / height as f64)
+        .min(1.0);
+    let scaled = |v: usize| ((v as f64 * scale).round() as usize).max(1);
+    (scaled(width), scaled(height))
+}
+
+fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension()
+        .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case(ext))
+}
+
+fn append_extension(path: &Path, ext: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(ext);
+    PathBuf::from(name)
+}
+
+fn is_jpeg(path: &Path) -> bool {
+    has_extension(path, "jpg") || has_extension(path, "jpeg")
+}
+
+/// `photo-3.jpg` for `photo.jpg` and 3.
+fn numbered(path: &Path, n: u32) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}-{n}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{n}"),
+    };
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::executor::execute;
+    use crate::import::executor_cancellation::CancellationToken;
+    use crate::import::planner::{ImportConfig, plan};
+    use crate::import::scanner::scan;
+    use crate::library::init::init_library;
+    use pixles_media::image::ImageDecode;
+    use pixles_media::image::buffer::{ComponentType, ImageBuffer, PixelFormat};
+    use pixles_media::image::types::StandardImageSize;
+    use pixles_media::metadata::ColorSpace;
+    use tempfile::TempDir;
+
+    fn import(lib: &Library, files: &[(&str, &[u8])]) {
+        let src = TempDir::new().unwrap();
+        for (name, bytes) in files {
+            fs::write(src.path().join(name), bytes).unwrap();
+        }
+        let config = ImportConfig {
+            thumbnail_format: None,
+            ..Default::default()
+        };
+        let plan_result = plan(
+            &scan(&[src.path().to_path_buf()]).unwrap(),
+            &lib.db,
+            &config,
+        )
+        .unwrap();
+        execute(
+            &plan_result,
+            lib,
+            &config,
+            |_| {},
+            &CancellationToken::new(),
+        )
+        .unwrap();
+    }
+
+    fn png(width: usize, height: usize) -> Vec<u8> {
+        let data = [200u8, 40, 40].repeat(width * height);
+        let buffer = ImageBuffer::new(
+            data,
+            width,
+            height,
+            PixelFormat::Rgb,
+            ComponentType::U8,
+            ColorSpace::Srgb,
+        )
+        .unwrap();
+        PngImage::from_raw_parts(buffer, ImageMetadata::default())
+            .unwrap()
+            .encode_to_bytes()
+            .unwrap()
+    }
+
+    fn everything() -> ExportSelection {
+        ExportSelection::Search(Box::default())
+    }
+
+    #[test]
+    fn test_export_names_and_collisions() {
+        let lib_dir = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        import(&lib, &[("IMG_1.jpg", b"first")]);
+        import(&lib, &[("IMG_1.jpg", b"second"), ("notes.xmp", b"<x/>")]);
+
+        let options = ExportOptions {
+            template: FilenameTemplate::parse("{camera_model}/{stem}").unwrap(),
+            metadata: ExportMetadata::Sidecar,
+            ..Default::default()
+        };
+        let report = lib.export(&everything(), dest.path(), &options).unwrap();
+        assert_eq!((report.written, report.unchanged), (2, 0));
+        assert!(report.skipped.is_empty());
+        let folder = dest.path().join("unknown");
+        let mut contents = [
+            fs::read(folder.join("IMG_1.jpg")).unwrap(),
+            fs::read(folder.join("IMG_1-1.jpg")).unwrap(),
+        ];
+        contents.sort();
+        assert_eq!(contents, [b"first".to_vec(), b"second".to_vec()]);
+        assert!(folder.join("IMG_1.xmp").exists());
+        assert!(folder.join("IMG_1-1.xmp").exists());
+        assert!(!folder.join("notes.xmp").exists());
+
+        // Exporting again finds both copies in place.
+        let report = lib.export(&everything(), dest.path(), &options).unwrap();
+        assert_eq!((report.written, report.unchanged), (0, 2));
+
+        // A stranger's file at the name is left alone, or replaced.
+        fs::write(folder.join("IMG_1.jpg"), b"someone else's").unwrap();
+        let skip = ExportOptions {
+            collision: ExportCollision::Skip,
+            ..options.clone()
+        };
+        let report = lib.export(&everything(), dest.path(), &skip).unwrap();
+        assert_eq!(report.skipped.len(), 2);
+        assert_eq!(
+            fs::read(folder.join("IMG_1.jpg")).unwrap(),
+            b"someone else's"
+        );
+        let overwrite = ExportOptions {
+            collision: ExportCollision::Overwrite,
+            ..options
+        };
+        let report = lib.export(&everything(), dest.path(), &overwrite).unwrap();
+        assert!(report.skipped.is_empty());
+        assert_ne!(
+            fs::read(folder.join("IMG_1.jpg")).unwrap(),
+            b"someone else's"
+        );
+    }
+
+    #[test]
+    fn test_export_renders_preset_with_embedded_xmp() {
+        let lib_dir = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        import(
+            &lib,
+            &[("wide.png", &png(3000, 1500)), ("clip.mov", b"video")],
+        );
+        let uuids: Vec<String> = lib
+            .db
+            .list_assets()
+            .unwrap()
+            .into_iter()
+            .map(|r| r.uuid)
+            .collect();
+        lib.add_tags(&uuids, &["Kyoto".to_string()]).unwrap();
+
+        let options = ExportOptions {
+            originals: false,
+            preset: Some(ExportPreset::WebJpeg),
+            metadata: ExportMetadata::Embedded,
+            ..Default::default()
+        };
+        let report = lib.export(&everything(), dest.path(), &options).unwrap();
+        assert_eq!(report.written, 1);
+        assert_eq!(report.skipped.len(), 1, "the video cannot be rendered");
+
+        let bytes = fs::read(dest.path().join("wide.jpg")).unwrap();
+        let image = JpegImage::decode_from_bytes(&bytes).unwrap();
+        assert_eq!(
+            (image.get_buffer().width, image.get_buffer().height),
+            (1920, 960)
+        );
+        let packet = String::from_utf8_lossy(&bytes);
+        assert!(packet.contains("Kyoto"));
+        assert!(!dest.path().join("wide.xmp").exists());
+
+        // With originals, rendered copies get a folder of their own.
+        let options = ExportOptions {
+            originals: true,
+            metadata: ExportMetadata::None,
+            ..options
+        };
+        let report = lib.export(&everything(), dest.path(), &options).unwrap();
+        assert_eq!(report.written, 3);
+        assert!(dest.path().join("wide.png").exists());
+        assert!(dest.path().join("web_jpeg/wide.jpg").exists());
+    }
+
+    #[test]
+    fn test_export_needs_something_to_write() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let options = ExportOptions {
+            originals: false,
+            ..Default::default()
+        };
+        assert!(matches!(
+            lib.export(&everything(), lib_dir.path(), &options),
+            Err(LibraryError::InvalidExport(_))
+        ));
+    }
+
+    #[test]
+    fn test_fit_within() {
+        let social = ImageResolution::Standard(StandardImageSize::SocialShare);
+        assert_eq!(fit_within(4000, 3000, social), (840, 630));
+        assert_eq!(fit_within(600, 400, social), (600, 400));
+        assert_eq!(
+            fit_within(1000, 4000, ImageResolution::MaxDimension(1920)),
+            (480, 1920)
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use std::path::{Path, PathBuf};
+
+use chrono::DateTime;
+use chrono::format::{Item, StrftimeItems};
+
+use crate::library::error::LibraryError;
+use crate::sidecar::AssetSidecar;
+
+/// The template [`ExportOptions`](crate::library::export::ExportOptions)
+/// uses unless told otherwise.
+pub const DEFAULT_EXPORT_TEMPLATE: &str = "{original_filename}";
+
+/// `{capture}` without a format.
+const DEFAULT_CAPTURE_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+/// Stands in for a field the asset has no value for.
+const MISSING: &str = "unknown";
+
+/// Characters replaced with `_` in field values. `/` in the template itself
+/// still separates folders.
+const UNSAFE: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// Names exported files from their asset, as in
+/// `{capture:%Y-%m-%d}_{camera_model}_{original_filename}`.
+///
+/// Fields:
+///
+/// - `{original_filename}`, `{stem}` and `{ext}`: the filename at import and
+///   its parts.
+/// - `{capture}` or `{capture:FORMAT}`: the local capture time, formatted
+///   with chrono's strftime syntax (default `%Y%m%d_%H%M%S`).
+/// - `{camera_make}`, `{camera_model}`, `{rating}` and `{uuid}`.
+///
+/// Missing values render as `unknown`. `{{` and `}}` are literal braces.
+/// A `/` in the template makes folders; values never do, since path
+/// separators and characters Windows rejects in names are replaced with `_`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilenameTemplate {
+    source: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Field(Field),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Field {
+    OriginalFilename,
+    Stem,
+    Ext,
+    Capture(String),
+    CameraMake,
+    CameraModel,
+    Rating,
+    Uuid,
+}
+
+impl FilenameTemplate {
+    /// Parses and checks a template, so that a mistake is reported before
+    /// anything is exported.
+    pub fn parse(template: &str) -> Result<Self, LibraryError> {
+        let invalid =
+            |reason: String| LibraryError::InvalidTemplate(format!("{template:?}: {reason}"));
+        if template.trim().is_empty() {
+            return Err(invalid("empty".to_string()));
+        }
+        if template.starts_with('/') || template.starts_with('\\') {
+            return Err(invalid("must be relative to the export folder".to_string()));
+        }
+
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => field.push(c),
+                            None => return Err(invalid("unclosed `{`".to_string())),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(parse_field(&field).map_err(invalid)?));
+                }
+                '}' => return Err(invalid("unmatched `}`".to_string())),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        let literal_components = parts.iter().filter_map(|p| match p {
+            Part::Literal(s) => Some(s),
+            Part::Field(_) => None,
+        });
+        for literal in literal_components {
+            if literal.split(['/', '\\']).any(|c| c == "..") {
+                return Err(invalid("`..` may not leave the export folder".to_string()));
+            }
+        }
+        Ok(Self {
+            source: template.to_string(),
+            parts,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// The path, relative to the export folder, the template gives the asset
+    /// described by `s`. Empty folder names are dropped, and a name made
+    /// only of dots becomes `_`.
+    pub fn render(&self, s: &AssetSidecar) -> PathBuf {
+        let mut rendered = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => rendered.push_str(text),
+                Part::Field(field) => rendered.push_str(&sanitize(&value(field, s))),
+            }
+        }
+        let path: PathBuf = rendered
+            .split(['/', '\\'])
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(|c| {
+                if c.chars().all(|ch| ch == '.') {
+                    "_"
+                } else {
+                    c
+                }
+            })
+            .collect();
+        if path.as_os_str().is_empty() {
+            PathBuf::from(&s.uuid)
+        } else {
+            path
+        }
+    }
+}
+
+impl Default for FilenameTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_EXPORT_TEMPLATE).expect("default template is valid")
+    }
+}
+
+fn parse_field(field: &str) -> Result<Field, String> {
+    let (name, format) = match field.split_once(':') {
+        Some((name, format)) => (name.trim(), Some(format)),
+        None => (field.trim(), None),
+    };
+    if format.is_some() && name != "capture" {
+        return Err(format!("`{{{name}}}` takes no format"));
+    }
+    Ok(match name {
+        "original_filename" => Field::OriginalFilename,
+        "stem" => Field::Stem,
+        "ext" => Field::Ext,
+        "capture" => {
+            let format = format.unwrap_or(DEFAULT_CAPTURE_FORMAT);
+            if format.is_empty() || StrftimeItems::new(format).any(|i| matches!(i, Item::Error)) {
+                return Err(format!("invalid capture time format {format:?}"));
+            }
+            Field::Capture(format.to_string())
+        }
+        "camera_make" => Field::CameraMake,
+        "camera_model" => Field::CameraModel,
+        "rating" => Field::Rating,
+        "uuid" => Field::Uuid,
+        other => return Err(format!("unknown field `{{{other}}}`")),
+    })
+}
+
+fn value(field: &Field, s: &AssetSidecar) -> String {
+    let original = Path::new(&s.original_filename);
+    let part = |p: Option<&std::ffi::OsStr>| {
+        p.map(|p| p.to_string_lossy().into_owned())
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| MISSING.to_string())
+    };
+    match field {
+        Field::OriginalFilename => part(Some(original.as_os_str())),
+        Field::Stem => part(original.file_stem()),
+        Field::Ext => part(original.extension()),
+        Field::Capture(format) => s
+            .capture_timestamp
+            .and_then(|t| DateTime::from_timestamp(t, 0))
+            .map(|t| t.naive_utc().format(format).to_string())
+            .unwrap_or_else(|| MISSING.to_string()),
+        Field::CameraMake => s.camera_make.clone().unwrap_or_else(|| MISSING.to_string()),
+        Field::CameraModel => s
+            .camera_model
+            .clone()
+            .unwrap_or_else(|| MISSING.to_string()),
+        Field::Rating => s.rating.to_string(),
+        Field::Uuid => s.uuid.clone(),
+    }
+}
+
+fn sanitize(value: &str) -> String {
+    value
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_control() || UNSAFE.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ImportMode;
+    use crate::metadata::AssetType;
+    use std::collections::BTreeMap;
+
+    fn sample_sidecar() -> AssetSidecar {
+        AssetSidecar {
+            version: 1,
+            uuid: "01956ef3-0000-7000-8000-000000000001".to_string(),
+            asset_type: AssetType::Photo,
+            original_filename: "IMG_1234.jpg".to_string(),
+            import_timestamp: 1720000000,
+            modified_timestamp: 1720000000,
+            hash_blake3: "a".repeat(64),
+            file_size: 1024,
+            is_deleted: false,
+            rating: 0,
+            tags: vec![],
+            import_mode: ImportMode::Copy,
+            importer_version: "0.1.0".to_string(),
+            rawshift_version: "0.1.0".to_string(),
+            capture_timestamp: None,
+            capture_utc: None,
+            capture_tz: None,
+            capture_tz_source: None,
+            tz_db_version: None,
+            original_capture_timestamp: None,
+            width: None,
+            height: None,
+            duration_ms: None,
+            stack_hint: None,
+            album_id: None,
+            deleted_at: None,
+            camera_make: None,
+            camera_model: None,
+            gps_lat: None,
+            gps_lon: None,
+            gps_source: None,
+            caption: None,
+            label: None,
+            pick: None,
+            reference_path: None,
+            unknown_fields: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_fields() {
+        let mut s = sample_sidecar();
+        s.original_filename = "IMG_0001.JPG".to_string();
+        s.camera_model = Some("X100V".to_string());
+        s.capture_timestamp = Some(1_711_963_800); // 2024-04-01 09:30 local
+
+        let t = FilenameTemplate::parse("{capture:%Y-%m-%d}_{camera_model}_{original_filename}")
+            .unwrap();
+        assert_eq!(t.render(&s), PathBuf::from("2024-04-01_X100V_IMG_0001.JPG"));
+
+        let t = FilenameTemplate::parse("{capture:%Y}/{capture}-{stem}.{ext}").unwrap();
+        assert_eq!(
+            t.render(&s),
+            PathBuf::from("2024/20240401_093000-IMG_0001.JPG")
+        );
+
+        s.capture_timestamp = None;
+        s.camera_make = None;
+        let t = FilenameTemplate::parse("{{{camera_make}}}/{capture:%Y}").unwrap();
+        assert_eq!(t.render(&s), PathBuf::from("{unknown}/unknown"));
+    }
+
+    #[test]
+    fn test_values_cannot_make_folders() {
+        let mut s = sample_sidecar();
+        s.camera_model = Some("../EOS R5: Mark/II".to_string());
+        let t = FilenameTemplate::parse("{camera_model}/{original_filename}").unwrap();
+        let path = t.render(&s);
+        assert_eq!(path.components().count(), 2);
+        assert!(path.starts_with(".._EOS R5_ Mark_II"));
+
+        s.camera_model = Some("..".to_string());
+        assert!(t.render(&s).starts_with("_"));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_templates() {
+        for bad in [
+            "",
+            "/abs/{original_filename}",
+            "../{original_filename}",
+            "{original_filename",
+            "name}",
+            "{lens}",
+            "{stem:%Y}",
+            "{capture:%Q}",
+        ] {
+            assert!(
+                matches!(
+                    FilenameTemplate::parse(bad),
+                    Err(LibraryError::InvalidTemplate(_))
+                ),
+                "{bad:?} should be rejected"
+            );
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::fs;
 use std::path::Path;
 
 use crate::db::DatabaseDriver;
+use crate::domain::ThumbnailFormat;
 use crate::library::error::LibraryError;
 use crate::library::library::Library;
 use crate::library::lock;
@@ -12,8 +13,9 @@ use crate::sidecar::{
 };
 
 /// Skeleton directory paths (relative to library root).
-const SKELETON_DIRS: &[&str] = &[
+pub(crate) const SKELETON_DIRS: &[&str] = &[
     "media",
+    "albums",
     "index",
     "index/thumbnails",
     "index/thumbnails/xs",
@@ -84,6 +86,8 @@ fn init_inner(root: &Path, name: &str) -> Result<Library, LibraryError> {
         library_name: name.to_string(),
         last_opened_at: now,
         last_scrubbed_at: None,
+        thumbnail_format: ThumbnailFormat::default(),
+        auto_xmp_export: None,
     };
     let config_path = root.join(".library/config.cbor");
     write_library_config(&config_path, &config).map_err(|e| LibraryError::Cbor(e.to_string()))?;
@@ -104,6 +108,7 @@ fn now_secs() -> i64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::schema::SCHEMA_VERSION;
     use crate::sidecar::io::read_library_version;
     use tempfile::TempDir;
 
@@ -123,7 +128,7 @@ mod tests {
         assert_eq!(ver.version, CURRENT_LIBRARY_VERSION);
 
         // SQLite has the correct schema version
-        assert_eq!(lib.db.schema_version().unwrap(), 1);
+        assert_eq!(lib.db.schema_version().unwrap(), SCHEMA_VERSION);
 
         // Config has the correct library name
         assert_eq!(lib.config().library_name, "My Library");
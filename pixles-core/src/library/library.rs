@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 
 use crate::db::DatabaseDriver;
+use crate::domain::XmpExportTarget;
 use crate::library::error::LibraryError;
 use crate::library::lock;
 use crate::sidecar::LibraryConfigCbor;
@@ -15,24 +16,59 @@ pub struct Library {
     pub root: PathBuf,
     pub db: DatabaseDriver,
     config: LibraryConfigCbor,
+    /// Opened with [`open_library_read_only`](crate::library::open_library_read_only);
+    /// closing it records nothing.
+    read_only: bool,
 }
 
 impl Library {
     pub(crate) fn new(root: PathBuf, db: DatabaseDriver, config: LibraryConfigCbor) -> Self {
-        Self { root, db, config }
+        Self {
+            root,
+            db,
+            config,
+            read_only: false,
+        }
+    }
+
+    pub(crate) fn new_read_only(
+        root: PathBuf,
+        db: DatabaseDriver,
+        config: LibraryConfigCbor,
+    ) -> Self {
+        Self {
+            root,
+            db,
+            config,
+            read_only: true,
+        }
     }
 
     pub fn config(&self) -> &LibraryConfigCbor {
         &self.config
     }
 
+    /// Turns automatic `.xmp` export on (`Some`) or off, saving the config
+    /// right away.
+    pub fn set_auto_xmp_export(
+        &mut self,
+        target: Option<XmpExportTarget>,
+    ) -> Result<(), LibraryError> {
+        self.config.auto_xmp_export = target;
+        let config_path = self.root.join(".library/config.cbor");
+        write_library_config(&config_path, &self.config)
+            .map_err(|e| LibraryError::Cbor(e.to_string()))
+    }
+
     /// Update `last_opened_at`, flush config, release lock, and consume `self`.
     /// After this returns `Ok`, the lock has been released and the Library is gone.
     pub fn close(mut self) -> Result<(), LibraryError> {
-        self.config.last_opened_at = now_secs();
-        let config_path = self.root.join(".library/config.cbor");
-        write_library_config(&config_path, &self.config)
-            .map_err(|e| LibraryError::Cbor(e.to_string()))?;
+        if !self.read_only {
+            self.config.last_opened_at = now_secs();
+            let config_path = self.root.join(".library/config.cbor");
+            write_library_config(&config_path, &self.config)
+                .map_err(|e| LibraryError::Cbor(e.to_string()))?;
+        }
         let root = self.root.clone();
         std::mem::forget(self); // Prevent Drop from double-releasing
         lock::release(&root)?;
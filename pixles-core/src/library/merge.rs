@@ -0,0 +1,760 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::db::rows::{AssetStackRow, StackMemberRow};
+use crate::domain::tag::dedup;
+use crate::domain::{DetectionMethod, MemberRole, MergePolicy, StackType};
+use crate::library::albums::album_row;
+use crate::library::error::LibraryError;
+use crate::library::library::Library;
+use crate::library::paths::{album_path, albums_dir, sidecar_path, tmp_path};
+use crate::library::rebuild::{
+    asset_row_from_sidecar, media_file_for, member_role_str, stack_type_str,
+};
+use crate::sidecar::AssetSidecar;
+use crate::sidecar::io::{read_album, read_sidecar, write_album, write_sidecar};
+use crate::sidecar::stack_hint::StackHint;
+use crate::utils::hash::copy_and_hash;
+
+/// Outcome of [`Library::merge_from`].
+#[derive(Debug, Default)]
+pub struct MergeReport {
+    /// Assets copied in under their own UUIDs.
+    pub added: usize,
+    /// Assets both libraries had whose curation changed here.
+    pub updated: usize,
+    /// Assets both libraries had with nothing to bring over.
+    pub unchanged: usize,
+    /// Albums created here or given new members.
+    pub albums: usize,
+    /// Assets and albums left out, with the reason.
+    pub skipped: Vec<(String, String)>,
+}
+
+/// A stack as its members' hints name it.
+type StackKey = (String, DetectionMethod);
+
+/// An asset of this library that stands for one of the other's.
+struct Counterpart {
+    uuid: String,
+    sidecar: PathBuf,
+    in_trash: bool,
+}
+
+impl Library {
+    /// Brings the assets, curation, stacks and albums of `other` into this
+    /// library. `other` is only read.
+    ///
+    /// An asset this library lacks is copied in with its sidecar as it is,
+    /// so it keeps its UUID, tags and stack, and merging the same library
+    /// again adds nothing. Each original is streamed to `{path}.tmp` and
+    /// renamed into place only if it matches its `hash_blake3`. An asset
+    /// found here under the same UUID or BLAKE3 hash is not copied; its
+    /// rating, tags, caption, label and pick are resolved with `policy`.
+    /// The other library's trash is left behind, and assets in this
+    /// library's trash stay there untouched.
+    ///
+    /// Only what the merge wrote is indexed: the new assets, the curation it
+    /// changed and the albums. A new asset joins the stack its counterparts
+    /// here are in, or forms one with them; stacks already here are kept.
+    /// Thumbnails are not copied.
+    pub fn merge_from(
+        &self,
+        other: &Library,
+        policy: MergePolicy,
+    ) -> Result<MergeReport, LibraryError> {
+        if other.root.canonicalize()? == self.root.canonicalize()? {
+            return Err(LibraryError::InvalidMerge(
+                "a library cannot be merged into itself".to_string(),
+            ));
+        }
+
+        let mut report = MergeReport::default();
+        // Their UUID → ours, for album members.
+        let mut mapped: HashMap<String, String> = HashMap::new();
+        // Assets added by this merge, by hash, as the index does not have
+        // them until the end.
+        let mut added: HashMap<String, (String, PathBuf)> = HashMap::new();
+        // Their stacks → the assets here standing for their members.
+        let mut counterparts: HashMap<StackKey, Vec<(String, MemberRole)>> = HashMap::new();
+        let mut changed = Vec::new();
+
+        for path in sidecar_files(&other.root) {
+            let theirs = match read_sidecar(&path) {
+                Ok(sidecar) => sidecar,
+                Err(e) => {
+                    report
+                        .skipped
+                        .push((path.display().to_string(), e.to_string()));
+                    continue;
+                }
+            };
+            if theirs.is_deleted {
+                continue;
+            }
+
+            let counterpart = match self.counterpart(&theirs, &added) {
+                Ok(counterpart) => counterpart,
+                Err(e) => {
+                    report.skipped.push((theirs.uuid.clone(), e.to_string()));
+                    continue;
+                }
+            };
+            if let (Some(ours), Some(hint)) = (&counterpart, &theirs.stack_hint) {
+                counterparts
+                    .entry(stack_key(hint))
+                    .or_default()
+                    .push((ours.uuid.clone(), hint.member_role));
+            }
+            match counterpart {
+                Some(ours) if ours.in_trash => {
+                    mapped.insert(theirs.uuid.clone(), ours.uuid);
+                    report.unchanged += 1;
+                }
+                Some(ours) => {
+                    mapped.insert(theirs.uuid.clone(), ours.uuid.clone());
+                    match merge_into(&ours.sidecar, &theirs, policy) {
+                        Ok(true) => {
+                            report.updated += 1;
+                            changed.push(ours.uuid);
+                        }
+                        Ok(false) => report.unchanged += 1,
+                        Err(e) => report.skipped.push((theirs.uuid.clone(), e.to_string())),
+                    }
+                }
+                None => match self.copy_asset(&path, &theirs) {
+                    Ok(sidecar) => {
+                        mapped.insert(theirs.uuid.clone(), theirs.uuid.clone());
+                        added.insert(theirs.hash_blake3.clone(), (theirs.uuid.clone(), sidecar));
+                        changed.push(theirs.uuid.clone());
+                        report.added += 1;
+                    }
+                    Err(reason) => report.skipped.push((theirs.uuid.clone(), reason)),
+                },
+            }
+        }
+
+        let mut copied: Vec<(String, PathBuf)> = added.into_values().collect();
+        copied.sort_by(|a, b| a.1.cmp(&b.1));
+        self.index_merged(&copied, &changed, &counterparts)?;
+        self.merge_albums(other, &mapped, &mut report);
+        self.refresh_auto_xmp(&changed);
+        Ok(report)
+    }
+
+    /// The asset here with the UUID of `theirs` or, failing that, its hash,
+    /// looked up in the index and then among the assets this merge `added`.
+    fn counterpart(
+        &self,
+        theirs: &AssetSidecar,
+        added: &HashMap<String, (String, PathBuf)>,
+    ) -> Result<Option<Counterpart>, LibraryError> {
+        let row = match self.db.find_by_uuid(&theirs.uuid)? {
+            Some(row) => Some(row),
+            None => self.db.find_by_hash(&theirs.hash_blake3)?,
+        };
+        if let Some(row) = row {
+            let (uuid, sidecar) = self.locate_sidecar(&row.uuid)?;
+            return Ok(Some(Counterpart {
+                uuid,
+                sidecar,
+                in_trash: row.is_deleted,
+            }));
+        }
+        Ok(added
+            .get(&theirs.hash_blake3)
+            .map(|(uuid, sidecar)| Counterpart {
+                uuid: uuid.clone(),
+                sidecar: sidecar.clone(),
+                in_trash: false,
+            }))
+    }
+
+    /// Copies the original described by the sidecar at `path` of another
+    /// library, then the sidecar itself, to the same place here. Returns
+    /// the new sidecar's path.
+    fn copy_asset(&self, path: &Path, theirs: &AssetSidecar) -> Result<PathBuf, String> {
+        let uuid = Uuid::parse_str(&theirs.uuid).map_err(|e| format!("invalid UUID: {e}"))?;
+        let dest = sidecar_path(&self.root, &uuid, "", theirs.capture_utc);
+        let source = media_file_for(path, theirs).ok_or("the original has no extension")?;
+
+        if theirs.reference_path.is_some() {
+            // The original stays where it was imported from.
+            if !source.exists() {
+                return Err(format!(
+                    "referenced original {} is missing",
+                    source.display()
+                ));
+            }
+        } else {
+            let media = media_file_for(&dest, theirs).ok_or("the original has no extension")?;
+            let tmp = tmp_path(&media);
+            if let Some(parent) = media.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            let (hash, _) =
+                copy_and_hash(&source, &tmp).map_err(|e| format!("{}: {e}", source.display()))?;
+            if hash != theirs.hash_blake3 {
+                let _ = fs::remove_file(&tmp);
+                return Err("the original does not match its recorded hash".to_string());
+            }
+            fs::rename(&tmp, &media).map_err(|e| e.to_string())?;
+        }
+
+        write_sidecar(&dest, theirs).map_err(|e| e.to_string())?;
+        Ok(dest)
+    }
+
+    /// Indexes the assets `copied` in and the curation of the `changed`
+    /// ones that were already here. Each copied asset with a stack hint
+    /// joins the stack its `counterparts` are in; failing one, they form a
+    /// new stack together.
+    fn index_merged(
+        &self,
+        copied: &[(String, PathBuf)],
+        changed: &[String],
+        counterparts: &HashMap<StackKey, Vec<(String, MemberRole)>>,
+    ) -> Result<(), LibraryError> {
+        let is_new = |uuid: &str| copied.iter().any(|(new, _)| new == uuid);
+        for uuid in changed.iter().filter(|uuid| !is_new(uuid.as_str())) {
+            let (_, path) = self.locate_sidecar(uuid)?;
+            let sidecar = read_sidecar(&path).map_err(|e| LibraryError::Cbor(e.to_string()))?;
+            let Some(mut row) = self.db.find_by_uuid(uuid)? else {
+                continue;
+            };
+            row.rating = sidecar.rating as i64;
+            row.caption = sidecar.caption;
+            self.db.upsert_asset(&row)?;
+            self.db.set_asset_tags(uuid, &sidecar.tags)?;
+        }
+
+        let sidecars = copied
+            .iter()
+            .map(|(_, path)| read_sidecar(path).map_err(|e| LibraryError::Cbor(e.to_string())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut groups: HashMap<StackKey, Vec<&AssetSidecar>> = HashMap::new();
+        for sidecar in &sidecars {
+            if let Some(hint) = &sidecar.stack_hint {
+                groups.entry(stack_key(hint)).or_default().push(sidecar);
+            }
+        }
+
+        // Copied asset → its stack and whether it is hidden behind another.
+        let mut placed: HashMap<&str, (String, bool)> = HashMap::new();
+        let now = now_secs();
+        for (key, members) in &groups {
+            let here = counterparts.get(key).map(Vec::as_slice).unwrap_or_default();
+            let mut existing = None;
+            for (uuid, _) in here {
+                existing = self.db.stack_of(uuid)?;
+                if existing.is_some() {
+                    break;
+                }
+            }
+            if let Some(stack_id) = existing {
+                // The stack keeps its primary; the new members go behind it.
+                let first = self.db.list_stack_members(&stack_id)?.len();
+                for (seq, sidecar) in members.iter().enumerate() {
+                    let seq = first + seq;
+                    self.add_stack_member(&stack_id, &sidecar.uuid, seq, hint_role(sidecar), now);
+                    placed.insert(&sidecar.uuid, (stack_id.clone(), true));
+                }
+                continue;
+            }
+
+            let mut entries: Vec<(&str, MemberRole)> = Vec::new();
+            let all = here
+                .iter()
+                .map(|(uuid, role)| (uuid.as_str(), *role))
+                .chain(members.iter().map(|s| (s.uuid.as_str(), hint_role(s))));
+            for (uuid, role) in all {
+                if !entries.iter().any(|(seen, _)| *seen == uuid) {
+                    entries.push((uuid, role));
+                }
+            }
+            let primary = entries
+                .iter()
+                .find(|(_, role)| *role == MemberRole::Primary)
+                .unwrap_or(&entries[0])
+                .0;
+            let stack_type = members[0]
+                .stack_hint
+                .as_ref()
+                .map_or(StackType::Custom, |h| h.stack_type);
+            let stack_id = format!("stack-{}", Uuid::now_v7().simple());
+            let _ = self.db.insert_stack(&AssetStackRow {
+                id: stack_id.clone(),
+                stack_type: stack_type_str(stack_type).to_string(),
+                primary_asset_id: primary.to_string(),
+                cover_asset_id: Some(primary.to_string()),
+                is_collapsed: true,
+                is_auto_generated: true,
+                created_at: now,
+                modified_at: now,
+            });
+            for (seq, (uuid, role)) in entries.into_iter().enumerate() {
+                self.add_stack_member(&stack_id, uuid, seq, role, now);
+                let hidden = uuid != primary;
+                if is_new(uuid) {
+                    placed.insert(uuid, (stack_id.clone(), hidden));
+                } else if let Some(mut row) = self.db.find_by_uuid(uuid)? {
+                    row.stack_id = Some(stack_id.clone());
+                    row.is_stack_hidden = hidden;
+                    self.db.upsert_asset(&row)?;
+                }
+            }
+        }
+
+        for sidecar in &sidecars {
+            let mut row = asset_row_from_sidecar(sidecar);
+            if let Some((stack_id, hidden)) = placed.get(sidecar.uuid.as_str()) {
+                row.stack_id = Some(stack_id.clone());
+                row.is_stack_hidden = *hidden;
+            }
+            self.db.insert_asset(&row)?;
+            self.db.set_asset_tags(&row.uuid, &sidecar.tags)?;
+        }
+        Ok(())
+    }
+
+    fn add_stack_member(&self, stack_id: &str, uuid: &str, seq: usize, role: MemberRole, now: i64) {
+        let _ = self.db.insert_stack_member(&StackMemberRow {
+            id: format!("{stack_id}#{seq}"),
+            stack_id: stack_id.to_string(),
+            asset_id: uuid.to_string(),
+            sequence_order: seq as i64,
+            member_role: member_role_str(role).to_string(),
+            created_at: now,
+        });
+    }
+
+    /// Creates the other library's albums here, or adds their members to
+    /// albums both have, with members mapped to the assets here.
+    fn merge_albums(
+        &self,
+        other: &Library,
+        mapped: &HashMap<String, String>,
+        report: &mut MergeReport,
+    ) {
+        let Ok(entries) = fs::read_dir(albums_dir(&other.root)) else {
+            return;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "cbor"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let theirs = match read_album(&path) {
+                Ok(album) => album,
+                Err(e) => {
+                    report
+                        .skipped
+                        .push((path.display().to_string(), e.to_string()));
+                    continue;
+                }
+            };
+            let id = match Uuid::parse_str(&theirs.id) {
+                Ok(id) => id,
+                Err(e) => {
+                    report
+                        .skipped
+                        .push((path.display().to_string(), format!("invalid album ID: {e}")));
+                    continue;
+                }
+            };
+
+            let dest = album_path(&self.root, &id);
+            let mut album = if dest.exists() {
+                match read_album(&dest) {
+                    Ok(album) => album,
+                    Err(e) => {
+                        report.skipped.push((theirs.id.clone(), e.to_string()));
+                        continue;
+                    }
+                }
+            } else {
+                let mut album = theirs.clone();
+                album.assets.clear();
+                album.cover_asset_id = theirs
+                    .cover_asset_id
+                    .as_ref()
+                    .and_then(|uuid| mapped.get(uuid).cloned());
+                album
+            };
+
+            let before = album.assets.len();
+            for member in &theirs.assets {
+                let Some(uuid) = mapped.get(&member.uuid) else {
+                    continue;
+                };
+                if !album.assets.iter().any(|m| &m.uuid == uuid) {
+                    let mut member = member.clone();
+                    member.uuid = uuid.clone();
+                    album.assets.push(member);
+                }
+            }
+            if dest.exists() && album.assets.len() == before {
+                continue;
+            }
+            album.modified_at = album.modified_at.max(theirs.modified_at);
+            if let Err(e) = write_album(&dest, &album) {
+                report.skipped.push((theirs.id.clone(), e.to_string()));
+                continue;
+            }
+            let members: Vec<(String, i64)> = album
+                .assets
+                .iter()
+                .map(|m| (m.uuid.clone(), m.added_at))
+                .collect();
+            if let Err(e) = self.db.upsert_album(&album_row(&album), &members) {
+                report.skipped.push((theirs.id.clone(), e.to_string()));
+                continue;
+            }
+            report.albums += 1;
+        }
+    }
+}
+
+fn stack_key(hint: &StackHint) -> StackKey {
+    (hint.detection_key.clone(), hint.detection_method)
+}
+
+fn hint_role(s: &AssetSidecar) -> MemberRole {
+    s.stack_hint
+        .as_ref()
+        .map_or(MemberRole::Primary, |h| h.member_role)
+}
+
+/// Sidecars under `root/media`, in path order.
+fn sidecar_files(root: &Path) -> Vec<PathBuf> {
+    WalkDir::new(root.join("media"))
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .map(|e| e.into_path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "cbor"))
+        .collect()
+}
+
+/// Resolves the curation of `theirs` into the sidecar at `path`. Returns
+/// whether the sidecar changed.
+fn merge_into(
+    path: &Path,
+    theirs: &AssetSidecar,
+    policy: MergePolicy,
+) -> Result<bool, LibraryError> {
+    let original = read_sidecar(path).map_err(|e| LibraryError::Cbor(e.to_string()))?;
+    let mut sidecar = original.clone();
+    merge_curation(&mut sidecar, theirs, policy);
+    if sidecar == original {
+        return Ok(false);
+    }
+    sidecar.modified_timestamp = now_secs();
+    write_sidecar(path, &sidecar).map_err(|e| LibraryError::Cbor(e.to_string()))?;
+    Ok(true)
+}
+
+/// Resolves rating, tags, caption, label and pick per `policy`. A stack
+/// hint is taken from `theirs` when `ours` has none, so that the other
+/// members of its stack find it.
+fn merge_curation(ours: &mut AssetSidecar, theirs: &AssetSidecar, policy: MergePolicy) {
+    match policy {
+        MergePolicy::Ours => {
+            if ours.rating == 0 {
+                ours.rating = theirs.rating;
+            }
+            if ours.tags.is_empty() {
+                ours.tags = theirs.tags.clone();
+            }
+            fill(&mut ours.caption, &theirs.caption);
+            fill(&mut ours.label, &theirs.label);
+            fill(&mut ours.pick, &theirs.pick);
+        }
+        MergePolicy::Theirs => {
+            if theirs.rating != 0 {
+                ours.rating = theirs.rating;
+            }
+            if !theirs.tags.is_empty() {
+                ours.tags = theirs.tags.clone();
+            }
+            prefer(&mut ours.caption, &theirs.caption);
+            prefer(&mut ours.label, &theirs.label);
+            prefer(&mut ours.pick, &theirs.pick);
+        }
+        MergePolicy::Combine => {
+            ours.rating = ours.rating.max(theirs.rating);
+            ours.tags = dedup(ours.tags.iter().chain(&theirs.tags).cloned());
+            ours.caption = match (ours.caption.take(), &theirs.caption) {
+                // Checked by containment so that merging again adds nothing.
+                (Some(mine), Some(their)) if !mine.contains(their.as_str()) => {
+                    Some(format!("{mine}\n\n{their}"))
+                }
+                (mine, their) => mine.or_else(|| their.clone()),
+            };
+            fill(&mut ours.label, &theirs.label);
+            fill(&mut ours.pick, &theirs.pick);
+        }
+    }
+    fill(&mut ours.stack_hint, &theirs.stack_hint);
+}
+
+/// Takes `theirs` if `ours` is unset.
+fn fill<T: Clone>(ours: &mut Option<T>, theirs: &Option<T>) {
+    if ours.is_none() {
+        ours.clone_from(theirs);
+    }
+}
+
+/// Takes `theirs` unless it is unset.
+fn prefer<T: Clone>(ours: &mut Option<T>, theirs: &Option<T>) {
+    if theirs.is_some() {
+        ours.clone_from(theirs);
+    }
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::rows::AssetRow;
+    use crate::import::executor::execute;
+    use crate::import::executor_cancellation::CancellationToken;
+    use crate::import::planner::{ImportConfig, plan};
+    use crate::import::scanner::scan;
+    use crate::library::init::init_library;
+    use tempfile::TempDir;
+
+    fn import(lib: &Library, files: &[(&str, &[u8])]) {
+        let src = TempDir::new().unwrap();
+        for (name, bytes) in files {
+            fs::write(src.path().join(name), bytes).unwrap();
+        }
+        let config = ImportConfig {
+            thumbnail_format: None,
+            ..Default::default()
+        };
+        let plan_result = plan(
+            &scan(&[src.path().to_path_buf()]).unwrap(),
+            &lib.db,
+            &config,
+        )
+        .unwrap();
+        execute(
+            &plan_result,
+            lib,
+            &config,
+            |_| {},
+            &CancellationToken::new(),
+        )
+        .unwrap();
+    }
+
+    /// Applies `edit` to the sidecar of the asset imported as `name`.
+    fn edit_sidecar(lib: &Library, name: &str, edit: impl FnOnce(&mut AssetSidecar)) -> String {
+        let row = lib
+            .db
+            .list_assets()
+            .unwrap()
+            .into_iter()
+            .find(|row| row.original_filename.as_deref() == Some(name))
+            .unwrap();
+        let (uuid, path) = lib.locate_sidecar(&row.uuid).unwrap();
+        let mut sidecar = read_sidecar(&path).unwrap();
+        edit(&mut sidecar);
+        write_sidecar(&path, &sidecar).unwrap();
+        uuid
+    }
+
+    #[test]
+    fn test_merge_adds_assets_with_their_uuids() {
+        let ours_dir = TempDir::new().unwrap();
+        let theirs_dir = TempDir::new().unwrap();
+        let ours = init_library(ours_dir.path(), "Ours").unwrap();
+        let theirs = init_library(theirs_dir.path(), "Theirs").unwrap();
+        import(&ours, &[("shared.jpg", b"shared")]);
+        import(
+            &theirs,
+            &[
+                ("img_0001.jpg", b"jpeg content"),
+                ("img_0001.ARW", b"raw content"),
+                ("copy.jpg", b"shared"),
+            ],
+        );
+        let album = theirs.create_album("Trip", None).unwrap();
+        let their_uuids: Vec<String> = theirs
+            .db
+            .list_assets()
+            .unwrap()
+            .into_iter()
+            .map(|row| row.uuid)
+            .collect();
+        theirs.add_to_album(&album.id, &their_uuids).unwrap();
+
+        let report = ours.merge_from(&theirs, MergePolicy::Ours).unwrap();
+        assert!(report.skipped.is_empty(), "{:?}", report.skipped);
+        assert_eq!((report.added, report.unchanged, report.albums), (2, 1, 1));
+
+        // The RAW and JPEG keep their UUIDs and form a stack again; the
+        // copy of shared.jpg is not duplicated.
+        let rows = ours.db.list_assets().unwrap();
+        assert_eq!(rows.len(), 3);
+        for row in theirs.db.list_assets().unwrap() {
+            if row.original_filename.as_deref() != Some("copy.jpg") {
+                assert!(ours.db.find_by_uuid(&row.uuid).unwrap().is_some());
+            }
+        }
+        assert_eq!(ours.db.query_timeline(0, 100).unwrap().len(), 2);
+        assert_eq!(ours.album_assets(&album.id).unwrap().len(), 3);
+
+        let again = ours.merge_from(&theirs, MergePolicy::Combine).unwrap();
+        assert_eq!((again.added, again.updated, again.albums), (0, 0, 0));
+        assert_eq!(ours.db.list_assets().unwrap().len(), 3);
+
+        assert!(matches!(
+            ours.merge_from(&ours, MergePolicy::Ours),
+            Err(LibraryError::InvalidMerge(_))
+        ));
+    }
+
+    #[test]
+    fn test_merge_keeps_stacks_already_here() {
+        let ours_dir = TempDir::new().unwrap();
+        let theirs_dir = TempDir::new().unwrap();
+        let ours = init_library(ours_dir.path(), "Ours").unwrap();
+        let theirs = init_library(theirs_dir.path(), "Theirs").unwrap();
+        import(&ours, &[("IMG_1.jpg", b"jpeg"), ("IMG_1.ARW", b"raw")]);
+        import(
+            &theirs,
+            &[
+                ("IMG_1.jpg", b"jpeg"),
+                ("IMG_1.ARW", b"raw"),
+                ("IMG_1.DNG", b"dng"),
+            ],
+        );
+        let stack_id = ours.db.list_assets().unwrap()[0].stack_id.clone().unwrap();
+
+        let report = ours.merge_from(&theirs, MergePolicy::Ours).unwrap();
+        assert!(report.skipped.is_empty(), "{:?}", report.skipped);
+        assert_eq!((report.added, report.unchanged), (1, 2));
+
+        // The DNG joins the stack here behind its primary; no second stack
+        // is made for the pair.
+        for row in ours.db.list_assets().unwrap() {
+            assert_eq!(row.stack_id.as_deref(), Some(stack_id.as_str()));
+            assert_eq!(ours.db.stack_of(&row.uuid).unwrap(), Some(stack_id.clone()));
+            let is_jpeg = row.original_filename.as_deref() == Some("IMG_1.jpg");
+            assert_eq!(row.is_stack_hidden, !is_jpeg);
+        }
+        assert_eq!(ours.db.list_stack_members(&stack_id).unwrap().len(), 3);
+        assert_eq!(ours.db.query_timeline(0, 100).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_merge_skips_an_asset_it_cannot_look_up() {
+        let ours_dir = TempDir::new().unwrap();
+        let theirs_dir = TempDir::new().unwrap();
+        let ours = init_library(ours_dir.path(), "Ours").unwrap();
+        let theirs = init_library(theirs_dir.path(), "Theirs").unwrap();
+        import(&theirs, &[("a.jpg", b"a"), ("b.jpg", b"b")]);
+
+        // An index row for a.jpg whose sidecar cannot be located.
+        let a = theirs
+            .db
+            .list_assets()
+            .unwrap()
+            .into_iter()
+            .find(|row| row.original_filename.as_deref() == Some("a.jpg"))
+            .unwrap();
+        ours.db
+            .insert_asset(&AssetRow {
+                uuid: "not-a-uuid".to_string(),
+                ..a.clone()
+            })
+            .unwrap();
+
+        let report = ours.merge_from(&theirs, MergePolicy::Ours).unwrap();
+        assert_eq!(report.added, 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].0, a.uuid);
+    }
+
+    #[test]
+    fn test_merge_resolves_curation_by_policy() {
+        let ours_dir = TempDir::new().unwrap();
+        let theirs_dir = TempDir::new().unwrap();
+        let ours = init_library(ours_dir.path(), "Ours").unwrap();
+        let theirs = init_library(theirs_dir.path(), "Theirs").unwrap();
+        import(&ours, &[("a.jpg", b"a")]);
+        import(&theirs, &[("a.jpg", b"a")]);
+        let uuid = edit_sidecar(&ours, "a.jpg", |s| {
+            s.rating = 2;
+            s.tags = vec!["Kyoto".to_string()];
+            s.caption = Some("Temple".to_string());
+        });
+        edit_sidecar(&theirs, "a.jpg", |s| {
+            s.rating = 4;
+            s.tags = vec!["Japan".to_string(), "Kyoto".to_string()];
+            s.caption = Some("At dusk".to_string());
+            s.label = Some("Red".to_string());
+        });
+
+        let report = ours.merge_from(&theirs, MergePolicy::Combine).unwrap();
+        assert_eq!(report.updated, 1);
+        let row = ours.db.find_by_uuid(&uuid).unwrap().unwrap();
+        assert_eq!(row.rating, 4);
+        assert_eq!(row.caption.as_deref(), Some("Temple\n\nAt dusk"));
+        let (_, path) = ours.locate_sidecar(&uuid).unwrap();
+        assert_eq!(read_sidecar(&path).unwrap().label.as_deref(), Some("Red"));
+        assert_eq!(ours.tags(&uuid).unwrap(), vec!["Japan", "Kyoto"]);
+        assert_eq!(
+            ours.merge_from(&theirs, MergePolicy::Combine)
+                .unwrap()
+                .updated,
+            0
+        );
+    }
+
+    #[test]
+    fn test_merge_curation_policies() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        import(&lib, &[("a.jpg", b"a")]);
+        let (_, path) = lib
+            .locate_sidecar(&lib.db.list_assets().unwrap()[0].uuid)
+            .unwrap();
+        let mut mine = read_sidecar(&path).unwrap();
+        mine.rating = 3;
+        mine.caption = Some("Mine".to_string());
+        let mut theirs = mine.clone();
+        theirs.rating = 1;
+        theirs.tags = vec!["Kyoto".to_string()];
+        theirs.caption = None;
+        theirs.label = Some("Blue".to_string());
+
+        let mut ours = mine.clone();
+        merge_curation(&mut ours, &theirs, MergePolicy::Ours);
+        assert_eq!(ours.rating, 3);
+        assert_eq!(ours.tags, vec!["Kyoto"]);
+        assert_eq!(ours.caption.as_deref(), Some("Mine"));
+        assert_eq!(ours.label.as_deref(), Some("Blue"));
+
+        let mut ours = mine.clone();
+        merge_curation(&mut ours, &theirs, MergePolicy::Theirs);
+        assert_eq!(ours.rating, 1);
+        assert_eq!(ours.caption.as_deref(), Some("Mine"));
+        assert_eq!(ours.label.as_deref(), Some("Blue"));
+    }
+}
@@ -0,0 +1,285 @@
+use std::fs::{self, OpenOptions};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use ciborium::value::Value;
+use walkdir::WalkDir;
+
+use crate::db::{DatabaseDriver, schema};
+use crate::library::error::LibraryError;
+use crate::library::paths::{backup_dir, tmp_path};
+use crate::sidecar::io::write_library_version;
+use crate::sidecar::library_version::{CURRENT_LIBRARY_VERSION, LibraryVersionCbor};
+
+/// One step of the on-disk format. `sidecar` rewrites the CBOR map of a
+/// sidecar at `version - 1` into `version`; the `version` key itself is
+/// updated afterwards.
+pub struct FormatMigration {
+    pub version: u8,
+    pub description: &'static str,
+    pub sidecar: fn(&mut Vec<(Value, Value)>),
+}
+
+/// Every format step after the baseline (1), oldest first. Append new steps
+/// here and bump [`CURRENT_LIBRARY_VERSION`] to match.
+pub const FORMAT_MIGRATIONS: &[FormatMigration] = &[];
+
+/// Upgrades the sidecars and `version.cbor` of a library at format `from`
+/// to [`CURRENT_LIBRARY_VERSION`].
+///
+/// Every sidecar, `config.cbor` and `version.cbor` is first copied to
+/// `.library/backups/{now}-library-v{from}/`.
+pub(crate) fn upgrade_format(root: &Path, from: u8) -> Result<(), LibraryError> {
+    upgrade_format_with(root, from, CURRENT_LIBRARY_VERSION, FORMAT_MIGRATIONS)
+}
+
+/// Applies pending index migrations, copying the database to
+/// `.library/backups/{now}-index-v{n}/` first. A database newer than this
+/// build is refused.
+///
+/// Returns `true` if the upgraded index must be repopulated from the
+/// sidecars (see [`schema::REINDEX_BELOW`]).
+pub(crate) fn upgrade_index(root: &Path, db: &DatabaseDriver) -> Result<bool, LibraryError> {
+    let found = db.schema_version()?;
+    if found > schema::SCHEMA_VERSION {
+        return Err(LibraryError::IndexTooNew {
+            found,
+            supported: schema::SCHEMA_VERSION,
+        });
+    }
+    // Version 0 is an empty database with nothing worth keeping.
+    if found > 0 && found < schema::SCHEMA_VERSION {
+        let backup = backup_dir(root, &format!("{}-index-v{found}", now_secs()));
+        fs::create_dir_all(&backup)?;
+        db.backup_to(&backup.join("library.sqlite"))?;
+    }
+    db.migrate()?;
+    Ok(found > 0 && found < schema::REINDEX_BELOW)
+}
+
+fn upgrade_format_with(
+    root: &Path,
+    from: u8,
+    to: u8,
+    migrations: &[FormatMigration],
+) -> Result<(), LibraryError> {
+    let backup = backup_dir(root, &format!("{}-library-v{from}", now_secs()));
+    for name in ["version.cbor", "config.cbor"] {
+        let src = root.join(".library").join(name);
+        if src.exists() {
+            copy_into(&src, &backup.join(".library").join(name))?;
+        }
+    }
+
+    let media_dir = root.join("media");
+    let sidecars = WalkDir::new(&media_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .map(|e| e.into_path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "cbor"));
+    for path in sidecars {
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        copy_into(&path, &backup.join(relative))?;
+        // An unreadable sidecar is left as-is for `library verify` to report.
+        if let Err(e) = upgrade_sidecar(&path, migrations) {
+            log::warn!("upgrade_format: skipping {}: {e}", path.display());
+        }
+    }
+
+    write_library_version(
+        &root.join(".library/version.cbor"),
+        &LibraryVersionCbor { version: to },
+    )
+    .map_err(|e| LibraryError::Cbor(e.to_string()))?;
+    log::info!("library format migrated from v{from} to v{to}");
+    Ok(())
+}
+
+/// Runs every step newer than the sidecar's own `version`, rewriting the
+/// file only if something changed.
+fn upgrade_sidecar(path: &Path, migrations: &[FormatMigration]) -> Result<(), LibraryError> {
+    let value: Value = ciborium::de::from_reader(BufReader::new(fs::File::open(path)?))
+        .map_err(|e| LibraryError::Migration(format!("{}: {e}", path.display())))?;
+    let Value::Map(mut map) = value else {
+        return Err(LibraryError::Migration(format!(
+            "{}: sidecar is not a map",
+            path.display()
+        )));
+    };
+
+    let version = sidecar_version(&map);
+    let pending: Vec<_> = migrations.iter().filter(|m| m.version > version).collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+    for migration in pending {
+        (migration.sidecar)(&mut map);
+        set_version(&mut map, migration.version);
+    }
+
+    let tmp = tmp_path(path);
+    {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&tmp)?;
+        ciborium::ser::into_writer(&Value::Map(map), BufWriter::new(file))
+            .map_err(|e| LibraryError::Cbor(e.to_string()))?;
+    }
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn sidecar_version(map: &[(Value, Value)]) -> u8 {
+    map.iter()
+        .find(|(k, _)| k.as_text() == Some("version"))
+        .and_then(|(_, v)| v.as_integer())
+        .and_then(|v| u8::try_from(v).ok())
+        .unwrap_or(1)
+}
+
+fn set_version(map: &mut Vec<(Value, Value)>, version: u8) {
+    let value = Value::Integer(version.into());
+    match map.iter_mut().find(|(k, _)| k.as_text() == Some("version")) {
+        Some((_, v)) => *v = value,
+        None => map.push((Value::Text("version".to_string()), value)),
+    }
+}
+
+fn copy_into(src: &Path, dest: &Path) -> Result<(), LibraryError> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(src, dest)?;
+    Ok(())
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sidecar::io::{read_library_version, read_sidecar};
+    use tempfile::TempDir;
+
+    // Signature fixed by `Migration::sidecar`.
+    #[allow(clippy::ptr_arg)]
+    fn rename_rating(map: &mut Vec<(Value, Value)>) {
+        for (k, _) in map.iter_mut() {
+            if k.as_text() == Some("stars") {
+                *k = Value::Text("rating".to_string());
+            }
+        }
+    }
+
+    fn write_value(path: &Path, value: &Value) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut buf = vec![];
+        ciborium::ser::into_writer(value, &mut buf).unwrap();
+        fs::write(path, buf).unwrap();
+    }
+
+    #[test]
+    fn test_format_migrations_match_current_version() {
+        let last = FORMAT_MIGRATIONS.last().map_or(1, |m| m.version);
+        assert_eq!(last, CURRENT_LIBRARY_VERSION);
+    }
+
+    #[test]
+    fn test_upgrade_format_rewrites_sidecars_and_backs_up() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join(".library")).unwrap();
+        write_library_version(
+            &root.join(".library/version.cbor"),
+            &LibraryVersionCbor { version: 1 },
+        )
+        .unwrap();
+
+        // A v1 sidecar that stored the rating under an older key.
+        let sidecar = root.join("media/2024/2024-07/abc.cbor");
+        let text = |s: &str| Value::Text(s.to_string());
+        let v1 = Value::Map(vec![
+            (text("version"), Value::Integer(1.into())),
+            (text("uuid"), text("01956ef3-1234-7abc-9def-123456789abc")),
+            (text("asset_type"), text("photo")),
+            (text("original_filename"), text("IMG_1.jpg")),
+            (text("import_timestamp"), Value::Integer(1720000000.into())),
+            (
+                text("modified_timestamp"),
+                Value::Integer(1720000000.into()),
+            ),
+            (text("hash_blake3"), text(&"a".repeat(64))),
+            (text("file_size"), Value::Integer(10.into())),
+            (text("is_deleted"), Value::Bool(false)),
+            (text("stars"), Value::Integer(3.into())),
+            (text("tags"), Value::Array(vec![])),
+            (text("import_mode"), text("copy")),
+            (text("importer_version"), text("0.1.0")),
+            (text("rawshift_version"), text("0.0.0")),
+        ]);
+        write_value(&sidecar, &v1);
+
+        let steps = [FormatMigration {
+            version: 2,
+            description: "rename stars to rating",
+            sidecar: rename_rating,
+        }];
+        upgrade_format_with(root, 1, 2, &steps).unwrap();
+
+        let upgraded = read_sidecar(&sidecar).unwrap();
+        assert_eq!(upgraded.version, 2);
+        assert_eq!(upgraded.rating, 3);
+        let version = read_library_version(&root.join(".library/version.cbor")).unwrap();
+        assert_eq!(version.version, 2);
+
+        let backups: Vec<_> = fs::read_dir(root.join(".library/backups"))
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        assert_eq!(backups.len(), 1);
+        let backed_up = backups[0].join("media/2024/2024-07/abc.cbor");
+        let original: Value =
+            ciborium::de::from_reader(fs::read(backed_up).unwrap().as_slice()).unwrap();
+        assert_eq!(original, v1);
+        assert!(backups[0].join(".library/version.cbor").exists());
+    }
+
+    #[test]
+    fn test_upgrade_index_refuses_newer_schema() {
+        let tmp = TempDir::new().unwrap();
+        let db = DatabaseDriver::open(&tmp.path().join("library.sqlite")).unwrap();
+        let newer = schema::SCHEMA_VERSION + 1;
+        let conn = rusqlite::Connection::open(tmp.path().join("library.sqlite")).unwrap();
+        conn.pragma_update(None, "user_version", newer).unwrap();
+
+        assert!(matches!(
+            upgrade_index(tmp.path(), &db),
+            Err(LibraryError::IndexTooNew { found, .. }) if found == newer
+        ));
+    }
+
+    #[test]
+    fn test_upgrade_index_from_v1_backs_up_and_requests_reindex() {
+        let tmp = TempDir::new().unwrap();
+        let db_path = tmp.path().join("library.sqlite");
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute_batch(schema::DDL).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        drop(conn);
+
+        let db = DatabaseDriver::open_unmigrated(&db_path).unwrap();
+        assert!(upgrade_index(tmp.path(), &db).unwrap());
+        assert_eq!(db.schema_version().unwrap(), schema::SCHEMA_VERSION);
+        assert!(fs::read_dir(tmp.path().join(".library/backups")).is_ok());
+
+        // Already current: nothing to back up or repopulate.
+        assert!(!upgrade_index(tmp.path(), &db).unwrap());
+    }
+}
@@ -1,20 +1,47 @@
+pub mod albums;
+pub mod backup;
+pub mod capture_time;
+pub mod duplicates;
 pub mod error;
+pub mod export;
+pub mod filename_template;
 pub mod init;
 #[allow(clippy::module_inception)]
 pub mod library;
 pub mod lock;
+pub mod merge;
+pub mod migrate;
 pub mod open;
 pub mod paths;
 pub mod rebuild;
 pub mod scrub;
+pub mod tags;
+pub mod thumbnails;
+pub mod timezones;
 pub mod trash;
+pub mod verify;
+pub mod xmp_export;
 
+pub use backup::{
+    BackupOptions, BackupReport, DEFAULT_BACKUP_RETENTION_SECS, RestoreReport, restore_library,
+};
+pub use capture_time::TimeShift;
 pub use error::LibraryError;
+pub use export::{ExportOptions, ExportReport, ExportSelection};
+pub use filename_template::{DEFAULT_EXPORT_TEMPLATE, FilenameTemplate};
 pub use init::init_library;
 pub use library::Library;
-pub use open::open_library;
+pub use merge::MergeReport;
+pub use open::{open_library, open_library_read_only};
 pub use paths::{
-    ThumbnailSize, media_dir, media_path, meta_cache_path, sidecar_path, tmp_path,
+    ThumbnailSize, album_path, albums_dir, backup_dir, backup_manifest_path, import_journal_dir,
+    media_dir, media_path, meta_cache_path, sidecar_path, thumbnail_path, tmp_path,
     transcode_h264_path, transcode_live_path, trash_path, uuid_shard,
 };
 pub use rebuild::rebuild_index;
+pub use thumbnails::{
+    DerivativeError, Derivatives, ThumbnailReport, backfill_thumbnails, generate_derivatives,
+};
+pub use timezones::RetzReport;
+pub use verify::{VerifyIssue, VerifyReport, verify_library};
+pub use xmp_export::{XmpExportReport, xmp_for_sidecar};
@@ -1,24 +1,31 @@
 use std::path::Path;
 
 use crate::db::DatabaseDriver;
+use crate::db::schema::SCHEMA_VERSION;
 use crate::library::error::LibraryError;
 use crate::library::library::Library;
 use crate::library::lock;
+use crate::library::migrate::{upgrade_format, upgrade_index};
+use crate::library::rebuild::rebuild_index;
 use crate::library::scrub::startup_scrub;
 use crate::sidecar::io::{read_library_config, read_library_version, write_library_config};
 use crate::sidecar::library_version::CURRENT_LIBRARY_VERSION;
 
 /// Open an existing Pixles library at `root`.
 ///
-/// Validates the version file, acquires the lock, runs a startup scrub if
-/// needed, and updates `last_opened_at`.
+/// Validates the version file, acquires the lock, upgrades the on-disk
+/// format and index schema if they are older than this build (backing both
+/// up first), runs a startup scrub if needed, and updates `last_opened_at`.
+/// An index upgraded past [`schema::REINDEX_BELOW`](crate::db::schema::REINDEX_BELOW)
+/// is rebuilt from the sidecars.
+/// Libraries written by a newer build are refused.
 pub fn open_library(root: &Path) -> Result<Library, LibraryError> {
     // 1. Read and validate version.
     let version_path = root.join(".library/version.cbor");
     let version = read_library_version(&version_path)
         .map_err(|e| LibraryError::CorruptVersion(e.to_string()))?;
 
-    if version.version != CURRENT_LIBRARY_VERSION {
+    if version.version > CURRENT_LIBRARY_VERSION {
         return Err(LibraryError::VersionMismatch {
             found: version.version,
             expected: CURRENT_LIBRARY_VERSION,
@@ -28,33 +35,103 @@ pub fn open_library(root: &Path) -> Result<Library, LibraryError> {
     // 2. Acquire lock.
     lock::try_acquire(root)?;
 
-    // 3. Open DB (release lock on failure).
+    // 3. Upgrade the sidecar format (release lock on failure).
+    if version.version < CURRENT_LIBRARY_VERSION {
+        upgrade_format(root, version.version).inspect_err(|_e| {
+            let _ = lock::release(root);
+        })?;
+    }
+
+    // 4. Open and migrate DB (release lock on failure).
     let db_path = root.join("index/library.sqlite");
-    let db = DatabaseDriver::open(&db_path).map_err(|e| {
+    let db = DatabaseDriver::open_unmigrated(&db_path).map_err(|e| {
         let _ = lock::release(root);
         LibraryError::Db(e)
     })?;
+    let reindex = upgrade_index(root, &db).inspect_err(|_e| {
+        let _ = lock::release(root);
+    })?;
 
-    // 4. Read config (release lock on failure).
+    // 5. Read config (release lock on failure).
     let config_path = root.join(".library/config.cbor");
     let mut config = read_library_config(&config_path).map_err(|e| {
         let _ = lock::release(root);
         LibraryError::Cbor(e.to_string())
     })?;
 
-    // 5. Startup scrub (release lock on failure).
+    // 6. Startup scrub (release lock on failure).
     startup_scrub(root, &mut config).inspect_err(|_e| {
         let _ = lock::release(root);
     })?;
 
-    // 6. Update last_opened_at.
+    // 7. Update last_opened_at.
     config.last_opened_at = now_secs();
     write_library_config(&config_path, &config).map_err(|e| {
         let _ = lock::release(root);
         LibraryError::Cbor(e.to_string())
     })?;
 
-    Ok(Library::new(root.to_path_buf(), db, config))
+    let library = Library::new(root.to_path_buf(), db, config);
+
+    // 8. Repopulate an index that predates columns filled from sidecars
+    // (dropping `library` releases the lock on failure).
+    if reindex {
+        rebuild_index(&library)?;
+    }
+
+    Ok(library)
+}
+
+/// Open an existing Pixles library at `root` only to read it, as the source
+/// of a merge.
+///
+/// The library is locked while open, but nothing else is written: no
+/// upgrade, no startup scrub, and closing it does not update
+/// `last_opened_at`. A library whose format or index is older or newer than
+/// this build is refused; opening it normally upgrades it.
+pub fn open_library_read_only(root: &Path) -> Result<Library, LibraryError> {
+    let version_path = root.join(".library/version.cbor");
+    let version = read_library_version(&version_path)
+        .map_err(|e| LibraryError::CorruptVersion(e.to_string()))?;
+    if version.version > CURRENT_LIBRARY_VERSION {
+        return Err(LibraryError::VersionMismatch {
+            found: version.version,
+            expected: CURRENT_LIBRARY_VERSION,
+        });
+    }
+    if version.version < CURRENT_LIBRARY_VERSION {
+        return Err(LibraryError::NeedsUpgrade(format!(
+            "format v{} is older than v{CURRENT_LIBRARY_VERSION}",
+            version.version
+        )));
+    }
+
+    lock::try_acquire(root)?;
+    let release = |e: LibraryError| {
+        let _ = lock::release(root);
+        e
+    };
+
+    let db = DatabaseDriver::open_read_only(&root.join("index/library.sqlite"))
+        .map_err(|e| release(LibraryError::Db(e)))?;
+    let found = db
+        .schema_version()
+        .map_err(|e| release(LibraryError::Db(e)))?;
+    if found > SCHEMA_VERSION {
+        return Err(release(LibraryError::IndexTooNew {
+            found,
+            supported: SCHEMA_VERSION,
+        }));
+    }
+    if found < SCHEMA_VERSION {
+        return Err(release(LibraryError::NeedsUpgrade(format!(
+            "index schema v{found} is older than v{SCHEMA_VERSION}"
+        ))));
+    }
+
+    let config = read_library_config(&root.join(".library/config.cbor"))
+        .map_err(|e| release(LibraryError::Cbor(e.to_string())))?;
+    Ok(Library::new_read_only(root.to_path_buf(), db, config))
 }
 
 fn now_secs() -> i64 {
@@ -132,4 +209,78 @@ mod tests {
         }
         assert!(!root.join(".library/lock").exists());
     }
+
+    #[test]
+    fn test_open_refuses_newer_library_version() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("lib");
+        init_library(&root, "T").unwrap().close().unwrap();
+        crate::sidecar::io::write_library_version(
+            &root.join(".library/version.cbor"),
+            &crate::sidecar::LibraryVersionCbor {
+                version: CURRENT_LIBRARY_VERSION + 1,
+            },
+        )
+        .unwrap();
+
+        assert!(matches!(
+            open_library(&root),
+            Err(LibraryError::VersionMismatch { .. })
+        ));
+        assert!(!root.join(".library/lock").exists());
+    }
+
+    #[test]
+    fn test_open_read_only_writes_nothing() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("lib");
+        init_library(&root, "T").unwrap().close().unwrap();
+        let config_path = root.join(".library/config.cbor");
+        let before = std::fs::read(&config_path).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+
+        let lib = open_library_read_only(&root).unwrap();
+        assert!(root.join(".library/lock").exists());
+        lib.close().unwrap();
+        assert!(!root.join(".library/lock").exists());
+        assert_eq!(std::fs::read(&config_path).unwrap(), before);
+    }
+
+    #[test]
+    fn test_open_read_only_refuses_an_index_to_upgrade() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("lib");
+        init_library(&root, "T").unwrap().close().unwrap();
+        let conn = rusqlite::Connection::open(root.join("index/library.sqlite")).unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION - 1)
+            .unwrap();
+        drop(conn);
+
+        assert!(matches!(
+            open_library_read_only(&root),
+            Err(LibraryError::NeedsUpgrade(_))
+        ));
+        assert!(!root.join(".library/lock").exists());
+        let conn = rusqlite::Connection::open(root.join("index/library.sqlite")).unwrap();
+        let version: u32 = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION - 1);
+    }
+
+    #[test]
+    fn test_open_refuses_newer_index_and_releases_lock() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("lib");
+        init_library(&root, "T").unwrap().close().unwrap();
+        let conn = rusqlite::Connection::open(root.join("index/library.sqlite")).unwrap();
+        conn.pragma_update(None, "user_version", 999).unwrap();
+        drop(conn);
+
+        assert!(matches!(
+            open_library(&root),
+            Err(LibraryError::IndexTooNew { found: 999, .. })
+        ));
+        assert!(!root.join(".library/lock").exists());
+    }
 }
@@ -13,6 +13,16 @@ pub enum ThumbnailSize {
 }
 
 impl ThumbnailSize {
+    /// Every size, largest first.
+    pub const ALL: [ThumbnailSize; 6] = [
+        ThumbnailSize::O,
+        ThumbnailSize::Xl,
+        ThumbnailSize::L,
+        ThumbnailSize::M,
+        ThumbnailSize::S,
+        ThumbnailSize::Xs,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             ThumbnailSize::Xs => "xs",
@@ -64,7 +74,7 @@ pub fn sidecar_path(root: &Path, uuid: &Uuid, _ext: &str, capture_utc: Option<i6
     media_dir(root, year, month).join(format!("{}.cbor", uuid.simple()))
 }
 
-/// `index/thumbnails/{size}/{s1}/{s2}/{uuid}.{format}` where format is "jxl" or "webp"
+/// `index/thumbnails/{size}/{s1}/{s2}/{uuid}.{format}` where format is a `ThumbnailFormat` extension
 pub fn thumbnail_path(root: &Path, uuid: &Uuid, size: ThumbnailSize) -> PathBuf {
     let (s1, s2) = shard_dirs(uuid);
     root.join("index")
@@ -73,7 +83,7 @@ pub fn thumbnail_path(root: &Path, uuid: &Uuid, size: ThumbnailSize) -> PathBuf
         .join(&s1)
         .join(&s2)
         .join(format!("{}", uuid.simple()))
-    // Note: caller appends .jxl or .webp as needed
+    // Note: caller appends the ThumbnailFormat extension
 }
 
 /// `index/meta/{s1}/{s2}/{uuid}.meta.cbor`
@@ -108,6 +118,16 @@ pub fn transcode_live_path(root: &Path, uuid: &Uuid) -> PathBuf {
         .join(format!("{}.mov", uuid.simple()))
 }
 
+/// `albums/`
+pub fn albums_dir(root: &Path) -> PathBuf {
+    root.join("albums")
+}
+
+/// `albums/{id}.cbor`
+pub fn album_path(root: &Path, id: &Uuid) -> PathBuf {
+    albums_dir(root).join(format!("{}.cbor", id.simple()))
+}
+
 /// `.library/trash/{uuid}.{ext}`
 pub fn trash_path(root: &Path, uuid: &Uuid, ext: &str) -> PathBuf {
     root.join(".library")
@@ -115,6 +135,21 @@ pub fn trash_path(root: &Path, uuid: &Uuid, ext: &str) -> PathBuf {
         .join(format!("{}.{}", uuid.simple(), ext))
 }
 
+/// `.library/imports/{run_id}/`
+pub fn import_journal_dir(root: &Path, run_id: &str) -> PathBuf {
+    root.join(".library").join("imports").join(run_id)
+}
+
+/// `.library/backups/{name}/`
+pub fn backup_dir(root: &Path, name: &str) -> PathBuf {
+    root.join(".library").join("backups").join(name)
+}
+
+/// `.pixles-backup.cbor` at the root of a backup mirror
+pub fn backup_manifest_path(mirror: &Path) -> PathBuf {
+    mirror.join(".pixles-backup.cbor")
+}
+
 /// Appends `.tmp` to any path
 pub fn tmp_path(path: &Path) -> PathBuf {
     let mut s = path.as_os_str().to_owned();
@@ -207,6 +242,16 @@ mod tests {
         assert!(path.to_str().unwrap().ends_with(".mov"));
     }
 
+    #[test]
+    fn test_album_path() {
+        let root = Path::new("/lib");
+        let path = album_path(root, &test_uuid());
+        assert_eq!(
+            path,
+            PathBuf::from("/lib/albums/01956ef312347abc9def123456789abc.cbor")
+        );
+    }
+
     #[test]
     fn test_trash_path() {
         let root = Path::new("/lib");
@@ -216,6 +261,23 @@ mod tests {
         assert!(path.to_str().unwrap().ends_with(".jpg"));
     }
 
+    #[test]
+    fn test_import_journal_dir() {
+        let root = Path::new("/lib");
+        let path = import_journal_dir(root, "run-1");
+        assert_eq!(path, PathBuf::from("/lib/.library/imports/run-1"));
+    }
+
+    #[test]
+    fn test_backup_dir() {
+        let root = Path::new("/lib");
+        let path = backup_dir(root, "1720000000-index-v1");
+        assert_eq!(
+            path,
+            PathBuf::from("/lib/.library/backups/1720000000-index-v1")
+        );
+    }
+
     #[test]
     fn test_tmp_path() {
         let p = PathBuf::from("/lib/media/2024/2024-07/abc.jpg");
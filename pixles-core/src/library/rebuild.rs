@@ -1,21 +1,40 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 type StackGroupKey = (String, String);
 type StackGroupMembers = Vec<(String, String, StackType)>;
+use uuid::Uuid;
 use walkdir::WalkDir;
 
 use crate::db::rows::{AssetRow, AssetStackRow, StackMemberRow};
 use crate::domain::{CaptureTzSource, DetectionMethod, MemberRole, StackType};
+use crate::exif::{extract_exif, resolve_timezone};
+use crate::import::group::is_video;
+use crate::library::albums::album_row;
 use crate::library::error::LibraryError;
 use crate::library::library::Library;
+use crate::library::paths::{album_path, albums_dir};
 use crate::metadata::AssetType;
-use crate::sidecar::io::read_sidecar;
+use crate::sidecar::io::{read_album, read_sidecar, write_album};
+use crate::sidecar::library_version::CURRENT_LIBRARY_VERSION;
+use crate::sidecar::stack_hint::StackHint;
+use crate::sidecar::{AlbumCbor, AlbumMember, AssetSidecar};
 
 /// Rebuild the SQLite index from the CBOR sidecar files on disk.
 ///
-/// For each `*.cbor` file under `media/`, an `assets` row is upserted.
+/// For each `*.cbor` file under `media/`, an `assets` row is upserted and
+/// its `asset_tags` replaced.
 /// Then stacks are reconstructed from `stack_hint` fields, inserting
-/// `asset_stacks` and `stack_members` rows.
+/// `asset_stacks` and `stack_members` rows, and albums are indexed from
+/// `albums/*.cbor`.
+///
+/// Video fields missing from a sidecar (duration, dimensions, capture time)
+/// are read from the media file's container, and unstacked HEIC/MOV halves
+/// that share an Apple content identifier are paired into Live Photo stacks.
+/// Sidecars on disk are left untouched. The only files written are album
+/// files for the `album_id` older builds stored in sidecars (see
+/// [`rebuild_albums`]).
 pub fn rebuild_index(library: &Library) -> Result<(), LibraryError> {
     let media_dir = library.root.join("media");
     if !media_dir.exists() {
@@ -23,6 +42,8 @@ pub fn rebuild_index(library: &Library) -> Result<(), LibraryError> {
     }
 
     let mut sidecars = Vec::new();
+    // Parallel to `sidecars`: content identifier of unstacked Live Photo halves.
+    let mut live_photo_halves = Vec::new();
 
     for entry in WalkDir::new(&media_dir).into_iter().filter_map(|e| e.ok()) {
         let path = entry.path();
@@ -34,7 +55,11 @@ pub fn rebuild_index(library: &Library) -> Result<(), LibraryError> {
             continue;
         }
         match read_sidecar(path) {
-            Ok(sidecar) => sidecars.push(sidecar),
+            Ok(mut sidecar) => {
+                let content_id = backfill_from_media(path, &mut sidecar);
+                sidecars.push(sidecar);
+                live_photo_halves.push(content_id);
+            }
             Err(e) => {
                 log::warn!(
                     "rebuild_index: skipping unreadable sidecar {}: {e}",
@@ -44,10 +69,19 @@ pub fn rebuild_index(library: &Library) -> Result<(), LibraryError> {
         }
     }
 
-    // Upsert all asset rows.
+    pair_live_photos(&mut sidecars, &live_photo_halves);
+
+    // Upsert all asset rows. Thumbnail-derived columns are not in sidecars,
+    // so keep whatever the index already has for them.
     for sidecar in &sidecars {
-        let row = asset_row_from_sidecar(sidecar);
+        let mut row = asset_row_from_sidecar(sidecar);
+        if let Some(existing) = library.db.find_by_uuid(&row.uuid)? {
+            row.chromahash = existing.chromahash;
+            row.dominant_color = existing.dominant_color;
+            row.perceptual_hash = existing.perceptual_hash;
+        }
         library.db.upsert_asset(&row)?;
+        library.db.set_asset_tags(&row.uuid, &sidecar.tags)?;
     }
 
     // Reconstruct stacks: group by (detection_key, detection_method) from stack_hint.
@@ -111,12 +145,177 @@ pub fn rebuild_index(library: &Library) -> Result<(), LibraryError> {
         }
     }
 
+    rebuild_albums(library, &sidecars)
+}
+
+/// Indexes every readable `albums/*.cbor`.
+///
+/// Sidecars written before albums had files of their own carry a single
+/// `album_id`. Each such ID without a matching album (by ID, or by name for
+/// IDs that are not UUIDs) becomes a new album named after it, holding the
+/// stack primaries that carry it in capture order, and its file is written.
+fn rebuild_albums(library: &Library, sidecars: &[AssetSidecar]) -> Result<(), LibraryError> {
+    let mut albums = Vec::new();
+    let dir = albums_dir(&library.root);
+    if dir.exists() {
+        for entry in fs::read_dir(&dir)?.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().is_none_or(|e| e != "cbor") {
+                continue;
+            }
+            match read_album(&path) {
+                Ok(album) => albums.push(album),
+                Err(e) => {
+                    log::warn!(
+                        "rebuild_index: skipping unreadable album {}: {e}",
+                        path.display()
+                    );
+                }
+            }
+        }
+    }
+
+    let mut legacy: BTreeMap<&str, Vec<&AssetSidecar>> = BTreeMap::new();
+    for sidecar in sidecars {
+        let is_primary = sidecar
+            .stack_hint
+            .as_ref()
+            .is_none_or(|hint| hint.member_role == MemberRole::Primary);
+        if let Some(album_id) = sidecar.album_id.as_deref()
+            && is_primary
+        {
+            legacy.entry(album_id).or_default().push(sidecar);
+        }
+    }
+    for (legacy_id, mut members) in legacy {
+        let uuid = Uuid::parse_str(legacy_id).ok();
+        let exists = albums.iter().any(|a| match uuid {
+            Some(uuid) => Uuid::parse_str(&a.id).is_ok_and(|id| id == uuid),
+            None => a.name == legacy_id,
+        });
+        if exists {
+            continue;
+        }
+        members.sort_by_key(|s| (s.capture_utc.or(s.capture_timestamp), s.import_timestamp));
+        let now = now_secs();
+        let id = uuid.unwrap_or_else(Uuid::now_v7);
+        let album = AlbumCbor {
+            version: CURRENT_LIBRARY_VERSION,
+            id: id.to_string(),
+            name: legacy_id.to_string(),
+            description: None,
+            cover_asset_id: None,
+            created_at: now,
+            modified_at: now,
+            assets: members
+                .iter()
+                .map(|s| AlbumMember {
+                    uuid: s.uuid.clone(),
+                    added_at: s.import_timestamp,
+                })
+                .collect(),
+        };
+        fs::create_dir_all(&dir)?;
+        write_album(&album_path(&library.root, &id), &album)
+            .map_err(|e| LibraryError::Cbor(e.to_string()))?;
+        albums.push(album);
+    }
+
+    for album in &albums {
+        let members: Vec<(String, i64)> = album
+            .assets
+            .iter()
+            .map(|m| (m.uuid.clone(), m.added_at))
+            .collect();
+        library.db.upsert_album(&album_row(album), &members)?;
+    }
     Ok(())
 }
 
 // ── helpers ─────────────────────────────────────────────────────────────────
 
-fn asset_row_from_sidecar(s: &crate::sidecar::AssetSidecar) -> AssetRow {
+/// `{uuid}.cbor` → `{uuid}.{ext}`, with the extension the importer used,
+/// or the original's own path when it was imported by reference.
+pub(crate) fn media_file_for(sidecar_path: &Path, s: &AssetSidecar) -> Option<PathBuf> {
+    if let Some(reference) = &s.reference_path {
+        return Some(PathBuf::from(reference));
+    }
+    let ext = Path::new(&s.original_filename)
+        .extension()?
+        .to_string_lossy()
+        .to_lowercase();
+    Some(sidecar_path.with_extension(ext))
+}
+
+/// Fills video fields the sidecar lacks from the media file's container.
+///
+/// Returns the Apple content identifier when the asset is an unstacked
+/// HEIC or video that could be half of a Live Photo.
+fn backfill_from_media(sidecar_path: &Path, s: &mut AssetSidecar) -> Option<String> {
+    let media = media_file_for(sidecar_path, s)?;
+    let ext = media.extension()?.to_string_lossy().to_lowercase();
+    let video = is_video(&ext);
+    let needs_video_fields = video && s.duration_ms.is_none();
+    let live_photo_half = s.stack_hint.is_none() && (video || ext == "heic" || ext == "heif");
+    if !needs_video_fields && !live_photo_half {
+        return None;
+    }
+
+    let exif = extract_exif(&media).ok()?;
+    if needs_video_fields {
+        s.duration_ms = exif.duration_ms;
+        s.width = s.width.or(exif.width);
+        s.height = s.height.or(exif.height);
+        s.gps_lat = s.gps_lat.or(exif.gps_lat);
+        s.gps_lon = s.gps_lon.or(exif.gps_lon);
+        if s.capture_timestamp.is_none() {
+            let tz = resolve_timezone(&exif);
+            s.capture_timestamp = tz.capture_timestamp;
+            s.capture_utc = tz.capture_utc;
+            s.capture_tz = tz.capture_tz;
+            s.capture_tz_source = tz.capture_tz_source;
+            s.tz_db_version = tz.tz_db_version;
+        }
+    }
+    exif.content_identifier.filter(|_| live_photo_half)
+}
+
+/// Gives a Live Photo stack hint to each still/video pair sharing a content
+/// identifier. Identifiers shared by any other combination are ignored.
+fn pair_live_photos(sidecars: &mut [AssetSidecar], content_ids: &[Option<String>]) {
+    let mut by_id: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, id) in content_ids.iter().enumerate() {
+        if let Some(id) = id {
+            by_id.entry(id.as_str()).or_default().push(i);
+        }
+    }
+
+    for (id, indices) in by_id {
+        let [a, b] = indices[..] else {
+            continue;
+        };
+        let is_video_file = |i: usize| {
+            Path::new(&sidecars[i].original_filename)
+                .extension()
+                .is_some_and(|e| is_video(&e.to_string_lossy()))
+        };
+        let (still, movie) = match (is_video_file(a), is_video_file(b)) {
+            (false, true) => (a, b),
+            (true, false) => (b, a),
+            _ => continue,
+        };
+        for (i, role) in [(still, MemberRole::Primary), (movie, MemberRole::Video)] {
+            sidecars[i].stack_hint = Some(StackHint {
+                detection_key: id.to_string(),
+                detection_method: DetectionMethod::ContentIdentifier,
+                member_role: role,
+                stack_type: StackType::LivePhoto,
+            });
+        }
+    }
+}
+
+pub(crate) fn asset_row_from_sidecar(s: &AssetSidecar) -> AssetRow {
     AssetRow {
         uuid: s.uuid.clone(),
         asset_type: asset_type_str(s.asset_type).to_string(),
@@ -132,10 +331,17 @@ fn asset_row_from_sidecar(s: &crate::sidecar::AssetSidecar) -> AssetRow {
         is_stack_hidden: false,
         chromahash: None,
         dominant_color: None,
+        perceptual_hash: None,
         album_id: s.album_id.clone(),
         rating: s.rating as i64,
         is_deleted: s.is_deleted,
         deleted_at: s.deleted_at,
+        original_filename: Some(s.original_filename.clone()),
+        camera_make: s.camera_make.clone(),
+        camera_model: s.camera_model.clone(),
+        gps_lat: s.gps_lat,
+        gps_lon: s.gps_lon,
+        caption: s.caption.clone(),
     }
 }
 
@@ -147,7 +353,7 @@ fn asset_type_str(t: AssetType) -> &'static str {
     }
 }
 
-fn tz_source_str(s: CaptureTzSource) -> &'static str {
+pub(crate) fn tz_source_str(s: CaptureTzSource) -> &'static str {
     match s {
         CaptureTzSource::OffsetExif => "offset_exif",
         CaptureTzSource::GpsLookup => "gps_lookup",
@@ -161,10 +367,13 @@ fn detection_method_str(m: DetectionMethod) -> &'static str {
         DetectionMethod::ContentIdentifier => "content_identifier",
         DetectionMethod::Timecode => "timecode",
         DetectionMethod::Manual => "manual",
+        DetectionMethod::BurstIdentifier => "burst_identifier",
+        DetectionMethod::ExposureSequence => "exposure_sequence",
+        DetectionMethod::EmbeddedVideo => "embedded_video",
     }
 }
 
-fn member_role_str(r: MemberRole) -> &'static str {
+pub(crate) fn member_role_str(r: MemberRole) -> &'static str {
     match r {
         MemberRole::Primary => "primary",
         MemberRole::Raw => "raw",
@@ -180,7 +389,7 @@ fn member_role_str(r: MemberRole) -> &'static str {
     }
 }
 
-fn stack_type_str(st: StackType) -> &'static str {
+pub(crate) fn stack_type_str(st: StackType) -> &'static str {
     match st {
         StackType::RawJpeg => "raw_jpeg",
         StackType::Burst => "burst",
@@ -237,6 +446,7 @@ mod tests {
             capture_tz: None,
             capture_tz_source: None,
             tz_db_version: None,
+            original_capture_timestamp: None,
             width: None,
             height: None,
             duration_ms: None,
@@ -247,6 +457,11 @@ mod tests {
             camera_model: None,
             gps_lat: None,
             gps_lon: None,
+            gps_source: None,
+            caption: None,
+            label: None,
+            pick: None,
+            reference_path: None,
             unknown_fields: BTreeMap::new(),
         }
     }
@@ -361,4 +576,44 @@ mod tests {
         let found = lib.db.find_by_hash(&"c".repeat(64)).unwrap();
         assert!(found.is_some());
     }
+
+    #[test]
+    fn test_rebuild_reads_video_duration_from_container() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().join("lib");
+        let lib = init_library(&root, "T").unwrap();
+
+        let media_dir = root.join("media/1970/1970-01");
+        std::fs::create_dir_all(&media_dir).unwrap();
+        let mut sidecar = make_sidecar(
+            "dddd0000-0000-0000-0000-000000000004",
+            &"d".repeat(64),
+            None,
+        );
+        sidecar.asset_type = AssetType::Video;
+        sidecar.original_filename = "clip.MOV".to_string();
+        write_sidecar(
+            &media_dir.join("dddd000000000000000000000000004.cbor"),
+            &sidecar,
+        )
+        .unwrap();
+
+        // ftyp + moov/mvhd: timescale 600, duration 1500 ticks = 2.5 s
+        let mut mvhd = vec![0u8; 12];
+        mvhd.extend_from_slice(&600u32.to_be_bytes());
+        mvhd.extend_from_slice(&1500u32.to_be_bytes());
+        let mut moov = ((mvhd.len() + 8) as u32).to_be_bytes().to_vec();
+        moov.extend_from_slice(b"mvhd");
+        moov.extend_from_slice(&mvhd);
+        let mut mov = b"\0\0\0\x10ftypqt  \0\0\0\0".to_vec();
+        mov.extend_from_slice(&((moov.len() + 8) as u32).to_be_bytes());
+        mov.extend_from_slice(b"moov");
+        mov.extend_from_slice(&moov);
+        std::fs::write(media_dir.join("dddd000000000000000000000000004.mov"), mov).unwrap();
+
+        rebuild_index(&lib).unwrap();
+
+        let found = lib.db.find_by_hash(&"d".repeat(64)).unwrap().unwrap();
+        assert_eq!(found.duration_ms, Some(2500));
+    }
 }
@@ -135,6 +135,8 @@ mod tests {
             library_name: "test".to_string(),
             last_opened_at: now,
             last_scrubbed_at: Some(now - 60), // 60 s ago — well under the 7-day threshold
+            thumbnail_format: crate::domain::ThumbnailFormat::Webp,
+            auto_xmp_export: None,
         };
 
         startup_scrub(tmp.path(), &mut config).unwrap();
@@ -156,6 +158,8 @@ mod tests {
             library_name: "test".to_string(),
             last_opened_at: now,
             last_scrubbed_at: None,
+            thumbnail_format: crate::domain::ThumbnailFormat::Webp,
+            auto_xmp_export: None,
         };
 
         startup_scrub(tmp.path(), &mut config).unwrap();
@@ -178,6 +182,8 @@ mod tests {
             library_name: "test".to_string(),
             last_opened_at: now,
             last_scrubbed_at: Some(now - 8 * 86400), // 8 days ago → overdue
+            thumbnail_format: crate::domain::ThumbnailFormat::Webp,
+            auto_xmp_export: None,
         };
 
         startup_scrub(tmp.path(), &mut config).unwrap();
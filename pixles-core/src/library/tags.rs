@@ -0,0 +1,304 @@
+use std::path::PathBuf;
+
+use uuid::Uuid;
+
+use crate::domain::tag::{dedup, is_within, normalize_tag, reparent};
+use crate::library::error::LibraryError;
+use crate::library::library::Library;
+use crate::library::paths::sidecar_path;
+use crate::sidecar::AssetSidecar;
+use crate::sidecar::io::{read_sidecar, write_sidecar};
+
+/// Tag editing. Tags are `|`-separated keyword paths (`Places|Japan|Kyoto`);
+/// operations on a tag also apply to the tags nested below it.
+///
+/// Every change rewrites the affected sidecars and then updates the index in
+/// a single transaction. If any step fails, the sidecars already rewritten
+/// are restored and the index is left as it was.
+impl Library {
+    /// Tags of one asset.
+    pub fn tags(&self, uuid: &str) -> Result<Vec<String>, LibraryError> {
+        let (uuid, _) = self.locate_sidecar(uuid)?;
+        Ok(self.db.tags_for(&uuid)?)
+    }
+
+    /// Every tag in use outside the trash, with the number of assets
+    /// carrying it.
+    pub fn list_tags(&self) -> Result<Vec<(String, usize)>, LibraryError> {
+        Ok(self
+            .db
+            .list_tags()?
+            .into_iter()
+            .map(|(tag, count)| (tag, count as usize))
+            .collect())
+    }
+
+    /// Adds `tags` to each asset. Returns the number of assets changed.
+    pub fn add_tags(&self, uuids: &[String], tags: &[String]) -> Result<usize, LibraryError> {
+        let tags = normalize_all(tags)?;
+        self.retag(uuids, |current| dedup(current.iter().chain(&tags).cloned()))
+    }
+
+    /// Removes `tags`, and every tag nested below them, from each asset.
+    /// Returns the number of assets changed.
+    pub fn remove_tags(&self, uuids: &[String], tags: &[String]) -> Result<usize, LibraryError> {
+        let tags = normalize_all(tags)?;
+        self.retag(uuids, |current| {
+            current
+                .iter()
+                .filter(|t| !tags.iter().any(|r| is_within(t, r)))
+                .cloned()
+                .collect()
+        })
+    }
+
+    /// Renames `from` across the library, moving nested tags along with it:
+    /// renaming `Places|Japan` to `Travel|Japan` turns `Places|Japan|Kyoto`
+    /// into `Travel|Japan|Kyoto`. Renaming onto a tag that already exists
+    /// merges the two. Returns the number of assets changed.
+    pub fn rename_tag(&self, from: &str, to: &str) -> Result<usize, LibraryError> {
+        self.merge_tags(&[from.to_string()], to)
+    }
+
+    /// Folds every tag in `sources`, and the tags nested below them, into
+    /// `into`. Returns the number of assets changed.
+    pub fn merge_tags(&self, sources: &[String], into: &str) -> Result<usize, LibraryError> {
+        let sources = normalize_all(sources)?;
+        let into = normalize_all(&[into.to_string()])?.remove(0);
+
+        let mut uuids = Vec::new();
+        for source in &sources {
+            uuids.extend(self.db.uuids_with_tag(source)?);
+        }
+        uuids.sort();
+        uuids.dedup();
+
+        self.retag(&uuids, |current| {
+            dedup(current.iter().map(|tag| {
+                sources
+                    .iter()
+                    .find_map(|source| reparent(tag, source, &into))
+                    .unwrap_or_else(|| tag.clone())
+            }))
+        })
+    }
+
+    /// Applies `edit` to the tags of each asset in `uuids`.
+    fn retag(
+        &self,
+        uuids: &[String],
+        edit: impl Fn(&[String]) -> Vec<String>,
+    ) -> Result<usize, LibraryError> {
+        let mut written = Vec::new();
+        let result = self.write_tags(uuids, edit, &mut written);
+        if result.is_err() {
+            for (path, original) in written.iter().rev() {
+                if let Err(e) = write_sidecar(path, original) {
+                    log::error!("retag: could not restore {}: {e}", path.display());
+                }
+            }
+        }
+        result
+    }
+
+    /// Rewrites each changed sidecar, recording its previous contents in
+    /// `written`, then commits all index changes at once.
+    fn write_tags(
+        &self,
+        uuids: &[String],
+        edit: impl Fn(&[String]) -> Vec<String>,
+        written: &mut Vec<(PathBuf, AssetSidecar)>,
+    ) -> Result<usize, LibraryError> {
+        let mut changes = Vec::new();
+        for uuid in uuids {
+            let (uuid, path) = self.locate_sidecar(uuid)?;
+            let original = read_sidecar(&path).map_err(|e| LibraryError::Cbor(e.to_string()))?;
+            let tags = edit(&original.tags);
+            if tags == original.tags {
+                continue;
+            }
+
+            let mut sidecar = original.clone();
+            sidecar.tags = tags.clone();
+            sidecar.modified_timestamp = now_secs();
+            write_sidecar(&path, &sidecar).map_err(|e| LibraryError::Cbor(e.to_string()))?;
+            written.push((path, original));
+            changes.push((uuid, tags));
+        }
+
+        self.db.set_tags_batch(&changes)?;
+        let uuids: Vec<String> = changes.into_iter().map(|(uuid, _)| uuid).collect();
+        self.refresh_auto_xmp(&uuids);
+        Ok(uuids.len())
+    }
+
+    /// The hyphenated UUID of an asset in the index and its sidecar path.
+    /// Accepts either UUID form.
+    pub(crate) fn locate_sidecar(&self, uuid: &str) -> Result<(String, PathBuf), LibraryError> {
+        let not_found = || LibraryError::AssetNotFound(uuid.to_string());
+        let parsed = Uuid::parse_str(uuid).map_err(|_| not_found())?;
+        let row = self
+            .db
+            .find_by_uuid(&parsed.to_string())?
+            .ok_or_else(not_found)?;
+        let path = sidecar_path(&self.root, &parsed, "", row.capture_utc);
+        Ok((row.uuid, path))
+    }
+}
+
+fn normalize_all(tags: &[String]) -> Result<Vec<String>, LibraryError> {
+    tags.iter()
+        .map(|t| normalize_tag(t).ok_or_else(|| LibraryError::InvalidTag(t.clone())))
+        .collect()
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::executor::execute;
+    use crate::import::executor_cancellation::CancellationToken;
+    use crate::import::planner::{ImportConfig, plan};
+    use crate::import::scanner::scan;
+    use crate::library::init::init_library;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const KEYWORDS: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+      <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+       <rdf:Description xmlns:dc="http://purl.org/dc/elements/1.1/"
+           xmlns:lr="http://ns.adobe.com/lightroom/1.0/">
+        <dc:subject><rdf:Bag><rdf:li>Kyoto</rdf:li><rdf:li>shrine</rdf:li></rdf:Bag></dc:subject>
+        <lr:hierarchicalSubject><rdf:Bag><rdf:li>Places|Japan|Kyoto</rdf:li></rdf:Bag></lr:hierarchicalSubject>
+       </rdf:Description>
+      </rdf:RDF>
+     </x:xmpmeta>"#;
+
+    /// Imports `kyoto.jpg` (with embedded keywords) and `plain.jpg`, and
+    /// returns their UUIDs in that order.
+    fn library_with_assets(lib_dir: &TempDir) -> (Library, String, String) {
+        let src = TempDir::new().unwrap();
+        fs::write(src.path().join("kyoto.jpg"), KEYWORDS).unwrap();
+        fs::write(src.path().join("plain.jpg"), b"no keywords").unwrap();
+
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let config = ImportConfig {
+            thumbnail_format: None,
+            ..Default::default()
+        };
+        let plan_result = plan(
+            &scan(&[src.path().to_path_buf()]).unwrap(),
+            &lib.db,
+            &config,
+        )
+        .unwrap();
+        execute(
+            &plan_result,
+            &lib,
+            &config,
+            |_| {},
+            &CancellationToken::new(),
+        )
+        .unwrap();
+
+        let uuid_of = |name: &str| {
+            lib.db
+                .list_assets()
+                .unwrap()
+                .into_iter()
+                .find(|r| r.original_filename.as_deref() == Some(name))
+                .unwrap()
+                .uuid
+        };
+        let (kyoto, plain) = (uuid_of("kyoto.jpg"), uuid_of("plain.jpg"));
+        (lib, kyoto, plain)
+    }
+
+    fn sidecar_tags(lib: &Library, uuid: &str) -> Vec<String> {
+        let (_, path) = lib.locate_sidecar(uuid).unwrap();
+        read_sidecar(&path).unwrap().tags
+    }
+
+    #[test]
+    fn test_import_seeds_tags_from_keywords() {
+        let lib_dir = TempDir::new().unwrap();
+        let (lib, kyoto, plain) = library_with_assets(&lib_dir);
+        let expected = vec!["Places|Japan|Kyoto".to_string(), "shrine".to_string()];
+        assert_eq!(sidecar_tags(&lib, &kyoto), expected);
+        assert_eq!(lib.tags(&kyoto).unwrap(), expected);
+        assert!(lib.tags(&plain).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_add_and_remove_write_sidecar_and_index() {
+        let lib_dir = TempDir::new().unwrap();
+        let (lib, kyoto, plain) = library_with_assets(&lib_dir);
+        let both = [kyoto.clone(), plain.clone()];
+
+        let changed = lib
+            .add_tags(&both, &[" Trips | 2024 ".to_string()])
+            .unwrap();
+        assert_eq!(changed, 2);
+        assert_eq!(sidecar_tags(&lib, &plain), vec!["Trips|2024"]);
+        assert_eq!(lib.tags(&plain).unwrap(), vec!["Trips|2024"]);
+
+        // Removing a parent removes the nested tags with it.
+        let changed = lib.remove_tags(&both, &["Places".to_string()]).unwrap();
+        assert_eq!(changed, 1);
+        assert_eq!(sidecar_tags(&lib, &kyoto), vec!["shrine", "Trips|2024"]);
+        assert_eq!(lib.tags(&kyoto).unwrap(), vec!["Trips|2024", "shrine"]);
+
+        assert!(matches!(
+            lib.add_tags(&both, &[" | ".to_string()]),
+            Err(LibraryError::InvalidTag(_))
+        ));
+    }
+
+    #[test]
+    fn test_rename_and_merge() {
+        let lib_dir = TempDir::new().unwrap();
+        let (lib, kyoto, plain) = library_with_assets(&lib_dir);
+        lib.add_tags(std::slice::from_ref(&plain), &["Travel|Japan".to_string()])
+            .unwrap();
+
+        let changed = lib.rename_tag("Places|Japan", "Travel|Japan").unwrap();
+        assert_eq!(changed, 1);
+        assert_eq!(
+            sidecar_tags(&lib, &kyoto),
+            vec!["Travel|Japan|Kyoto", "shrine"]
+        );
+
+        let changed = lib
+            .merge_tags(
+                &["shrine".to_string(), "Travel|Japan|Kyoto".to_string()],
+                "Kyoto",
+            )
+            .unwrap();
+        assert_eq!(changed, 1);
+        assert_eq!(sidecar_tags(&lib, &kyoto), vec!["Kyoto"]);
+        let tags: Vec<_> = lib.list_tags().unwrap();
+        assert_eq!(
+            tags,
+            vec![("Kyoto".to_string(), 1), ("Travel|Japan".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_failed_change_restores_sidecars() {
+        let lib_dir = TempDir::new().unwrap();
+        let (lib, kyoto, _) = library_with_assets(&lib_dir);
+        let before = sidecar_tags(&lib, &kyoto);
+
+        let missing = Uuid::now_v7().to_string();
+        let result = lib.add_tags(&[kyoto.clone(), missing], &["new".to_string()]);
+        assert!(matches!(result, Err(LibraryError::AssetNotFound(_))));
+        assert_eq!(sidecar_tags(&lib, &kyoto), before);
+        assert_eq!(lib.tags(&kyoto).unwrap(), before);
+    }
+}
@@ -0,0 +1,432 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use pixles_media::image::buffer::{ImageBuffer, ImageBufferError};
+use pixles_media::image::formats::{
+    bmp::BmpImage, gif::GifImage, jpeg::JpegImage, png::PngImage, webp::WebpImage,
+};
+use pixles_media::image::lqip::{LQIP, LQIPError};
+use pixles_media::image::metadata::{ImageMetadata, ImageMetadataProvider};
+use pixles_media::image::phash::dhash;
+use pixles_media::image::resample::ResampleOptions;
+use pixles_media::image::types::{StandardImageSize, WebPSettings};
+use pixles_media::image::{
+    Image, ImageDecode, ImageEncode, ImageError, normalize_orientation, resize_to_max_dimension,
+};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::domain::ThumbnailFormat;
+use crate::library::error::LibraryError;
+use crate::library::library::Library;
+use crate::library::paths::{ThumbnailSize, sidecar_path, thumbnail_path, tmp_path};
+use crate::library::rebuild::media_file_for;
+use crate::sidecar::io::read_sidecar;
+
+const WEBP_QUALITY: u8 = 80;
+const WEBP_EFFORT: u8 = 4;
+/// Largest width or height each format can encode.
+const WEBP_MAX_DIMENSION: usize = 16_383;
+const JPEG_MAX_DIMENSION: usize = 65_535;
+
+#[derive(Debug, Error)]
+pub enum DerivativeError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("image error: {0}")]
+    Image(#[from] ImageError),
+
+    #[error("image buffer error: {0}")]
+    Buffer(#[from] ImageBufferError),
+
+    #[error("LQIP error: {0}")]
+    Lqip(#[from] LQIPError),
+}
+
+/// Files and index values generated for one asset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Derivatives {
+    /// Base64 thumbhash for the `assets.chromahash` column.
+    pub chromahash: String,
+    /// `#rrggbb` for the `assets.dominant_color` column.
+    pub dominant_color: String,
+    /// dHash of the smallest thumbnail for the `assets.perceptual_hash`
+    /// column.
+    pub perceptual_hash: u64,
+    /// Every thumbnail written, largest first.
+    pub paths: Vec<PathBuf>,
+}
+
+/// Counts reported by [`backfill_thumbnails`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThumbnailReport {
+    pub generated: usize,
+    pub skipped: usize,
+    pub unsupported: usize,
+    pub failed: usize,
+}
+
+/// Generate every [`ThumbnailSize`] plus the LQIP for the media file at `media`.
+///
+/// The image is decoded with pixles-media, rotated upright from its EXIF
+/// orientation and scaled down (never up) from the largest size to the
+/// smallest, each size resampled from the previous one. `O` is capped at the
+/// largest dimension `format` can encode. Files are written to `{path}.tmp`
+/// and renamed into place.
+///
+/// A size that fails to encode or write is logged and left out of
+/// [`Derivatives::paths`]; the other sizes are still written, and
+/// [`backfill_thumbnails`] fills in the gap later.
+///
+/// Returns `Ok(None)` for media pixles-media cannot decode (video, RAW, HEIF).
+pub fn generate_derivatives(
+    root: &Path,
+    uuid: &Uuid,
+    media: &Path,
+    format: ThumbnailFormat,
+) -> Result<Option<Derivatives>, DerivativeError> {
+    let Some((buffer, mut metadata)) = decode(media)? else {
+        return Ok(None);
+    };
+    let mut current = normalize_orientation(&buffer, &mut metadata).into_u8()?;
+
+    let mut paths = Vec::with_capacity(ThumbnailSize::ALL.len());
+    for size in ThumbnailSize::ALL {
+        let (max, options) = match standard_size(size) {
+            Some(standard) => (
+                standard.dimensions().0 as usize,
+                ResampleOptions::for_size(standard),
+            ),
+            None => (max_dimension(format), ResampleOptions::default()),
+        };
+        let (width, height) = resize_to_max_dimension(current.width, current.height, max);
+        if (width, height) != (current.width, current.height) {
+            current = current.resize_with(width, height, &options)?;
+        }
+        let path = thumbnail_path(root, uuid, size).with_extension(format.extension());
+        let written = encode(&current, format)
+            .map_err(DerivativeError::from)
+            .and_then(|bytes| Ok(write_atomic(&path, &bytes)?));
+        match written {
+            Ok(()) => paths.push(path),
+            Err(e) => log::warn!(
+                "thumbnail {} failed for {}: {e}",
+                size.as_str(),
+                media.display()
+            ),
+        }
+    }
+
+    let perceptual_hash = dhash(&current)?;
+    let lqip = LQIP::from_image_buffer_blocking(current.to_rgba8()?)?;
+    let [r, g, b, _] = lqip.average_rgba()?;
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Ok(Some(Derivatives {
+        chromahash: lqip.to_base64(),
+        dominant_color: format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b)),
+        perceptual_hash,
+        paths,
+    }))
+}
+
+/// The perceptual hash [`generate_derivatives`] would store for `media`,
+/// computed without writing any thumbnails. Used to compare files that are
+/// not in the library yet.
+///
+/// Returns `Ok(None)` for media pixles-media cannot decode.
+pub fn perceptual_hash(media: &Path) -> Result<Option<u64>, DerivativeError> {
+    let Some((buffer, mut metadata)) = decode(media)? else {
+        return Ok(None);
+    };
+    let upright = normalize_orientation(&buffer, &mut metadata).into_u8()?;
+    let smallest = StandardImageSize::Tiny;
+    let (width, height) = resize_to_max_dimension(
+        upright.width,
+        upright.height,
+        smallest.dimensions().0 as usize,
+    );
+    let small = upright.resize_with(width, height, &ResampleOptions::for_size(smallest))?;
+    Ok(Some(dhash(&small)?))
+}
+
+/// Generate derivatives for every asset in the library that is not in the
+/// trash, and store the LQIP, dominant colour and perceptual hash in the
+/// index.
+///
+/// Assets that already have a chromahash, a perceptual hash and every
+/// thumbnail file are skipped unless `force` is set. Per-asset failures are logged and counted rather
+/// than aborting the run.
+pub fn backfill_thumbnails(
+    library: &Library,
+    force: bool,
+) -> Result<ThumbnailReport, LibraryError> {
+    let root = &library.root;
+    let format = library.config().thumbnail_format;
+    let mut report = ThumbnailReport::default();
+
+    for asset in library.db.list_assets()? {
+        let Ok(uuid) = Uuid::parse_str(&asset.uuid) else {
+            log::warn!("backfill_thumbnails: invalid uuid {}", asset.uuid);
+            report.failed += 1;
+            continue;
+        };
+        if !force
+            && asset.chromahash.is_some()
+            && asset.perceptual_hash.is_some()
+            && has_thumbnails(root, &uuid, format)
+        {
+            report.skipped += 1;
+            continue;
+        }
+
+        let sidecar = sidecar_path(root, &uuid, "", asset.capture_utc);
+        let media = match read_sidecar(&sidecar) {
+            Ok(s) => media_file_for(&sidecar, &s),
+            Err(e) => {
+                log::warn!(
+                    "backfill_thumbnails: unreadable sidecar {}: {e}",
+                    sidecar.display()
+                );
+                report.failed += 1;
+                continue;
+            }
+        };
+        let Some(media) = media else {
+            report.unsupported += 1;
+            continue;
+        };
+
+        match generate_derivatives(root, &uuid, &media, format) {
+            Ok(Some(d)) => {
+                library.db.update_derivatives(
+                    &asset.uuid,
+                    &d.chromahash,
+                    &d.dominant_color,
+                    d.perceptual_hash as i64,
+                )?;
+                report.generated += 1;
+            }
+            Ok(None) => report.unsupported += 1,
+            Err(e) => {
+                log::warn!("backfill_thumbnails: {}: {e}", media.display());
+                report.failed += 1;
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Removes every thumbnail of `uuid`, in any format.
+pub fn remove_thumbnails(root: &Path, uuid: &Uuid) {
+    for size in ThumbnailSize::ALL {
+        for format in [ThumbnailFormat::Webp, ThumbnailFormat::Jpeg] {
+            let _ = fs::remove_file(
+                thumbnail_path(root, uuid, size).with_extension(format.extension()),
+            );
+        }
+    }
+}
+
+fn has_thumbnails(root: &Path, uuid: &Uuid, format: ThumbnailFormat) -> bool {
+    ThumbnailSize::ALL.iter().all(|&size| {
+        thumbnail_path(root, uuid, size)
+            .with_extension(format.extension())
+            .is_file()
+    })
+}
+
+/// Bounding box of each size; `O` keeps the original resolution.
+fn standard_size(size: ThumbnailSize) -> Option<StandardImageSize> {
+    match size {
+        ThumbnailSize::Xs => Some(StandardImageSize::Tiny),
+        ThumbnailSize::S => Some(StandardImageSize::MediumThumb),
+        ThumbnailSize::M => Some(StandardImageSize::LargeThumb),
+        ThumbnailSize::L => Some(StandardImageSize::WebMedium),
+        ThumbnailSize::Xl => Some(StandardImageSize::WebExtraLarge),
+        ThumbnailSize::O => None,
+    }
+}
+
+fn max_dimension(format: ThumbnailFormat) -> usize {
+    match format {
+        ThumbnailFormat::Webp => WEBP_MAX_DIMENSION,
+        ThumbnailFormat::Jpeg => JPEG_MAX_DIMENSION,
+    }
+}
+
+/// Decodes formats pixles-media implements, chosen by file extension.
+pub(crate) fn decode(
+    media: &Path,
+) -> Result<Option<(ImageBuffer, ImageMetadata)>, DerivativeError> {
+    fn parts<T: Image + ImageDecode + ImageMetadataProvider>(
+        bytes: &[u8],
+    ) -> Result<Option<(ImageBuffer, ImageMetadata)>, DerivativeError> {
+        let image = T::decode_from_bytes(bytes)?;
+        Ok(Some((image.get_buffer(), image.get_metadata())))
+    }
+
+    let ext = media
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    let bytes = || fs::read(media);
+    match ext.as_str() {
+        "jpg" | "jpeg" => parts::<JpegImage>(&bytes()?),
+        "png" => parts::<PngImage>(&bytes()?),
+        "gif" => parts::<GifImage>(&bytes()?),
+        "bmp" => parts::<BmpImage>(&bytes()?),
+        "webp" => parts::<WebpImage>(&bytes()?),
+        _ => Ok(None),
+    }
+}
+
+fn encode(buffer: &ImageBuffer, format: ThumbnailFormat) -> Result<Vec<u8>, ImageError> {
+    match format {
+        ThumbnailFormat::Webp => {
+            WebpImage::from_raw_parts(buffer.clone(), ImageMetadata::default())?
+                .with_settings(WebPSettings {
+                    quality: WEBP_QUALITY,
+                    lossless: false,
+                    effort: WEBP_EFFORT,
+                    resolution: None,
+                })
+                .encode_to_bytes()
+        }
+        ThumbnailFormat::Jpeg => {
+            JpegImage::from_raw_parts(buffer.clone(), ImageMetadata::default())?.encode_to_bytes()
+        }
+    }
+}
+
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = tmp_path(path);
+    fs::write(&tmp, bytes).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })?;
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pixles_media::image::buffer::{ComponentType, PixelFormat};
+    use pixles_media::metadata::ColorSpace;
+    use tempfile::TempDir;
+
+    fn write_png(path: &Path, width: usize, height: usize) {
+        let data = [200u8, 40, 40].repeat(width * height);
+        let buffer = ImageBuffer::new(
+            data,
+            width,
+            height,
+            PixelFormat::Rgb,
+            ComponentType::U8,
+            ColorSpace::Srgb,
+        )
+        .unwrap();
+        let bytes = PngImage::from_raw_parts(buffer, ImageMetadata::default())
+            .unwrap()
+            .encode_to_bytes()
+            .unwrap();
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn test_generate_derivatives_writes_every_size() {
+        let tmp = TempDir::new().unwrap();
+        let media = tmp.path().join("photo.png");
+        write_png(&media, 600, 300);
+        let uuid = Uuid::now_v7();
+
+        let derivatives = generate_derivatives(tmp.path(), &uuid, &media, ThumbnailFormat::Webp)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(derivatives.paths.len(), ThumbnailSize::ALL.len());
+        for path in &derivatives.paths {
+            assert!(path.is_file(), "{} should exist", path.display());
+            assert!(!tmp_path(path).exists());
+        }
+        let xs = WebpImage::decode_from_bytes(&fs::read(&derivatives.paths[5]).unwrap()).unwrap();
+        assert_eq!(xs.get_buffer().width, 64);
+        assert_eq!(xs.get_buffer().height, 32);
+        let m = WebpImage::decode_from_bytes(&fs::read(&derivatives.paths[3]).unwrap()).unwrap();
+        assert_eq!(m.get_buffer().width, 512);
+        // L is larger than the source and is not upscaled
+        let l = WebpImage::decode_from_bytes(&fs::read(&derivatives.paths[2]).unwrap()).unwrap();
+        assert_eq!(l.get_buffer().width, 600);
+
+        assert!(!derivatives.chromahash.is_empty());
+        assert!(derivatives.dominant_color.starts_with('#'));
+        assert_eq!(derivatives.dominant_color.len(), 7);
+    }
+
+    #[test]
+    fn test_original_size_is_capped_for_webp() {
+        let tmp = TempDir::new().unwrap();
+        let media = tmp.path().join("panorama.png");
+        write_png(&media, 20_000, 200);
+
+        let derivatives =
+            generate_derivatives(tmp.path(), &Uuid::now_v7(), &media, ThumbnailFormat::Webp)
+                .unwrap()
+                .unwrap();
+
+        assert_eq!(derivatives.paths.len(), ThumbnailSize::ALL.len());
+        let o = WebpImage::decode_from_bytes(&fs::read(&derivatives.paths[0]).unwrap()).unwrap();
+        assert_eq!(o.get_buffer().width, WEBP_MAX_DIMENSION);
+    }
+
+    #[test]
+    fn test_failed_size_keeps_the_others() {
+        let tmp = TempDir::new().unwrap();
+        let media = tmp.path().join("photo.png");
+        write_png(&media, 100, 100);
+        let uuid = Uuid::now_v7();
+        // A directory in the way of the original-size file.
+        let blocked = thumbnail_path(tmp.path(), &uuid, ThumbnailSize::O).with_extension("jpg");
+        fs::create_dir_all(&blocked).unwrap();
+
+        let derivatives = generate_derivatives(tmp.path(), &uuid, &media, ThumbnailFormat::Jpeg)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(derivatives.paths.len(), ThumbnailSize::ALL.len() - 1);
+        assert!(!derivatives.paths.contains(&blocked));
+        assert!(derivatives.paths.iter().all(|p| p.is_file()));
+    }
+
+    #[test]
+    fn test_generate_derivatives_skips_undecodable_formats() {
+        let tmp = TempDir::new().unwrap();
+        let media = tmp.path().join("clip.mov");
+        fs::write(&media, b"not decoded").unwrap();
+        let result =
+            generate_derivatives(tmp.path(), &Uuid::now_v7(), &media, ThumbnailFormat::Jpeg)
+                .unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_remove_thumbnails() {
+        let tmp = TempDir::new().unwrap();
+        let media = tmp.path().join("photo.png");
+        write_png(&media, 20, 20);
+        let uuid = Uuid::now_v7();
+        let derivatives = generate_derivatives(tmp.path(), &uuid, &media, ThumbnailFormat::Jpeg)
+            .unwrap()
+            .unwrap();
+        assert!(has_thumbnails(tmp.path(), &uuid, ThumbnailFormat::Jpeg));
+
+        remove_thumbnails(tmp.path(), &uuid);
+        assert!(derivatives.paths.iter().all(|p| !p.exists()));
+    }
+}
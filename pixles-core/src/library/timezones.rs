@@ -0,0 +1,254 @@
+use std::path::Path;
+
+use chrono::DateTime;
+use uuid::Uuid;
+
+use crate::domain::CaptureTzSource;
+use crate::exif::{TZ_DB_VERSION, TimezoneResolution, resolve_gps_timezone};
+use crate::library::error::LibraryError;
+use crate::library::library::Library;
+use crate::library::paths::sidecar_path;
+use crate::sidecar::AssetSidecar;
+use crate::sidecar::io::read_sidecar;
+
+/// Outcome of [`Library::retz`].
+#[derive(Debug, Default)]
+pub struct RetzReport {
+    /// Assets whose time zone or UTC capture time changed.
+    pub changed: usize,
+    /// Assets that resolved as before; only their `tz_db_version` moved on.
+    pub unchanged: usize,
+    /// Assets left as they were because of an error (see log for details).
+    pub failed: usize,
+}
+
+impl Library {
+    /// Re-resolves the time zone of GPS-located assets with the bundled tz
+    /// database, as `pixles library retz` does after an upgrade brings in new
+    /// zone boundaries or DST rules.
+    ///
+    /// Assets already resolved with [`TZ_DB_VERSION`] are skipped unless
+    /// `all` is set. Floating assets with a GPS position are retried, in
+    /// case their position now falls inside a zone; offsets recorded by the
+    /// camera are never replaced.
+    pub fn retz(&self, all: bool) -> Result<RetzReport, LibraryError> {
+        let mut report = RetzReport::default();
+        let mut changed = Vec::new();
+
+        for row in self.db.list_assets()? {
+            let Ok(uuid) = Uuid::parse_str(&row.uuid) else {
+                log::warn!("retz: invalid uuid {}", row.uuid);
+                report.failed += 1;
+                continue;
+            };
+            let path = sidecar_path(&self.root, &uuid, "", row.capture_utc);
+            let original = match read_sidecar(&path) {
+                Ok(s) => s,
+                Err(e) => {
+                    log::warn!("retz: unreadable sidecar {}: {e}", path.display());
+                    report.failed += 1;
+                    continue;
+                }
+            };
+            if !needs_retz(&original, all) {
+                continue;
+            }
+            let Some(tz) = re_resolve(&original) else {
+                report.unchanged += 1;
+                continue;
+            };
+
+            let mut updated = original.clone();
+            updated.capture_utc = tz.capture_utc;
+            updated.capture_tz = tz.capture_tz;
+            updated.capture_tz_source = tz.capture_tz_source;
+            updated.tz_db_version = tz.tz_db_version;
+            if updated == original {
+                report.unchanged += 1;
+                continue;
+            }
+            let moved = (updated.capture_utc, &updated.capture_tz)
+                != (original.capture_utc, &original.capture_tz);
+            updated.modified_timestamp = now_secs();
+
+            match self.retz_one(uuid, &path, &original, updated) {
+                Ok(()) if moved => {
+                    report.changed += 1;
+                    changed.push(row.uuid);
+                }
+                Ok(()) => report.unchanged += 1,
+                Err(e) => {
+                    log::warn!("retz: {}: {e}", row.uuid);
+                    report.failed += 1;
+                }
+            }
+        }
+
+        self.refresh_auto_xmp(&changed);
+        Ok(report)
+    }
+
+    /// Refiles one asset and updates its index row, or puts it back.
+    fn retz_one(
+        &self,
+        uuid: Uuid,
+        path: &Path,
+        original: &AssetSidecar,
+        updated: AssetSidecar,
+    ) -> Result<(), LibraryError> {
+        let refiled = self.refile(uuid, path, original, updated)?;
+        match self
+            .db
+            .set_capture_times_batch(&[refiled.capture_columns()])
+        {
+            Ok(()) => {
+                refiled.finish();
+                Ok(())
+            }
+            Err(e) => {
+                refiled.undo();
+                Err(e.into())
+            }
+        }
+    }
+}
+
+/// Whether `s` has a GPS position and a zone that could change with the
+/// tz database: one looked up from the position, or none at all.
+fn needs_retz(s: &AssetSidecar, all: bool) -> bool {
+    let from_gps = match s.capture_tz_source {
+        Some(CaptureTzSource::GpsLookup) => true,
+        Some(CaptureTzSource::Floating) | None => false,
+        Some(CaptureTzSource::OffsetExif) => return false,
+    };
+    if s.gps_lat.is_none() || s.gps_lon.is_none() {
+        return false;
+    }
+    let up_to_date = from_gps
+        && s.tz_db_version.as_deref() == Some(TZ_DB_VERSION)
+        && (s.capture_utc.is_some() || s.capture_timestamp.is_none());
+    all || !up_to_date
+}
+
+fn re_resolve(s: &AssetSidecar) -> Option<TimezoneResolution> {
+    let local = s
+        .capture_timestamp
+        .and_then(|ts| DateTime::from_timestamp(ts, 0))
+        .map(|t| t.naive_utc());
+    resolve_gps_timezone(local, s.gps_lat?, s.gps_lon?)
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::executor::execute;
+    use crate::import::executor_cancellation::CancellationToken;
+    use crate::import::planner::{ImportConfig, plan};
+    use crate::import::scanner::scan;
+    use crate::library::init::init_library;
+    use crate::library::rebuild::media_file_for;
+    use crate::sidecar::io::write_sidecar;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Imports a photo without EXIF, then gives its sidecar a GPS position
+    /// in Kyoto and a local capture time, as an older importer that could
+    /// not apply zone rules would have written it.
+    fn import_located(lib: &Library) -> (Uuid, AssetSidecar) {
+        let src = TempDir::new().unwrap();
+        fs::write(src.path().join("kyoto.jpg"), b"pixels").unwrap();
+        let config = ImportConfig {
+            thumbnail_format: None,
+            max_workers: 1,
+            ..Default::default()
+        };
+        let plan_result = plan(
+            &scan(&[src.path().to_path_buf()]).unwrap(),
+            &lib.db,
+            &config,
+        )
+        .unwrap();
+        execute(
+            &plan_result,
+            lib,
+            &config,
+            |_| {},
+            &CancellationToken::new(),
+        )
+        .unwrap();
+
+        let row = lib.db.list_assets().unwrap().remove(0);
+        let uuid = Uuid::parse_str(&row.uuid).unwrap();
+        let path = sidecar_path(&lib.root, &uuid, "", row.capture_utc);
+        let mut sidecar = read_sidecar(&path).unwrap();
+        // 2024-07-15 10:30 local time.
+        sidecar.capture_timestamp = Some(1_721_039_400);
+        sidecar.capture_utc = None;
+        sidecar.capture_tz = Some("Asia/Tokyo".to_string());
+        sidecar.capture_tz_source = Some(CaptureTzSource::GpsLookup);
+        sidecar.tz_db_version = Some("2019c".to_string());
+        sidecar.gps_lat = Some(35.0116);
+        sidecar.gps_lon = Some(135.7681);
+        write_sidecar(&path, &sidecar).unwrap();
+        (uuid, sidecar)
+    }
+
+    #[test]
+    fn test_retz_applies_zone_rules_and_refiles() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let (uuid, before) = import_located(&lib);
+        let old_path = sidecar_path(&lib.root, &uuid, "", None);
+        let old_media = media_file_for(&old_path, &before).unwrap();
+
+        let report = lib.retz(false).unwrap();
+        assert_eq!((report.changed, report.unchanged, report.failed), (1, 0, 0));
+
+        // 10:30 JST = 01:30 UTC.
+        let expected_utc = 1_721_039_400 - 9 * 3600;
+        let row = lib.db.find_by_uuid(&uuid.to_string()).unwrap().unwrap();
+        assert_eq!(row.capture_utc, Some(expected_utc));
+        assert_eq!(row.capture_tz_source.as_deref(), Some("gps_lookup"));
+
+        let new_path = sidecar_path(&lib.root, &uuid, "", Some(expected_utc));
+        assert!(new_path.ends_with(format!("2024/2024-07/{}.cbor", uuid.simple())));
+        let after = read_sidecar(&new_path).unwrap();
+        assert_eq!(after.capture_utc, Some(expected_utc));
+        assert_eq!(after.tz_db_version.as_deref(), Some(TZ_DB_VERSION));
+        assert!(!old_path.exists());
+        assert!(!old_media.exists());
+        assert_eq!(
+            fs::read(media_file_for(&new_path, &after).unwrap()).unwrap(),
+            b"pixels"
+        );
+
+        // Already resolved with this database: nothing to do.
+        let report = lib.retz(false).unwrap();
+        assert_eq!((report.changed, report.unchanged, report.failed), (0, 0, 0));
+        let report = lib.retz(true).unwrap();
+        assert_eq!((report.changed, report.unchanged, report.failed), (0, 1, 0));
+    }
+
+    #[test]
+    fn test_retz_keeps_camera_offsets() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let (uuid, mut sidecar) = import_located(&lib);
+        sidecar.capture_tz = Some("+08:00".to_string());
+        sidecar.capture_tz_source = Some(CaptureTzSource::OffsetExif);
+        sidecar.tz_db_version = None;
+        let path = sidecar_path(&lib.root, &uuid, "", None);
+        write_sidecar(&path, &sidecar).unwrap();
+
+        let report = lib.retz(true).unwrap();
+        assert_eq!((report.changed, report.unchanged, report.failed), (0, 0, 0));
+        assert_eq!(read_sidecar(&path).unwrap(), sidecar);
+    }
+}
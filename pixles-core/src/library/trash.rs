@@ -9,7 +9,8 @@ use crate::sidecar::io::{read_sidecar, write_sidecar};
 ///
 /// 1. Update the DB row (`is_deleted = 1`, `deleted_at = now`).
 /// 2. Update the CBOR sidecar (`is_deleted = true`, `deleted_at = now`).
-/// 3. Move the media file to `.library/trash/{uuid}.{ext}`.
+/// 3. Move the media file to `.library/trash/{uuid}.{ext}`. An original
+///    imported by reference is not the library's to move and stays put.
 ///
 /// `media_path` must be the current absolute path of the media file.
 /// `sidecar_cbor_path` must be the current absolute path of the `.cbor` sidecar.
@@ -25,17 +26,19 @@ pub fn soft_delete(
     library.db.soft_delete(uuid, now)?;
 
     // 2. Sidecar
+    let mut referenced = false;
     if sidecar_cbor_path.exists() {
         let mut sidecar =
             read_sidecar(sidecar_cbor_path).map_err(|e| LibraryError::Cbor(e.to_string()))?;
         sidecar.is_deleted = true;
         sidecar.deleted_at = Some(now);
+        referenced = sidecar.reference_path.is_some();
         write_sidecar(sidecar_cbor_path, &sidecar)
             .map_err(|e| LibraryError::Cbor(e.to_string()))?;
     }
 
     // 3. Move media file to trash (use uuid without hyphens, matching paths::trash_path)
-    if media_path.exists() {
+    if !referenced && media_path.exists() {
         let ext = media_path.extension().unwrap_or_default().to_string_lossy();
         let uuid_plain = uuid.replace('-', "");
         let trash_file = library
@@ -139,6 +142,7 @@ mod tests {
             capture_tz: None,
             capture_tz_source: None,
             tz_db_version: None,
+            original_capture_timestamp: None,
             width: None,
             height: None,
             duration_ms: None,
@@ -149,6 +153,11 @@ mod tests {
             camera_model: None,
             gps_lat: None,
             gps_lon: None,
+            gps_source: None,
+            caption: None,
+            label: None,
+            pick: None,
+            reference_path: None,
             unknown_fields: BTreeMap::new(),
         }
     }
@@ -189,10 +198,17 @@ mod tests {
             is_stack_hidden: false,
             chromahash: None,
             dominant_color: None,
+            perceptual_hash: None,
             album_id: None,
             rating: 0,
             is_deleted: false,
             deleted_at: None,
+            original_filename: None,
+            camera_make: None,
+            camera_model: None,
+            gps_lat: None,
+            gps_lon: None,
+            caption: None,
         };
         lib.db.insert_asset(&row).unwrap();
 
@@ -239,10 +255,17 @@ mod tests {
             is_stack_hidden: false,
             chromahash: None,
             dominant_color: None,
+            perceptual_hash: None,
             album_id: None,
             rating: 0,
             is_deleted: true,
             deleted_at: Some(100), // far in the past
+            original_filename: None,
+            camera_make: None,
+            camera_model: None,
+            gps_lat: None,
+            gps_lon: None,
+            caption: None,
         };
         lib.db.insert_asset(&row).unwrap();
 
@@ -0,0 +1,579 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::library::error::LibraryError;
+use crate::library::library::Library;
+use crate::library::paths::trash_path;
+use crate::library::rebuild::{media_file_for, rebuild_index};
+use crate::sidecar::AssetSidecar;
+use crate::sidecar::io::{read_sidecar, write_sidecar};
+use crate::utils::hash::get_file_hash;
+
+/// A single problem found by [`verify_library`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyIssue {
+    /// The original no longer hashes to the sidecar's `hash_blake3`.
+    Corrupted {
+        uuid: String,
+        path: PathBuf,
+        expected: String,
+        actual: String,
+    },
+    /// The original's size differs from the sidecar's `file_size`.
+    SizeMismatch {
+        uuid: String,
+        path: PathBuf,
+        expected: u64,
+        actual: u64,
+    },
+    /// The original exists but could not be read.
+    UnreadableMedia { path: PathBuf, error: String },
+    /// A sidecar that could not be decoded.
+    UnreadableSidecar { path: PathBuf, error: String },
+    /// A file under `media/` with no sidecar beside it.
+    OrphanMedia { path: PathBuf },
+    /// A sidecar whose original is missing.
+    OrphanSidecar { uuid: String, path: PathBuf },
+    /// An original imported by reference is gone from its recorded path and
+    /// was not found near it.
+    MissingReference { uuid: String, path: PathBuf },
+    /// An original imported by reference was found, unchanged, at a new path.
+    MovedReference {
+        uuid: String,
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// A sidecar with no `assets` row.
+    MissingRow { uuid: String, sidecar: PathBuf },
+    /// An `assets` row that disagrees with its sidecar.
+    StaleRow { uuid: String },
+    /// An `assets` row with no sidecar on disk.
+    OrphanRow { uuid: String },
+}
+
+impl VerifyIssue {
+    /// Whether `--repair` fixes this issue. Only the index, and the path a
+    /// moved reference is tracked by, are repaired; originals are never
+    /// modified.
+    pub fn is_repairable(&self) -> bool {
+        matches!(
+            self,
+            VerifyIssue::MissingRow { .. }
+                | VerifyIssue::StaleRow { .. }
+                | VerifyIssue::OrphanRow { .. }
+                | VerifyIssue::MovedReference { .. }
+        )
+    }
+}
+
+impl fmt::Display for VerifyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyIssue::Corrupted {
+                path,
+                expected,
+                actual,
+                ..
+            } => write!(
+                f,
+                "corrupted: {} (expected {expected}, found {actual})",
+                path.display()
+            ),
+            VerifyIssue::SizeMismatch {
+                path,
+                expected,
+                actual,
+                ..
+            } => write!(
+                f,
+                "size mismatch: {} (expected {expected} bytes, found {actual})",
+                path.display()
+            ),
+            VerifyIssue::UnreadableMedia { path, error } => {
+                write!(f, "unreadable media: {}: {error}", path.display())
+            }
+            VerifyIssue::UnreadableSidecar { path, error } => {
+                write!(f, "unreadable sidecar: {}: {error}", path.display())
+            }
+            VerifyIssue::OrphanMedia { path } => {
+                write!(f, "media without sidecar: {}", path.display())
+            }
+            VerifyIssue::OrphanSidecar { uuid, path } => {
+                write!(f, "missing original for {uuid}: {}", path.display())
+            }
+            VerifyIssue::MissingReference { uuid, path } => {
+                write!(
+                    f,
+                    "referenced original missing for {uuid}: {}",
+                    path.display()
+                )
+            }
+            VerifyIssue::MovedReference { from, to, .. } => write!(
+                f,
+                "referenced original moved: {} -> {}",
+                from.display(),
+                to.display()
+            ),
+            VerifyIssue::MissingRow { uuid, .. } => write!(f, "not in index: {uuid}"),
+            VerifyIssue::StaleRow { uuid } => write!(f, "index out of date: {uuid}"),
+            VerifyIssue::OrphanRow { uuid } => write!(f, "indexed but not on disk: {uuid}"),
+        }
+    }
+}
+
+/// Result of [`verify_library`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VerifyReport {
+    /// Originals whose hash was checked.
+    pub checked: usize,
+    pub issues: Vec<VerifyIssue>,
+    /// Whether the index was re-synced from the sidecars.
+    pub repaired: bool,
+}
+
+impl VerifyReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Issues left after a repair (all of them if no repair was made).
+    pub fn unrepaired(&self) -> impl Iterator<Item = &VerifyIssue> {
+        self.issues
+            .iter()
+            .filter(|i| !(self.repaired && i.is_repairable()))
+    }
+}
+
+/// Re-hash every original under `media/` (and `.library/trash/` for deleted
+/// assets) against its sidecar, and cross-check the `assets` table.
+///
+/// Originals imported by reference are checked at their recorded path. One
+/// that is gone is looked for, by size and hash, under the directory two
+/// levels above where it was, which covers renamed and reorganised folders.
+///
+/// With `repair`, an index that disagrees with the sidecars is re-synced
+/// from them: rows are upserted via [`rebuild_index`] and rows without a
+/// sidecar are removed. The sidecar of a moved reference is pointed at its
+/// new path first. Originals are only ever read.
+pub fn verify_library(library: &Library, repair: bool) -> Result<VerifyReport, LibraryError> {
+    let root = &library.root;
+    let mut report = VerifyReport::default();
+
+    // Sidecars and everything else under `media/`.
+    let mut sidecars = Vec::new();
+    let mut media = HashSet::new();
+    let media_dir = root.join("media");
+    if media_dir.exists() {
+        for entry in WalkDir::new(&media_dir).into_iter().filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("tmp") => {}
+                Some("cbor") => sidecars.push(path.to_path_buf()),
+                _ => {
+                    media.insert(path.to_path_buf());
+                }
+            }
+        }
+    }
+    sidecars.sort();
+
+    let mut on_disk = HashSet::new();
+    let mut claimed = HashSet::new();
+    let mut missing_references = Vec::new();
+    for sidecar_path in &sidecars {
+        let sidecar = match read_sidecar(sidecar_path) {
+            Ok(s) => s,
+            Err(e) => {
+                // The original beside it is accounted for, just unverifiable.
+                claimed.extend(media.iter().filter(|m| same_stem(m, sidecar_path)).cloned());
+                report.issues.push(VerifyIssue::UnreadableSidecar {
+                    path: sidecar_path.clone(),
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        match original_path(root, sidecar_path, &sidecar) {
+            Some(original) if original.is_file() => {
+                claimed.insert(original.clone());
+                report.checked += 1;
+                if let Some(issue) = check_original(&original, &sidecar) {
+                    report.issues.push(issue);
+                }
+            }
+            Some(original) if sidecar.reference_path.is_some() => {
+                missing_references.push((sidecar_path.clone(), sidecar.clone(), original));
+            }
+            _ => report.issues.push(VerifyIssue::OrphanSidecar {
+                uuid: sidecar.uuid.clone(),
+                path: sidecar_path.clone(),
+            }),
+        }
+
+        match library.db.find_by_uuid(&sidecar.uuid)? {
+            None => report.issues.push(VerifyIssue::MissingRow {
+                uuid: sidecar.uuid.clone(),
+                sidecar: sidecar_path.clone(),
+            }),
+            Some(row)
+                if row.hash_blake3 != sidecar.hash_blake3
+                    || row.is_deleted != sidecar.is_deleted
+                    || row.rating != i64::from(sidecar.rating) =>
+            {
+                report.issues.push(VerifyIssue::StaleRow {
+                    uuid: sidecar.uuid.clone(),
+                });
+            }
+            Some(_) => {}
+        }
+        on_disk.insert(sidecar.uuid);
+    }
+
+    let mut listings = HashMap::new();
+    let mut moved = Vec::new();
+    for (sidecar_path, sidecar, from) in missing_references {
+        match find_moved(&from, &sidecar, &claimed, &mut listings) {
+            Some(to) => {
+                claimed.insert(to.clone());
+                report.checked += 1;
+                report.issues.push(VerifyIssue::MovedReference {
+                    uuid: sidecar.uuid.clone(),
+                    from,
+                    to: to.clone(),
+                });
+                moved.push((sidecar_path, sidecar, to));
+            }
+            None => report.issues.push(VerifyIssue::MissingReference {
+                uuid: sidecar.uuid,
+                path: from,
+            }),
+        }
+    }
+
+    let mut orphans: Vec<_> = media.difference(&claimed).cloned().collect();
+    orphans.sort();
+    report.issues.extend(
+        orphans
+            .into_iter()
+            .map(|path| VerifyIssue::OrphanMedia { path }),
+    );
+
+    let orphan_rows: Vec<String> = library
+        .db
+        .list_asset_uuids()?
+        .into_iter()
+        .filter(|uuid| !on_disk.contains(uuid))
+        .collect();
+    report.issues.extend(
+        orphan_rows
+            .iter()
+            .map(|uuid| VerifyIssue::OrphanRow { uuid: uuid.clone() }),
+    );
+
+    if repair && report.issues.iter().any(VerifyIssue::is_repairable) {
+        for (sidecar_path, mut sidecar, to) in moved {
+            let Some(to) = to.to_str() else {
+                continue;
+            };
+            sidecar.reference_path = Some(to.to_string());
+            write_sidecar(&sidecar_path, &sidecar)
+                .map_err(|e| LibraryError::Cbor(e.to_string()))?;
+        }
+        for uuid in &orphan_rows {
+            library.db.delete_asset(uuid)?;
+        }
+        rebuild_index(library)?;
+        report.repaired = true;
+    }
+
+    Ok(report)
+}
+
+/// Where the original should be: beside the sidecar, or in the trash once
+/// the asset is deleted. A referenced original stays at its own path.
+fn original_path(root: &Path, sidecar_path: &Path, sidecar: &AssetSidecar) -> Option<PathBuf> {
+    let in_media = media_file_for(sidecar_path, sidecar)?;
+    if !sidecar.is_deleted || sidecar.reference_path.is_some() {
+        return Some(in_media);
+    }
+    let uuid = Uuid::parse_str(&sidecar.uuid).ok()?;
+    let ext = in_media.extension()?.to_string_lossy();
+    Some(trash_path(root, &uuid, &ext))
+}
+
+fn check_original(path: &Path, sidecar: &AssetSidecar) -> Option<VerifyIssue> {
+    let unreadable = |e: std::io::Error| VerifyIssue::UnreadableMedia {
+        path: path.to_path_buf(),
+        error: e.to_string(),
+    };
+
+    let size = match path.metadata() {
+        Ok(m) => m.len(),
+        Err(e) => return Some(unreadable(e)),
+    };
+    if size != sidecar.file_size {
+        return Some(VerifyIssue::SizeMismatch {
+            uuid: sidecar.uuid.clone(),
+            path: path.to_path_buf(),
+            expected: sidecar.file_size,
+            actual: size,
+        });
+    }
+
+    match get_file_hash(path) {
+        Ok(hash) if hash == sidecar.hash_blake3 => None,
+        Ok(hash) => Some(VerifyIssue::Corrupted {
+            uuid: sidecar.uuid.clone(),
+            path: path.to_path_buf(),
+            expected: sidecar.hash_blake3.clone(),
+            actual: hash,
+        }),
+        Err(e) => Some(unreadable(e)),
+    }
+}
+
+/// Looks for a referenced original that left `from`: a file with the
+/// sidecar's size and hash under the directory two levels up, preferring
+/// one that kept its name. Files in `claimed` belong to other assets.
+///
+/// `listings` caches each searched directory's files and sizes, as the
+/// originals of a renamed folder all share one.
+fn find_moved(
+    from: &Path,
+    sidecar: &AssetSidecar,
+    claimed: &HashSet<PathBuf>,
+    listings: &mut HashMap<PathBuf, Vec<(PathBuf, u64)>>,
+) -> Option<PathBuf> {
+    let search_root = from.parent()?.parent()?;
+    // Never walk a whole filesystem.
+    if search_root.parent().is_none() || !search_root.is_dir() {
+        return None;
+    }
+    let files = listings
+        .entry(search_root.to_path_buf())
+        .or_insert_with(|| {
+            WalkDir::new(search_root)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .filter_map(|e| Some((e.path().to_path_buf(), e.metadata().ok()?.len())))
+                .collect()
+        });
+
+    let mut candidates: Vec<&PathBuf> = files
+        .iter()
+        .filter(|(path, size)| *size == sidecar.file_size && !claimed.contains(path))
+        .map(|(path, _)| path)
+        .collect();
+    candidates.sort_by_key(|path| (path.file_name() != from.file_name(), path.to_path_buf()));
+    candidates
+        .into_iter()
+        .find(|path| get_file_hash(path).is_ok_and(|h| h == sidecar.hash_blake3))
+        .cloned()
+}
+
+fn same_stem(a: &Path, b: &Path) -> bool {
+    a.parent() == b.parent() && a.file_stem() == b.file_stem()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ImportMode;
+    use crate::import::executor::execute;
+    use crate::import::executor_cancellation::CancellationToken;
+    use crate::import::planner::{ImportConfig, plan};
+    use crate::import::scanner::scan;
+    use crate::library::init::init_library;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Imports one file and returns the library with its original's path.
+    fn library_with_one_asset(lib_dir: &TempDir) -> (Library, PathBuf) {
+        let src = TempDir::new().unwrap();
+        fs::write(src.path().join("photo.jpg"), b"original bytes").unwrap();
+
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let config = ImportConfig {
+            thumbnail_format: None,
+            ..Default::default()
+        };
+        let plan_result = plan(
+            &scan(&[src.path().to_path_buf()]).unwrap(),
+            &lib.db,
+            &config,
+        )
+        .unwrap();
+        execute(
+            &plan_result,
+            &lib,
+            &config,
+            |_| {},
+            &CancellationToken::new(),
+        )
+        .unwrap();
+
+        let original = WalkDir::new(lib.root.join("media"))
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .map(|e| e.into_path())
+            .find(|p| p.extension().is_some_and(|e| e == "jpg"))
+            .unwrap();
+        (lib, original)
+    }
+
+    #[test]
+    fn test_clean_library() {
+        let lib_dir = TempDir::new().unwrap();
+        let (lib, _) = library_with_one_asset(&lib_dir);
+        let report = verify_library(&lib, false).unwrap();
+        assert_eq!(report.checked, 1);
+        assert!(report.is_clean(), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn test_detects_bit_rot() {
+        let lib_dir = TempDir::new().unwrap();
+        let (lib, original) = library_with_one_asset(&lib_dir);
+        fs::write(&original, b"original bytez").unwrap();
+
+        let report = verify_library(&lib, false).unwrap();
+        assert!(matches!(report.issues[..], [VerifyIssue::Corrupted { .. }]));
+
+        fs::write(&original, b"short").unwrap();
+        let report = verify_library(&lib, false).unwrap();
+        assert!(matches!(
+            report.issues[..],
+            [VerifyIssue::SizeMismatch {
+                expected: 14,
+                actual: 5,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_detects_orphans() {
+        let lib_dir = TempDir::new().unwrap();
+        let (lib, original) = library_with_one_asset(&lib_dir);
+        let stray = original.with_file_name("stray.png");
+        fs::write(&stray, b"no sidecar").unwrap();
+        fs::remove_file(&original).unwrap();
+
+        let report = verify_library(&lib, false).unwrap();
+        assert_eq!(report.checked, 0);
+        assert!(
+            report
+                .issues
+                .contains(&VerifyIssue::OrphanMedia { path: stray })
+        );
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|i| matches!(i, VerifyIssue::OrphanSidecar { .. }))
+        );
+    }
+
+    #[test]
+    fn test_repair_resyncs_index() {
+        let lib_dir = TempDir::new().unwrap();
+        let (lib, original) = library_with_one_asset(&lib_dir);
+        let sidecar_path = original.with_extension("cbor");
+        let mut sidecar = read_sidecar(&sidecar_path).unwrap();
+        sidecar.rating = 4;
+        write_sidecar(&sidecar_path, &sidecar).unwrap();
+
+        // The index drifts: the real row goes missing and a phantom appears.
+        let mut phantom = lib.db.find_by_uuid(&sidecar.uuid).unwrap().unwrap();
+        phantom.uuid = "phantom".to_string();
+        lib.db.insert_asset(&phantom).unwrap();
+        lib.db.delete_asset(&sidecar.uuid).unwrap();
+
+        let report = verify_library(&lib, true).unwrap();
+        assert!(report.repaired);
+        assert!(report.issues.contains(&VerifyIssue::OrphanRow {
+            uuid: "phantom".to_string()
+        }));
+        assert_eq!(report.unrepaired().count(), 0);
+
+        let after = verify_library(&lib, false).unwrap();
+        assert!(after.is_clean(), "{:?}", after.issues);
+        assert_eq!(
+            lib.db.find_by_uuid(&sidecar.uuid).unwrap().unwrap().rating,
+            4
+        );
+    }
+
+    #[test]
+    fn test_detects_moved_and_missing_references() {
+        let src = TempDir::new().unwrap();
+        let trip = src.path().join("2024/trip");
+        fs::create_dir_all(&trip).unwrap();
+        fs::write(trip.join("a.jpg"), b"first original").unwrap();
+        fs::write(trip.join("b.jpg"), b"second original").unwrap();
+
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        let config = ImportConfig {
+            import_mode: ImportMode::Reference,
+            thumbnail_format: None,
+            ..Default::default()
+        };
+        let plan_result = plan(
+            &scan(&[src.path().to_path_buf()]).unwrap(),
+            &lib.db,
+            &config,
+        )
+        .unwrap();
+        execute(
+            &plan_result,
+            &lib,
+            &config,
+            |_| {},
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        let report = verify_library(&lib, false).unwrap();
+        assert_eq!(report.checked, 2);
+        assert!(report.is_clean(), "{:?}", report.issues);
+
+        // The folder is renamed, and one of its files is then deleted.
+        let kyoto = src.path().join("2024/kyoto");
+        fs::rename(&trip, &kyoto).unwrap();
+        fs::remove_file(kyoto.join("b.jpg")).unwrap();
+
+        let report = verify_library(&lib, true).unwrap();
+        let to = fs::canonicalize(kyoto.join("a.jpg")).unwrap();
+        assert!(
+            report.issues.iter().any(
+                |i| matches!(i, VerifyIssue::MovedReference { to: moved, .. } if *moved == to)
+            )
+        );
+        assert!(report.issues.iter().any(|i| matches!(
+            i,
+            VerifyIssue::MissingReference { path, .. } if path.ends_with("trip/b.jpg")
+        )));
+        assert!(
+            !report
+                .issues
+                .iter()
+                .any(|i| matches!(i, VerifyIssue::OrphanSidecar { .. }))
+        );
+
+        // The repair points the sidecar at the new path.
+        let after = verify_library(&lib, false).unwrap();
+        assert!(matches!(
+            after.issues[..],
+            [VerifyIssue::MissingReference { .. }]
+        ));
+    }
+}
@@ -0,0 +1,388 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, FixedOffset};
+use pixles_media::metadata::xmp::{
+    XmpBasic, XmpData, XmpDublinCore, XmpDynamicMedia, XmpExif, XmpLightroom,
+};
+use uuid::Uuid;
+
+use crate::db::rows::AssetRow;
+use crate::domain::tag::{TAG_SEPARATOR, dedup};
+use crate::domain::{Pick, XmpNaming};
+use crate::import::group::is_xmp;
+use crate::library::error::LibraryError;
+use crate::library::library::Library;
+use crate::library::paths::{sidecar_path, tmp_path};
+use crate::library::rebuild::media_file_for;
+use crate::sidecar::AssetSidecar;
+use crate::sidecar::io::read_sidecar;
+use crate::utils::hash::get_file_hash;
+
+/// Outcome of [`Library::export_xmp`].
+#[derive(Debug, Default)]
+pub struct XmpExportReport {
+    /// `.xmp` files written.
+    pub written: usize,
+    /// Originals copied into the export directory.
+    pub copied: usize,
+    /// Assets left out, with the reason.
+    pub skipped: Vec<(String, String)>,
+}
+
+impl Library {
+    /// Writes every asset's original (other than imported sidecar files such
+    /// as `.xmp`) into `dest`, unless an identical copy
+    /// is already there, and renders its curation into an `.xmp` beside it.
+    ///
+    /// An original whose filename is taken by a different file is exported
+    /// as `{stem}-{uuid tail}.{ext}`. Stack primaries are exported first,
+    /// so with [`XmpNaming::Stem`] they keep `IMG_1.xmp` when a RAW+JPEG
+    /// pair would otherwise share it.
+    pub fn export_xmp(
+        &self,
+        dest: &Path,
+        naming: XmpNaming,
+    ) -> Result<XmpExportReport, LibraryError> {
+        fs::create_dir_all(dest)?;
+        let mut rows: Vec<AssetRow> = self
+            .db
+            .list_assets()?
+            .into_iter()
+            .filter(|r| !r.is_deleted && !is_sidecar_file(r))
+            .collect();
+        rows.sort_by(|a, b| (a.is_stack_hidden, &a.uuid).cmp(&(b.is_stack_hidden, &b.uuid)));
+        Ok(self.export_rows(&rows, dest, naming))
+    }
+
+    /// Re-renders the `.xmp` of `uuids` into the automatic export directory,
+    /// if one is configured. Failures are logged, not returned: the edit
+    /// that triggered the refresh has already been committed.
+    pub(crate) fn refresh_auto_xmp(&self, uuids: &[String]) {
+        let Some(target) = &self.config().auto_xmp_export else {
+            return;
+        };
+        let mut rows = Vec::new();
+        for uuid in uuids {
+            match self.db.find_by_uuid(uuid) {
+                Ok(Some(row)) if !row.is_deleted && !is_sidecar_file(&row) => rows.push(row),
+                Ok(_) => {}
+                Err(e) => log::warn!("refresh_auto_xmp: {uuid}: {e}"),
+            }
+        }
+        let report = self.export_rows(&rows, &target.dir, target.naming);
+        for (uuid, reason) in &report.skipped {
+            log::warn!("refresh_auto_xmp: {uuid}: {reason}");
+        }
+    }
+
+    fn export_rows(&self, rows: &[AssetRow], dest: &Path, naming: XmpNaming) -> XmpExportReport {
+        let mut report = XmpExportReport::default();
+        let mut claimed = HashSet::new();
+        for row in rows {
+            match self.export_one(row, dest, naming, &mut claimed) {
+                Ok(copied) => {
+                    report.written += 1;
+                    report.copied += usize::from(copied);
+                }
+                Err(reason) => report.skipped.push((row.uuid.clone(), reason)),
+            }
+        }
+        report
+    }
+
+    /// Exports one asset. Returns whether its original had to be copied.
+    fn export_one(
+        &self,
+        row: &AssetRow,
+        dest: &Path,
+        naming: XmpNaming,
+        claimed: &mut HashSet<PathBuf>,
+    ) -> Result<bool, String> {
+        let uuid = Uuid::parse_str(&row.uuid).map_err(|e| e.to_string())?;
+        let sidecar_file = sidecar_path(&self.root, &uuid, "", row.capture_utc);
+        let sidecar = read_sidecar(&sidecar_file).map_err(|e| format!("sidecar: {e}"))?;
+        let media = media_file_for(&sidecar_file, &sidecar)
+            .filter(|m| m.exists())
+            .ok_or("original is missing")?;
+
+        let (original, copied) = place_original(&media, &sidecar, &uuid, dest)?;
+        let xmp_path = naming.sidecar_for(&original);
+        if !claimed.insert(xmp_path.clone()) {
+            return Err(format!(
+                "{} was already written for another asset",
+                xmp_path.display()
+            ));
+        }
+        write_atomically(&xmp_path, xmp_for_sidecar(&sidecar).to_xml().as_bytes())
+            .map_err(|e| format!("{}: {e}", xmp_path.display()))?;
+        Ok(copied)
+    }
+}
+
+/// Renders the curation recorded in a sidecar as XMP: rating, pick flag,
+/// color label, caption, tags, capture time and GPS position.
+///
+/// Tags go to `lr:hierarchicalSubject` whole and to `dc:subject` one level
+/// at a time, the way Lightroom writes keywords.
+pub fn xmp_for_sidecar(s: &AssetSidecar) -> XmpData {
+    let subject = dedup(
+        s.tags
+            .iter()
+            .flat_map(|t| t.split(TAG_SEPARATOR))
+            .map(str::to_string),
+    );
+    let dublin_core = (s.caption.is_some() || !subject.is_empty()).then(|| XmpDublinCore {
+        description: s
+            .caption
+            .clone()
+            .map(|c| HashMap::from([("x-default".to_string(), c)])),
+        subject,
+        ..Default::default()
+    });
+
+    let rejected = s.pick == Some(Pick::Rejected);
+    let captured = capture_time(s);
+    let modified = DateTime::from_timestamp(s.modified_timestamp, 0)
+        .map(|t| t.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+    let has_gps = s.gps_lat.is_some() && s.gps_lon.is_some();
+
+    XmpData {
+        dublin_core,
+        basic: Some(XmpBasic {
+            rating: Some(if rejected { -1 } else { s.rating.min(5) as i8 }),
+            label: s.label.clone(),
+            create_date: captured.clone(),
+            modify_date: modified,
+        }),
+        dynamic_media: s.pick.map(|pick| XmpDynamicMedia {
+            pick: Some(if pick == Pick::Picked { 1 } else { -1 }),
+        }),
+        exif: (captured.is_some() || has_gps).then(|| XmpExif {
+            date_time_original: captured,
+            gps_latitude: s.gps_lat.filter(|_| has_gps),
+            gps_longitude: s.gps_lon.filter(|_| has_gps),
+        }),
+        lightroom: (!s.tags.is_empty()).then(|| XmpLightroom {
+            hierarchical_subject: s.tags.clone(),
+        }),
+        ..Default::default()
+    }
+}
+
+/// Local capture time in ISO 8601, with its UTC offset when the capture
+/// time zone is known.
+fn capture_time(s: &AssetSidecar) -> Option<String> {
+    let local = DateTime::from_timestamp(s.capture_timestamp?, 0)?.naive_utc();
+    let offset = s
+        .capture_utc
+        .and_then(|utc| i32::try_from(s.capture_timestamp? - utc).ok())
+        .and_then(FixedOffset::east_opt);
+    Some(
+        match offset.and_then(|o| local.and_local_timezone(o).single()) {
+            Some(zoned) => zoned.format("%Y-%m-%dT%H:%M:%S%:z").to_string(),
+            None => local.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        },
+    )
+}
+
+/// Finds or makes the copy of `media` in `dest`, trying the original
+/// filename first. Returns its path and whether it was copied now.
+fn place_original(
+    media: &Path,
+    s: &AssetSidecar,
+    uuid: &Uuid,
+    dest: &Path,
+) -> Result<(PathBuf, bool), String> {
+    let original = Path::new(&s.original_filename);
+    let stem = original.file_stem().unwrap_or_default().to_string_lossy();
+    // The tail of a v7 UUID is random; its head is a timestamp.
+    let suffix = &uuid.simple().to_string()[24..];
+    let fallback = match original.extension() {
+        Some(ext) => format!("{stem}-{suffix}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{suffix}"),
+    };
+
+    for name in [s.original_filename.clone(), fallback] {
+        let path = dest.join(&name);
+        if !path.exists() {
+            let tmp = tmp_path(&path);
+            fs::copy(media, &tmp)
+                .and_then(|_| fs::rename(&tmp, &path))
+                .map_err(|e| {
+                    let _ = fs::remove_file(&tmp);
+                    format!("copying to {}: {e}", path.display())
+                })?;
+            return Ok((path, true));
+        }
+        let same_size = fs::metadata(&path).is_ok_and(|m| m.len() == s.file_size);
+        if same_size && get_file_hash(&path).is_ok_and(|h| h == s.hash_blake3) {
+            return Ok((path, false));
+        }
+    }
+    Err(format!("{} is taken by another file", s.original_filename))
+}
+
+/// Whether the asset is itself a sidecar, like an `.xmp` imported next to
+/// its RAW, rather than media.
+pub(crate) fn is_sidecar_file(row: &AssetRow) -> bool {
+    let ext = row
+        .original_filename
+        .as_deref()
+        .and_then(|name| Path::new(name).extension())
+        .unwrap_or_default();
+    row.asset_type == "sidecar" || is_xmp(&ext.to_string_lossy())
+}
+
+fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let tmp = tmp_path(path);
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::XmpExportTarget;
+    use crate::exif::annotations::read_xmp_sidecar;
+    use crate::import::executor::execute;
+    use crate::import::executor_cancellation::CancellationToken;
+    use crate::import::planner::{ImportConfig, plan};
+    use crate::import::scanner::scan;
+    use crate::library::init::init_library;
+    use tempfile::TempDir;
+
+    fn import(lib: &Library, files: &[(&str, &[u8])]) {
+        let src = TempDir::new().unwrap();
+        for (name, bytes) in files {
+            fs::write(src.path().join(name), bytes).unwrap();
+        }
+        let config = ImportConfig {
+            thumbnail_format: None,
+            ..Default::default()
+        };
+        let plan_result = plan(
+            &scan(&[src.path().to_path_buf()]).unwrap(),
+            &lib.db,
+            &config,
+        )
+        .unwrap();
+        execute(
+            &plan_result,
+            lib,
+            &config,
+            |_| {},
+            &CancellationToken::new(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_xmp_for_sidecar_maps_curation() {
+        let lib_dir = TempDir::new().unwrap();
+        let lib = init_library(lib_dir.path(), "Test").unwrap();
+        import(&lib, &[("a.jpg", b"a")]);
+        let row = lib.db.list_assets().unwrap().remove(0);
+        let uuid = Uuid::parse_str(&row.uuid).unwrap();
+        let mut s = read_sidecar(&sidecar_path(&lib.root, &uuid, "", row.capture_utc)).unwrap();
+
+        s.rating = 3;
+        s.pick = Some(Pick::Rejected);
+        s.caption = Some("Dusk".to_string());
+        s.tags = vec!["Places|Japan|Kyoto".to_string(), "Japan".to_string()];
+        s.capture_timestamp = Some(1_711_963_800); // 2024-04-01 09:30 local
+        s.capture_utc = Some(1_711_963_800 - 9 * 3600);
+        s.gps_lat = Some(34.9671);
+        s.gps_lon = Some(135.7727);
+
+        let xmp = xmp_for_sidecar(&s);
+        assert_eq!(xmp.basic.as_ref().unwrap().rating, Some(-1));
+        assert_eq!(xmp.dynamic_media.as_ref().unwrap().pick, Some(-1));
+        let dc = xmp.dublin_core.as_ref().unwrap();
+        assert_eq!(dc.subject, vec!["Places", "Japan", "Kyoto"]);
+        assert_eq!(dc.description.as_ref().unwrap()["x-default"], "Dusk");
+        let exif = xmp.exif.as_ref().unwrap();
+        assert_eq!(
+            exif.date_time_original.as_deref(),
+            Some("2024-04-01T09:30:00+09:00")
+        );
+
+        // What we write reads back as the same curation.
+        let path = lib_dir.path().join("check.xmp");
+        fs::write(&path, xmp.to_xml()).unwrap();
+        let back = read_xmp_sidecar(&path).unwrap();
+        assert_eq!(back.rating, Some(0));
+        assert_eq!(back.pick, Some(Pick::Rejected));
+        assert_eq!(back.caption.as_deref(), Some("Dusk"));
+        assert_eq!(back.tags, s.tags);
+    }
+
+    #[test]
+    fn test_export_writes_originals_and_sidecars() {
+        let lib_dir = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        let mut lib = init_library(lib_dir.path(), "Test").unwrap();
+        import(&lib, &[("IMG_1.jpg", b"jpeg"), ("IMG_1.ARW", b"raw")]);
+
+        let report = lib.export_xmp(dest.path(), XmpNaming::Filename).unwrap();
+        assert_eq!((report.written, report.copied), (2, 2));
+        assert!(report.skipped.is_empty());
+        assert_eq!(fs::read(dest.path().join("IMG_1.ARW")).unwrap(), b"raw");
+        assert!(dest.path().join("IMG_1.ARW.xmp").exists());
+        assert!(dest.path().join("IMG_1.jpg.xmp").exists());
+
+        // Re-running reuses the copies; stem naming lets the primary win.
+        let report = lib.export_xmp(dest.path(), XmpNaming::Stem).unwrap();
+        assert_eq!((report.written, report.copied), (1, 0));
+        assert_eq!(report.skipped.len(), 1);
+
+        // A different file under the same name is exported beside it.
+        fs::write(dest.path().join("IMG_1.ARW"), b"someone else's").unwrap();
+        let report = lib.export_xmp(dest.path(), XmpNaming::Filename).unwrap();
+        assert_eq!(report.copied, 1);
+
+        // Automatic mode refreshes the sidecar after a tag edit.
+        lib.set_auto_xmp_export(Some(XmpExportTarget {
+            dir: dest.path().to_path_buf(),
+            naming: XmpNaming::Filename,
+        }))
+        .unwrap();
+        let jpeg = lib
+            .db
+            .list_assets()
+            .unwrap()
+            .into_iter()
+            .find(|r| r.original_filename.as_deref() == Some("IMG_1.jpg"))
+            .unwrap();
+        lib.add_tags(&[jpeg.uuid], &["Kyoto".to_string()]).unwrap();
+        let back = read_xmp_sidecar(&dest.path().join("IMG_1.jpg.xmp")).unwrap();
+        assert_eq!(back.tags, vec!["Kyoto"]);
+    }
+
+    #[test]
+    fn test_auto_export_follows_imports() {
+        let lib_dir = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        let mut lib = init_library(lib_dir.path(), "Test").unwrap();
+        lib.set_auto_xmp_export(Some(XmpExportTarget {
+            dir: dest.path().to_path_buf(),
+            naming: XmpNaming::Filename,
+        }))
+        .unwrap();
+
+        // The primary of each import is exported; a lone .xmp is not.
+        import(
+            &lib,
+            &[
+                ("IMG_1.jpg", b"jpeg"),
+                ("IMG_1.ARW", b"raw"),
+                ("notes.xmp", b"x"),
+            ],
+        );
+        assert!(dest.path().join("IMG_1.jpg").exists());
+        assert!(dest.path().join("IMG_1.jpg.xmp").exists());
+        assert!(!dest.path().join("notes.xmp.xmp").exists());
+        assert!(!dest.path().join("notes.xmp").exists());
+    }
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+/// On-disk form of an album, stored at `albums/{id}.cbor`. The index's
+/// `albums` and `album_assets` tables are rebuilt from these files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlbumCbor {
+    pub version: u8,
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub cover_asset_id: Option<String>,
+    pub created_at: i64,
+    pub modified_at: i64,
+    /// Members in album order.
+    #[serde(default)]
+    pub assets: Vec<AlbumMember>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlbumMember {
+    pub uuid: String,
+    pub added_at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let album = AlbumCbor {
+            version: 1,
+            id: "01956ef3-1234-7abc-9def-123456789abc".to_string(),
+            name: "Kyoto 2024".to_string(),
+            description: Some("Spring trip".to_string()),
+            cover_asset_id: Some("b".to_string()),
+            created_at: 1720000000,
+            modified_at: 1720000100,
+            assets: vec![
+                AlbumMember {
+                    uuid: "b".to_string(),
+                    added_at: 1720000000,
+                },
+                AlbumMember {
+                    uuid: "a".to_string(),
+                    added_at: 1720000100,
+                },
+            ],
+        };
+        let mut buf = vec![];
+        ciborium::ser::into_writer(&album, &mut buf).unwrap();
+        let back: AlbumCbor = ciborium::de::from_reader(buf.as_slice()).unwrap();
+        assert_eq!(album, back);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::domain::{CaptureTzSource, ImportMode};
+use crate::domain::{CaptureTzSource, GpsSource, ImportMode, Pick};
 use crate::metadata::AssetType;
 use crate::sidecar::StackHint;
 use ciborium::value::Value;
@@ -10,6 +10,8 @@ use std::collections::BTreeMap;
 #[derive(Debug, Clone, PartialEq)]
 pub struct AssetSidecar {
     // Required fields
+    /// Library format version the sidecar was written under; older ones are
+    /// upgraded by `library::migrate` when the library is opened.
     pub version: u8,
     pub uuid: String,
     pub asset_type: AssetType,
@@ -31,16 +33,32 @@ pub struct AssetSidecar {
     pub capture_tz: Option<String>,
     pub capture_tz_source: Option<CaptureTzSource>,
     pub tz_db_version: Option<String>,
+    /// `capture_timestamp` as first imported, kept once a time shift has
+    /// corrected it. Absent for assets never shifted.
+    pub original_capture_timestamp: Option<i64>,
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub duration_ms: Option<u64>,
     pub stack_hint: Option<StackHint>,
+    /// Single album set at import by older builds. Membership now lives in
+    /// `albums/*.cbor`; this is only read by the index rebuild, which turns
+    /// it into an album of its own.
     pub album_id: Option<String>,
     pub deleted_at: Option<i64>,
     pub camera_make: Option<String>,
     pub camera_model: Option<String>,
     pub gps_lat: Option<f64>,
     pub gps_lon: Option<f64>,
+    /// Where `gps_lat`/`gps_lon` came from. Absent in sidecars written
+    /// before track-log geotagging, whose positions are all from EXIF.
+    pub gps_source: Option<GpsSource>,
+    pub caption: Option<String>,
+    /// Color label name (`Red`, `Yellow`, ...), as Lightroom writes `xmp:Label`.
+    pub label: Option<String>,
+    pub pick: Option<Pick>,
+    /// Absolute path of an original imported in `reference` mode, which
+    /// stays where it was instead of being copied under `media/`.
+    pub reference_path: Option<String>,
 
     /// Unknown fields preserved for forward compatibility.
     pub unknown_fields: BTreeMap<String, Value>,
@@ -100,6 +118,10 @@ impl Serialize for AssetSidecar {
         insert_opt!("capture_tz", self.capture_tz);
         insert_opt!("capture_tz_source", self.capture_tz_source);
         insert_opt!("tz_db_version", self.tz_db_version);
+        insert_opt!(
+            "original_capture_timestamp",
+            self.original_capture_timestamp
+        );
         insert_opt!("width", self.width);
         insert_opt!("height", self.height);
         insert_opt!("duration_ms", self.duration_ms);
@@ -110,6 +132,11 @@ impl Serialize for AssetSidecar {
         insert_opt!("camera_model", self.camera_model);
         insert_opt!("gps_lat", self.gps_lat);
         insert_opt!("gps_lon", self.gps_lon);
+        insert_opt!("gps_source", self.gps_source);
+        insert_opt!("caption", self.caption);
+        insert_opt!("label", self.label);
+        insert_opt!("pick", self.pick);
+        insert_opt!("reference_path", self.reference_path);
 
         // Merge unknown fields last so they are preserved verbatim.
         for (k, v) in &self.unknown_fields {
@@ -176,6 +203,7 @@ impl<'de> Deserialize<'de> for AssetSidecar {
         let capture_tz = opt!("capture_tz", String);
         let capture_tz_source = opt!("capture_tz_source", CaptureTzSource);
         let tz_db_version = opt!("tz_db_version", String);
+        let original_capture_timestamp = opt!("original_capture_timestamp", i64);
         let width = opt!("width", u32);
         let height = opt!("height", u32);
         let duration_ms = opt!("duration_ms", u64);
@@ -186,6 +214,11 @@ impl<'de> Deserialize<'de> for AssetSidecar {
         let camera_model = opt!("camera_model", String);
         let gps_lat = opt!("gps_lat", f64);
         let gps_lon = opt!("gps_lon", f64);
+        let gps_source = opt!("gps_source", GpsSource);
+        let caption = opt!("caption", String);
+        let label = opt!("label", String);
+        let pick = opt!("pick", Pick);
+        let reference_path = opt!("reference_path", String);
 
         // Any remaining fields are unknown — preserve them.
         let unknown_fields = fields;
@@ -210,6 +243,7 @@ impl<'de> Deserialize<'de> for AssetSidecar {
             capture_tz,
             capture_tz_source,
             tz_db_version,
+            original_capture_timestamp,
             width,
             height,
             duration_ms,
@@ -220,6 +254,11 @@ impl<'de> Deserialize<'de> for AssetSidecar {
             camera_model,
             gps_lat,
             gps_lon,
+            gps_source,
+            caption,
+            label,
+            pick,
+            reference_path,
             unknown_fields,
         })
     }
@@ -228,7 +267,9 @@ impl<'de> Deserialize<'de> for AssetSidecar {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::{CaptureTzSource, DetectionMethod, ImportMode, MemberRole, StackType};
+    use crate::domain::{
+        CaptureTzSource, DetectionMethod, GpsSource, ImportMode, MemberRole, Pick, StackType,
+    };
     use crate::metadata::AssetType;
     use crate::sidecar::StackHint;
     use std::collections::BTreeMap;
@@ -254,6 +295,7 @@ mod tests {
             capture_tz: None,
             capture_tz_source: None,
             tz_db_version: None,
+            original_capture_timestamp: None,
             width: None,
             height: None,
             duration_ms: None,
@@ -264,6 +306,11 @@ mod tests {
             camera_model: None,
             gps_lat: None,
             gps_lon: None,
+            gps_source: None,
+            caption: None,
+            label: None,
+            pick: None,
+            reference_path: None,
             unknown_fields: BTreeMap::new(),
         }
     }
@@ -288,12 +335,17 @@ mod tests {
         s.capture_tz = Some("America/New_York".to_string());
         s.capture_tz_source = Some(CaptureTzSource::GpsLookup);
         s.tz_db_version = Some("2024b".to_string());
+        s.original_capture_timestamp = Some(1719986400);
         s.width = Some(4032);
         s.height = Some(3024);
         s.camera_make = Some("Apple".to_string());
         s.camera_model = Some("iPhone 15 Pro".to_string());
         s.gps_lat = Some(40.7128);
         s.gps_lon = Some(-74.0060);
+        s.gps_source = Some(GpsSource::TrackLog);
+        s.caption = Some("Brooklyn Bridge at dusk".to_string());
+        s.label = Some("Red".to_string());
+        s.pick = Some(Pick::Picked);
         s.tags = vec!["vacation".to_string(), "2024".to_string()];
         s.rating = 4;
         s.stack_hint = Some(StackHint {
@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+pub const CURRENT_BACKUP_MANIFEST_VERSION: u8 = 1;
+
+/// `.pixles-backup.cbor` at the root of a backup mirror: every file the
+/// mirror holds, so that a backup run can tell what changed without
+/// re-reading it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupManifestCbor {
+    pub version: u8,
+    /// Name of the library last backed up here.
+    pub library_name: String,
+    pub last_backup_at: Option<i64>,
+    /// Keyed by path relative to the mirror root, `/`-separated.
+    pub files: BTreeMap<String, MirroredFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MirroredFile {
+    pub hash_blake3: String,
+    pub size: u64,
+    /// When a backup first found the file gone from the library. It is
+    /// deleted from the mirror once the retention window has passed.
+    #[serde(default)]
+    pub removed_at: Option<i64>,
+}
+
+impl Default for BackupManifestCbor {
+    fn default() -> Self {
+        Self {
+            version: CURRENT_BACKUP_MANIFEST_VERSION,
+            library_name: String::new(),
+            last_backup_at: None,
+            files: BTreeMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut manifest = BackupManifestCbor {
+            library_name: "My Photos".to_string(),
+            last_backup_at: Some(1720000000),
+            ..Default::default()
+        };
+        manifest.files.insert(
+            "media/2024/2024-07/0191.jpg".to_string(),
+            MirroredFile {
+                hash_blake3: "a".repeat(64),
+                size: 1024,
+                removed_at: Some(1720000000),
+            },
+        );
+        let mut buf = vec![];
+        ciborium::ser::into_writer(&manifest, &mut buf).unwrap();
+        let back: BackupManifestCbor = ciborium::de::from_reader(buf.as_slice()).unwrap();
+        assert_eq!(back, manifest);
+    }
+}
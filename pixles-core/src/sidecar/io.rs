@@ -3,7 +3,9 @@ use std::io::{BufReader, BufWriter};
 use std::path::Path;
 
 use crate::library::paths::tmp_path;
-use crate::sidecar::{AssetSidecar, LibraryConfigCbor, LibraryVersionCbor};
+use crate::sidecar::{
+    AlbumCbor, AssetSidecar, BackupManifestCbor, LibraryConfigCbor, LibraryVersionCbor,
+};
 
 pub fn read_sidecar(path: &Path) -> Result<AssetSidecar, Box<dyn std::error::Error + Send + Sync>> {
     let file = fs::File::open(path)?;
@@ -28,6 +30,29 @@ pub fn write_sidecar(
     Ok(())
 }
 
+pub fn read_album(path: &Path) -> Result<AlbumCbor, Box<dyn std::error::Error + Send + Sync>> {
+    let file = fs::File::open(path)?;
+    let album = ciborium::de::from_reader(BufReader::new(file))?;
+    Ok(album)
+}
+
+pub fn write_album(
+    path: &Path,
+    album: &AlbumCbor,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let tmp = tmp_path(path);
+    {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&tmp)?;
+        ciborium::ser::into_writer(album, BufWriter::new(file))?;
+    }
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
 pub fn read_library_version(
     path: &Path,
 ) -> Result<LibraryVersionCbor, Box<dyn std::error::Error + Send + Sync>> {
@@ -78,10 +103,35 @@ pub fn write_library_config(
     Ok(())
 }
 
+pub fn read_backup_manifest(
+    path: &Path,
+) -> Result<BackupManifestCbor, Box<dyn std::error::Error + Send + Sync>> {
+    let file = fs::File::open(path)?;
+    let manifest = ciborium::de::from_reader(BufReader::new(file))?;
+    Ok(manifest)
+}
+
+pub fn write_backup_manifest(
+    path: &Path,
+    manifest: &BackupManifestCbor,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let tmp = tmp_path(path);
+    {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&tmp)?;
+        ciborium::ser::into_writer(manifest, BufWriter::new(file))?;
+    }
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::ImportMode;
+    use crate::domain::{ImportMode, ThumbnailFormat};
     use crate::metadata::AssetType;
     use crate::sidecar::{AssetSidecar, LibraryConfigCbor, LibraryVersionCbor};
     use std::collections::BTreeMap;
@@ -108,6 +158,7 @@ mod tests {
             capture_tz: None,
             capture_tz_source: None,
             tz_db_version: None,
+            original_capture_timestamp: None,
             width: None,
             height: None,
             duration_ms: None,
@@ -118,6 +169,11 @@ mod tests {
             camera_model: None,
             gps_lat: None,
             gps_lon: None,
+            gps_source: None,
+            caption: None,
+            label: None,
+            pick: None,
+            reference_path: None,
             unknown_fields: BTreeMap::new(),
         }
     }
@@ -157,6 +213,8 @@ mod tests {
             library_name: "Test".to_string(),
             last_opened_at: 1720000000,
             last_scrubbed_at: None,
+            thumbnail_format: ThumbnailFormat::Webp,
+            auto_xmp_export: None,
         };
         write_library_config(&path, &cfg).unwrap();
         let read_back = read_library_config(&path).unwrap();
@@ -172,6 +230,8 @@ mod tests {
             library_name: "My Library".to_string(),
             last_opened_at: 1720000000,
             last_scrubbed_at: Some(1719990000),
+            thumbnail_format: ThumbnailFormat::Webp,
+            auto_xmp_export: None,
         };
         write_library_config(&path, &cfg).unwrap();
         let read_back = read_library_config(&path).unwrap();
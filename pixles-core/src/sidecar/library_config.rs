@@ -1,11 +1,20 @@
 use serde::{Deserialize, Serialize};
 
+use crate::domain::{ThumbnailFormat, XmpExportTarget};
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LibraryConfigCbor {
     pub schema_version: u8,
     pub library_name: String,
     pub last_opened_at: i64,
     pub last_scrubbed_at: Option<i64>,
+    /// Absent in configs written before thumbnails were generated.
+    #[serde(default)]
+    pub thumbnail_format: ThumbnailFormat,
+    /// Set by `export-xmp --auto`; imports, tag and metadata edits then
+    /// refresh the `.xmp` of the assets they touch.
+    #[serde(default)]
+    pub auto_xmp_export: Option<XmpExportTarget>,
 }
 
 #[cfg(test)]
@@ -29,6 +38,8 @@ mod tests {
             library_name: "My Photos".to_string(),
             last_opened_at: 1720000000,
             last_scrubbed_at: Some(1719990000),
+            thumbnail_format: ThumbnailFormat::Webp,
+            auto_xmp_export: None,
         };
         assert_eq!(cfg, cbor_roundtrip(&cfg));
     }
@@ -40,7 +51,30 @@ mod tests {
             library_name: "Library".to_string(),
             last_opened_at: 1720000000,
             last_scrubbed_at: None,
+            thumbnail_format: ThumbnailFormat::Webp,
+            auto_xmp_export: None,
         };
         assert_eq!(cfg, cbor_roundtrip(&cfg));
     }
+
+    #[test]
+    fn test_thumbnail_format_defaults_when_absent() {
+        #[derive(Serialize)]
+        struct LegacyConfig {
+            schema_version: u8,
+            library_name: String,
+            last_opened_at: i64,
+            last_scrubbed_at: Option<i64>,
+        }
+        let legacy = LegacyConfig {
+            schema_version: 1,
+            library_name: "Old".to_string(),
+            last_opened_at: 1720000000,
+            last_scrubbed_at: None,
+        };
+        let mut buf = vec![];
+        ciborium::ser::into_writer(&legacy, &mut buf).unwrap();
+        let cfg: LibraryConfigCbor = ciborium::de::from_reader(buf.as_slice()).unwrap();
+        assert_eq!(cfg.thumbnail_format, ThumbnailFormat::Webp);
+    }
 }
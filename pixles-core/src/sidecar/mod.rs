@@ -1,13 +1,17 @@
+pub mod album;
 pub mod asset_sidecar;
+pub mod backup_manifest;
 pub mod io;
 pub mod library_config;
 pub mod library_version;
 pub mod stack_hint;
 
+pub use album::{AlbumCbor, AlbumMember};
 pub use asset_sidecar::AssetSidecar;
+pub use backup_manifest::{BackupManifestCbor, MirroredFile};
 pub use io::{
-    read_library_config, read_library_version, read_sidecar, write_library_config,
-    write_library_version, write_sidecar,
+    read_album, read_backup_manifest, read_library_config, read_library_version, read_sidecar,
+    write_album, write_backup_manifest, write_library_config, write_library_version, write_sidecar,
 };
 pub use library_config::LibraryConfigCbor;
 pub use library_version::LibraryVersionCbor;
@@ -1,8 +1,133 @@
-use std::{fs, io, path::Path};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Files at least this large are hashed through a read-only memory map
+/// instead of buffered reads.
+const MMAP_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// Chunk size used when streaming a file through the hasher.
+const STREAM_CHUNK_SIZE: usize = 1024 * 1024;
 
 /// Get BLAKE3 hash of a file as a 64-char lowercase hex string.
-// TODO: switch to streaming version for large files
+///
+/// The file is never read fully into memory: small files are streamed through
+/// the hasher, large ones are memory-mapped.
 pub fn get_file_hash(path: &Path) -> io::Result<String> {
-    let bytes = fs::read(path)?;
-    Ok(blake3::hash(&bytes).to_hex().to_string())
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let mut hasher = blake3::Hasher::new();
+    if len >= MMAP_THRESHOLD {
+        // SAFETY: the map is read-only and dropped before returning. A file
+        // truncated underneath us by another process is the usual mmap hazard
+        // and is accepted here, as in pixles-media's decoders.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        hasher.update(&mmap);
+    } else {
+        hasher.update_reader(file)?;
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Copy `src` to `dest` in a single streaming pass.
+///
+/// Returns the BLAKE3 hash of the bytes read from `src` and the number of
+/// bytes copied. `dest` is created or truncated.
+pub fn copy_and_hash(src: &Path, dest: &Path) -> io::Result<(String, u64)> {
+    let reader = File::open(src)?;
+    copy_reader_and_hash(reader, dest)
+}
+
+/// Copy `length` bytes starting at `offset` in `src` to `dest`.
+///
+/// Used to extract members embedded in another file, such as the video
+/// trailer of a Motion Photo. Fails with `UnexpectedEof` if `src` ends
+/// before the range does.
+pub fn copy_range_and_hash(
+    src: &Path,
+    offset: u64,
+    length: u64,
+    dest: &Path,
+) -> io::Result<(String, u64)> {
+    let mut reader = File::open(src)?;
+    reader.seek(SeekFrom::Start(offset))?;
+    let (hash, total) = copy_reader_and_hash(reader.take(length), dest)?;
+    if total != length {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("expected {length} bytes at offset {offset}, copied {total}"),
+        ));
+    }
+    Ok((hash, total))
+}
+
+fn copy_reader_and_hash(mut reader: impl Read, dest: &Path) -> io::Result<(String, u64)> {
+    let mut writer = File::create(dest)?;
+
+    let mut hasher = blake3::Hasher::new();
+    let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut total: u64 = 0;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buf[..n]);
+        writer.write_all(&buf[..n])?;
+        total += n as u64;
+    }
+    writer.flush()?;
+
+    Ok((hasher.finalize().to_hex().to_string(), total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_get_file_hash_matches_in_memory_hash() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("a.bin");
+        let content = vec![7u8; 3 * STREAM_CHUNK_SIZE + 17];
+        fs::write(&path, &content).unwrap();
+
+        let expected = blake3::hash(&content).to_hex().to_string();
+        assert_eq!(get_file_hash(&path).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_copy_and_hash() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("src.bin");
+        let dest = tmp.path().join("dest.bin");
+        let content = b"streamed copy content";
+        fs::write(&src, content).unwrap();
+
+        let (hash, len) = copy_and_hash(&src, &dest).unwrap();
+        assert_eq!(hash, blake3::hash(content).to_hex().to_string());
+        assert_eq!(len, content.len() as u64);
+        assert_eq!(fs::read(&dest).unwrap(), content);
+    }
+
+    #[test]
+    fn test_copy_range_and_hash() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("src.bin");
+        let dest = tmp.path().join("dest.bin");
+        fs::write(&src, b"headerPAYLOADtrailer").unwrap();
+
+        let (hash, len) = copy_range_and_hash(&src, 6, 7, &dest).unwrap();
+        assert_eq!(hash, blake3::hash(b"PAYLOAD").to_hex().to_string());
+        assert_eq!(len, 7);
+        assert_eq!(fs::read(&dest).unwrap(), b"PAYLOAD");
+
+        let err = copy_range_and_hash(&src, 15, 10, &dest).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
 }
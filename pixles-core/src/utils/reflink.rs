@@ -0,0 +1,90 @@
+use std::io;
+use std::path::Path;
+
+/// Clones `src` to `dest` as a copy-on-write reflink, sharing the data
+/// blocks until either file is written to.
+///
+/// Supported on Linux filesystems implementing `FICLONE` (btrfs, XFS,
+/// bcachefs) and on APFS. Fails, leaving `dest` absent, when the filesystem
+/// cannot clone or the two paths are on different filesystems; callers fall
+/// back to a regular copy.
+pub fn reflink(src: &Path, dest: &Path) -> io::Result<()> {
+    imp::reflink(src, dest)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod imp {
+    use std::fs::{self, File, OpenOptions};
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+    use std::path::Path;
+
+    /// `_IOW(0x94, 9, int)` from `linux/fs.h`.
+    const FICLONE: u32 = 0x4004_9409;
+
+    pub fn reflink(src: &Path, dest: &Path) -> io::Result<()> {
+        let src_file = File::open(src)?;
+        let dest_file = OpenOptions::new().write(true).create_new(true).open(dest)?;
+        // SAFETY: both descriptors are open for the duration of the call.
+        let ret = unsafe { libc::ioctl(dest_file.as_raw_fd(), FICLONE as _, src_file.as_raw_fd()) };
+        if ret == -1 {
+            let err = io::Error::last_os_error();
+            drop(dest_file);
+            let _ = fs::remove_file(dest);
+            return Err(err);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_vendor = "apple")]
+mod imp {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    pub fn reflink(src: &Path, dest: &Path) -> io::Result<()> {
+        let src = CString::new(src.as_os_str().as_bytes())?;
+        let dest = CString::new(dest.as_os_str().as_bytes())?;
+        // SAFETY: both strings are NUL-terminated and outlive the call.
+        if unsafe { libc::clonefile(src.as_ptr(), dest.as_ptr(), 0) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_vendor = "apple")))]
+mod imp {
+    use std::io;
+    use std::path::Path;
+
+    pub fn reflink(_src: &Path, _dest: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "reflinks are not supported on this platform",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_reflink_clones_or_leaves_nothing_behind() {
+        let dir = TempDir::new().unwrap();
+        let src = dir.path().join("a.jpg");
+        let dest = dir.path().join("b.jpg");
+        fs::write(&src, b"pixels").unwrap();
+
+        // tmpfs and ext4 cannot clone; either outcome must be clean.
+        match reflink(&src, &dest) {
+            Ok(()) => assert_eq!(fs::read(&dest).unwrap(), b"pixels"),
+            Err(_) => assert!(!dest.exists()),
+        }
+    }
+}
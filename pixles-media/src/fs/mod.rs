@@ -14,7 +14,7 @@ use crate::{
         },
         types::ImageFormat,
     },
-    video::{VideoFile, types::VideoFormat},
+    video::{VideoFile, VideoMetadata, VideoMetadataError, types::VideoFormat},
 };
 
 pub mod ext;
@@ -66,8 +66,14 @@ async fn read_image(file_path: &Path, t: ImageFormat) -> Result<ImageFile, ReadM
 }
 
 /// Reads a video file from the given path and returns a VideoFile enum.
-async fn read_video(_file_path: &Path, _t: VideoFormat) -> Result<VideoFile, ReadMediaError> {
-    unimplemented!()
+async fn read_video(file_path: &Path, _t: VideoFormat) -> Result<VideoFile, ReadMediaError> {
+    let path = file_path.to_path_buf();
+    let metadata = tokio::task::spawn_blocking(move || VideoMetadata::from_path(&path)).await??;
+
+    Ok(VideoFile {
+        path: file_path.to_path_buf(),
+        metadata,
+    })
 }
 
 #[derive(Error, Debug)]
@@ -82,6 +88,8 @@ pub enum ReadMediaError {
     JoinError(#[from] tokio::task::JoinError),
     #[error("Image error: {0}")]
     Image(#[from] crate::image::ImageError),
+    #[error("Video error: {0}")]
+    Video(#[from] VideoMetadataError),
 }
 
 #[derive(Debug)]
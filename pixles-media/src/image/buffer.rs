@@ -1,3 +1,4 @@
+use crate::image::resample::{ResampleOptions, resample};
 use crate::metadata::ColorSpace;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -21,6 +22,7 @@ impl PixelFormat {
     }
 }
 
+/// Component storage. Multi-byte components are native-endian.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ComponentType {
     U8,
@@ -45,8 +47,6 @@ use thiserror::Error;
 pub enum ImageBufferError {
     #[error("Invalid data size: expected {expected}, got {actual}")]
     InvalidDataSize { expected: usize, actual: usize },
-    #[error("Resize currently only supported for U8 data (got {0:?})")]
-    ResizeUnsupported(ComponentType),
     #[error("Target dimensions must be non-zero")]
     InvalidDimensions,
     #[error("Operation not supported for component type {0:?}")]
@@ -105,58 +105,52 @@ impl ImageBuffer {
         self.format.num_components() * self.component_type.bytes_per_component()
     }
 
-    /// Resize the image using Nearest Neighbor algorithm.
-    /// Currently only supports U8 component type.
+    /// Resize the image with the default resampling options
+    /// (Lanczos3, linear light, premultiplied alpha).
     pub fn resize(
         &self,
         new_width: usize,
         new_height: usize,
     ) -> Result<ImageBuffer, ImageBufferError> {
-        // TODO: Reimplement real implementation. Perhaps use zune-procs for this.
-        // For upscaling, give option for Bicubic and Bilinear
-        // For downscaling, give option for Lanczos3 and Mitchell-Netravali
-        // implementation could use image or zune-image
-
-        if self.component_type != ComponentType::U8 {
-            return Err(ImageBufferError::ResizeUnsupported(self.component_type));
-        }
-        if new_width == 0 || new_height == 0 {
-            return Err(ImageBufferError::InvalidDimensions);
-        }
-
-        let stride = self.pixel_stride();
-        let mut new_data = Vec::with_capacity(new_width * new_height * stride);
-
-        // Nearest neighbor
-        // x_ratio = original_width / new_width
-        // y_ratio = original_height / new_height
-        // src_x = (dest_x * x_ratio).floor() ... simple scaling
-
-        // Use fixed point or float logic. Float is fine.
-        let x_scale = self.width as f32 / new_width as f32;
-        let y_scale = self.height as f32 / new_height as f32;
-
-        for y in 0..new_height {
-            let src_y = ((y as f32) * y_scale).floor() as usize;
-            let src_y = src_y.min(self.height - 1); // Clamp
-            let y_offset = src_y * self.width * stride;
-
-            for x in 0..new_width {
-                let src_x = ((x as f32) * x_scale).floor() as usize;
-                let src_x = src_x.min(self.width - 1); // Clamp
+        self.resize_with(new_width, new_height, &ResampleOptions::default())
+    }
 
-                let pixel_offset = y_offset + (src_x * stride);
-                let pixel = &self.data[pixel_offset..pixel_offset + stride];
-                new_data.extend_from_slice(pixel);
-            }
-        }
+    /// Resize the image with an explicit filter and options.
+    pub fn resize_with(
+        &self,
+        new_width: usize,
+        new_height: usize,
+        options: &ResampleOptions,
+    ) -> Result<ImageBuffer, ImageBufferError> {
+        resample(self, new_width, new_height, options)
+    }
 
+    /// Consumes the buffer and returns it with 8-bit components.
+    ///
+    /// U16 keeps the high byte; F32 is clamped to `0.0..=1.0` and scaled.
+    pub fn into_u8(self) -> Result<ImageBuffer, ImageBufferError> {
+        let data = match self.component_type {
+            ComponentType::U8 => return Ok(self),
+            ComponentType::U16 => self
+                .data
+                .chunks_exact(2)
+                .map(|c| (u16::from_ne_bytes([c[0], c[1]]) >> 8) as u8)
+                .collect(),
+            ComponentType::F32 => self
+                .data
+                .chunks_exact(4)
+                .map(|c| {
+                    let v = f32::from_ne_bytes([c[0], c[1], c[2], c[3]]);
+                    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+                })
+                .collect(),
+        };
         ImageBuffer::new(
-            new_data,
-            new_width,
-            new_height,
+            data,
+            self.width,
+            self.height,
             self.format,
-            self.component_type,
+            ComponentType::U8,
             self.color_space,
         )
     }
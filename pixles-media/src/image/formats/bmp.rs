@@ -9,67 +9,95 @@ use crate::image::metadata::{
     raw::RawSensorInfo,
 };
 use crate::image::{
-    Image, ImageDecode, ImageEncode, ImageError, ImageMetadata, buffer::ImageBuffer,
+    Image, ImageDecode, ImageEncode, ImageError, ImageMetadata,
+    buffer::{ComponentType, ImageBuffer, PixelFormat},
 };
 use crate::metadata::{
     ColorSpace, DeviceMetadata, c2pa::C2PAManifest, exif::ExifData, geo::GpsLocation,
     icc::IccProfile, xmp::XmpData,
 };
 
+const FILE_HEADER_SIZE: usize = 14;
+const CORE_HEADER_SIZE: usize = 12;
+const INFO_HEADER_SIZE: usize = 40;
+const V4_HEADER_SIZE: usize = 108;
+
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+/// `LCS_sRGB` colour space tag of a V4 header.
+const LCS_SRGB: u32 = u32::from_be_bytes(*b"sRGB");
+
+/// 72 DPI in pixels per metre.
+const PIXELS_PER_METRE: i32 = 2835;
+
+/// Uncompressed Windows bitmap.
+///
+/// Decodes 1/4/8-bit palette, 16-bit, 24-bit and 32-bit (including
+/// bitfield) images; RLE compression is not supported. Encodes 24-bit BGR,
+/// or 32-bit BGRA with a V4 header when the image has alpha.
 #[derive(Debug, Clone)]
-pub struct BmpImage {}
+pub struct BmpImage {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+    format: PixelFormat,
+    color_space: ColorSpace,
+    file_size_bytes: u64,
+}
 
 impl ImageMetadataExtractor for BmpImage {
     fn get_date_taken(&self) -> Option<chrono::DateTime<chrono::Utc>> {
-        unimplemented!()
+        None
     }
     fn get_dimensions(&self) -> (u32, u32) {
-        unimplemented!()
+        (self.width, self.height)
     }
     fn get_bit_depth(&self) -> u8 {
-        unimplemented!()
+        8
     }
     fn get_color_space(&self) -> ColorSpace {
-        unimplemented!()
+        self.color_space
     }
     fn get_file_size(&self) -> u64 {
-        unimplemented!()
+        self.file_size_bytes
     }
     fn get_device_metadata(&self) -> Option<DeviceMetadata> {
-        unimplemented!()
+        None
     }
     fn get_capture_settings(&self) -> Option<CaptureSettings> {
-        unimplemented!()
+        None
     }
     fn get_location(&self) -> Option<GpsLocation> {
-        unimplemented!()
+        None
     }
     fn get_content(&self) -> Option<ContentMetadata> {
-        unimplemented!()
+        None
     }
     fn raw_info(&self) -> Option<RawSensorInfo> {
-        unimplemented!()
+        None
     }
     fn exif(&self) -> Option<ExifData> {
-        unimplemented!()
+        None
     }
     fn xmp(&self) -> Option<XmpData> {
-        unimplemented!()
+        None
     }
     fn iptc(&self) -> Option<IptcData> {
-        unimplemented!()
+        None
     }
     fn icc_profile(&self) -> Option<IccProfile> {
-        unimplemented!()
+        None
     }
     fn motion_metadata(&self) -> Option<MotionPhotoInfo> {
-        unimplemented!()
+        None
     }
     fn auxiliary_images(&self) -> Vec<AuxiliaryImage> {
-        unimplemented!()
+        vec![]
     }
     fn c2pa_manifest(&self) -> Option<C2PAManifest> {
-        unimplemented!()
+        None
     }
 }
 
@@ -79,26 +107,434 @@ impl Image for BmpImage {
     }
 
     fn get_buffer(&self) -> ImageBuffer {
-        unimplemented!()
+        ImageBuffer::new(
+            self.data.clone(),
+            self.width as usize,
+            self.height as usize,
+            self.format,
+            ComponentType::U8,
+            self.color_space,
+        )
+        .expect("Failed to create ImageBuffer from internal data")
     }
 
-    fn from_raw_parts(_buffer: ImageBuffer, _metadata: ImageMetadata) -> Result<Self, ImageError> {
-        unimplemented!()
+    fn from_raw_parts(buffer: ImageBuffer, _metadata: ImageMetadata) -> Result<Self, ImageError> {
+        let buffer = match buffer.format {
+            PixelFormat::Cmyk => buffer.into_u8()?.into_rgba8()?,
+            _ => buffer.into_u8()?,
+        };
+
+        Ok(Self {
+            width: buffer.width as u32,
+            height: buffer.height as u32,
+            format: buffer.format,
+            color_space: buffer.color_space,
+            data: buffer.data,
+            file_size_bytes: 0, // Generated image, no file size yet
+        })
     }
 }
 
 impl ImageDecode for BmpImage {
-    fn decode_from_bytes(_bytes: &[u8]) -> Result<Self, ImageError> {
-        unimplemented!()
+    fn decode_from_bytes(bytes: &[u8]) -> Result<Self, ImageError> {
+        let header = BmpHeader::parse(bytes)?;
+        let data = header.decode_pixels(bytes)?;
+
+        Ok(Self {
+            width: header.width,
+            height: header.height,
+            data,
+            format: if header.masks.alpha != 0 {
+                PixelFormat::Rgba
+            } else {
+                PixelFormat::Rgb
+            },
+            color_space: ColorSpace::Srgb,
+            file_size_bytes: bytes.len() as u64,
+        })
     }
 }
 
 impl ImageEncode for BmpImage {
-    fn encode<W: Write>(&self, _writer: &mut W) -> Result<(), ImageError> {
-        unimplemented!()
+    fn encode<W: Write>(&self, writer: &mut W) -> Result<(), ImageError> {
+        let with_alpha = self.format == PixelFormat::Rgba;
+        let (header_size, bytes_per_pixel) = if with_alpha {
+            (V4_HEADER_SIZE, 4)
+        } else {
+            (INFO_HEADER_SIZE, 3)
+        };
+        let width = self.width as usize;
+        let row_size = (width * bytes_per_pixel).div_ceil(4) * 4;
+        let image_size = row_size * self.height as usize;
+        let pixel_offset = FILE_HEADER_SIZE + header_size;
+        let file_size = u32::try_from(pixel_offset + image_size)
+            .map_err(|_| ImageError::Encode("Image too large for BMP".to_string()))?;
+
+        let mut out = Vec::with_capacity(pixel_offset + image_size);
+        out.extend_from_slice(b"BM");
+        out.extend_from_slice(&file_size.to_le_bytes());
+        out.extend_from_slice(&[0; 4]); // Reserved
+        out.extend_from_slice(&(pixel_offset as u32).to_le_bytes());
+
+        out.extend_from_slice(&(header_size as u32).to_le_bytes());
+        out.extend_from_slice(&(self.width as i32).to_le_bytes());
+        // Positive height: rows are stored bottom-up
+        out.extend_from_slice(&(self.height as i32).to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes()); // Planes
+        out.extend_from_slice(&(bytes_per_pixel as u16 * 8).to_le_bytes());
+        let compression = if with_alpha { BI_BITFIELDS } else { BI_RGB };
+        out.extend_from_slice(&compression.to_le_bytes());
+        out.extend_from_slice(&(image_size as u32).to_le_bytes());
+        out.extend_from_slice(&PIXELS_PER_METRE.to_le_bytes());
+        out.extend_from_slice(&PIXELS_PER_METRE.to_le_bytes());
+        out.extend_from_slice(&[0; 8]); // Palette sizes
+        if with_alpha {
+            for mask in [0x00FF_0000u32, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000] {
+                out.extend_from_slice(&mask.to_le_bytes());
+            }
+            out.extend_from_slice(&LCS_SRGB.to_le_bytes());
+            out.extend_from_slice(&[0; 48]); // Endpoints and gamma, unused for sRGB
+        }
+
+        let channels = self.format.num_components();
+        for row in self.data.chunks_exact(width * channels).rev() {
+            let start = out.len();
+            for pixel in row.chunks_exact(channels) {
+                match self.format {
+                    PixelFormat::Gray => out.extend_from_slice(&[pixel[0]; 3]),
+                    PixelFormat::Rgb => out.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]),
+                    PixelFormat::Rgba => {
+                        out.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]])
+                    }
+                    PixelFormat::Cmyk => {
+                        return Err(ImageError::Encode("BMP does not support CMYK".to_string()));
+                    }
+                }
+            }
+            out.resize(start + row_size, 0);
+        }
+
+        writer.write_all(&out).map_err(ImageError::Io)
+    }
+
+    async fn save(&self, path: &Path) -> Result<(), ImageError> {
+        let data = self.encode_to_bytes()?;
+        tokio::fs::write(path, data).await.map_err(ImageError::Io)
+    }
+}
+
+// ── Decoding ─────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Masks {
+    red: u32,
+    green: u32,
+    blue: u32,
+    alpha: u32,
+}
+
+impl Masks {
+    const RGB555: Masks = Masks {
+        red: 0x7C00,
+        green: 0x03E0,
+        blue: 0x001F,
+        alpha: 0,
+    };
+    const BGRX: Masks = Masks {
+        red: 0x00FF_0000,
+        green: 0x0000_FF00,
+        blue: 0x0000_00FF,
+        alpha: 0,
+    };
+}
+
+#[derive(Debug)]
+struct BmpHeader {
+    width: u32,
+    height: u32,
+    top_down: bool,
+    bits_per_pixel: u16,
+    pixel_offset: usize,
+    /// Only meaningful for 16 and 32 bits per pixel
+    masks: Masks,
+    /// RGB entries for palette images
+    palette: Vec<[u8; 3]>,
+}
+
+impl BmpHeader {
+    fn parse(bytes: &[u8]) -> Result<Self, ImageError> {
+        if !bytes.starts_with(b"BM") {
+            return Err(ImageError::Decode("Missing BMP signature".to_string()));
+        }
+        let pixel_offset = read_u32(bytes, 10)? as usize;
+        let header_size = read_u32(bytes, FILE_HEADER_SIZE)? as usize;
+
+        let (width, height, bits_per_pixel, compression, colors_used) =
+            if header_size == CORE_HEADER_SIZE {
+                (
+                    i32::from(read_u16(bytes, 18)?),
+                    i32::from(read_u16(bytes, 20)?),
+                    read_u16(bytes, 24)?,
+                    BI_RGB,
+                    0,
+                )
+            } else if header_size >= INFO_HEADER_SIZE {
+                (
+                    read_u32(bytes, 18)? as i32,
+                    read_u32(bytes, 22)? as i32,
+                    read_u16(bytes, 28)?,
+                    read_u32(bytes, 30)?,
+                    read_u32(bytes, 46)? as usize,
+                )
+            } else {
+                return Err(ImageError::Decode(format!(
+                    "Unsupported BMP header size {header_size}"
+                )));
+            };
+        if width <= 0 || height == 0 || height == i32::MIN {
+            return Err(ImageError::Decode(format!(
+                "Invalid BMP dimensions {width}x{height}"
+            )));
+        }
+
+        // Masks live inside V2+ headers, or directly after a plain INFO header.
+        let mut palette_start = FILE_HEADER_SIZE + header_size;
+        let masks = match compression {
+            BI_RGB if bits_per_pixel == 16 => Masks::RGB555,
+            BI_RGB => Masks::BGRX,
+            BI_BITFIELDS | BI_ALPHABITFIELDS => {
+                let masks_start = FILE_HEADER_SIZE + INFO_HEADER_SIZE;
+                let has_alpha = compression == BI_ALPHABITFIELDS || header_size >= 56;
+                if header_size == INFO_HEADER_SIZE {
+                    palette_start += if has_alpha { 16 } else { 12 };
+                }
+                Masks {
+                    red: read_u32(bytes, masks_start)?,
+                    green: read_u32(bytes, masks_start + 4)?,
+                    blue: read_u32(bytes, masks_start + 8)?,
+                    alpha: if has_alpha {
+                        read_u32(bytes, masks_start + 12)?
+                    } else {
+                        0
+                    },
+                }
+            }
+            other => {
+                return Err(ImageError::Decode(format!(
+                    "Unsupported BMP compression {other}"
+                )));
+            }
+        };
+
+        let palette = if bits_per_pixel <= 8 {
+            let entry_size = if header_size == CORE_HEADER_SIZE {
+                3
+            } else {
+                4
+            };
+            let count = match colors_used {
+                0 => 1 << bits_per_pixel,
+                n => n.min(1 << bits_per_pixel),
+            };
+            let end = palette_start + count * entry_size;
+            bytes
+                .get(palette_start..end)
+                .ok_or_else(|| ImageError::Decode("Truncated BMP palette".to_string()))?
+                .chunks_exact(entry_size)
+                .map(|bgr| [bgr[2], bgr[1], bgr[0]])
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            width: width as u32,
+            height: height.unsigned_abs(),
+            top_down: height < 0,
+            bits_per_pixel,
+            pixel_offset,
+            masks,
+            palette,
+        })
+    }
+
+    /// Decodes the pixel array into top-down RGB, or RGBA when an alpha mask is set.
+    fn decode_pixels(&self, bytes: &[u8]) -> Result<Vec<u8>, ImageError> {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let bpp = usize::from(self.bits_per_pixel);
+        let too_large = || ImageError::Decode("BMP dimensions are too large".to_string());
+        let row_size = width.checked_mul(bpp).ok_or_else(too_large)?.div_ceil(32) * 4;
+        let pixel_bytes = row_size.checked_mul(height).ok_or_else(too_large)?;
+        let pixels = bytes
+            .get(self.pixel_offset..)
+            .filter(|p| p.len() >= pixel_bytes)
+            .ok_or_else(|| ImageError::Decode("Truncated BMP pixel data".to_string()))?;
+
+        let channels = if self.masks.alpha != 0 { 4 } else { 3 };
+        let out_size = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(channels))
+            .ok_or_else(too_large)?;
+        let mut out = Vec::with_capacity(out_size);
+        for y in 0..height {
+            let stored_row = if self.top_down { y } else { height - 1 - y };
+            let row = &pixels[stored_row * row_size..(stored_row + 1) * row_size];
+            for x in 0..width {
+                match bpp {
+                    1 | 2 | 4 | 8 => {
+                        let bit = x * bpp;
+                        let shift = 8 - bpp - bit % 8;
+                        let index = usize::from((row[bit / 8] >> shift) & ((1 << bpp) - 1) as u8);
+                        let rgb = self.palette.get(index).copied().unwrap_or_default();
+                        out.extend_from_slice(&rgb);
+                    }
+                    24 => out.extend_from_slice(&[row[x * 3 + 2], row[x * 3 + 1], row[x * 3]]),
+                    16 | 32 => {
+                        let value = if bpp == 16 {
+                            u32::from(u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]))
+                        } else {
+                            u32::from_le_bytes(row[x * 4..x * 4 + 4].try_into().unwrap())
+                        };
+                        out.push(extract_channel(value, self.masks.red));
+                        out.push(extract_channel(value, self.masks.green));
+                        out.push(extract_channel(value, self.masks.blue));
+                        if channels == 4 {
+                            out.push(extract_channel(value, self.masks.alpha));
+                        }
+                    }
+                    other => {
+                        return Err(ImageError::Decode(format!(
+                            "Unsupported BMP bit depth {other}"
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Extracts the masked bits of `value`, rescaled to 8 bits.
+fn extract_channel(value: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let bits = mask.count_ones();
+    let raw = u64::from((value & mask) >> mask.trailing_zeros());
+    let max = (1u64 << bits) - 1;
+    ((raw * 255 + max / 2) / max) as u8
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, ImageError> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| ImageError::Decode("Truncated BMP header".to_string()))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, ImageError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| ImageError::Decode("Truncated BMP header".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(data: Vec<u8>, width: usize, format: PixelFormat) -> BmpImage {
+        let height = data.len() / width / format.num_components();
+        let buffer = ImageBuffer::new(
+            data,
+            width,
+            height,
+            format,
+            ComponentType::U8,
+            ColorSpace::Srgb,
+        )
+        .unwrap();
+        BmpImage::from_raw_parts(buffer, ImageMetadata::default()).unwrap()
+    }
+
+    #[test]
+    fn test_rgb_round_trip_with_row_padding() {
+        // 3 px wide → 9 bytes per row, padded to 12
+        let pixels: Vec<u8> = (0..18).collect();
+        let encoded = raw(pixels.clone(), 3, PixelFormat::Rgb)
+            .encode_to_bytes()
+            .unwrap();
+        assert_eq!(encoded.len(), FILE_HEADER_SIZE + INFO_HEADER_SIZE + 24);
+
+        let decoded = BmpImage::decode_from_bytes(&encoded).unwrap();
+        assert_eq!(decoded.get_dimensions(), (3, 2));
+        assert_eq!(decoded.get_buffer().data, pixels);
+    }
+
+    #[test]
+    fn test_rgba_round_trip() {
+        let pixels = vec![255, 0, 0, 0, 0, 255, 0, 128, 0, 0, 255, 255, 1, 2, 3, 4];
+        let encoded = raw(pixels.clone(), 2, PixelFormat::Rgba)
+            .encode_to_bytes()
+            .unwrap();
+        let decoded = BmpImage::decode_from_bytes(&encoded).unwrap();
+        let buffer = decoded.get_buffer();
+        assert_eq!(buffer.format, PixelFormat::Rgba);
+        assert_eq!(buffer.data, pixels);
+    }
+
+    #[test]
+    fn test_decode_top_down_palette() {
+        // 2x2, 1 bpp, negative height
+        let mut bmp = Vec::new();
+        bmp.extend_from_slice(b"BM");
+        bmp.extend_from_slice(&70u32.to_le_bytes());
+        bmp.extend_from_slice(&[0; 4]);
+        bmp.extend_from_slice(&62u32.to_le_bytes());
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&2i32.to_le_bytes());
+        bmp.extend_from_slice(&(-2i32).to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&[0; 24]);
+        bmp.extend_from_slice(&[0, 0, 0, 0, 0xFF, 0x80, 0x00, 0]); // black, BGR(255,128,0)
+        bmp.extend_from_slice(&[0b0100_0000, 0, 0, 0]); // row 0: black, colour
+        bmp.extend_from_slice(&[0b1000_0000, 0, 0, 0]); // row 1: colour, black
+
+        let decoded = BmpImage::decode_from_bytes(&bmp).unwrap();
+        assert_eq!(
+            decoded.get_buffer().data,
+            vec![0, 0, 0, 0, 128, 255, 0, 128, 255, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_extract_channel_rescales() {
+        assert_eq!(extract_channel(0x7C00, Masks::RGB555.red), 255);
+        assert_eq!(extract_channel(0x0010, Masks::RGB555.blue), 132);
+        assert_eq!(extract_channel(0xFFFF, 0), 0);
+    }
+
+    #[test]
+    fn test_rejects_rle() {
+        let mut bmp = raw(vec![0; 3], 1, PixelFormat::Rgb)
+            .encode_to_bytes()
+            .unwrap();
+        bmp[30] = 1; // BI_RLE8
+        assert!(BmpImage::decode_from_bytes(&bmp).is_err());
     }
 
-    async fn save(&self, _path: &Path) -> Result<(), ImageError> {
-        unimplemented!()
+    #[test]
+    fn test_rejects_oversized_dimensions() {
+        let mut bmp = raw(vec![0; 3], 1, PixelFormat::Rgb)
+            .encode_to_bytes()
+            .unwrap();
+        bmp[18..22].copy_from_slice(&i32::MAX.to_le_bytes());
+        bmp[22..26].copy_from_slice(&i32::MAX.to_le_bytes());
+        assert!(matches!(
+            BmpImage::decode_from_bytes(&bmp),
+            Err(ImageError::Decode(_))
+        ));
     }
 }
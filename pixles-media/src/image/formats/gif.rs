@@ -9,67 +9,79 @@ use crate::image::metadata::{
     raw::RawSensorInfo,
 };
 use crate::image::{
-    Image, ImageDecode, ImageEncode, ImageError, ImageMetadata, buffer::ImageBuffer,
+    Image, ImageDecode, ImageEncode, ImageError, ImageMetadata,
+    buffer::{ComponentType, ImageBuffer, PixelFormat},
 };
 use crate::metadata::{
     ColorSpace, DeviceMetadata, c2pa::C2PAManifest, exif::ExifData, geo::GpsLocation,
     icc::IccProfile, xmp::XmpData,
 };
 
+/// NeuQuant sampling speed for palette generation (1 = best, 30 = fastest).
+const QUANTIZE_SPEED: i32 = 10;
+
+/// Still GIF image: the first frame composited onto the logical screen,
+/// stored as RGBA so transparency survives conversion.
 #[derive(Debug, Clone)]
-pub struct GifImage {}
+pub struct GifImage {
+    width: u16,
+    height: u16,
+    data: Vec<u8>,
+    color_space: ColorSpace,
+    file_size_bytes: u64,
+}
 
 impl ImageMetadataExtractor for GifImage {
     fn get_date_taken(&self) -> Option<chrono::DateTime<chrono::Utc>> {
-        unimplemented!()
+        None
     }
     fn get_dimensions(&self) -> (u32, u32) {
-        unimplemented!()
+        (u32::from(self.width), u32::from(self.height))
     }
     fn get_bit_depth(&self) -> u8 {
-        unimplemented!()
+        8
     }
     fn get_color_space(&self) -> ColorSpace {
-        unimplemented!()
+        self.color_space
     }
     fn get_file_size(&self) -> u64 {
-        unimplemented!()
+        self.file_size_bytes
     }
     fn get_device_metadata(&self) -> Option<DeviceMetadata> {
-        unimplemented!()
+        None
     }
     fn get_capture_settings(&self) -> Option<CaptureSettings> {
-        unimplemented!()
+        None
     }
     fn get_location(&self) -> Option<GpsLocation> {
-        unimplemented!()
+        None
     }
     fn get_content(&self) -> Option<ContentMetadata> {
-        unimplemented!()
+        None
     }
     fn raw_info(&self) -> Option<RawSensorInfo> {
-        unimplemented!()
+        None
     }
     fn exif(&self) -> Option<ExifData> {
-        unimplemented!()
+        None
     }
     fn xmp(&self) -> Option<XmpData> {
-        unimplemented!()
+        None
     }
     fn iptc(&self) -> Option<IptcData> {
-        unimplemented!()
+        None
     }
     fn icc_profile(&self) -> Option<IccProfile> {
-        unimplemented!()
+        None
     }
     fn motion_metadata(&self) -> Option<MotionPhotoInfo> {
-        unimplemented!()
+        None
     }
     fn auxiliary_images(&self) -> Vec<AuxiliaryImage> {
-        unimplemented!()
+        vec![]
     }
     fn c2pa_manifest(&self) -> Option<C2PAManifest> {
-        unimplemented!()
+        None
     }
 }
 
@@ -79,26 +91,187 @@ impl Image for GifImage {
     }
 
     fn get_buffer(&self) -> ImageBuffer {
-        unimplemented!()
+        ImageBuffer::new(
+            self.data.clone(),
+            self.width as usize,
+            self.height as usize,
+            PixelFormat::Rgba,
+            ComponentType::U8,
+            self.color_space,
+        )
+        .expect("Failed to create ImageBuffer from internal data")
     }
 
-    fn from_raw_parts(_buffer: ImageBuffer, _metadata: ImageMetadata) -> Result<Self, ImageError> {
-        unimplemented!()
+    fn from_raw_parts(buffer: ImageBuffer, _metadata: ImageMetadata) -> Result<Self, ImageError> {
+        let buffer = buffer.into_u8()?.into_rgba8()?;
+        let dimension = |v: usize| {
+            u16::try_from(v).map_err(|_| {
+                ImageError::Encode(format!("GIF dimensions are limited to 65535, got {v}"))
+            })
+        };
+
+        Ok(Self {
+            width: dimension(buffer.width)?,
+            height: dimension(buffer.height)?,
+            color_space: buffer.color_space,
+            data: buffer.data,
+            file_size_bytes: 0, // Generated image, no file size yet
+        })
     }
 }
 
 impl ImageDecode for GifImage {
-    fn decode_from_bytes(_bytes: &[u8]) -> Result<Self, ImageError> {
-        unimplemented!()
+    fn decode_from_bytes(bytes: &[u8]) -> Result<Self, ImageError> {
+        let mut options = ::gif::DecodeOptions::new();
+        options.set_color_output(::gif::ColorOutput::RGBA);
+        let mut decoder = options
+            .read_info(std::io::Cursor::new(bytes))
+            .map_err(|e| ImageError::Decode(e.to_string()))?;
+        let (width, height) = (decoder.width(), decoder.height());
+
+        let frame = decoder
+            .read_next_frame()
+            .map_err(|e| ImageError::Decode(e.to_string()))?
+            .ok_or_else(|| ImageError::Decode("GIF contains no frames".to_string()))?;
+
+        if frame.width == 0 || frame.height == 0 {
+            return Err(ImageError::Decode("GIF frame has zero size".to_string()));
+        }
+
+        // Frames may be smaller than the screen and offset within it; the
+        // parts outside the screen are not drawn.
+        let screen_width = usize::from(width);
+        let size = screen_width
+            .checked_mul(usize::from(height))
+            .and_then(|n| n.checked_mul(4))
+            .ok_or_else(|| ImageError::Decode("GIF screen is too large".to_string()))?;
+        let mut data = vec![0u8; size];
+        let (left, top) = (usize::from(frame.left), usize::from(frame.top));
+        let visible_width = usize::from(frame.width).min(screen_width.saturating_sub(left));
+        if visible_width > 0 {
+            for (row, pixels) in frame
+                .buffer
+                .chunks_exact(usize::from(frame.width) * 4)
+                .enumerate()
+                .take(usize::from(height).saturating_sub(top))
+            {
+                let start = ((top + row) * screen_width + left) * 4;
+                data[start..start + visible_width * 4]
+                    .copy_from_slice(&pixels[..visible_width * 4]);
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            data,
+            color_space: ColorSpace::Srgb,
+            file_size_bytes: bytes.len() as u64,
+        })
     }
 }
 
 impl ImageEncode for GifImage {
-    fn encode<W: Write>(&self, _writer: &mut W) -> Result<(), ImageError> {
-        unimplemented!()
+    fn encode<W: Write>(&self, writer: &mut W) -> Result<(), ImageError> {
+        let mut rgba = self.data.clone();
+        let frame =
+            ::gif::Frame::from_rgba_speed(self.width, self.height, &mut rgba, QUANTIZE_SPEED);
+
+        let mut encoder = ::gif::Encoder::new(writer, self.width, self.height, &[])
+            .map_err(|e| ImageError::Encode(e.to_string()))?;
+        encoder
+            .write_frame(&frame)
+            .map_err(|e| ImageError::Encode(e.to_string()))?;
+        encoder.into_inner().map_err(ImageError::Io)?;
+        Ok(())
+    }
+
+    async fn save(&self, path: &Path) -> Result<(), ImageError> {
+        let data = self.encode_to_bytes()?;
+        tokio::fs::write(path, data).await.map_err(ImageError::Io)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_preserves_palette_colors_and_transparency() {
+        // Four distinct colours survive quantisation exactly.
+        let pixels = vec![
+            255, 0, 0, 255, //
+            0, 255, 0, 255, //
+            0, 0, 255, 255, //
+            0, 0, 0, 0,
+        ];
+        let buffer = ImageBuffer::new(
+            pixels,
+            2,
+            2,
+            PixelFormat::Rgba,
+            ComponentType::U8,
+            ColorSpace::Srgb,
+        )
+        .unwrap();
+        let image = GifImage::from_raw_parts(buffer, ImageMetadata::default()).unwrap();
+
+        let decoded = GifImage::decode_from_bytes(&image.encode_to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.get_dimensions(), (2, 2));
+        let data = decoded.get_buffer().data;
+        assert_eq!(
+            &data[..12],
+            &[255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255]
+        );
+        assert_eq!(data[15], 0, "transparent pixel keeps zero alpha");
+    }
+
+    /// A 2x2 screen with one frame of `frame_width` x 2 red pixels at `left`.
+    fn gif_with_frame(frame_width: u16, left: u16) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = ::gif::Encoder::new(&mut bytes, 2, 2, &[255, 0, 0, 0, 0, 0]).unwrap();
+        let frame = ::gif::Frame {
+            width: frame_width,
+            height: 2,
+            left,
+            buffer: vec![0; usize::from(frame_width) * 2].into(),
+            ..Default::default()
+        };
+        encoder.write_frame(&frame).unwrap();
+        drop(encoder);
+        bytes
+    }
+
+    #[test]
+    fn test_frame_outside_screen_is_clipped() {
+        let decoded = GifImage::decode_from_bytes(&gif_with_frame(2, 1)).unwrap();
+        let data = decoded.get_buffer().data;
+        // Only the frame's first column lands on the screen, at x = 1.
+        assert_eq!(&data[..8], &[0, 0, 0, 0, 255, 0, 0, 255]);
+
+        let decoded = GifImage::decode_from_bytes(&gif_with_frame(2, 5)).unwrap();
+        assert!(decoded.get_buffer().data.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_zero_sized_frame_is_a_decode_error() {
+        assert!(matches!(
+            GifImage::decode_from_bytes(&gif_with_frame(0, 0)),
+            Err(ImageError::Decode(_))
+        ));
     }
 
-    async fn save(&self, _path: &Path) -> Result<(), ImageError> {
-        unimplemented!()
+    #[test]
+    fn test_rejects_oversized_dimensions() {
+        let buffer = ImageBuffer::new(
+            vec![0; 70_000],
+            70_000,
+            1,
+            PixelFormat::Gray,
+            ComponentType::U8,
+            ColorSpace::Srgb,
+        )
+        .unwrap();
+        assert!(GifImage::from_raw_parts(buffer, ImageMetadata::default()).is_err());
     }
 }
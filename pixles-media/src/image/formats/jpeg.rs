@@ -1,6 +1,6 @@
 use std::path::Path;
 
-use jpeg_encoder::{ColorType, Encoder as JpegEncoderStruct};
+use jpeg_encoder::{ColorType, Encoder as JpegEncoderStruct, SamplingFactor};
 use zune_core::colorspace::ColorSpace as ZuneColorSpace;
 use zune_core::options::DecoderOptions;
 use zune_jpeg::JpegDecoder;
@@ -15,10 +15,16 @@ use crate::image::metadata::{
 use crate::image::{
     Image, ImageDecode, ImageEncode, ImageError, ImageMetadata,
     buffer::{ComponentType, ImageBuffer, PixelFormat},
+    types::{ChromaSubsampling, JpegSettings},
 };
 use crate::metadata::ColorSpace;
 use crate::metadata::{
-    DeviceMetadata, c2pa::C2PAManifest, exif::ExifData, geo::GpsLocation, icc::IccProfile,
+    DeviceMetadata,
+    c2pa::C2PAManifest,
+    exif::{ExifData, Ifd0Tags},
+    geo::GpsLocation,
+    icc::IccProfile,
+    orientation::Orientation,
     xmp::XmpData,
 };
 
@@ -31,6 +37,24 @@ pub struct JpegImage {
     color_space: ColorSpace,
     #[allow(dead_code)]
     file_size_bytes: u64,
+    /// APP1 EXIF block of a decoded file; only the orientation is parsed.
+    exif: Option<ExifData>,
+    settings: Option<JpegSettings>,
+}
+
+/// Encoder quality when no settings are attached.
+const DEFAULT_QUALITY: u8 = 80;
+
+/// Namespace that opens the payload of an XMP APP1 segment.
+const XMP_SIGNATURE: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+impl JpegImage {
+    /// Attaches encoder settings (quality, chroma subsampling, progressive
+    /// mode). `resolution` is the caller's to apply before encoding.
+    pub fn with_settings(mut self, settings: JpegSettings) -> Self {
+        self.settings = Some(settings);
+        self
+    }
 }
 
 impl ImageMetadataExtractor for JpegImage {
@@ -65,7 +89,7 @@ impl ImageMetadataExtractor for JpegImage {
         None
     }
     fn exif(&self) -> Option<ExifData> {
-        None
+        self.exif.clone()
     }
     fn xmp(&self) -> Option<XmpData> {
         None
@@ -120,6 +144,8 @@ impl Image for JpegImage {
             format,
             color_space: buffer.color_space,
             file_size_bytes: 0, // Generated image, no file size yet
+            exif: None,
+            settings: None,
         })
     }
 }
@@ -162,13 +188,34 @@ impl ImageDecode for JpegImage {
             format: pixel_format,
             color_space: ColorSpace::Srgb, // Assuming SRGB for now
             file_size_bytes: data.len() as u64,
+            exif: exif_segment(data).map(|tiff| ExifData {
+                raw_bytes: Some(tiff.to_vec()),
+                ifd0: Ifd0Tags {
+                    orientation: Orientation::from_tiff(tiff),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            settings: None,
         })
     }
 }
 
 impl ImageEncode for JpegImage {
     fn encode<W: std::io::Write>(&self, writer: &mut W) -> Result<(), ImageError> {
-        let encoder = JpegEncoderStruct::new(writer, 80); // Quality 80
+        let quality = self
+            .settings
+            .as_ref()
+            .map_or(DEFAULT_QUALITY, |s| s.quality);
+        let mut encoder = JpegEncoderStruct::new(writer, quality.clamp(1, 100));
+        if let Some(settings) = &self.settings {
+            encoder.set_progressive(settings.progressive);
+            encoder.set_sampling_factor(match settings.chroma_subsampling {
+                ChromaSubsampling::Cs444 => SamplingFactor::R_4_4_4,
+                ChromaSubsampling::Cs422 => SamplingFactor::R_4_2_2,
+                ChromaSubsampling::Cs420 => SamplingFactor::R_4_2_0,
+            });
+        }
 
         let color_type = match self.format {
             PixelFormat::Gray => ColorType::Luma,
@@ -188,3 +235,156 @@ impl ImageEncode for JpegImage {
         tokio::fs::write(path, data).await.map_err(ImageError::Io)
     }
 }
+
+/// Returns `data` with `packet` as its XMP, replacing any XMP segment it
+/// already has. The new segment goes after the JFIF and EXIF headers, where
+/// readers look for it.
+///
+/// `None` when `data` is not a JPEG or the packet does not fit in one
+/// segment (about 64 KiB).
+pub fn embed_xmp(data: &[u8], packet: &str) -> Option<Vec<u8>> {
+    const APP0: u8 = 0xE0;
+    const APP1: u8 = 0xE1;
+    const SOS: u8 = 0xDA;
+
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let length = u16::try_from(2 + XMP_SIGNATURE.len() + packet.len()).ok()?;
+    let mut xmp = vec![0xFF, APP1];
+    xmp.extend_from_slice(&length.to_be_bytes());
+    xmp.extend_from_slice(XMP_SIGNATURE);
+    xmp.extend_from_slice(packet.as_bytes());
+    let mut pending = Some(xmp);
+
+    let mut out = Vec::with_capacity(data.len() + usize::from(length) + 2);
+    out.extend_from_slice(&data[..2]);
+    let mut pos = 2;
+    while let Some(&[0xFF, marker, hi, lo]) = data.get(pos..pos + 4) {
+        if marker == SOS {
+            break;
+        }
+        let end = pos + 2 + usize::from(u16::from_be_bytes([hi, lo]));
+        let segment = data.get(pos..end)?;
+        let payload = segment.get(4..)?;
+        let header = marker == APP0 || (marker == APP1 && payload.starts_with(b"Exif\0\0"));
+        if !header && let Some(xmp) = pending.take() {
+            out.extend_from_slice(&xmp);
+        }
+        if !(marker == APP1 && payload.starts_with(XMP_SIGNATURE)) {
+            out.extend_from_slice(segment);
+        }
+        pos = end;
+    }
+    if let Some(xmp) = pending {
+        out.extend_from_slice(&xmp);
+    }
+    out.extend_from_slice(&data[pos..]);
+    Some(out)
+}
+
+/// Returns the TIFF payload of the first `Exif` APP1 segment.
+fn exif_segment(data: &[u8]) -> Option<&[u8]> {
+    const SOI: u8 = 0xD8;
+    const APP1: u8 = 0xE1;
+    const SOS: u8 = 0xDA;
+
+    if !data.starts_with(&[0xFF, SOI]) {
+        return None;
+    }
+    let mut pos = 2;
+    while let [0xFF, marker, hi, lo, ..] = *data.get(pos..)? {
+        if marker == SOS {
+            return None;
+        }
+        let length = usize::from(u16::from_be_bytes([hi, lo]));
+        let payload = data.get(pos + 4..pos + 2 + length)?;
+        if marker == APP1 && payload.starts_with(b"Exif\0\0") {
+            return Some(&payload[6..]);
+        }
+        pos += 2 + length;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::ImageEncode;
+
+    #[test]
+    fn test_decode_reads_exif_orientation() {
+        let buffer = ImageBuffer::new(
+            vec![128; 8 * 4 * 3],
+            8,
+            4,
+            PixelFormat::Rgb,
+            ComponentType::U8,
+            ColorSpace::Srgb,
+        )
+        .unwrap();
+        let encoded = JpegImage::from_raw_parts(buffer, ImageMetadata::default())
+            .unwrap()
+            .encode_to_bytes()
+            .unwrap();
+
+        // Big-endian TIFF with a single Orientation = 6 entry
+        let mut tiff = b"MM\0\x2A\0\0\0\x08\0\x01".to_vec();
+        tiff.extend_from_slice(&[0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, 0, 0, 0, 0]);
+        let mut app1 = vec![0xFF, 0xE1];
+        app1.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        app1.extend_from_slice(b"Exif\0\0");
+        app1.extend_from_slice(&tiff);
+        let jpeg = [&encoded[..2], &app1, &encoded[2..]].concat();
+
+        let image = JpegImage::decode_from_bytes(&jpeg).unwrap();
+        let exif = image.exif().unwrap();
+        assert_eq!(exif.ifd0.orientation, Some(Orientation::RightTop));
+        assert_eq!(exif.raw_bytes.as_deref(), Some(&tiff[..]));
+        assert!(
+            JpegImage::decode_from_bytes(&encoded)
+                .unwrap()
+                .exif()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_embed_xmp_replaces_packet_after_exif() {
+        let buffer = ImageBuffer::new(
+            vec![128; 8 * 8 * 3],
+            8,
+            8,
+            PixelFormat::Rgb,
+            ComponentType::U8,
+            ColorSpace::Srgb,
+        )
+        .unwrap();
+        let encoded = JpegImage::from_raw_parts(buffer, ImageMetadata::default())
+            .unwrap()
+            .encode_to_bytes()
+            .unwrap();
+        let mut exif = vec![0xFF, 0xE1, 0, 8];
+        exif.extend_from_slice(b"Exif\0\0");
+        let jpeg = [&encoded[..2], &exif, &encoded[2..]].concat();
+
+        let once = embed_xmp(&jpeg, "<x:xmpmeta>first</x:xmpmeta>").unwrap();
+        let twice = embed_xmp(&once, "<x:xmpmeta>again</x:xmpmeta>").unwrap();
+
+        // EXIF stays first, and only the new packet is left.
+        assert_eq!(&twice[2..12], &exif[..]);
+        let packets = twice
+            .windows(XMP_SIGNATURE.len())
+            .filter(|w| *w == XMP_SIGNATURE)
+            .count();
+        assert_eq!(packets, 1);
+        assert_eq!(
+            XmpData::find_packet(&twice),
+            Some("<x:xmpmeta>again</x:xmpmeta>")
+        );
+        assert_eq!(twice.len(), once.len());
+        JpegImage::decode_from_bytes(&twice).unwrap();
+
+        assert!(embed_xmp(b"GIF89a", "<x:xmpmeta/>").is_none());
+    }
+}
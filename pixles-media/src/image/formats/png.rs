@@ -8,68 +8,91 @@ use crate::image::metadata::{
     motion::{AuxiliaryImage, MotionPhotoInfo},
     raw::RawSensorInfo,
 };
+use crate::image::types::PngSettings;
 use crate::image::{
-    Image, ImageDecode, ImageEncode, ImageError, ImageMetadata, buffer::ImageBuffer,
+    Image, ImageDecode, ImageEncode, ImageError, ImageMetadata,
+    buffer::{ComponentType, ImageBuffer, PixelFormat},
 };
 use crate::metadata::{
     ColorSpace, DeviceMetadata, c2pa::C2PAManifest, exif::ExifData, geo::GpsLocation,
     icc::IccProfile, xmp::XmpData,
 };
 
+/// Compression level used when no `PngSettings` are attached.
+const DEFAULT_COMPRESSION_LEVEL: u8 = 6;
+
+/// PNG image. 16-bit images are kept as `ComponentType::U16` (native-endian).
 #[derive(Debug, Clone)]
-pub struct PngImage {}
+pub struct PngImage {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+    format: PixelFormat,
+    component_type: ComponentType,
+    color_space: ColorSpace,
+    file_size_bytes: u64,
+    settings: Option<PngSettings>,
+}
+
+impl PngImage {
+    /// Attaches encoder settings (compression level, output bit depth).
+    pub fn with_settings(mut self, settings: PngSettings) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+}
 
 impl ImageMetadataExtractor for PngImage {
     fn get_date_taken(&self) -> Option<chrono::DateTime<chrono::Utc>> {
-        unimplemented!()
+        None
     }
     fn get_dimensions(&self) -> (u32, u32) {
-        unimplemented!()
+        (self.width, self.height)
     }
     fn get_bit_depth(&self) -> u8 {
-        unimplemented!()
+        (self.component_type.bytes_per_component() * 8) as u8
     }
     fn get_color_space(&self) -> ColorSpace {
-        unimplemented!()
+        self.color_space
     }
     fn get_file_size(&self) -> u64 {
-        unimplemented!()
+        self.file_size_bytes
     }
     fn get_device_metadata(&self) -> Option<DeviceMetadata> {
-        unimplemented!()
+        None
     }
     fn get_capture_settings(&self) -> Option<CaptureSettings> {
-        unimplemented!()
+        None
     }
     fn get_location(&self) -> Option<GpsLocation> {
-        unimplemented!()
+        None
     }
     fn get_content(&self) -> Option<ContentMetadata> {
-        unimplemented!()
+        None
     }
     fn raw_info(&self) -> Option<RawSensorInfo> {
-        unimplemented!()
+        None
     }
     fn exif(&self) -> Option<ExifData> {
-        unimplemented!()
+        None
     }
     fn xmp(&self) -> Option<XmpData> {
-        unimplemented!()
+        None
     }
     fn iptc(&self) -> Option<IptcData> {
-        unimplemented!()
+        None
     }
     fn icc_profile(&self) -> Option<IccProfile> {
-        unimplemented!()
+        None
     }
     fn motion_metadata(&self) -> Option<MotionPhotoInfo> {
-        unimplemented!()
+        None
     }
     fn auxiliary_images(&self) -> Vec<AuxiliaryImage> {
-        unimplemented!()
+        vec![]
     }
     fn c2pa_manifest(&self) -> Option<C2PAManifest> {
-        unimplemented!()
+        None
     }
 }
 
@@ -79,26 +102,231 @@ impl Image for PngImage {
     }
 
     fn get_buffer(&self) -> ImageBuffer {
-        unimplemented!()
+        ImageBuffer::new(
+            self.data.clone(),
+            self.width as usize,
+            self.height as usize,
+            self.format,
+            self.component_type,
+            self.color_space,
+        )
+        .expect("Failed to create ImageBuffer from internal data")
     }
 
-    fn from_raw_parts(_buffer: ImageBuffer, _metadata: ImageMetadata) -> Result<Self, ImageError> {
-        unimplemented!()
+    fn from_raw_parts(buffer: ImageBuffer, _metadata: ImageMetadata) -> Result<Self, ImageError> {
+        // PNG has no CMYK; U16 is kept as-is, F32 is narrowed to 8 bits.
+        let buffer = match (buffer.format, buffer.component_type) {
+            (PixelFormat::Cmyk, _) => buffer.into_u8()?.into_rgba8()?,
+            (_, ComponentType::F32) => buffer.into_u8()?,
+            _ => buffer,
+        };
+
+        Ok(Self {
+            width: buffer.width as u32,
+            height: buffer.height as u32,
+            format: buffer.format,
+            component_type: buffer.component_type,
+            color_space: buffer.color_space,
+            data: buffer.data,
+            file_size_bytes: 0, // Generated image, no file size yet
+            settings: None,
+        })
     }
 }
 
 impl ImageDecode for PngImage {
-    fn decode_from_bytes(_bytes: &[u8]) -> Result<Self, ImageError> {
-        unimplemented!()
+    fn decode_from_bytes(bytes: &[u8]) -> Result<Self, ImageError> {
+        let mut decoder = ::png::Decoder::new(std::io::Cursor::new(bytes));
+        // Palette → RGB(A), low bit depths → 8 bits, tRNS → alpha channel
+        decoder.set_transformations(::png::Transformations::EXPAND);
+        let mut reader = decoder
+            .read_info()
+            .map_err(|e| ImageError::Decode(e.to_string()))?;
+
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut data)
+            .map_err(|e| ImageError::Decode(e.to_string()))?;
+        data.truncate(info.buffer_size());
+
+        let format = match info.color_type {
+            ::png::ColorType::Grayscale => PixelFormat::Gray,
+            ::png::ColorType::Rgb => PixelFormat::Rgb,
+            ::png::ColorType::Rgba => PixelFormat::Rgba,
+            ::png::ColorType::GrayscaleAlpha => {
+                // No gray+alpha pixel format; widen to RGBA.
+                data = expand_gray_alpha(&data, info.bit_depth == ::png::BitDepth::Sixteen);
+                PixelFormat::Rgba
+            }
+            ::png::ColorType::Indexed => {
+                return Err(ImageError::Decode(
+                    "Indexed PNG was not expanded".to_string(),
+                ));
+            }
+        };
+
+        let component_type = if info.bit_depth == ::png::BitDepth::Sixteen {
+            // PNG samples are big-endian; buffers are native-endian.
+            for sample in data.chunks_exact_mut(2) {
+                let v = u16::from_be_bytes([sample[0], sample[1]]);
+                sample.copy_from_slice(&v.to_ne_bytes());
+            }
+            ComponentType::U16
+        } else {
+            ComponentType::U8
+        };
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            data,
+            format,
+            component_type,
+            color_space: ColorSpace::Srgb,
+            file_size_bytes: bytes.len() as u64,
+            settings: None,
+        })
     }
 }
 
 impl ImageEncode for PngImage {
-    fn encode<W: Write>(&self, _writer: &mut W) -> Result<(), ImageError> {
-        unimplemented!()
+    fn encode<W: Write>(&self, writer: &mut W) -> Result<(), ImageError> {
+        let level = self
+            .settings
+            .as_ref()
+            .map_or(DEFAULT_COMPRESSION_LEVEL, |s| s.compression_level);
+        let sixteen_bit = match self.settings.as_ref().map(|s| s.bit_depth) {
+            Some(16) => true,
+            Some(_) => false,
+            None => self.component_type == ComponentType::U16,
+        };
+
+        let color_type = match self.format {
+            PixelFormat::Gray => ::png::ColorType::Grayscale,
+            PixelFormat::Rgb => ::png::ColorType::Rgb,
+            PixelFormat::Rgba => ::png::ColorType::Rgba,
+            PixelFormat::Cmyk => {
+                return Err(ImageError::Encode("PNG does not support CMYK".to_string()));
+            }
+        };
+
+        let mut encoder = ::png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(color_type);
+        encoder.set_depth(if sixteen_bit {
+            ::png::BitDepth::Sixteen
+        } else {
+            ::png::BitDepth::Eight
+        });
+        encoder.set_compression(match level {
+            0..=2 => ::png::Compression::Fast,
+            3..=6 => ::png::Compression::Default,
+            _ => ::png::Compression::Best,
+        });
+
+        let samples = match (self.component_type, sixteen_bit) {
+            (ComponentType::U16, true) => self
+                .data
+                .chunks_exact(2)
+                .flat_map(|c| u16::from_ne_bytes([c[0], c[1]]).to_be_bytes())
+                .collect(),
+            (ComponentType::U16, false) => self
+                .data
+                .chunks_exact(2)
+                .map(|c| (u16::from_ne_bytes([c[0], c[1]]) >> 8) as u8)
+                .collect(),
+            (_, true) => self
+                .data
+                .iter()
+                .flat_map(|&v| (u16::from(v) * 257).to_be_bytes())
+                .collect(),
+            (_, false) => self.data.clone(),
+        };
+
+        let mut png_writer = encoder
+            .write_header()
+            .map_err(|e| ImageError::Encode(e.to_string()))?;
+        png_writer
+            .write_image_data(&samples)
+            .map_err(|e| ImageError::Encode(e.to_string()))?;
+        png_writer
+            .finish()
+            .map_err(|e| ImageError::Encode(e.to_string()))
+    }
+
+    async fn save(&self, path: &Path) -> Result<(), ImageError> {
+        let data = self.encode_to_bytes()?;
+        tokio::fs::write(path, data).await.map_err(ImageError::Io)
+    }
+}
+
+/// Gray+alpha samples → RGBA samples of the same width.
+fn expand_gray_alpha(data: &[u8], sixteen_bit: bool) -> Vec<u8> {
+    let sample = if sixteen_bit { 2 } else { 1 };
+    let mut out = Vec::with_capacity(data.len() * 2);
+    for pixel in data.chunks_exact(sample * 2) {
+        let (gray, alpha) = pixel.split_at(sample);
+        out.extend_from_slice(gray);
+        out.extend_from_slice(gray);
+        out.extend_from_slice(gray);
+        out.extend_from_slice(alpha);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(image: &PngImage) -> PngImage {
+        PngImage::decode_from_bytes(&image.encode_to_bytes().unwrap()).unwrap()
+    }
+
+    fn raw(data: Vec<u8>, format: PixelFormat, component_type: ComponentType) -> PngImage {
+        let buffer =
+            ImageBuffer::new(data, 2, 1, format, component_type, ColorSpace::Srgb).unwrap();
+        PngImage::from_raw_parts(buffer, ImageMetadata::default()).unwrap()
+    }
+
+    #[test]
+    fn test_rgba_round_trip_keeps_alpha() {
+        let pixels = vec![255, 0, 0, 128, 0, 255, 0, 0];
+        let decoded = round_trip(&raw(pixels.clone(), PixelFormat::Rgba, ComponentType::U8));
+        let buffer = decoded.get_buffer();
+        assert_eq!(buffer.format, PixelFormat::Rgba);
+        assert_eq!(buffer.data, pixels);
+        assert_eq!(decoded.get_dimensions(), (2, 1));
+    }
+
+    #[test]
+    fn test_sixteen_bit_round_trip() {
+        let samples: Vec<u8> = [1000u16, 65535, 0, 42, 7, 60000]
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect();
+        let decoded = round_trip(&raw(samples.clone(), PixelFormat::Rgb, ComponentType::U16));
+        assert_eq!(decoded.get_bit_depth(), 16);
+        assert_eq!(decoded.get_buffer().data, samples);
+    }
+
+    #[test]
+    fn test_settings_narrow_to_eight_bit() {
+        let samples: Vec<u8> = [0x1234u16, 0xFF00]
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect();
+        let image =
+            raw(samples, PixelFormat::Gray, ComponentType::U16).with_settings(PngSettings {
+                compression_level: 9,
+                bit_depth: 8,
+                resolution: None,
+            });
+        let decoded = round_trip(&image);
+        assert_eq!(decoded.get_bit_depth(), 8);
+        assert_eq!(decoded.get_buffer().data, vec![0x12, 0xFF]);
     }
 
-    async fn save(&self, _path: &Path) -> Result<(), ImageError> {
-        unimplemented!()
+    #[test]
+    fn test_expand_gray_alpha() {
+        assert_eq!(expand_gray_alpha(&[10, 200], false), vec![10, 10, 10, 200]);
     }
 }
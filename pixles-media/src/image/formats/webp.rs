@@ -1,8 +1,10 @@
 use std::io::Write;
 use std::path::Path;
 
+use crate::image::types::WebPSettings;
 use crate::image::{
-    Image, ImageDecode, ImageEncode, ImageError, ImageMetadata, buffer::ImageBuffer,
+    Image, ImageDecode, ImageEncode, ImageError, ImageMetadata,
+    buffer::{ComponentType, ImageBuffer, PixelFormat},
 };
 use crate::metadata::{
     ColorSpace, DeviceMetadata, exif::ExifData, geo::GpsLocation, icc::IccProfile, xmp::XmpData,
@@ -18,60 +20,82 @@ use crate::{
     metadata::c2pa::C2PAManifest,
 };
 
+/// Lossy quality used when no `WebPSettings` are attached.
+const DEFAULT_QUALITY: u8 = 80;
+/// libwebp's default compression method (0 = fast, 6 = slowest/best).
+const DEFAULT_EFFORT: u8 = 4;
+
+/// WebP image (first frame of animated files). Stored as 8-bit RGB or RGBA.
 #[derive(Debug, Clone)]
-pub struct WebpImage {}
+pub struct WebpImage {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+    format: PixelFormat,
+    color_space: ColorSpace,
+    file_size_bytes: u64,
+    settings: Option<WebPSettings>,
+}
+
+impl WebpImage {
+    /// Attaches encoder settings (quality, lossless mode, effort).
+    pub fn with_settings(mut self, settings: WebPSettings) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+}
 
 impl ImageMetadataExtractor for WebpImage {
     fn get_date_taken(&self) -> Option<chrono::DateTime<chrono::Utc>> {
-        unimplemented!()
+        None
     }
     fn get_dimensions(&self) -> (u32, u32) {
-        unimplemented!()
+        (self.width, self.height)
     }
     fn get_bit_depth(&self) -> u8 {
-        unimplemented!()
+        8
     }
     fn get_color_space(&self) -> ColorSpace {
-        unimplemented!()
+        self.color_space
     }
     fn get_file_size(&self) -> u64 {
-        unimplemented!()
+        self.file_size_bytes
     }
     fn get_device_metadata(&self) -> Option<DeviceMetadata> {
-        unimplemented!()
+        None
     }
     fn get_capture_settings(&self) -> Option<CaptureSettings> {
-        unimplemented!()
+        None
     }
     fn get_location(&self) -> Option<GpsLocation> {
-        unimplemented!()
+        None
     }
     fn get_content(&self) -> Option<ContentMetadata> {
-        unimplemented!()
+        None
     }
     fn raw_info(&self) -> Option<RawSensorInfo> {
-        unimplemented!()
+        None
     }
     fn exif(&self) -> Option<ExifData> {
-        unimplemented!()
+        None
     }
     fn xmp(&self) -> Option<XmpData> {
-        unimplemented!()
+        None
     }
     fn iptc(&self) -> Option<IptcData> {
-        unimplemented!()
+        None
     }
     fn icc_profile(&self) -> Option<IccProfile> {
-        unimplemented!()
+        None
     }
     fn motion_metadata(&self) -> Option<MotionPhotoInfo> {
-        unimplemented!()
+        None
     }
     fn auxiliary_images(&self) -> Vec<AuxiliaryImage> {
-        unimplemented!()
+        vec![]
     }
     fn c2pa_manifest(&self) -> Option<C2PAManifest> {
-        unimplemented!()
+        None
     }
 }
 
@@ -81,26 +105,151 @@ impl Image for WebpImage {
     }
 
     fn get_buffer(&self) -> ImageBuffer {
-        unimplemented!()
+        ImageBuffer::new(
+            self.data.clone(),
+            self.width as usize,
+            self.height as usize,
+            self.format,
+            ComponentType::U8,
+            self.color_space,
+        )
+        .expect("Failed to create ImageBuffer from internal data")
     }
 
-    fn from_raw_parts(_buffer: ImageBuffer, _metadata: ImageMetadata) -> Result<Self, ImageError> {
-        unimplemented!()
+    fn from_raw_parts(buffer: ImageBuffer, _metadata: ImageMetadata) -> Result<Self, ImageError> {
+        // WebP only encodes RGB and RGBA
+        let buffer = match buffer.format {
+            PixelFormat::Rgb | PixelFormat::Rgba => buffer.into_u8()?,
+            _ => buffer.into_u8()?.into_rgba8()?,
+        };
+
+        Ok(Self {
+            width: buffer.width as u32,
+            height: buffer.height as u32,
+            format: buffer.format,
+            color_space: buffer.color_space,
+            data: buffer.data,
+            file_size_bytes: 0, // Generated image, no file size yet
+            settings: None,
+        })
     }
 }
 
 impl ImageDecode for WebpImage {
-    fn decode_from_bytes(_bytes: &[u8]) -> Result<Self, ImageError> {
-        unimplemented!()
+    fn decode_from_bytes(bytes: &[u8]) -> Result<Self, ImageError> {
+        let image = ::webp::Decoder::new(bytes)
+            .decode()
+            .ok_or_else(|| ImageError::Decode("Invalid WebP data".to_string()))?;
+
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            format: if image.is_alpha() {
+                PixelFormat::Rgba
+            } else {
+                PixelFormat::Rgb
+            },
+            data: image.to_vec(),
+            color_space: ColorSpace::Srgb,
+            file_size_bytes: bytes.len() as u64,
+            settings: None,
+        })
     }
 }
 
 impl ImageEncode for WebpImage {
-    fn encode<W: Write>(&self, _writer: &mut W) -> Result<(), ImageError> {
-        unimplemented!()
+    fn encode<W: Write>(&self, writer: &mut W) -> Result<(), ImageError> {
+        let (quality, lossless, effort) = self
+            .settings
+            .as_ref()
+            .map_or((DEFAULT_QUALITY, false, DEFAULT_EFFORT), |s| {
+                (s.quality, s.lossless, s.effort)
+            });
+
+        let mut config = ::webp::WebPConfig::new()
+            .map_err(|_| ImageError::Encode("Failed to initialise WebP config".to_string()))?;
+        config.quality = f32::from(quality.min(100));
+        config.lossless = i32::from(lossless);
+        config.method = i32::from(effort.min(6));
+
+        let encoder = match self.format {
+            PixelFormat::Rgba => ::webp::Encoder::from_rgba(&self.data, self.width, self.height),
+            _ => ::webp::Encoder::from_rgb(&self.data, self.width, self.height),
+        };
+        let encoded = encoder
+            .encode_advanced(&config)
+            .map_err(|e| ImageError::Encode(format!("{e:?}")))?;
+        writer.write_all(&encoded).map_err(ImageError::Io)
+    }
+
+    async fn save(&self, path: &Path) -> Result<(), ImageError> {
+        let data = self.encode_to_bytes()?;
+        tokio::fs::write(path, data).await.map_err(ImageError::Io)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(data: Vec<u8>, format: PixelFormat) -> WebpImage {
+        let buffer =
+            ImageBuffer::new(data, 2, 2, format, ComponentType::U8, ColorSpace::Srgb).unwrap();
+        WebpImage::from_raw_parts(buffer, ImageMetadata::default()).unwrap()
+    }
+
+    #[test]
+    fn test_lossless_round_trip_keeps_alpha() {
+        let pixels = vec![
+            255, 0, 0, 255, //
+            0, 255, 0, 128, //
+            0, 0, 255, 0, //
+            10, 20, 30, 255,
+        ];
+        let image = raw(pixels.clone(), PixelFormat::Rgba).with_settings(WebPSettings {
+            quality: 100,
+            lossless: true,
+            effort: 6,
+            resolution: None,
+        });
+
+        let decoded = WebpImage::decode_from_bytes(&image.encode_to_bytes().unwrap()).unwrap();
+        let buffer = decoded.get_buffer();
+        assert_eq!(buffer.format, PixelFormat::Rgba);
+        assert_eq!(decoded.get_dimensions(), (2, 2));
+        // Lossless WebP may rewrite the colour of fully transparent pixels.
+        for (i, (got, want)) in buffer.data.chunks(4).zip(pixels.chunks(4)).enumerate() {
+            if want[3] != 0 {
+                assert_eq!(got, want, "pixel {i}");
+            } else {
+                assert_eq!(got[3], 0, "pixel {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_lossy_default_encodes_rgb() {
+        let image = raw(vec![128; 12], PixelFormat::Rgb);
+        let encoded = image.encode_to_bytes().unwrap();
+        assert_eq!(&encoded[..4], b"RIFF");
+        assert_eq!(&encoded[8..12], b"WEBP");
+
+        let decoded = WebpImage::decode_from_bytes(&encoded).unwrap();
+        assert_eq!(decoded.get_buffer().format, PixelFormat::Rgb);
     }
 
-    async fn save(&self, _path: &Path) -> Result<(), ImageError> {
-        unimplemented!()
+    #[test]
+    fn test_gray_is_expanded() {
+        let buffer = ImageBuffer::new(
+            vec![7; 4],
+            2,
+            2,
+            PixelFormat::Gray,
+            ComponentType::U8,
+            ColorSpace::Srgb,
+        )
+        .unwrap();
+        let image = WebpImage::from_raw_parts(buffer, ImageMetadata::default()).unwrap();
+        assert_eq!(image.get_buffer().format, PixelFormat::Rgba);
     }
 }
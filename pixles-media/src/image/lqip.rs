@@ -1,3 +1,5 @@
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
 use thiserror::Error;
 
 use crate::{
@@ -18,10 +20,20 @@ impl LQIP {
 
     /// Generate a LQIP (thumbhash) from an ImageBuffer
     ///
-    /// The buffer MUST be RGBA.
+    /// The buffer MUST be RGBA, and upright (see
+    /// [`normalize_orientation`](crate::image::normalize_orientation)).
     /// You do not need to resize as it will be done to input internally.
     /// Returns a byte sequence
     pub async fn from_image_buffer<T>(buffer: T) -> Result<LQIP, LQIPError>
+    where
+        T: AsRef<ImageBuffer>,
+    {
+        Self::from_image_buffer_blocking(buffer)
+    }
+
+    /// Synchronous variant of [`LQIP::from_image_buffer`] for callers
+    /// without an async runtime.
+    pub fn from_image_buffer_blocking<T>(buffer: T) -> Result<LQIP, LQIPError>
     where
         T: AsRef<ImageBuffer>,
     {
@@ -81,6 +93,11 @@ impl LQIP {
     pub fn as_bytes(&self) -> &[u8] {
         &self.0
     }
+
+    /// Standard base64 encoding of the hash, for text columns and JSON.
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(&self.0)
+    }
 }
 
 #[derive(Error, Debug)]
@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
 
+/// Photoshop image resource holding IPTC-IIM records.
+const IPTC_RESOURCE: &[u8] = b"8BIM\x04\x04";
+
 // TODO: Complete all the tags in the spec.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct IptcData {
     // ApplicationRecord tags
     pub byline: Option<Vec<String>>,
@@ -14,3 +17,112 @@ pub struct IptcData {
     pub country_name: Option<String>,
     pub source: Option<String>,
 }
+
+impl IptcData {
+    /// Finds the IPTC-IIM block among a file's Photoshop image resources
+    /// (JPEG APP13, TIFF tag 34377) by scanning for its `8BIM` header.
+    pub fn find_block(bytes: &[u8]) -> Option<&[u8]> {
+        let start = bytes
+            .windows(IPTC_RESOURCE.len())
+            .position(|w| w == IPTC_RESOURCE)?;
+        let mut pos = start + IPTC_RESOURCE.len();
+        // Pascal-string name, padded to an even length including its length byte.
+        let name_len = usize::from(*bytes.get(pos)?);
+        pos += (name_len + 2) & !1;
+        let size = u32::from_be_bytes(bytes.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        pos += 4;
+        bytes.get(pos..pos + size)
+    }
+
+    /// Parses a block of IPTC-IIM datasets. Text is read as UTF-8, falling
+    /// back to Latin-1. Returns `None` if the block holds no datasets.
+    pub fn from_iim(block: &[u8]) -> Option<IptcData> {
+        let mut data = IptcData::default();
+        let mut found = false;
+        let mut pos = 0;
+        while pos + 5 <= block.len() && block[pos] == 0x1C {
+            let (record, dataset) = (block[pos + 1], block[pos + 2]);
+            let mut len = usize::from(u16::from_be_bytes([block[pos + 3], block[pos + 4]]));
+            pos += 5;
+            // Extended dataset: the low bits give the size of the length field.
+            if len & 0x8000 != 0 {
+                let width = len & 0x7FFF;
+                let field = block.get(pos..pos + width).filter(|f| f.len() <= 4)?;
+                len = field.iter().fold(0, |acc, &b| (acc << 8) | usize::from(b));
+                pos += width;
+            }
+            let value = block.get(pos..pos + len)?;
+            pos += len;
+            found = true;
+
+            if record != 2 {
+                continue;
+            }
+            let text = decode_text(value);
+            match dataset {
+                25 => data.keywords.push(text),
+                80 => data.byline.get_or_insert_with(Vec::new).push(text),
+                85 => data.byline_title.get_or_insert_with(Vec::new).push(text),
+                90 => data.city = Some(text),
+                95 => data.province_state = Some(text),
+                101 => data.country_name = Some(text),
+                115 => data.source = Some(text),
+                116 => data.copyright_notice = Some(text),
+                120 => data.caption = Some(text),
+                _ => {}
+            }
+        }
+        found.then_some(data)
+    }
+}
+
+fn decode_text(bytes: &[u8]) -> String {
+    let text = match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_string(),
+        Err(_) => bytes.iter().map(|&b| char::from(b)).collect(),
+    };
+    text.trim_end_matches('\0').trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset(record: u8, dataset: u8, value: &[u8]) -> Vec<u8> {
+        let mut out = vec![0x1C, record, dataset];
+        out.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        out.extend_from_slice(value);
+        out
+    }
+
+    #[test]
+    fn test_from_iim_reads_application_record() {
+        let mut block = dataset(1, 90, b"\x1b%G");
+        block.extend(dataset(2, 25, b"Kyoto"));
+        block.extend(dataset(2, 25, "Fushimi Inari-taisha".as_bytes()));
+        block.extend(dataset(2, 120, b"Caf\xe9"));
+        block.extend(dataset(2, 80, b"A. Photographer"));
+
+        let iptc = IptcData::from_iim(&block).unwrap();
+        assert_eq!(iptc.keywords, vec!["Kyoto", "Fushimi Inari-taisha"]);
+        assert_eq!(iptc.caption.as_deref(), Some("Café"));
+        assert_eq!(iptc.byline, Some(vec!["A. Photographer".to_string()]));
+        assert!(IptcData::from_iim(b"").is_none());
+    }
+
+    #[test]
+    fn test_find_block_in_photoshop_resources() {
+        let iim = dataset(2, 25, b"sunset");
+        let mut app13 = b"Photoshop 3.0\0".to_vec();
+        // An unrelated resource first, then IPTC with an empty name.
+        app13.extend_from_slice(b"8BIM\x03\xed\0\0\0\0\0\x02ab");
+        app13.extend_from_slice(IPTC_RESOURCE);
+        app13.extend_from_slice(&[0, 0]);
+        app13.extend_from_slice(&(iim.len() as u32).to_be_bytes());
+        app13.extend_from_slice(&iim);
+
+        let block = IptcData::find_block(&app13).unwrap();
+        assert_eq!(block, iim.as_slice());
+        assert_eq!(IptcData::from_iim(block).unwrap().keywords, vec!["sunset"]);
+    }
+}
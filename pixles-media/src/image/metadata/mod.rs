@@ -14,7 +14,7 @@ use crate::{
     },
     metadata::{
         ColorSpace, DeviceMetadata, c2pa::C2PAManifest, exif::ExifData, geo::GpsLocation,
-        icc::IccProfile, xmp::XmpData,
+        icc::IccProfile, orientation::Orientation, xmp::XmpData,
     },
 };
 
@@ -122,6 +122,16 @@ pub struct ImageMetadata {
     pub c2pa_manifest: Option<C2PAManifest>,
 }
 
+impl ImageMetadata {
+    /// EXIF orientation of the stored pixels (`TopLeft` when absent).
+    pub fn orientation(&self) -> Orientation {
+        self.exif
+            .as_ref()
+            .and_then(|exif| exif.ifd0.orientation)
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ContentMetadata {
     pub title: Option<String>,
@@ -1,5 +1,14 @@
+use std::ops::Range;
+
 use serde::{Deserialize, Serialize};
 
+/// XMP packets are expected within this many bytes of the file start.
+const XMP_SEARCH_LIMIT: usize = 256 * 1024;
+
+const SAMSUNG_TRAILER_MAGIC: &[u8; 4] = b"SEFT";
+const SAMSUNG_DIRECTORY_MAGIC: &[u8; 4] = b"SEFH";
+const SAMSUNG_MOTION_NAME: &[u8] = b"MotionPhoto_Data";
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MotionPhotoInfo {
     pub format: MotionFormat,
@@ -12,8 +21,129 @@ pub struct MotionPhotoInfo {
 
     /// Apple Live Photo UUID (connects HEIC to a separate .MOV file)
     pub content_identifier: Option<String>,
-    // TODO: If you support file extraction, add a method to stream
-    // this data out to a separate .mp4 file.
+}
+
+impl MotionPhotoInfo {
+    /// Detects an embedded motion video trailer in a JPEG/HEIC file's bytes.
+    ///
+    /// Recognises Google Motion Photos (`GCamera:MicroVideoOffset` or a
+    /// `Container:Directory` item with `Semantic="MotionPhoto"`) and Samsung
+    /// Motion Photos (`MotionPhoto_Data` in the SEF trailer). The candidate
+    /// range must start with an ISO-BMFF `ftyp` box, so false positives are
+    /// unlikely. `data_offset` is measured from the end of the file.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        let file_len = data.len() as u64;
+        let (format, start, length) = google_motion_range(data)
+            .map(|r| (MotionFormat::GoogleMicroVideo, r))
+            .or_else(|| samsung_motion_range(data).map(|r| (MotionFormat::SamsungMotion, r)))
+            .map(|(format, r)| (format, r.start, r.end - r.start))?;
+
+        Some(MotionPhotoInfo {
+            format,
+            data_offset: Some(file_len - start),
+            data_length: Some(length),
+            content_identifier: None,
+        })
+    }
+
+    /// Byte range of the embedded video within a file of `file_len` bytes.
+    pub fn video_range(&self, file_len: u64) -> Option<Range<u64>> {
+        let offset = self.data_offset?;
+        let start = file_len.checked_sub(offset)?;
+        let end = start.checked_add(self.data_length.unwrap_or(offset))?;
+        (end <= file_len && start < end).then_some(start..end)
+    }
+}
+
+fn google_motion_range(data: &[u8]) -> Option<Range<u64>> {
+    let head = &data[..data.len().min(XMP_SEARCH_LIMIT)];
+    // Offset from the end is also the length: the video runs to end of file.
+    let from_end = xmp_number(head, b"MicroVideoOffset").or_else(|| {
+        // Newer format: the motion item's length in the container directory.
+        let semantic = find(head, b"Semantic=\"MotionPhoto\"")?;
+        let item_start = head[..semantic].iter().rposition(|&b| b == b'<')?;
+        let item_end = semantic + head[semantic..].iter().position(|&b| b == b'>')?;
+        xmp_number(&head[item_start..item_end], b"Length")
+    })?;
+
+    let len = data.len() as u64;
+    let start = len.checked_sub(from_end)?;
+    (from_end > 0 && is_iso_bmff(data, start)).then_some(start..len)
+}
+
+fn samsung_motion_range(data: &[u8]) -> Option<Range<u64>> {
+    // Trailer: ... [SEFH directory] [u32 directory length] "SEFT"
+    let len = data.len();
+    if len < 8 || &data[len - 4..] != SAMSUNG_TRAILER_MAGIC {
+        return None;
+    }
+    let dir_len = read_u32_le(data, len - 8)? as usize;
+    let dir_pos = (len - 8).checked_sub(dir_len)?;
+    if data.get(dir_pos..dir_pos + 4)? != SAMSUNG_DIRECTORY_MAGIC {
+        return None;
+    }
+    let count = read_u32_le(data, dir_pos + 8)? as usize;
+
+    // Entries: u16 padding, u16 type, u32 offset back from the directory,
+    // u32 block length. Blocks: u16 padding, u16 type, u32 name length,
+    // name, payload.
+    for i in 0..count.min(64) {
+        let entry = dir_pos + 12 + i * 12;
+        let back = read_u32_le(data, entry + 4)? as usize;
+        let size = read_u32_le(data, entry + 8)? as usize;
+        let block = dir_pos.checked_sub(back)?;
+        let name_len = read_u32_le(data, block + 4)? as usize;
+        let name = data.get(block + 8..block + 8 + name_len)?;
+        if name != SAMSUNG_MOTION_NAME {
+            continue;
+        }
+        let start = block + 8 + name_len;
+        let end = (block + size).min(dir_pos);
+        if start < end && is_iso_bmff(data, start as u64) {
+            return Some(start as u64..end as u64);
+        }
+    }
+    None
+}
+
+/// Parses `name="digits"` (attribute form) or `<ns:name>digits<` (element form).
+fn xmp_number(xmp: &[u8], name: &[u8]) -> Option<u64> {
+    let mut pos = 0;
+    while let Some(found) = find(&xmp[pos..], name) {
+        let after = pos + found + name.len();
+        let rest = &xmp[after..];
+        let digits = match rest.first() {
+            Some(b'=') => rest.get(2..),
+            Some(b'>') => rest.get(1..),
+            _ => None,
+        };
+        if let Some(digits) = digits {
+            let n = digits.iter().take_while(|b| b.is_ascii_digit()).count();
+            if let Some(v) = std::str::from_utf8(&digits[..n])
+                .ok()
+                .and_then(|s| s.parse().ok())
+            {
+                return Some(v);
+            }
+        }
+        pos = after;
+    }
+    None
+}
+
+fn is_iso_bmff(data: &[u8], start: u64) -> bool {
+    usize::try_from(start)
+        .ok()
+        .and_then(|s| data.get(s + 4..s + 8))
+        .is_some_and(|t| t == b"ftyp")
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn read_u32_le(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -43,3 +173,95 @@ pub enum AuxType {
     AlphaMatte, // Portrait mode transparency mask
     Disparity,  // Stereo disparity
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_mp4() -> Vec<u8> {
+        let mut v = vec![0, 0, 0, 16];
+        v.extend_from_slice(b"ftypisom");
+        v.extend_from_slice(&[0; 4]);
+        v.extend_from_slice(b"moov and mdat payload");
+        v
+    }
+
+    #[test]
+    fn test_google_micro_video_offset() {
+        let video = fake_mp4();
+        let mut file = b"\xFF\xD8 <x:xmpmeta GCamera:MicroVideo=\"1\" ".to_vec();
+        file.extend_from_slice(format!("GCamera:MicroVideoOffset=\"{}\"", video.len()).as_bytes());
+        file.extend_from_slice(b"/> jpeg body \xFF\xD9");
+        let photo_len = file.len() as u64;
+        file.extend_from_slice(&video);
+
+        let info = MotionPhotoInfo::detect(&file).unwrap();
+        assert!(matches!(info.format, MotionFormat::GoogleMicroVideo));
+        assert_eq!(
+            info.video_range(file.len() as u64),
+            Some(photo_len..file.len() as u64)
+        );
+    }
+
+    #[test]
+    fn test_google_container_directory() {
+        let video = fake_mp4();
+        let mut file = b"\xFF\xD8 <Container:Directory><rdf:Seq>".to_vec();
+        file.extend_from_slice(b"<rdf:li><Container:Item Item:Semantic=\"Primary\" Item:Mime=\"image/jpeg\"/></rdf:li>");
+        file.extend_from_slice(
+            format!(
+                "<rdf:li><Container:Item Item:Mime=\"video/mp4\" Item:Semantic=\"MotionPhoto\" Item:Length=\"{}\"/></rdf:li>",
+                video.len()
+            )
+            .as_bytes(),
+        );
+        file.extend_from_slice(b"</rdf:Seq></Container:Directory> \xFF\xD9");
+        let photo_len = file.len() as u64;
+        file.extend_from_slice(&video);
+
+        let info = MotionPhotoInfo::detect(&file).unwrap();
+        assert_eq!(
+            info.video_range(file.len() as u64),
+            Some(photo_len..file.len() as u64)
+        );
+    }
+
+    #[test]
+    fn test_samsung_sef_trailer() {
+        let video = fake_mp4();
+        let mut file = b"\xFF\xD8 jpeg body \xFF\xD9".to_vec();
+
+        let block_pos = file.len();
+        file.extend_from_slice(&[0, 0, 0x30, 0x0A]);
+        file.extend_from_slice(&(SAMSUNG_MOTION_NAME.len() as u32).to_le_bytes());
+        file.extend_from_slice(SAMSUNG_MOTION_NAME);
+        let video_start = file.len() as u64;
+        file.extend_from_slice(&video);
+        let block_size = file.len() - block_pos;
+
+        let dir_pos = file.len();
+        file.extend_from_slice(SAMSUNG_DIRECTORY_MAGIC);
+        file.extend_from_slice(&106u32.to_le_bytes());
+        file.extend_from_slice(&1u32.to_le_bytes());
+        file.extend_from_slice(&[0, 0, 0x30, 0x0A]);
+        file.extend_from_slice(&((dir_pos - block_pos) as u32).to_le_bytes());
+        file.extend_from_slice(&(block_size as u32).to_le_bytes());
+        let dir_len = file.len() - dir_pos;
+        file.extend_from_slice(&(dir_len as u32).to_le_bytes());
+        file.extend_from_slice(SAMSUNG_TRAILER_MAGIC);
+
+        let info = MotionPhotoInfo::detect(&file).unwrap();
+        assert!(matches!(info.format, MotionFormat::SamsungMotion));
+        assert_eq!(
+            info.video_range(file.len() as u64),
+            Some(video_start..video_start + video.len() as u64)
+        );
+    }
+
+    #[test]
+    fn test_plain_jpeg_has_no_motion() {
+        assert!(MotionPhotoInfo::detect(b"\xFF\xD8 plain \xFF\xD9").is_none());
+        // Offset pointing at something that is not MP4 is rejected.
+        assert!(MotionPhotoInfo::detect(b"MicroVideoOffset=\"4\" abcd").is_none());
+    }
+}
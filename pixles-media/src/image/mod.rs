@@ -10,12 +10,16 @@ use crate::image::{
     metadata::{ImageMetadata, ImageMetadataProvider},
     types::ImageFormat,
 };
+use crate::metadata::orientation::Orientation;
 
 pub mod buffer;
 pub mod formats;
 pub mod lqip;
 pub mod metadata;
+pub mod phash;
 pub mod presets;
+pub mod resample;
+pub mod transform;
 pub mod types;
 
 #[derive(Debug)]
@@ -264,6 +268,27 @@ pub trait ImageEncode: Image + Sync {
     fn save(&self, path: &Path) -> impl Future<Output = Result<(), ImageError>> + Send;
 }
 
+/// Rotates/flips `buffer` upright according to the EXIF orientation in
+/// `metadata`, then resets that orientation to `TopLeft` and updates the
+/// recorded dimensions.
+///
+/// Derivatives (thumbnails, previews, LQIPs) are generated from the result so
+/// that they display upright without consulting EXIF.
+pub fn normalize_orientation(buffer: &ImageBuffer, metadata: &mut ImageMetadata) -> ImageBuffer {
+    let orientation = metadata.orientation();
+    if orientation == Orientation::TopLeft {
+        return buffer.clone();
+    }
+
+    let upright = buffer.apply_orientation(orientation);
+    if let Some(exif) = metadata.exif.as_mut() {
+        exif.ifd0.orientation = Some(Orientation::TopLeft);
+    }
+    metadata.width = upright.width as u32;
+    metadata.height = upright.height as u32;
+    upright
+}
+
 /// Returns dimensions that maintain aspect ratio while ensuring the largest dimension is at most target_max
 pub fn resize_to_max_dimension(w: usize, h: usize, target_max: usize) -> (usize, usize) {
     // Determine the scale factor based on the larger dimension
@@ -308,7 +333,40 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "PngImage not yet implemented"]
+    fn test_normalize_orientation() {
+        let buffer = ImageBuffer::new(
+            vec![1, 2, 3, 4, 5, 6],
+            3,
+            2,
+            buffer::PixelFormat::Gray,
+            buffer::ComponentType::U8,
+            crate::metadata::ColorSpace::Srgb,
+        )
+        .unwrap();
+        let mut metadata = ImageMetadata {
+            width: 3,
+            height: 2,
+            exif: Some(crate::metadata::exif::ExifData {
+                ifd0: crate::metadata::exif::Ifd0Tags {
+                    orientation: Some(Orientation::RightTop),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let upright = normalize_orientation(&buffer, &mut metadata);
+        assert_eq!((upright.width, upright.height), (2, 3));
+        assert_eq!((metadata.width, metadata.height), (2, 3));
+        assert_eq!(metadata.orientation(), Orientation::TopLeft);
+
+        // Already upright: untouched
+        let again = normalize_orientation(&upright, &mut metadata);
+        assert_eq!(again.data, upright.data);
+    }
+
+    #[test]
     fn test_image_conversion() {
         use crate::image::formats::{jpeg::JpegImage, png::PngImage};
 
@@ -0,0 +1,92 @@
+use crate::image::buffer::{ImageBuffer, ImageBufferError};
+
+/// Width of the grid a [`dhash`] is computed on: one column more than the
+/// number of comparisons per row.
+const GRID_WIDTH: usize = 9;
+const GRID_HEIGHT: usize = 8;
+
+/// 64-bit difference hash (dHash) of an image.
+///
+/// The image is scaled to a 9×8 grid, ignoring its aspect ratio, and each
+/// bit records whether a cell is brighter than its right-hand neighbour.
+/// Resized, recompressed and lightly edited copies of a photo hash to values
+/// a few bits apart; compare them with [`hamming_distance`].
+///
+/// The buffer should be upright (see
+/// [`normalize_orientation`](crate::image::normalize_orientation)), so that
+/// rotated copies are recognised. Any size and component type is accepted.
+pub fn dhash(buffer: &ImageBuffer) -> Result<u64, ImageBufferError> {
+    let grid = buffer
+        .clone()
+        .into_u8()?
+        .into_rgba8()?
+        .resize(GRID_WIDTH, GRID_HEIGHT)?;
+    let luma: Vec<u32> = grid
+        .data
+        .chunks_exact(4)
+        .map(|px| 299 * u32::from(px[0]) + 587 * u32::from(px[1]) + 114 * u32::from(px[2]))
+        .collect();
+
+    let mut hash = 0u64;
+    for row in luma.chunks_exact(GRID_WIDTH) {
+        for pair in row.windows(2) {
+            hash = (hash << 1) | u64::from(pair[0] > pair[1]);
+        }
+    }
+    Ok(hash)
+}
+
+/// Number of bits in which two hashes differ, from 0 (identical) to 64.
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::buffer::{ComponentType, PixelFormat};
+    use crate::metadata::ColorSpace;
+
+    /// A horizontal gradient with a bright square, `width`×`height` pixels.
+    fn scene(width: usize, height: usize, invert: bool) -> ImageBuffer {
+        let mut data = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let mut v = (255 * x / width) as u8;
+                if x > width / 4 && x < width / 2 && y > height / 3 && y < height * 2 / 3 {
+                    v = 255;
+                }
+                if invert {
+                    v = 255 - v;
+                }
+                data.extend_from_slice(&[v, v, v]);
+            }
+        }
+        ImageBuffer::new(
+            data,
+            width,
+            height,
+            PixelFormat::Rgb,
+            ComponentType::U8,
+            ColorSpace::Srgb,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resized_copy_hashes_close() {
+        let original = dhash(&scene(400, 300, false)).unwrap();
+        let smaller = dhash(&scene(120, 90, false)).unwrap();
+        let inverted = dhash(&scene(400, 300, true)).unwrap();
+
+        assert!(hamming_distance(original, smaller) <= 4);
+        assert!(hamming_distance(original, inverted) > 32);
+    }
+
+    #[test]
+    fn test_hamming_distance() {
+        assert_eq!(hamming_distance(0, 0), 0);
+        assert_eq!(hamming_distance(0b1011, 0b0001), 2);
+        assert_eq!(hamming_distance(0, u64::MAX), 64);
+    }
+}
@@ -0,0 +1,516 @@
+//! Separable image resampling.
+//!
+//! Pixels are widened to `f32`, optionally converted to linear light and
+//! premultiplied by alpha, filtered horizontally then vertically, and
+//! converted back to the source component type.
+//!
+//! Work is done one row at a time: only the horizontally filtered source
+//! rows under the vertical kernel are held as `f32`, so memory follows the
+//! output width and the kernel height rather than the source image size.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::image::buffer::{ComponentType, ImageBuffer, ImageBufferError, PixelFormat};
+use crate::image::types::StandardImageSize;
+use crate::metadata::ColorSpace;
+
+/// Resampling kernel.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResampleFilter {
+    /// Nearest neighbour (no filtering)
+    Nearest,
+    /// Box / area average. Exact for integer downscales.
+    Box,
+    /// Triangle filter
+    Bilinear,
+    /// Mitchell-Netravali cubic (B = C = 1/3). Soft, little ringing.
+    Mitchell,
+    /// Windowed sinc with 3 lobes. Sharpest, may ring at hard edges.
+    #[default]
+    Lanczos3,
+}
+
+impl ResampleFilter {
+    /// Kernel radius in source pixels at a scale of 1.
+    pub fn support(&self) -> f32 {
+        match self {
+            Self::Nearest | Self::Box => 0.5,
+            Self::Bilinear => 1.0,
+            Self::Mitchell => 2.0,
+            Self::Lanczos3 => 3.0,
+        }
+    }
+
+    fn weight(&self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            Self::Nearest | Self::Box => {
+                if x <= 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Self::Bilinear => (1.0 - x).max(0.0),
+            Self::Mitchell => {
+                const B: f32 = 1.0 / 3.0;
+                const C: f32 = 1.0 / 3.0;
+                if x < 1.0 {
+                    ((12.0 - 9.0 * B - 6.0 * C) * x.powi(3)
+                        + (-18.0 + 12.0 * B + 6.0 * C) * x.powi(2)
+                        + (6.0 - 2.0 * B))
+                        / 6.0
+                } else if x < 2.0 {
+                    ((-B - 6.0 * C) * x.powi(3)
+                        + (6.0 * B + 30.0 * C) * x.powi(2)
+                        + (-12.0 * B - 48.0 * C) * x
+                        + (8.0 * B + 24.0 * C))
+                        / 6.0
+                } else {
+                    0.0
+                }
+            }
+            Self::Lanczos3 => {
+                if x < 3.0 {
+                    sinc(x) * sinc(x / 3.0)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let x = x * std::f32::consts::PI;
+        x.sin() / x
+    }
+}
+
+/// Options for [`ImageBuffer::resize_with`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResampleOptions {
+    pub filter: ResampleFilter,
+    /// Filter in linear light rather than on gamma-encoded values.
+    /// Ignored for CMYK and `ColorSpace::Linear` buffers.
+    pub linear_light: bool,
+    /// Premultiply colour by alpha while filtering (RGBA only), so that
+    /// transparent pixels do not bleed their colour into neighbours.
+    pub premultiply_alpha: bool,
+}
+
+impl Default for ResampleOptions {
+    fn default() -> Self {
+        Self {
+            filter: ResampleFilter::default(),
+            linear_light: true,
+            premultiply_alpha: true,
+        }
+    }
+}
+
+impl ResampleOptions {
+    pub fn with_filter(filter: ResampleFilter) -> Self {
+        Self {
+            filter,
+            ..Default::default()
+        }
+    }
+
+    /// Default options with the filter chosen for `size`.
+    pub fn for_size(size: StandardImageSize) -> Self {
+        Self::with_filter(size.resample_filter())
+    }
+}
+
+/// Transfer function used to move between encoded values and linear light.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Transfer {
+    Srgb,
+    Gamma(f32),
+}
+
+impl Transfer {
+    fn for_color_space(color_space: ColorSpace) -> Option<Self> {
+        match color_space {
+            ColorSpace::Srgb | ColorSpace::DisplayP3 => Some(Self::Srgb),
+            ColorSpace::AdobeRgb => Some(Self::Gamma(563.0 / 256.0)),
+            ColorSpace::ProPhoto => Some(Self::Gamma(1.8)),
+            ColorSpace::Linear => None,
+        }
+    }
+
+    fn linearize(self, v: f32) -> f32 {
+        match self {
+            Self::Srgb if v <= 0.040_45 => v / 12.92,
+            Self::Srgb => ((v + 0.055) / 1.055).powf(2.4),
+            Self::Gamma(g) => v.max(0.0).powf(g),
+        }
+    }
+
+    fn encode(self, v: f32) -> f32 {
+        match self {
+            Self::Srgb if v <= 0.003_130_8 => v * 12.92,
+            Self::Srgb => 1.055 * v.powf(1.0 / 2.4) - 0.055,
+            Self::Gamma(g) => v.max(0.0).powf(1.0 / g),
+        }
+    }
+}
+
+/// Contributions of source samples to one output sample.
+struct Taps {
+    start: usize,
+    weights: Vec<f32>,
+}
+
+/// Computes the taps for every output coordinate along one axis.
+fn taps(filter: ResampleFilter, src_len: usize, dst_len: usize) -> Vec<Taps> {
+    let scale = src_len as f32 / dst_len as f32;
+    // Widen the kernel when downscaling so it covers every source sample.
+    let filter_scale = scale.max(1.0);
+    let support = filter.support() * filter_scale;
+
+    (0..dst_len)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * scale;
+            if filter == ResampleFilter::Nearest {
+                let start = (center as usize).min(src_len - 1);
+                return Taps {
+                    start,
+                    weights: vec![1.0],
+                };
+            }
+
+            let start = (center - support).floor().max(0.0) as usize;
+            let end = ((center + support).ceil() as usize).min(src_len);
+            let mut weights: Vec<f32> = (start..end)
+                .map(|j| filter.weight((j as f32 + 0.5 - center) / filter_scale))
+                .collect();
+            let sum: f32 = weights.iter().sum();
+            if sum.abs() > f32::EPSILON {
+                weights.iter_mut().for_each(|w| *w /= sum);
+            } else {
+                // Kernel fell between samples; use the closest one.
+                let nearest = (center as usize).clamp(start, end - 1);
+                weights.iter_mut().for_each(|w| *w = 0.0);
+                weights[nearest - start] = 1.0;
+            }
+            Taps { start, weights }
+        })
+        .collect()
+}
+
+/// Resamples `buffer` to `new_width` x `new_height`.
+pub fn resample(
+    buffer: &ImageBuffer,
+    new_width: usize,
+    new_height: usize,
+    options: &ResampleOptions,
+) -> Result<ImageBuffer, ImageBufferError> {
+    if new_width == 0 || new_height == 0 || buffer.width == 0 || buffer.height == 0 {
+        return Err(ImageBufferError::InvalidDimensions);
+    }
+
+    let channels = buffer.format.num_components();
+    let has_alpha = buffer.format == PixelFormat::Rgba;
+    let color_channels = if has_alpha { 3 } else { channels };
+    let transfer = match buffer.format {
+        PixelFormat::Cmyk => None,
+        _ if options.linear_light => Transfer::for_color_space(buffer.color_space),
+        _ => None,
+    };
+    let premultiply = has_alpha && options.premultiply_alpha;
+
+    let horizontal = taps(options.filter, buffer.width, new_width);
+    let vertical = taps(options.filter, buffer.height, new_height);
+    let component_size = buffer.component_type.bytes_per_component();
+    let src_row_bytes = buffer.width * channels * component_size;
+    let row_len = new_width * channels;
+
+    let mut src_row = vec![0.0f32; buffer.width * channels];
+    // Horizontally filtered source rows, starting at source row `first`.
+    let mut window: VecDeque<Vec<f32>> = VecDeque::new();
+    let mut spare: Vec<Vec<f32>> = Vec::new();
+    let mut first = 0;
+    let mut out_row = vec![0.0f32; row_len];
+    let mut data = Vec::with_capacity(new_height * row_len * component_size);
+
+    // Vertical taps start at non-decreasing rows, so rows above the kernel
+    // are never needed again.
+    for tap in &vertical {
+        let stale = tap.start.saturating_sub(first).min(window.len());
+        spare.extend(window.drain(..stale));
+        first = first.max(tap.start);
+
+        let end = tap.start + tap.weights.len();
+        while first + window.len() < end {
+            let y = first + window.len();
+            // Decode to normalised floats
+            to_f32(
+                &buffer.data[y * src_row_bytes..(y + 1) * src_row_bytes],
+                buffer.component_type,
+                &mut src_row,
+            );
+            for pixel in src_row.chunks_exact_mut(channels) {
+                if let Some(transfer) = transfer {
+                    for v in &mut pixel[..color_channels] {
+                        *v = transfer.linearize(*v);
+                    }
+                }
+                if premultiply {
+                    let alpha = pixel[3];
+                    for v in &mut pixel[..3] {
+                        *v *= alpha;
+                    }
+                }
+            }
+
+            // Horizontal pass: width changes, rows stay
+            let mut wide = spare.pop().unwrap_or_else(|| vec![0.0f32; row_len]);
+            wide.fill(0.0);
+            for (x, tap) in horizontal.iter().enumerate() {
+                let out = &mut wide[x * channels..(x + 1) * channels];
+                for (k, &w) in tap.weights.iter().enumerate() {
+                    let src = &src_row[(tap.start + k) * channels..(tap.start + k + 1) * channels];
+                    for (o, s) in out.iter_mut().zip(src) {
+                        *o += s * w;
+                    }
+                }
+            }
+            window.push_back(wide);
+        }
+
+        // Vertical pass
+        out_row.fill(0.0);
+        for (k, &w) in tap.weights.iter().enumerate() {
+            for (d, s) in out_row.iter_mut().zip(&window[tap.start + k - first]) {
+                *d += s * w;
+            }
+        }
+
+        // Undo premultiplication and linearisation
+        for pixel in out_row.chunks_exact_mut(channels) {
+            if premultiply {
+                let alpha = pixel[3].clamp(0.0, 1.0);
+                pixel[3] = alpha;
+                for v in &mut pixel[..3] {
+                    *v = if alpha > 0.0 { *v / alpha } else { 0.0 };
+                }
+            }
+            if let Some(transfer) = transfer {
+                for v in &mut pixel[..color_channels] {
+                    *v = transfer.encode(*v);
+                }
+            }
+        }
+        from_f32(&out_row, buffer.component_type, &mut data);
+    }
+
+    ImageBuffer::new(
+        data,
+        new_width,
+        new_height,
+        buffer.format,
+        buffer.component_type,
+        buffer.color_space,
+    )
+}
+
+/// Decodes one row of `component_type` samples into `out`.
+fn to_f32(row: &[u8], component_type: ComponentType, out: &mut [f32]) {
+    match component_type {
+        ComponentType::U8 => {
+            for (o, &v) in out.iter_mut().zip(row) {
+                *o = f32::from(v) / 255.0;
+            }
+        }
+        ComponentType::U16 => {
+            for (o, c) in out.iter_mut().zip(row.chunks_exact(2)) {
+                *o = f32::from(u16::from_ne_bytes([c[0], c[1]])) / 65535.0;
+            }
+        }
+        ComponentType::F32 => {
+            for (o, c) in out.iter_mut().zip(row.chunks_exact(4)) {
+                *o = f32::from_ne_bytes([c[0], c[1], c[2], c[3]]);
+            }
+        }
+    }
+}
+
+/// Appends `values` to `out` as `component_type` samples. Integer types are
+/// clamped (filters with negative lobes overshoot); F32 is not.
+fn from_f32(values: &[f32], component_type: ComponentType, out: &mut Vec<u8>) {
+    match component_type {
+        ComponentType::U8 => out.extend(
+            values
+                .iter()
+                .map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8),
+        ),
+        ComponentType::U16 => out.extend(
+            values
+                .iter()
+                .flat_map(|v| ((v.clamp(0.0, 1.0) * 65535.0).round() as u16).to_ne_bytes()),
+        ),
+        ComponentType::F32 => out.extend(values.iter().flat_map(|v| v.to_ne_bytes())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(data: Vec<u8>, width: usize, color_space: ColorSpace) -> ImageBuffer {
+        let height = data.len() / width;
+        ImageBuffer::new(
+            data,
+            width,
+            height,
+            PixelFormat::Gray,
+            ComponentType::U8,
+            color_space,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_filters_preserve_flat_colour() {
+        let buffer = gray(vec![100; 64], 8, ColorSpace::Srgb);
+        for filter in [
+            ResampleFilter::Nearest,
+            ResampleFilter::Box,
+            ResampleFilter::Bilinear,
+            ResampleFilter::Mitchell,
+            ResampleFilter::Lanczos3,
+        ] {
+            for (w, h) in [(3, 5), (16, 16)] {
+                let out = resample(&buffer, w, h, &ResampleOptions::with_filter(filter)).unwrap();
+                assert!(out.data.iter().all(|&v| v == 100), "{filter:?} {w}x{h}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_box_downscale_is_area_average() {
+        let buffer = gray(vec![0, 255, 0, 255], 4, ColorSpace::Linear);
+        let out = resample(
+            &buffer,
+            2,
+            1,
+            &ResampleOptions::with_filter(ResampleFilter::Box),
+        )
+        .unwrap();
+        assert_eq!(out.data, vec![128, 128]);
+    }
+
+    #[test]
+    fn test_linear_light_averaging() {
+        // Black/white checker averages to 50% linear, i.e. ~188 in sRGB.
+        let buffer = gray(vec![0, 255, 255, 0], 2, ColorSpace::Srgb);
+        let options = ResampleOptions::with_filter(ResampleFilter::Box);
+        assert_eq!(resample(&buffer, 1, 1, &options).unwrap().data, vec![188]);
+
+        let gamma_space = ResampleOptions {
+            linear_light: false,
+            ..options
+        };
+        assert_eq!(
+            resample(&buffer, 1, 1, &gamma_space).unwrap().data,
+            vec![128]
+        );
+    }
+
+    #[test]
+    fn test_premultiplied_alpha_does_not_bleed() {
+        // Opaque red next to fully transparent green
+        let buffer = ImageBuffer::new(
+            vec![255, 0, 0, 255, 0, 255, 0, 0],
+            2,
+            1,
+            PixelFormat::Rgba,
+            ComponentType::U8,
+            ColorSpace::Srgb,
+        )
+        .unwrap();
+        let out = resample(
+            &buffer,
+            1,
+            1,
+            &ResampleOptions::with_filter(ResampleFilter::Box),
+        )
+        .unwrap();
+        assert_eq!(&out.data[..3], &[255, 0, 0]);
+        assert_eq!(out.data[3], 128);
+    }
+
+    #[test]
+    fn test_u16_and_f32_buffers() {
+        let samples: Vec<u8> = [0u16, 65535].iter().flat_map(|v| v.to_ne_bytes()).collect();
+        let buffer = ImageBuffer::new(
+            samples,
+            2,
+            1,
+            PixelFormat::Gray,
+            ComponentType::U16,
+            ColorSpace::Linear,
+        )
+        .unwrap();
+        let out = resample(&buffer, 1, 1, &ResampleOptions::default()).unwrap();
+        assert_eq!(out.component_type, ComponentType::U16);
+        assert_eq!(u16::from_ne_bytes([out.data[0], out.data[1]]), 32768);
+
+        let floats: Vec<u8> = [0.25f32, 0.75]
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect();
+        let buffer = ImageBuffer::new(
+            floats,
+            2,
+            1,
+            PixelFormat::Gray,
+            ComponentType::F32,
+            ColorSpace::Linear,
+        )
+        .unwrap();
+        let out = resample(&buffer, 1, 1, &ResampleOptions::default()).unwrap();
+        let v = f32::from_ne_bytes(out.data[..4].try_into().unwrap());
+        assert!((v - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rows_are_filtered_as_they_stream_past() {
+        // Alternating black and white rows average out; the kernel window
+        // slides down the image as each output row is produced.
+        let rows: Vec<u8> = (0..16).flat_map(|y| [(y % 2) as u8 * 255; 2]).collect();
+        let buffer = gray(rows, 2, ColorSpace::Linear);
+        let out = resample(
+            &buffer,
+            2,
+            4,
+            &ResampleOptions::with_filter(ResampleFilter::Box),
+        )
+        .unwrap();
+        assert_eq!(out.data, vec![128; 8]);
+
+        // Upscaling reuses each source row for several output rows.
+        let buffer = gray(vec![0, 255], 1, ColorSpace::Linear);
+        let out = resample(
+            &buffer,
+            1,
+            4,
+            &ResampleOptions::with_filter(ResampleFilter::Bilinear),
+        )
+        .unwrap();
+        assert_eq!(out.data, vec![0, 64, 191, 255]);
+    }
+
+    #[test]
+    fn test_rejects_empty_target() {
+        let buffer = gray(vec![0; 4], 2, ColorSpace::Srgb);
+        assert!(resample(&buffer, 0, 1, &ResampleOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! Lossless geometric transforms (rotations, flips, transposes).
+//!
+//! These move whole pixels, so they work for every pixel format and
+//! component type.
+
+use crate::image::buffer::ImageBuffer;
+use crate::metadata::orientation::Orientation;
+
+impl ImageBuffer {
+    /// Returns the buffer rotated/flipped so that it displays upright for
+    /// the given EXIF orientation of the stored pixels.
+    pub fn apply_orientation(&self, orientation: Orientation) -> ImageBuffer {
+        match orientation {
+            Orientation::TopLeft => self.clone(),
+            Orientation::TopRight => self.flip_horizontal(),
+            Orientation::BottomRight => self.rotate180(),
+            Orientation::BottomLeft => self.flip_vertical(),
+            Orientation::LeftTop => self.transpose(),
+            Orientation::RightTop => self.rotate90(),
+            Orientation::RightBottom => self.transverse(),
+            Orientation::LeftBottom => self.rotate270(),
+        }
+    }
+
+    /// Mirrors left to right.
+    pub fn flip_horizontal(&self) -> ImageBuffer {
+        let w = self.width;
+        self.remap(false, |x, y| (w - 1 - x, y))
+    }
+
+    /// Mirrors top to bottom.
+    pub fn flip_vertical(&self) -> ImageBuffer {
+        let h = self.height;
+        self.remap(false, |x, y| (x, h - 1 - y))
+    }
+
+    /// Rotates 90° clockwise.
+    pub fn rotate90(&self) -> ImageBuffer {
+        let h = self.height;
+        self.remap(true, |x, y| (y, h - 1 - x))
+    }
+
+    /// Rotates 180°.
+    pub fn rotate180(&self) -> ImageBuffer {
+        let (w, h) = (self.width, self.height);
+        self.remap(false, |x, y| (w - 1 - x, h - 1 - y))
+    }
+
+    /// Rotates 90° counter-clockwise.
+    pub fn rotate270(&self) -> ImageBuffer {
+        let w = self.width;
+        self.remap(true, |x, y| (w - 1 - y, x))
+    }
+
+    /// Mirrors across the top-left to bottom-right diagonal.
+    pub fn transpose(&self) -> ImageBuffer {
+        self.remap(true, |x, y| (y, x))
+    }
+
+    /// Mirrors across the top-right to bottom-left diagonal.
+    pub fn transverse(&self) -> ImageBuffer {
+        let (w, h) = (self.width, self.height);
+        self.remap(true, |x, y| (w - 1 - y, h - 1 - x))
+    }
+
+    /// Builds a new buffer where each destination pixel `(x, y)` is copied
+    /// from the source pixel `source(x, y)`.
+    fn remap(
+        &self,
+        swap_dimensions: bool,
+        source: impl Fn(usize, usize) -> (usize, usize),
+    ) -> ImageBuffer {
+        let (width, height) = if swap_dimensions {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        };
+        let stride = self.pixel_stride();
+        let mut data = Vec::with_capacity(self.data.len());
+        for y in 0..height {
+            for x in 0..width {
+                let (sx, sy) = source(x, y);
+                let offset = (sy * self.width + sx) * stride;
+                data.extend_from_slice(&self.data[offset..offset + stride]);
+            }
+        }
+
+        ImageBuffer {
+            data,
+            width,
+            height,
+            format: self.format,
+            component_type: self.component_type,
+            color_space: self.color_space,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::buffer::{ComponentType, PixelFormat};
+    use crate::metadata::ColorSpace;
+
+    /// 3x2:
+    /// 1 2 3
+    /// 4 5 6
+    fn sample() -> ImageBuffer {
+        ImageBuffer::new(
+            vec![1, 2, 3, 4, 5, 6],
+            3,
+            2,
+            PixelFormat::Gray,
+            ComponentType::U8,
+            ColorSpace::Srgb,
+        )
+        .unwrap()
+    }
+
+    fn oriented(orientation: Orientation) -> (usize, usize, Vec<u8>) {
+        let out = sample().apply_orientation(orientation);
+        (out.width, out.height, out.data)
+    }
+
+    #[test]
+    fn test_all_orientations() {
+        let cases = [
+            (Orientation::TopLeft, (3, 2, vec![1, 2, 3, 4, 5, 6])),
+            (Orientation::TopRight, (3, 2, vec![3, 2, 1, 6, 5, 4])),
+            (Orientation::BottomRight, (3, 2, vec![6, 5, 4, 3, 2, 1])),
+            (Orientation::BottomLeft, (3, 2, vec![4, 5, 6, 1, 2, 3])),
+            (Orientation::LeftTop, (2, 3, vec![1, 4, 2, 5, 3, 6])),
+            (Orientation::RightTop, (2, 3, vec![4, 1, 5, 2, 6, 3])),
+            (Orientation::RightBottom, (2, 3, vec![6, 3, 5, 2, 4, 1])),
+            (Orientation::LeftBottom, (2, 3, vec![3, 6, 2, 5, 1, 4])),
+        ];
+        for (orientation, expected) in cases {
+            assert_eq!(oriented(orientation), expected, "{orientation:?}");
+        }
+    }
+
+    #[test]
+    fn test_multi_byte_pixels_move_as_a_unit() {
+        let data: Vec<u8> = [1u16, 2, 3, 4, 5, 6]
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect();
+        let buffer = ImageBuffer::new(
+            data,
+            3,
+            2,
+            PixelFormat::Gray,
+            ComponentType::U16,
+            ColorSpace::Srgb,
+        )
+        .unwrap();
+        let rotated = buffer.rotate90();
+        let values: Vec<u16> = rotated
+            .data
+            .chunks_exact(2)
+            .map(|c| u16::from_ne_bytes([c[0], c[1]]))
+            .collect();
+        assert_eq!(values, vec![4, 1, 5, 2, 6, 3]);
+    }
+
+    #[test]
+    fn test_rotations_compose() {
+        let buffer = sample();
+        assert_eq!(buffer.rotate90().rotate270().data, buffer.data);
+        assert_eq!(buffer.rotate90().rotate90().data, buffer.rotate180().data);
+        assert_eq!(
+            buffer.flip_horizontal().rotate90().data,
+            buffer.transverse().data
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::image::resample::ResampleFilter;
+
 /// Image Format
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ImageFormat {
@@ -230,6 +232,19 @@ impl StandardImageSize {
     pub fn to_image_resolution(&self) -> ImageResolution {
         ImageResolution::Standard(*self)
     }
+
+    /// Resampling filter suited to this size.
+    ///
+    /// Thumbnails use Mitchell, which rings less than Lanczos at the large
+    /// reduction factors involved; larger outputs use Lanczos3 for sharpness.
+    pub fn resample_filter(&self) -> ResampleFilter {
+        match self {
+            Self::Tiny | Self::SmallThumb | Self::MediumThumb | Self::LargeThumb => {
+                ResampleFilter::Mitchell
+            }
+            _ => ResampleFilter::Lanczos3,
+        }
+    }
 }
 
 impl ImageResolution {
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::metadata::orientation::Orientation;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ExifData {
     // --- RAW BLOB ---
     // If we are just copying data from JPG->JPG, use this raw buffer
@@ -24,7 +24,7 @@ pub struct ExifData {
     pub makernote: Option<Vec<u8>>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Ifd0Tags {
     pub make: Option<String>,
     pub model: Option<String>,
@@ -33,7 +33,7 @@ pub struct Ifd0Tags {
     pub datetime: Option<String>, // Format: YYYY:MM:DD HH:MM:SS
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct ExifSubIfdTags {
     pub exposure_time: Option<Ratio<u32>>,
     pub f_number: Option<Ratio<u32>>,
@@ -5,6 +5,8 @@ pub mod exif;
 pub mod geo;
 pub mod icc;
 pub mod orientation;
+pub mod tracklog;
+mod xml;
 pub mod xmp;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
@@ -1,9 +1,13 @@
 use serde::{Deserialize, Serialize};
 
+/// EXIF `Orientation` tag (0x0112).
+const ORIENTATION_TAG: u16 = 0x0112;
+
 /// Represents the orientation of the pixel data relative to the camera sensor.
 /// Conversion logic: If you rotate pixels, you MUST reset this to `TopLeft`.
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
 pub enum Orientation {
+    #[default]
     TopLeft = 1,
     TopRight = 2,
     BottomRight = 3,
@@ -13,3 +17,117 @@ pub enum Orientation {
     RightBottom = 7,
     LeftBottom = 8,
 }
+
+impl Orientation {
+    /// Maps the raw EXIF value (1-8).
+    pub fn from_exif_value(value: u16) -> Option<Self> {
+        Some(match value {
+            1 => Self::TopLeft,
+            2 => Self::TopRight,
+            3 => Self::BottomRight,
+            4 => Self::BottomLeft,
+            5 => Self::LeftTop,
+            6 => Self::RightTop,
+            7 => Self::RightBottom,
+            8 => Self::LeftBottom,
+            _ => return None,
+        })
+    }
+
+    /// Whether displaying the image swaps its stored width and height.
+    pub fn swaps_dimensions(&self) -> bool {
+        matches!(
+            self,
+            Self::LeftTop | Self::RightTop | Self::RightBottom | Self::LeftBottom
+        )
+    }
+
+    /// Reads the orientation from IFD0 of a TIFF-structured EXIF block
+    /// (the APP1 payload after `Exif\0\0`).
+    pub fn from_tiff(tiff: &[u8]) -> Option<Self> {
+        let big_endian = match tiff.get(..4)? {
+            [b'M', b'M', 0, 42] => true,
+            [b'I', b'I', 42, 0] => false,
+            _ => return None,
+        };
+        let u16_at = |pos: usize| {
+            let b: [u8; 2] = tiff.get(pos..pos + 2)?.try_into().ok()?;
+            Some(if big_endian {
+                u16::from_be_bytes(b)
+            } else {
+                u16::from_le_bytes(b)
+            })
+        };
+        let u32_at = |pos: usize| {
+            let b: [u8; 4] = tiff.get(pos..pos + 4)?.try_into().ok()?;
+            Some(if big_endian {
+                u32::from_be_bytes(b)
+            } else {
+                u32::from_le_bytes(b)
+            })
+        };
+
+        let ifd = u32_at(4)? as usize;
+        let count = usize::from(u16_at(ifd)?);
+        (0..count)
+            .map(|i| ifd + 2 + i * 12)
+            .find(|&entry| u16_at(entry) == Some(ORIENTATION_TAG))
+            // SHORT value, stored left-aligned in the 4-byte value field
+            .and_then(|entry| u16_at(entry + 8))
+            .and_then(Self::from_exif_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type ToBytes = (fn(u16) -> [u8; 2], fn(u32) -> [u8; 4]);
+
+    fn tiff(big_endian: bool, orientation: u16) -> Vec<u8> {
+        let (u16b, u32b): ToBytes = if big_endian {
+            (u16::to_be_bytes, u32::to_be_bytes)
+        } else {
+            (u16::to_le_bytes, u32::to_le_bytes)
+        };
+        let mut out = if big_endian {
+            b"MM\0\x2A".to_vec()
+        } else {
+            b"II\x2A\0".to_vec()
+        };
+        out.extend_from_slice(&u32b(8));
+        out.extend_from_slice(&u16b(2));
+        // Make (ASCII, pointing nowhere useful) then Orientation (SHORT)
+        out.extend_from_slice(&u16b(0x010F));
+        out.extend_from_slice(&u16b(2));
+        out.extend_from_slice(&u32b(4));
+        out.extend_from_slice(b"ACME");
+        out.extend_from_slice(&u16b(ORIENTATION_TAG));
+        out.extend_from_slice(&u16b(3));
+        out.extend_from_slice(&u32b(1));
+        out.extend_from_slice(&u16b(orientation));
+        out.extend_from_slice(&[0, 0]);
+        out.extend_from_slice(&u32b(0));
+        out
+    }
+
+    #[test]
+    fn test_from_tiff_both_byte_orders() {
+        assert_eq!(
+            Orientation::from_tiff(&tiff(true, 6)),
+            Some(Orientation::RightTop)
+        );
+        assert_eq!(
+            Orientation::from_tiff(&tiff(false, 8)),
+            Some(Orientation::LeftBottom)
+        );
+        assert_eq!(Orientation::from_tiff(&tiff(false, 0)), None);
+        assert_eq!(Orientation::from_tiff(b"not tiff"), None);
+    }
+
+    #[test]
+    fn test_swaps_dimensions() {
+        assert!(!Orientation::BottomRight.swaps_dimensions());
+        assert!(Orientation::RightTop.swaps_dimensions());
+    }
+}
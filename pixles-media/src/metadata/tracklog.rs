@@ -0,0 +1,351 @@
+//! GPS track logs (GPX and KML) for geotagging by capture time.
+
+use chrono::DateTime;
+use thiserror::Error;
+
+use crate::metadata::geo::GpsLocation;
+use crate::metadata::xml::{self, Element};
+
+/// One timed fix from a track log.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackPoint {
+    /// UTC, seconds since the Unix epoch.
+    pub time: i64,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f32>,
+}
+
+/// The fixes of one or more track logs, in time order.
+#[derive(Debug, Clone, Default)]
+pub struct TrackLog {
+    points: Vec<TrackPoint>,
+}
+
+#[derive(Error, Debug)]
+pub enum TrackLogError {
+    #[error("Malformed track log: {0}")]
+    Malformed(String),
+    #[error("Not a GPX or KML document (root element <{0}>)")]
+    UnknownFormat(String),
+    #[error("Track log has no timed points")]
+    NoPoints,
+}
+
+impl TrackLog {
+    /// Parses a GPX or KML document, telling them apart by root element.
+    ///
+    /// GPX track points (`trkpt`) are read with their `time` and `ele`.
+    /// KML is read from `gx:Track` elements, pairing each `when` with the
+    /// `gx:coord` in the same position, and from placemarks with a
+    /// `TimeStamp` and a `Point`. Points without a readable time are
+    /// skipped. Namespaces are not checked, so GPX 1.0 and KML 2.1 work
+    /// as well.
+    pub fn parse(src: &str) -> Result<TrackLog, TrackLogError> {
+        let root = xml::parse(src).map_err(TrackLogError::Malformed)?;
+        let mut points = Vec::new();
+        match root.name.as_str() {
+            "gpx" => collect_gpx(&root, &mut points),
+            "kml" => collect_kml(&root, &mut points),
+            other => return Err(TrackLogError::UnknownFormat(other.to_string())),
+        }
+        if points.is_empty() {
+            return Err(TrackLogError::NoPoints);
+        }
+        Ok(TrackLog::from_points(points))
+    }
+
+    pub fn from_points(mut points: Vec<TrackPoint>) -> TrackLog {
+        points.sort_by_key(|p| p.time);
+        TrackLog { points }
+    }
+
+    /// Merges the points of `other` into this log.
+    pub fn extend(&mut self, other: TrackLog) {
+        self.points.extend(other.points);
+        self.points.sort_by_key(|p| p.time);
+    }
+
+    pub fn points(&self) -> &[TrackPoint] {
+        &self.points
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Where the log places the receiver at `time` (UTC seconds).
+    ///
+    /// Between two fixes at most `max_gap` seconds apart, the position is
+    /// interpolated linearly. Otherwise the nearest fix is used if it is
+    /// within `max_gap` seconds; a longer gap is taken to mean the logger
+    /// was off, and gives `None`.
+    pub fn position_at(&self, time: i64, max_gap: i64) -> Option<GpsLocation> {
+        let after = self.points.partition_point(|p| p.time < time);
+        let before = after.checked_sub(1).map(|i| &self.points[i]);
+        let after = self.points.get(after);
+
+        if let (Some(a), Some(b)) = (before, after)
+            && b.time - a.time <= max_gap
+        {
+            return Some(interpolate(a, b, time));
+        }
+        [before, after]
+            .into_iter()
+            .flatten()
+            .filter(|p| (p.time - time).abs() <= max_gap)
+            .min_by_key(|p| (p.time - time).abs())
+            .map(|p| location(p.latitude, p.longitude, p.altitude))
+    }
+}
+
+fn interpolate(a: &TrackPoint, b: &TrackPoint, time: i64) -> GpsLocation {
+    if b.time == a.time {
+        return location(b.latitude, b.longitude, b.altitude);
+    }
+    let t = (time - a.time) as f64 / (b.time - a.time) as f64;
+    // Take the short way round when the track crosses the antimeridian.
+    let mut d_lon = b.longitude - a.longitude;
+    if d_lon > 180.0 {
+        d_lon -= 360.0;
+    } else if d_lon < -180.0 {
+        d_lon += 360.0;
+    }
+    let mut longitude = a.longitude + d_lon * t;
+    if longitude > 180.0 {
+        longitude -= 360.0;
+    } else if longitude < -180.0 {
+        longitude += 360.0;
+    }
+    let altitude = match (a.altitude, b.altitude) {
+        (Some(x), Some(y)) => Some(x + (y - x) * t as f32),
+        _ => None,
+    };
+    location(
+        a.latitude + (b.latitude - a.latitude) * t,
+        longitude,
+        altitude,
+    )
+}
+
+fn location(latitude: f64, longitude: f64, altitude: Option<f32>) -> GpsLocation {
+    GpsLocation {
+        latitude,
+        longitude,
+        altitude,
+    }
+}
+
+fn collect_gpx(element: &Element, points: &mut Vec<TrackPoint>) {
+    for child in &element.children {
+        if child.name != "trkpt" {
+            collect_gpx(child, points);
+            continue;
+        }
+        let coordinate = |name: &str| {
+            child
+                .attributes
+                .iter()
+                .find(|a| a.name == name)
+                .and_then(|a| a.value.trim().parse::<f64>().ok())
+        };
+        let (Some(latitude), Some(longitude)) = (coordinate("lat"), coordinate("lon")) else {
+            continue;
+        };
+        let Some(time) = child_text(child, "time").and_then(parse_time) else {
+            continue;
+        };
+        let altitude = child_text(child, "ele").and_then(|e| e.trim().parse().ok());
+        push_valid(points, time, latitude, longitude, altitude);
+    }
+}
+
+fn collect_kml(element: &Element, points: &mut Vec<TrackPoint>) {
+    match element.name.as_str() {
+        "Track" => {
+            let whens = element.children.iter().filter(|c| c.name == "when");
+            let coords = element.children.iter().filter(|c| c.name == "coord");
+            for (when, coord) in whens.zip(coords) {
+                // `gx:coord` is "lon lat [alt]".
+                let mut parts = coord.text.split_whitespace().map(str::parse::<f64>);
+                if let (Some(time), Some(Ok(longitude)), Some(Ok(latitude))) =
+                    (parse_time(&when.text), parts.next(), parts.next())
+                {
+                    let altitude = parts.next().and_then(Result::ok).map(|a| a as f32);
+                    push_valid(points, time, latitude, longitude, altitude);
+                }
+            }
+        }
+        "Placemark" => {
+            let time = element
+                .children
+                .iter()
+                .find(|c| c.name == "TimeStamp")
+                .and_then(|ts| child_text(ts, "when"))
+                .and_then(parse_time);
+            let point = element.children.iter().find(|c| c.name == "Point");
+            if let (Some(time), Some(point)) = (time, point)
+                && let Some(coordinates) = child_text(point, "coordinates")
+            {
+                // `coordinates` is "lon,lat[,alt]".
+                let mut parts = coordinates
+                    .trim()
+                    .split(',')
+                    .map(|p| p.trim().parse::<f64>());
+                if let (Some(Ok(longitude)), Some(Ok(latitude))) = (parts.next(), parts.next()) {
+                    let altitude = parts.next().and_then(Result::ok).map(|a| a as f32);
+                    push_valid(points, time, latitude, longitude, altitude);
+                }
+            }
+            for child in &element.children {
+                collect_kml(child, points);
+            }
+        }
+        _ => {
+            for child in &element.children {
+                collect_kml(child, points);
+            }
+        }
+    }
+}
+
+fn push_valid(
+    points: &mut Vec<TrackPoint>,
+    time: i64,
+    latitude: f64,
+    longitude: f64,
+    altitude: Option<f32>,
+) {
+    if (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude) {
+        points.push(TrackPoint {
+            time,
+            latitude,
+            longitude,
+            altitude,
+        });
+    }
+}
+
+fn child_text<'a>(element: &'a Element, name: &str) -> Option<&'a str> {
+    element
+        .children
+        .iter()
+        .find(|c| c.name == name)
+        .map(|c| c.text.as_str())
+}
+
+/// An ISO 8601 timestamp with a zone, as GPX and KML write them.
+fn parse_time(s: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(s.trim())
+        .ok()
+        .map(|t| t.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+ <trk><trkseg>
+  <trkpt lat="35.0000" lon="135.7000"><ele>40</ele><time>2024-07-15T01:30:00Z</time></trkpt>
+  <trkpt lat="35.0100" lon="135.7100"><ele>60</ele><time>2024-07-15T01:40:00Z</time></trkpt>
+  <trkpt lat="35.0200" lon="135.7200"><time>2024-07-15T03:00:00+00:00</time></trkpt>
+  <trkpt lat="35.0300" lon="135.7300"></trkpt>
+ </trkseg></trk>
+</gpx>"#;
+
+    const KML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2" xmlns:gx="http://www.google.com/kml/ext/2.2">
+ <Document>
+  <Placemark>
+   <gx:Track>
+    <when>2024-07-15T10:30:00+09:00</when>
+    <when>2024-07-15T10:31:00+09:00</when>
+    <gx:coord>135.7 35.0 40</gx:coord>
+    <gx:coord>135.8 35.1 50</gx:coord>
+   </gx:Track>
+  </Placemark>
+  <Placemark>
+   <TimeStamp><when>2024-07-15T02:00:00Z</when></TimeStamp>
+   <Point><coordinates>135.9,35.2,0</coordinates></Point>
+  </Placemark>
+ </Document>
+</kml>"#;
+
+    /// 2024-07-15 01:30:00 UTC.
+    const T0: i64 = 1_721_007_000;
+
+    #[test]
+    fn test_parse_gpx() {
+        let log = TrackLog::parse(GPX).unwrap();
+        let times: Vec<_> = log.points().iter().map(|p| p.time - T0).collect();
+        assert_eq!(times, vec![0, 600, 5400]);
+        assert_eq!(log.points()[0].altitude, Some(40.0));
+        assert_eq!(log.points()[2].altitude, None);
+    }
+
+    #[test]
+    fn test_parse_kml() {
+        let log = TrackLog::parse(KML).unwrap();
+        let points = log.points();
+        assert_eq!(points.len(), 3);
+        assert_eq!((points[0].time, points[0].latitude), (T0, 35.0));
+        assert_eq!((points[1].time, points[1].longitude), (T0 + 60, 135.8));
+        assert_eq!((points[2].time, points[2].latitude), (T0 + 1800, 35.2));
+    }
+
+    #[test]
+    fn test_rejects_other_documents() {
+        assert!(matches!(
+            TrackLog::parse("<svg/>"),
+            Err(TrackLogError::UnknownFormat(_))
+        ));
+        assert!(matches!(
+            TrackLog::parse("<gpx><trk/></gpx>"),
+            Err(TrackLogError::NoPoints)
+        ));
+    }
+
+    #[test]
+    fn test_position_at() {
+        let log = TrackLog::parse(GPX).unwrap();
+
+        // Halfway between the first two fixes.
+        let p = log.position_at(T0 + 300, 900).unwrap();
+        assert!((p.latitude - 35.005).abs() < 1e-9);
+        assert!((p.longitude - 135.705).abs() < 1e-9);
+        assert_eq!(p.altitude, Some(50.0));
+
+        // The last two fixes are 80 minutes apart: only close to either
+        // end is there a position, that of the nearer fix.
+        let p = log.position_at(T0 + 900, 900).unwrap();
+        assert_eq!(p.latitude, 35.01);
+        assert!(log.position_at(T0 + 3000, 900).is_none());
+        let p = log.position_at(T0 + 5400 + 600, 900).unwrap();
+        assert_eq!(p.latitude, 35.02);
+
+        // Before the log starts.
+        assert!(log.position_at(T0 - 1000, 900).is_none());
+    }
+
+    #[test]
+    fn test_interpolation_crosses_antimeridian() {
+        let log = TrackLog::from_points(vec![
+            TrackPoint {
+                time: 0,
+                latitude: -17.0,
+                longitude: 179.0,
+                altitude: None,
+            },
+            TrackPoint {
+                time: 100,
+                latitude: -17.0,
+                longitude: -179.0,
+                altitude: None,
+            },
+        ]);
+        let p = log.position_at(75, 100).unwrap();
+        assert!((p.longitude - -179.5).abs() < 1e-9);
+    }
+}
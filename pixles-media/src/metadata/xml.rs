@@ -0,0 +1,333 @@
+//! Minimal namespace-aware XML reader for metadata packets.
+//!
+//! XMP packets are small and machine-written, so this builds a tree in one
+//! pass. Comments, processing instructions and DOCTYPEs are skipped; only
+//! the predefined entities and character references are decoded.
+
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Element {
+    /// Namespace URI (empty when the element is in no namespace).
+    pub ns: String,
+    pub name: String,
+    pub attributes: Vec<Attribute>,
+    pub children: Vec<Element>,
+    /// Concatenated character data directly inside this element.
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Attribute {
+    pub ns: String,
+    pub name: String,
+    pub value: String,
+}
+
+impl Element {
+    pub fn is(&self, ns: &str, name: &str) -> bool {
+        self.ns == ns && self.name == name
+    }
+
+    pub fn attribute(&self, ns: &str, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|a| a.ns == ns && a.name == name)
+            .map(|a| a.value.as_str())
+    }
+
+    pub fn lang(&self) -> Option<&str> {
+        self.attribute(XML_NS, "lang")
+    }
+}
+
+struct Open {
+    qname: String,
+    element: Element,
+    /// `(prefix, uri)` declared on this element; the default namespace has
+    /// an empty prefix.
+    scope: Vec<(String, String)>,
+}
+
+/// Parses `src` and returns its root element.
+pub(crate) fn parse(src: &str) -> Result<Element, String> {
+    let src = src.trim_start_matches('\u{feff}');
+    let mut stack: Vec<Open> = Vec::new();
+    let mut pos = 0;
+
+    while pos < src.len() {
+        let rest = &src[pos..];
+        let Some(lt) = rest.find('<') else {
+            break;
+        };
+        if lt > 0 {
+            if let Some(open) = stack.last_mut() {
+                open.element.text.push_str(&decode(&rest[..lt]));
+            }
+            pos += lt;
+            continue;
+        }
+
+        if let Some(body) = rest.strip_prefix("<![CDATA[") {
+            let end = body.find("]]>").ok_or("unterminated CDATA")?;
+            if let Some(open) = stack.last_mut() {
+                open.element.text.push_str(&body[..end]);
+            }
+            pos += "<![CDATA[".len() + end + 3;
+        } else if rest.starts_with("<!--") {
+            pos += rest.find("-->").ok_or("unterminated comment")? + 3;
+        } else if rest.starts_with("<?") {
+            pos += rest
+                .find("?>")
+                .ok_or("unterminated processing instruction")?
+                + 2;
+        } else if rest.starts_with("<!") {
+            pos += rest.find('>').ok_or("unterminated declaration")? + 1;
+        } else if let Some(body) = rest.strip_prefix("</") {
+            let end = body.find('>').ok_or("unterminated end tag")?;
+            let qname = body[..end].trim();
+            let open = stack.pop().ok_or("unexpected end tag")?;
+            if open.qname != qname {
+                return Err(format!("expected </{}>, found </{qname}>", open.qname));
+            }
+            match stack.last_mut() {
+                Some(parent) => parent.element.children.push(open.element),
+                None => return Ok(open.element),
+            }
+            pos += 2 + end + 1;
+        } else {
+            let (tag, len) = read_tag(rest)?;
+            stack.push(resolve(&tag, &stack)?);
+            if tag.empty {
+                let open = stack.pop().expect("just pushed");
+                match stack.last_mut() {
+                    Some(parent) => parent.element.children.push(open.element),
+                    None => return Ok(open.element),
+                }
+            }
+            pos += len;
+        }
+    }
+
+    Err("missing root element".to_string())
+}
+
+struct Tag {
+    qname: String,
+    attributes: Vec<(String, String)>,
+    empty: bool,
+}
+
+/// Reads `<name attr="value" ...>` or `.../>` at the start of `src`,
+/// returning the tag and its length in bytes.
+fn read_tag(src: &str) -> Result<(Tag, usize), String> {
+    let bytes = src.as_bytes();
+    let is_name_end = |b: u8| b.is_ascii_whitespace() || b == b'>' || b == b'/';
+    let mut i = 1;
+    while i < bytes.len() && !is_name_end(bytes[i]) {
+        i += 1;
+    }
+    let qname = src[1..i].to_string();
+    if qname.is_empty() {
+        return Err("empty tag name".to_string());
+    }
+
+    let mut attributes = Vec::new();
+    loop {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        match bytes.get(i) {
+            None => return Err(format!("unterminated <{qname}>")),
+            Some(b'>') => {
+                let tag = Tag {
+                    qname,
+                    attributes,
+                    empty: false,
+                };
+                return Ok((tag, i + 1));
+            }
+            Some(b'/') if bytes.get(i + 1) == Some(&b'>') => {
+                let tag = Tag {
+                    qname,
+                    attributes,
+                    empty: true,
+                };
+                return Ok((tag, i + 2));
+            }
+            Some(_) => {
+                let start = i;
+                while i < bytes.len() && bytes[i] != b'=' && !is_name_end(bytes[i]) {
+                    i += 1;
+                }
+                let name = src[start..i].to_string();
+                while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                if bytes.get(i) != Some(&b'=') {
+                    return Err(format!("attribute {name} has no value"));
+                }
+                i += 1;
+                while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                    i += 1;
+                }
+                let quote = match bytes.get(i) {
+                    Some(&q @ (b'"' | b'\'')) => q,
+                    _ => return Err(format!("attribute {name} is not quoted")),
+                };
+                let value_start = i + 1;
+                let value_len = bytes[value_start..]
+                    .iter()
+                    .position(|&b| b == quote)
+                    .ok_or_else(|| format!("unterminated value for {name}"))?;
+                let value = decode(&src[value_start..value_start + value_len]);
+                attributes.push((name, value));
+                i = value_start + value_len + 1;
+            }
+        }
+    }
+}
+
+/// Resolves the namespaces of `tag` against its own declarations and
+/// those of its open ancestors in `stack`.
+fn resolve(tag: &Tag, stack: &[Open]) -> Result<Open, String> {
+    let mut scope = Vec::new();
+    let mut plain = Vec::new();
+    for (name, value) in &tag.attributes {
+        if name == "xmlns" {
+            scope.push((String::new(), value.clone()));
+        } else if let Some(prefix) = name.strip_prefix("xmlns:") {
+            scope.push((prefix.to_string(), value.clone()));
+        } else {
+            plain.push((name, value));
+        }
+    }
+
+    let lookup = |prefix: &str| -> Option<String> {
+        if prefix == "xml" {
+            return Some(XML_NS.to_string());
+        }
+        scope
+            .iter()
+            .chain(stack.iter().rev().flat_map(|o| o.scope.iter()))
+            .find(|(p, _)| p == prefix)
+            .map(|(_, uri)| uri.clone())
+    };
+
+    let (prefix, name) = split_qname(&tag.qname);
+    let ns = match prefix {
+        Some(p) => lookup(p).ok_or_else(|| format!("undeclared prefix {p}"))?,
+        None => lookup("").unwrap_or_default(),
+    };
+    let mut attributes = Vec::new();
+    for (qname, value) in plain {
+        let (prefix, name) = split_qname(qname);
+        // Unprefixed attributes are in no namespace.
+        let ns = match prefix {
+            Some(p) => lookup(p).ok_or_else(|| format!("undeclared prefix {p}"))?,
+            None => String::new(),
+        };
+        attributes.push(Attribute {
+            ns,
+            name: name.to_string(),
+            value: value.clone(),
+        });
+    }
+
+    Ok(Open {
+        qname: tag.qname.clone(),
+        element: Element {
+            ns,
+            name: name.to_string(),
+            attributes,
+            ..Default::default()
+        },
+        scope,
+    })
+}
+
+fn split_qname(qname: &str) -> (Option<&str>, &str) {
+    match qname.split_once(':') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, qname),
+    }
+}
+
+/// Decodes the predefined entities and character references. Anything
+/// else is kept verbatim.
+fn decode(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse::<u32>))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_resolves_namespaces() {
+        let root = parse(
+            r#"<?xml version="1.0"?>
+            <!-- comment -->
+            <a:root xmlns:a="urn:a" xmlns="urn:default" a:id='1' plain="x">
+                <child xml:lang="en">Fish &amp; chips &#x2713;</child>
+                <b:leaf xmlns:b="urn:b"/>
+            </a:root>"#,
+        )
+        .unwrap();
+        assert!(root.is("urn:a", "root"));
+        assert_eq!(root.attribute("urn:a", "id"), Some("1"));
+        assert_eq!(root.attribute("", "plain"), Some("x"));
+        assert_eq!(root.children.len(), 2);
+        let child = &root.children[0];
+        assert!(child.is("urn:default", "child"));
+        assert_eq!(child.lang(), Some("en"));
+        assert_eq!(child.text, "Fish & chips \u{2713}");
+        assert!(root.children[1].is("urn:b", "leaf"));
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed() {
+        assert!(parse("<a><b></a>").is_err());
+        assert!(parse("<p:a/>").is_err());
+        assert!(parse("<a>").is_err());
+        assert!(parse("no markup").is_err());
+    }
+}
@@ -1,8 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+use crate::metadata::xml::{self, Element};
+
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const DC: &str = "http://purl.org/dc/elements/1.1/";
+const LR: &str = "http://ns.adobe.com/lightroom/1.0/";
+const XMP: &str = "http://ns.adobe.com/xap/1.0/";
+const XMP_DM: &str = "http://ns.adobe.com/xmp/1.0/DynamicMedia/";
+const DARKTABLE: &str = "http://darktable.sf.net/";
+const EXIF: &str = "http://ns.adobe.com/exif/1.0/";
+
+/// Prefixes written by [`XmpData::to_xml`], in declaration order.
+const PREFIXES: &[(&str, &str)] = &[
+    ("dc", DC),
+    ("xmp", XMP),
+    ("xmpDM", XMP_DM),
+    ("exif", EXIF),
+    ("lr", LR),
+    ("darktable", DARKTABLE),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct XmpData {
     // --- RAW PACKET ---
     // The raw XML string. Required because XMP allows custom user-defined namespaces
@@ -14,15 +35,400 @@ pub struct XmpData {
     pub photoshop: Option<XmpPhotoshop>,    // Color mode, ICC name
     pub crs: Option<XmpCameraRaw>,          // Adobe Lightroom edits
     pub google_depth: Option<GDepth>,       // Android Depth
+    #[serde(default)]
+    pub lightroom: Option<XmpLightroom>, // Hierarchical keywords
+    #[serde(default)]
+    pub basic: Option<XmpBasic>, // Rating, Label
+    #[serde(default)]
+    pub dynamic_media: Option<XmpDynamicMedia>, // Pick flag
+    #[serde(default)]
+    pub darktable: Option<XmpDarktable>, // Color labels
+    #[serde(default)]
+    pub exif: Option<XmpExif>, // Capture time, GPS
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// The "http://ns.adobe.com/xap/1.0/" namespace.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct XmpBasic {
+    /// -1 (rejected) to 5 stars. Stored as a real; rounded here.
+    pub rating: Option<i8>,
+    /// Color label name as the writing application shows it (`Red`, ...).
+    pub label: Option<String>,
+    pub create_date: Option<String>, // ISO 8601
+    pub modify_date: Option<String>, // ISO 8601
+}
+
+/// The "http://ns.adobe.com/xmp/1.0/DynamicMedia/" namespace.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct XmpDynamicMedia {
+    /// 1 picked, 0 unflagged, -1 rejected (`xmpDM:pick`).
+    pub pick: Option<i8>,
+}
+
+/// The "http://ns.adobe.com/exif/1.0/" namespace, as far as editors read it
+/// back from a sidecar.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct XmpExif {
+    pub date_time_original: Option<String>, // ISO 8601
+    /// Decimal degrees; written as XMP's `DDD,MM.mmmmmmN` form.
+    pub gps_latitude: Option<f64>,
+    pub gps_longitude: Option<f64>,
+}
+
+/// The "http://darktable.sf.net/" namespace.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct XmpDarktable {
+    /// 0 red, 1 yellow, 2 green, 3 blue, 4 purple (`darktable:colorlabels`).
+    pub color_labels: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct XmpDublinCore {
     pub format: Option<String>,
     pub title: Option<HashMap<String, String>>, // Lang -> Text
     pub description: Option<HashMap<String, String>>, // Lang -> Text
     pub creator: Vec<String>,
     pub rights: Option<HashMap<String, String>>,
+    /// Flat keywords (`dc:subject`).
+    #[serde(default)]
+    pub subject: Vec<String>,
+}
+
+/// The "http://ns.adobe.com/lightroom/1.0/" namespace.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct XmpLightroom {
+    /// Keyword paths such as `Places|Japan|Kyoto` (`lr:hierarchicalSubject`).
+    pub hierarchical_subject: Vec<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum XmpError {
+    #[error("Malformed XMP: {0}")]
+    Malformed(String),
+}
+
+impl XmpData {
+    /// Finds the XMP packet embedded in a file (JPEG APP1, TIFF/DNG tag 700,
+    /// PNG iTXt, ...) by scanning for its `x:xmpmeta` element.
+    pub fn find_packet(bytes: &[u8]) -> Option<&str> {
+        const OPEN: &[u8] = b"<x:xmpmeta";
+        const CLOSE: &[u8] = b"</x:xmpmeta>";
+        let start = find(bytes, OPEN, 0)?;
+        let end = find(bytes, CLOSE, start)? + CLOSE.len();
+        std::str::from_utf8(&bytes[start..end]).ok()
+    }
+
+    /// Parses an XMP packet or a standalone `.xmp` sidecar.
+    ///
+    /// Properties may be written as attributes of `rdf:Description` or as
+    /// child elements. Only the Dublin Core, XMP basic, Dynamic Media, EXIF,
+    /// Lightroom and darktable namespaces are read so far; the full packet is
+    /// kept in `raw_xml`.
+    pub fn parse(xml: &str) -> Result<XmpData, XmpError> {
+        let root = xml::parse(xml).map_err(XmpError::Malformed)?;
+        let mut properties = Vec::new();
+        collect_properties(&root, &mut properties);
+
+        let mut dublin_core = XmpDublinCore::default();
+        let mut lightroom = XmpLightroom::default();
+        let mut basic = XmpBasic::default();
+        let mut dynamic_media = XmpDynamicMedia::default();
+        let mut darktable = XmpDarktable::default();
+        let mut exif = XmpExif::default();
+        let mut seen = HashSet::new();
+        for (ns, name, value) in properties {
+            match (ns.as_str(), name.as_str()) {
+                (DC, "format") => dublin_core.format = value.texts().into_iter().next(),
+                (DC, "title") => dublin_core.title = Some(value.lang_map()),
+                (DC, "description") => dublin_core.description = Some(value.lang_map()),
+                (DC, "rights") => dublin_core.rights = Some(value.lang_map()),
+                (DC, "creator") => dublin_core.creator = value.texts(),
+                (DC, "subject") => dublin_core.subject = value.texts(),
+                (LR, "hierarchicalSubject") => lightroom.hierarchical_subject = value.texts(),
+                (XMP, "Rating") => basic.rating = value.number().map(|r| r.round() as i8),
+                (XMP, "Label") => basic.label = value.first().filter(|l| !l.is_empty()),
+                (XMP, "CreateDate") => basic.create_date = value.first(),
+                (XMP, "ModifyDate") => basic.modify_date = value.first(),
+                (XMP_DM, "pick") => dynamic_media.pick = value.number().map(|p| p as i8),
+                (DARKTABLE, "colorlabels") => {
+                    darktable.color_labels = value
+                        .texts()
+                        .iter()
+                        .filter_map(|l| l.parse().ok())
+                        .collect()
+                }
+                (EXIF, "DateTimeOriginal") => exif.date_time_original = value.first(),
+                (EXIF, "GPSLatitude") => {
+                    exif.gps_latitude = value.first().and_then(|v| parse_gps(&v, 'N', 'S'))
+                }
+                (EXIF, "GPSLongitude") => {
+                    exif.gps_longitude = value.first().and_then(|v| parse_gps(&v, 'E', 'W'))
+                }
+                _ => continue,
+            }
+            seen.insert(ns);
+        }
+
+        Ok(XmpData {
+            raw_xml: xml.to_string(),
+            dublin_core: seen.contains(DC).then_some(dublin_core),
+            photoshop: None,
+            crs: None,
+            google_depth: None,
+            lightroom: seen.contains(LR).then_some(lightroom),
+            basic: seen.contains(XMP).then_some(basic),
+            dynamic_media: seen.contains(XMP_DM).then_some(dynamic_media),
+            darktable: seen.contains(DARKTABLE).then_some(darktable),
+            exif: seen.contains(EXIF).then_some(exif),
+        })
+    }
+
+    /// Renders the modelled namespaces as a standalone `.xmp` sidecar, with
+    /// every property written as an element. `raw_xml` is not consulted, so
+    /// properties outside those namespaces are dropped.
+    pub fn to_xml(&self) -> String {
+        let mut w = Writer::default();
+        if let Some(dc) = &self.dublin_core {
+            w.simple("dc:format", dc.format.as_deref());
+            w.alt("dc:title", dc.title.as_ref());
+            w.alt("dc:description", dc.description.as_ref());
+            w.array("dc:creator", "Seq", &dc.creator);
+            w.alt("dc:rights", dc.rights.as_ref());
+            w.array("dc:subject", "Bag", &dc.subject);
+        }
+        if let Some(basic) = &self.basic {
+            w.simple("xmp:Rating", basic.rating.map(|r| r.to_string()).as_deref());
+            w.simple("xmp:Label", basic.label.as_deref());
+            w.simple("xmp:CreateDate", basic.create_date.as_deref());
+            w.simple("xmp:ModifyDate", basic.modify_date.as_deref());
+        }
+        if let Some(dm) = &self.dynamic_media {
+            w.simple("xmpDM:pick", dm.pick.map(|p| p.to_string()).as_deref());
+        }
+        if let Some(exif) = &self.exif {
+            w.simple("exif:DateTimeOriginal", exif.date_time_original.as_deref());
+            let lat = exif.gps_latitude.map(|v| format_gps(v, 'N', 'S'));
+            let lon = exif.gps_longitude.map(|v| format_gps(v, 'E', 'W'));
+            w.simple("exif:GPSLatitude", lat.as_deref());
+            w.simple("exif:GPSLongitude", lon.as_deref());
+        }
+        if let Some(lr) = &self.lightroom {
+            w.array("lr:hierarchicalSubject", "Bag", &lr.hierarchical_subject);
+        }
+        if let Some(dt) = &self.darktable {
+            let labels: Vec<String> = dt.color_labels.iter().map(u8::to_string).collect();
+            w.array("darktable:colorlabels", "Seq", &labels);
+        }
+        w.finish()
+    }
+}
+
+/// Builds the body of an `rdf:Description`, tracking the prefixes it uses.
+#[derive(Default)]
+struct Writer {
+    body: String,
+    used: HashSet<&'static str>,
+}
+
+impl Writer {
+    fn open(&mut self, qname: &str) {
+        let prefix = qname.split(':').next().unwrap_or_default();
+        if let Some((p, _)) = PREFIXES.iter().find(|(p, _)| *p == prefix) {
+            self.used.insert(*p);
+        }
+        self.body.push_str(&format!("   <{qname}>"));
+    }
+
+    fn simple(&mut self, qname: &str, value: Option<&str>) {
+        if let Some(value) = value {
+            self.open(qname);
+            self.body
+                .push_str(&format!("{}</{qname}>\n", escape(value)));
+        }
+    }
+
+    fn array(&mut self, qname: &str, kind: &str, items: &[String]) {
+        if items.is_empty() {
+            return;
+        }
+        self.open(qname);
+        self.body.push_str(&format!("\n    <rdf:{kind}>\n"));
+        for item in items {
+            self.body
+                .push_str(&format!("     <rdf:li>{}</rdf:li>\n", escape(item)));
+        }
+        self.body
+            .push_str(&format!("    </rdf:{kind}>\n   </{qname}>\n"));
+    }
+
+    fn alt(&mut self, qname: &str, values: Option<&HashMap<String, String>>) {
+        let Some(values) = values.filter(|v| !v.is_empty()) else {
+            return;
+        };
+        // x-default must come first; the rest in a stable order.
+        let mut items: Vec<(&String, &String)> = values.iter().collect();
+        items.sort_by_key(|(lang, _)| (lang.as_str() != "x-default", lang.as_str()));
+        self.open(qname);
+        self.body.push_str("\n    <rdf:Alt>\n");
+        for (lang, text) in items {
+            self.body.push_str(&format!(
+                "     <rdf:li xml:lang=\"{}\">{}</rdf:li>\n",
+                escape(lang),
+                escape(text)
+            ));
+        }
+        self.body
+            .push_str(&format!("    </rdf:Alt>\n   </{qname}>\n"));
+    }
+
+    fn finish(self) -> String {
+        let mut out = String::from(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+             <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n\
+             \x20<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n\
+             \x20 <rdf:Description rdf:about=\"\"",
+        );
+        for (prefix, uri) in PREFIXES {
+            if self.used.contains(prefix) {
+                out.push_str(&format!("\n    xmlns:{prefix}=\"{uri}\""));
+            }
+        }
+        out.push_str(">\n");
+        out.push_str(&self.body);
+        out.push_str("  </rdf:Description>\n </rdf:RDF>\n</x:xmpmeta>\n<?xpacket end=\"w\"?>\n");
+        out
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Formats decimal degrees as XMP's `DDD,MM.mmmmmmK` coordinate.
+fn format_gps(value: f64, positive: char, negative: char) -> String {
+    let direction = if value < 0.0 { negative } else { positive };
+    let value = value.abs();
+    let degrees = value.trunc();
+    format!(
+        "{},{:.6}{direction}",
+        degrees as u32,
+        (value - degrees) * 60.0
+    )
+}
+
+/// Parses `DDD,MM,SSK` or `DDD,MM.mmK` into decimal degrees.
+fn parse_gps(value: &str, positive: char, negative: char) -> Option<f64> {
+    let value = value.trim();
+    let direction = value.chars().last()?;
+    let sign = match direction.to_ascii_uppercase() {
+        d if d == positive => 1.0,
+        d if d == negative => -1.0,
+        _ => return None,
+    };
+    let mut parts = value[..value.len() - 1].split(',');
+    let degrees: f64 = parts.next()?.trim().parse().ok()?;
+    let minutes: f64 = parts.next()?.trim().parse().ok()?;
+    let seconds: f64 = match parts.next() {
+        Some(s) => s.trim().parse().ok()?,
+        None => 0.0,
+    };
+    Some(sign * (degrees + minutes / 60.0 + seconds / 3600.0))
+}
+
+/// Value of one XMP property.
+enum XmpValue {
+    Text(String),
+    /// `rdf:Bag` or `rdf:Seq` items.
+    Array(Vec<String>),
+    /// `rdf:Alt` items with their `xml:lang`.
+    LangAlt(Vec<(String, String)>),
+}
+
+impl XmpValue {
+    fn texts(self) -> Vec<String> {
+        match self {
+            XmpValue::Text(t) => vec![t],
+            XmpValue::Array(items) => items,
+            XmpValue::LangAlt(items) => items.into_iter().map(|(_, t)| t).collect(),
+        }
+    }
+
+    fn first(self) -> Option<String> {
+        self.texts().into_iter().next()
+    }
+
+    fn number(self) -> Option<f64> {
+        self.first()?.trim().parse().ok()
+    }
+
+    fn lang_map(self) -> HashMap<String, String> {
+        match self {
+            XmpValue::Text(t) => HashMap::from([("x-default".to_string(), t)]),
+            XmpValue::Array(items) => items
+                .into_iter()
+                .take(1)
+                .map(|t| ("x-default".to_string(), t))
+                .collect(),
+            XmpValue::LangAlt(items) => items.into_iter().collect(),
+        }
+    }
+}
+
+/// Collects `(namespace, name, value)` for every simple or array property
+/// of every `rdf:Description` under `element`. Structs are skipped.
+fn collect_properties(element: &Element, out: &mut Vec<(String, String, XmpValue)>) {
+    if !element.is(RDF, "Description") {
+        for child in &element.children {
+            collect_properties(child, out);
+        }
+        return;
+    }
+
+    for attr in &element.attributes {
+        if attr.ns != RDF && !attr.ns.is_empty() {
+            out.push((
+                attr.ns.clone(),
+                attr.name.clone(),
+                XmpValue::Text(attr.value.clone()),
+            ));
+        }
+    }
+    for prop in &element.children {
+        let value = match prop.children.first() {
+            Some(c) if c.is(RDF, "Bag") || c.is(RDF, "Seq") => {
+                XmpValue::Array(list_items(c).map(|li| li.text.trim().to_string()).collect())
+            }
+            Some(c) if c.is(RDF, "Alt") => XmpValue::LangAlt(
+                list_items(c)
+                    .map(|li| {
+                        let lang = li.lang().unwrap_or("x-default").to_string();
+                        (lang, li.text.trim().to_string())
+                    })
+                    .collect(),
+            ),
+            Some(_) => continue,
+            None => match prop.attribute(RDF, "resource") {
+                Some(resource) => XmpValue::Text(resource.to_string()),
+                None => XmpValue::Text(prop.text.trim().to_string()),
+            },
+        };
+        out.push((prop.ns.clone(), prop.name.clone(), value));
+    }
+}
+
+fn list_items(container: &Element) -> impl Iterator<Item = &Element> {
+    container.children.iter().filter(|li| li.is(RDF, "li"))
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|i| i + from)
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -109,3 +515,150 @@ pub enum PhotoshopColorMode {
     Background = 9,
     Raw = 10,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIDECAR: &str = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:lr="http://ns.adobe.com/lightroom/1.0/"
+    dc:format="image/x-fuji-raf">
+   <dc:title><rdf:Alt><rdf:li xml:lang="x-default">Fushimi Inari</rdf:li></rdf:Alt></dc:title>
+   <dc:subject>
+    <rdf:Bag>
+     <rdf:li>Kyoto</rdf:li>
+     <rdf:li>shrine</rdf:li>
+    </rdf:Bag>
+   </dc:subject>
+   <lr:hierarchicalSubject>
+    <rdf:Bag><rdf:li>Places|Japan|Kyoto</rdf:li></rdf:Bag>
+   </lr:hierarchicalSubject>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+
+    #[test]
+    fn test_parse_sidecar() {
+        let xmp = XmpData::parse(SIDECAR).unwrap();
+        let dc = xmp.dublin_core.unwrap();
+        assert_eq!(dc.format.as_deref(), Some("image/x-fuji-raf"));
+        assert_eq!(dc.title.unwrap()["x-default"], "Fushimi Inari");
+        assert_eq!(dc.subject, vec!["Kyoto", "shrine"]);
+        assert_eq!(
+            xmp.lightroom.unwrap().hierarchical_subject,
+            vec!["Places|Japan|Kyoto"]
+        );
+    }
+
+    #[test]
+    fn test_parse_culling_properties() {
+        let xmp = XmpData::parse(
+            r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+                <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+                 <rdf:Description rdf:about=""
+                   xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+                   xmlns:xmpDM="http://ns.adobe.com/xmp/1.0/DynamicMedia/"
+                   xmlns:darktable="http://darktable.sf.net/"
+                   xmp:Rating="-1.0" xmp:Label="Red" xmpDM:pick="1">
+                  <darktable:colorlabels>
+                   <rdf:Seq><rdf:li>0</rdf:li><rdf:li>3</rdf:li></rdf:Seq>
+                  </darktable:colorlabels>
+                 </rdf:Description>
+                </rdf:RDF>
+               </x:xmpmeta>"#,
+        )
+        .unwrap();
+        let basic = xmp.basic.unwrap();
+        assert_eq!(basic.rating, Some(-1));
+        assert_eq!(basic.label.as_deref(), Some("Red"));
+        assert_eq!(xmp.dynamic_media.unwrap().pick, Some(1));
+        assert_eq!(xmp.darktable.unwrap().color_labels, vec![0, 3]);
+    }
+
+    #[test]
+    fn test_to_xml_round_trips() {
+        let xmp = XmpData {
+            dublin_core: Some(XmpDublinCore {
+                description: Some(HashMap::from([(
+                    "x-default".to_string(),
+                    "Fish & chips <3".to_string(),
+                )])),
+                subject: vec!["Kyoto".to_string()],
+                ..Default::default()
+            }),
+            basic: Some(XmpBasic {
+                rating: Some(4),
+                label: Some("Red".to_string()),
+                ..Default::default()
+            }),
+            exif: Some(XmpExif {
+                date_time_original: Some("2024-04-01T09:30:00+09:00".to_string()),
+                gps_latitude: Some(34.967146),
+                gps_longitude: Some(-135.772695),
+            }),
+            lightroom: Some(XmpLightroom {
+                hierarchical_subject: vec!["Places|Japan|Kyoto".to_string()],
+            }),
+            ..Default::default()
+        };
+        let xml = xmp.to_xml();
+        assert!(xml.contains("<exif:GPSLatitude>34,58.028760N</exif:GPSLatitude>"));
+        assert!(!xml.contains("xmlns:darktable"));
+
+        let parsed = XmpData::parse(&xml).unwrap();
+        let dc = parsed.dublin_core.unwrap();
+        assert_eq!(dc.description.unwrap()["x-default"], "Fish & chips <3");
+        assert_eq!(dc.subject, vec!["Kyoto"]);
+        let basic = parsed.basic.unwrap();
+        assert_eq!(basic.rating, Some(4));
+        assert_eq!(basic.label.as_deref(), Some("Red"));
+        let exif = parsed.exif.unwrap();
+        assert_eq!(
+            exif.date_time_original.as_deref(),
+            Some("2024-04-01T09:30:00+09:00")
+        );
+        assert!((exif.gps_latitude.unwrap() - 34.967146).abs() < 1e-6);
+        assert!((exif.gps_longitude.unwrap() + 135.772695).abs() < 1e-6);
+        assert_eq!(
+            parsed.lightroom.unwrap().hierarchical_subject,
+            vec!["Places|Japan|Kyoto"]
+        );
+    }
+
+    #[test]
+    fn test_parse_gps_forms() {
+        assert_eq!(parse_gps("35,30,0N", 'N', 'S'), Some(35.5));
+        assert_eq!(parse_gps("135,45.5W", 'E', 'W'), Some(-135.0 - 45.5 / 60.0));
+        assert_eq!(parse_gps("135,45.5X", 'E', 'W'), None);
+    }
+
+    #[test]
+    fn test_find_packet_in_file_bytes() {
+        let mut bytes = b"\xff\xd8\xff\xe1\0\0http://ns.adobe.com/xap/1.0/\0".to_vec();
+        bytes.extend_from_slice(SIDECAR.as_bytes());
+        bytes.extend_from_slice(b"\xff\xd9");
+        let packet = XmpData::find_packet(&bytes).unwrap();
+        assert!(packet.starts_with("<x:xmpmeta"));
+        assert!(packet.ends_with("</x:xmpmeta>"));
+        assert!(XmpData::find_packet(b"no metadata here").is_none());
+    }
+
+    #[test]
+    fn test_parse_without_known_namespaces() {
+        let xmp = XmpData::parse(
+            r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+                 <rdf:Description xmlns:xmp="http://ns.adobe.com/xap/1.0/" xmp:Rating="3"/>
+               </rdf:RDF>"#,
+        )
+        .unwrap();
+        assert!(xmp.dublin_core.is_none());
+        assert!(xmp.lightroom.is_none());
+        assert_eq!(xmp.basic.unwrap().rating, Some(3));
+        assert!(matches!(XmpData::parse("<a>"), Err(XmpError::Malformed(_))));
+    }
+}
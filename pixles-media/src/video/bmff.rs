@@ -0,0 +1,504 @@
+//! ISO Base Media File Format (MP4, QuickTime MOV, 3GP) metadata parser.
+//!
+//! Only the `moov` box tree is walked; `mdat` is skipped by size.
+
+use chrono::{DateTime, FixedOffset, Utc};
+
+use crate::metadata::geo::GpsLocation;
+use crate::video::types::VideoFormat;
+use crate::video::{VideoMetadata, VideoMetadataError};
+
+/// Seconds from the QuickTime epoch (1904-01-01) to the Unix epoch.
+const QUICKTIME_EPOCH_OFFSET: i64 = 2_082_844_800;
+
+const KEY_CONTENT_IDENTIFIER: &[u8] = b"com.apple.quicktime.content.identifier";
+const KEY_CREATION_DATE: &[u8] = b"com.apple.quicktime.creationdate";
+const KEY_LOCATION: &[u8] = b"com.apple.quicktime.location.ISO6709";
+
+/// Top-level box types that may start an ISO-BMFF file.
+const LEADING_BOXES: [&[u8; 4]; 7] = [
+    b"ftyp", b"moov", b"mdat", b"wide", b"free", b"skip", b"pnot",
+];
+
+/// Returns `true` if `data` starts with a recognisable ISO-BMFF box.
+pub fn is_bmff(data: &[u8]) -> bool {
+    data.get(4..8)
+        .is_some_and(|kind| LEADING_BOXES.iter().any(|b| kind == *b))
+}
+
+pub fn parse(data: &[u8]) -> Result<VideoMetadata, VideoMetadataError> {
+    let moov = find_box(data, b"moov").ok_or(VideoMetadataError::MissingHeader)?;
+
+    let mut meta = VideoMetadata {
+        container: Some(container_format(data)),
+        ..Default::default()
+    };
+
+    if let Some(mvhd) = find_box(moov, b"mvhd") {
+        parse_mvhd(mvhd, &mut meta);
+    }
+    for (kind, trak) in boxes(moov) {
+        if &kind == b"trak" && meta.codec.is_none() {
+            parse_video_track(trak, &mut meta);
+        }
+    }
+    if let Some(xyz) = find_box(moov, b"udta").and_then(|udta| find_box(udta, b"\xA9xyz")) {
+        // u16 string length, u16 language code, ISO 6709 string
+        meta.location = xyz
+            .get(4..)
+            .and_then(|s| std::str::from_utf8(s).ok())
+            .and_then(parse_iso6709);
+    }
+    if let Some(meta_box) = find_box(moov, b"meta") {
+        parse_mdta_keys(meta_box, &mut meta);
+    }
+
+    Ok(meta)
+}
+
+fn container_format(data: &[u8]) -> VideoFormat {
+    match find_box(data, b"ftyp").and_then(|ftyp| ftyp.get(..4)) {
+        Some(b"qt  ") => VideoFormat::Mov,
+        Some(_) => VideoFormat::Mp4,
+        // Old QuickTime files have no ftyp at all.
+        None => VideoFormat::Mov,
+    }
+}
+
+// ── Box walking ──────────────────────────────────────────────────────────────
+
+/// Iterator over sibling boxes, yielding `(type, body)`.
+struct Boxes<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for Boxes<'a> {
+    type Item = ([u8; 4], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let size = be_u32(self.data, 0)?;
+        let kind: [u8; 4] = self.data.get(4..8)?.try_into().ok()?;
+        let (header, size) = match size {
+            0 => (8, self.data.len() as u64),
+            1 => (16, be_u64(self.data, 8)?),
+            n => (8, u64::from(n)),
+        };
+        let Some(size) = usize::try_from(size)
+            .ok()
+            .filter(|&s| s >= header && s <= self.data.len())
+        else {
+            self.data = &[];
+            return None;
+        };
+        let body = &self.data[header..size];
+        self.data = &self.data[size..];
+        Some((kind, body))
+    }
+}
+
+fn boxes(data: &[u8]) -> Boxes<'_> {
+    Boxes { data }
+}
+
+fn find_box<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    boxes(data).find(|(k, _)| k == kind).map(|(_, body)| body)
+}
+
+// ── Individual boxes ─────────────────────────────────────────────────────────
+
+/// `(timescale, duration)` from an `mvhd` or `mdhd` full box.
+fn header_timing(body: &[u8]) -> Option<(u32, u64)> {
+    match *body.first()? {
+        1 => Some((be_u32(body, 20)?, be_u64(body, 24)?)),
+        _ => Some((be_u32(body, 12)?, u64::from(be_u32(body, 16)?))),
+    }
+}
+
+fn duration_ms(timescale: u32, duration: u64) -> Option<u64> {
+    // All-ones durations mean "unknown".
+    if timescale == 0 || duration == u64::MAX || duration == u64::from(u32::MAX) {
+        return None;
+    }
+    Some((u128::from(duration) * 1000 / u128::from(timescale)) as u64)
+}
+
+fn parse_mvhd(body: &[u8], meta: &mut VideoMetadata) {
+    if let Some((timescale, duration)) = header_timing(body) {
+        meta.duration_ms = duration_ms(timescale, duration);
+    }
+    let creation = match body.first() {
+        Some(1) => be_u64(body, 4),
+        _ => be_u32(body, 4).map(u64::from),
+    };
+    // Many encoders leave the creation time at zero.
+    meta.creation_time = creation
+        .filter(|&t| t > 0)
+        .and_then(|t| i64::try_from(t).ok())
+        .and_then(|t| DateTime::<Utc>::from_timestamp(t - QUICKTIME_EPOCH_OFFSET, 0))
+        .map(|t| t.fixed_offset());
+}
+
+fn parse_video_track(trak: &[u8], meta: &mut VideoMetadata) -> Option<()> {
+    let mdia = find_box(trak, b"mdia")?;
+    if find_box(mdia, b"hdlr")?.get(8..12)? != b"vide" {
+        return None;
+    }
+
+    if let Some(tkhd) = find_box(trak, b"tkhd") {
+        let (matrix, dims) = match *tkhd.first()? {
+            1 => (52, 88),
+            _ => (40, 76),
+        };
+        let fixed = |pos| be_u32(tkhd, pos).map(|v| v as i32);
+        meta.rotation = match (fixed(matrix)?, fixed(matrix + 4)?, fixed(matrix + 12)?) {
+            (0, 0x0001_0000, -0x0001_0000) => Some(90),
+            (-0x0001_0000, 0, 0) => Some(180),
+            (0, -0x0001_0000, 0x0001_0000) => Some(270),
+            _ => Some(0),
+        };
+        // 16.16 fixed point
+        meta.width = be_u32(tkhd, dims).map(|w| w >> 16).filter(|&w| w > 0);
+        meta.height = be_u32(tkhd, dims + 4).map(|h| h >> 16).filter(|&h| h > 0);
+    }
+
+    let (timescale, track_duration) = find_box(mdia, b"mdhd").and_then(header_timing)?;
+    if meta.duration_ms.is_none() {
+        meta.duration_ms = duration_ms(timescale, track_duration);
+    }
+
+    let stbl = find_box(find_box(mdia, b"minf")?, b"stbl")?;
+    if let Some(stsd) = find_box(stbl, b"stsd") {
+        // Full box header, entry count, then the first sample entry.
+        let fourcc = stsd.get(12..16)?;
+        meta.codec = Some(String::from_utf8_lossy(fourcc).trim().to_string());
+        // Visual sample entries carry their own dimensions.
+        if meta.width.is_none() {
+            meta.width = be_u16(stsd, 40).map(u32::from).filter(|&w| w > 0);
+            meta.height = be_u16(stsd, 42).map(u32::from).filter(|&h| h > 0);
+        }
+    }
+    if let Some(stts) = find_box(stbl, b"stts") {
+        meta.frame_rate = frame_rate(stts, timescale);
+    }
+
+    Some(())
+}
+
+/// Average frame rate from a `stts` (decoding time-to-sample) box. `None`
+/// when the table is empty or its totals overflow.
+fn frame_rate(stts: &[u8], timescale: u32) -> Option<f64> {
+    let entries = be_u32(stts, 4)? as usize;
+    let (mut samples, mut ticks) = (0u64, 0u64);
+    for i in 0..entries {
+        let count = u64::from(be_u32(stts, 8 + i * 8)?);
+        let delta = u64::from(be_u32(stts, 12 + i * 8)?);
+        samples = samples.checked_add(count)?;
+        ticks = ticks.checked_add(count.checked_mul(delta)?)?;
+    }
+    (samples > 0 && ticks > 0).then(|| samples as f64 * f64::from(timescale) / ticks as f64)
+}
+
+/// QuickTime `meta` box with `keys` + `ilst` (Apple `mdta` metadata).
+fn parse_mdta_keys(meta_box: &[u8], meta: &mut VideoMetadata) -> Option<()> {
+    // QuickTime's `meta` is a plain box; ISO's is a full box with 4 extra bytes.
+    let body = if meta_box.get(4..8) == Some(&b"hdlr"[..]) {
+        meta_box
+    } else {
+        meta_box.get(4..)?
+    };
+    let keys = find_box(body, b"keys")?;
+    let ilst = find_box(body, b"ilst")?;
+
+    let mut names = Vec::new();
+    let mut pos = 8;
+    for _ in 0..be_u32(keys, 4)? {
+        let size = be_u32(keys, pos)? as usize;
+        if size < 8 {
+            break;
+        }
+        // u32 size, u32 namespace ("mdta"), key name
+        names.push(keys.get(pos + 8..pos + size)?);
+        pos += size;
+    }
+
+    for (index, item) in boxes(ilst) {
+        // Item types are 1-based indices into the key table.
+        let Some(name) = (u32::from_be_bytes(index) as usize)
+            .checked_sub(1)
+            .and_then(|i| names.get(i))
+        else {
+            continue;
+        };
+        // `data` box: u32 type indicator, u32 locale, value
+        let Some(value) = find_box(item, b"data")
+            .and_then(|data| data.get(8..))
+            .and_then(|v| std::str::from_utf8(v).ok())
+            .map(|v| v.trim_end_matches('\0'))
+        else {
+            continue;
+        };
+        match *name {
+            KEY_CONTENT_IDENTIFIER => meta.content_identifier = Some(value.to_string()),
+            KEY_CREATION_DATE => {
+                if let Some(time) = parse_creation_date(value) {
+                    meta.creation_time = Some(time);
+                    meta.creation_time_is_local = true;
+                }
+            }
+            KEY_LOCATION => meta.location = parse_iso6709(value).or(meta.location.take()),
+            _ => {}
+        }
+    }
+    Some(())
+}
+
+/// Parses Apple's `creationdate`, e.g. `2024-05-01T12:34:56+0200`.
+fn parse_creation_date(s: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(s)
+        .or_else(|_| DateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%z"))
+        .ok()
+}
+
+/// Parses an ISO 6709 decimal-degree string such as `+37.7749-122.4194+010.000/`.
+fn parse_iso6709(s: &str) -> Option<GpsLocation> {
+    let s = s.trim().trim_end_matches('/');
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (i, c) in s.char_indices().skip(1) {
+        if c == '+' || c == '-' {
+            parts.push(&s[start..i]);
+            start = i;
+        }
+    }
+    parts.push(&s[start..]);
+
+    let latitude: f64 = parts.first()?.parse().ok()?;
+    let longitude: f64 = parts.get(1)?.parse().ok()?;
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return None;
+    }
+    Some(GpsLocation {
+        latitude,
+        longitude,
+        altitude: parts.get(2).and_then(|a| a.parse().ok()),
+    })
+}
+
+fn be_u16(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn be_u32(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+fn be_u64(data: &[u8], pos: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(pos..pos + 8)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn full_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut with_header = vec![0, 0, 0, 0];
+        with_header.extend_from_slice(body);
+        mp4_box(kind, &with_header)
+    }
+
+    fn video_track(rotation_matrix: [i32; 9]) -> Vec<u8> {
+        // tkhd v0: times, track id, reserved, duration, reserved, layer..volume, matrix, size
+        let mut tkhd = vec![0; 36];
+        for m in rotation_matrix {
+            tkhd.extend_from_slice(&m.to_be_bytes());
+        }
+        tkhd.extend_from_slice(&(1920u32 << 16).to_be_bytes());
+        tkhd.extend_from_slice(&(1080u32 << 16).to_be_bytes());
+
+        let mut mdhd = vec![0; 8];
+        mdhd.extend_from_slice(&600u32.to_be_bytes());
+        mdhd.extend_from_slice(&1800u32.to_be_bytes());
+
+        let mut hdlr = vec![0; 4];
+        hdlr.extend_from_slice(b"vide");
+
+        let mut stsd = 1u32.to_be_bytes().to_vec();
+        let mut entry = vec![0; 24];
+        entry.extend_from_slice(&1920u16.to_be_bytes());
+        entry.extend_from_slice(&1080u16.to_be_bytes());
+        stsd.extend_from_slice(&mp4_box(b"hvc1", &entry));
+
+        // 90 frames of 20 ticks at timescale 600 = 30 fps
+        let mut stts = 1u32.to_be_bytes().to_vec();
+        stts.extend_from_slice(&90u32.to_be_bytes());
+        stts.extend_from_slice(&20u32.to_be_bytes());
+
+        let stbl = [full_box(b"stsd", &stsd), full_box(b"stts", &stts)].concat();
+        let minf = mp4_box(b"stbl", &stbl);
+        let mdia = [
+            full_box(b"mdhd", &mdhd),
+            full_box(b"hdlr", &hdlr),
+            mp4_box(b"minf", &minf),
+        ]
+        .concat();
+        mp4_box(
+            b"trak",
+            &[full_box(b"tkhd", &tkhd), mp4_box(b"mdia", &mdia)].concat(),
+        )
+    }
+
+    fn apple_meta(entries: &[(&[u8], &str)]) -> Vec<u8> {
+        let mut keys = (entries.len() as u32).to_be_bytes().to_vec();
+        let mut ilst = Vec::new();
+        for (i, (key, value)) in entries.iter().enumerate() {
+            keys.extend_from_slice(&((key.len() + 8) as u32).to_be_bytes());
+            keys.extend_from_slice(b"mdta");
+            keys.extend_from_slice(key);
+
+            let mut data = 1u32.to_be_bytes().to_vec();
+            data.extend_from_slice(&[0; 4]);
+            data.extend_from_slice(value.as_bytes());
+            ilst.extend_from_slice(&mp4_box(
+                &(i as u32 + 1).to_be_bytes(),
+                &mp4_box(b"data", &data),
+            ));
+        }
+        let mut hdlr = vec![0; 4];
+        hdlr.extend_from_slice(b"mdta");
+        mp4_box(
+            b"meta",
+            &[
+                full_box(b"hdlr", &hdlr),
+                full_box(b"keys", &keys),
+                mp4_box(b"ilst", &ilst),
+            ]
+            .concat(),
+        )
+    }
+
+    fn mvhd(creation: u32) -> Vec<u8> {
+        let mut body = creation.to_be_bytes().to_vec();
+        body.extend_from_slice(&[0; 4]);
+        body.extend_from_slice(&1000u32.to_be_bytes());
+        body.extend_from_slice(&3000u32.to_be_bytes());
+        full_box(b"mvhd", &body)
+    }
+
+    #[test]
+    fn test_parse_quicktime_live_photo_video() {
+        let identity = [0x0001_0000, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000];
+        let moov = [
+            mvhd(0),
+            video_track(identity),
+            apple_meta(&[
+                (
+                    KEY_CONTENT_IDENTIFIER,
+                    "0B1C3A9E-5B2D-4C8E-9F1A-2D3E4F5A6B7C",
+                ),
+                (KEY_CREATION_DATE, "2024-05-01T12:34:56+0200"),
+                (KEY_LOCATION, "+35.6586+139.7454+040.000/"),
+            ]),
+        ]
+        .concat();
+        let file = [
+            mp4_box(b"ftyp", b"qt  \0\0\0\0qt  "),
+            mp4_box(b"mdat", &[0; 32]),
+            mp4_box(b"moov", &moov),
+        ]
+        .concat();
+
+        let meta = VideoMetadata::from_bytes(&file).unwrap();
+        assert_eq!(meta.container, Some(VideoFormat::Mov));
+        assert_eq!(meta.duration_ms, Some(3000));
+        assert_eq!((meta.width, meta.height), (Some(1920), Some(1080)));
+        assert_eq!(meta.rotation, Some(0));
+        assert_eq!(meta.codec.as_deref(), Some("hvc1"));
+        assert_eq!(meta.frame_rate, Some(30.0));
+        assert_eq!(
+            meta.content_identifier.as_deref(),
+            Some("0B1C3A9E-5B2D-4C8E-9F1A-2D3E4F5A6B7C")
+        );
+        let created = meta.creation_time.unwrap();
+        assert_eq!(created.to_rfc3339(), "2024-05-01T12:34:56+02:00");
+        assert!(meta.creation_time_is_local);
+        let location = meta.location.unwrap();
+        assert_eq!(location.latitude, 35.6586);
+        assert_eq!(location.longitude, 139.7454);
+        assert_eq!(location.altitude, Some(40.0));
+    }
+
+    #[test]
+    fn test_parse_mp4_rotation_and_udta_location() {
+        let rotate_90 = [0, 0x0001_0000, 0, -0x0001_0000, 0, 0, 0, 0, 0x4000_0000];
+        // 2024-01-01T00:00:00Z in the QuickTime epoch
+        let creation = (1_704_067_200 + QUICKTIME_EPOCH_OFFSET) as u32;
+        let mut xyz = 18u16.to_be_bytes().to_vec();
+        xyz.extend_from_slice(&[0x15, 0xC7]);
+        xyz.extend_from_slice(b"+48.8584+002.2945/");
+        let moov = [
+            mvhd(creation),
+            video_track(rotate_90),
+            mp4_box(b"udta", &mp4_box(b"\xA9xyz", &xyz)),
+        ]
+        .concat();
+        let file = [
+            mp4_box(b"ftyp", b"isom\0\0\x02\0isomiso2mp41"),
+            mp4_box(b"moov", &moov),
+        ]
+        .concat();
+
+        let meta = VideoMetadata::from_bytes(&file).unwrap();
+        assert_eq!(meta.container, Some(VideoFormat::Mp4));
+        assert_eq!(meta.rotation, Some(90));
+        assert_eq!(
+            meta.creation_time.unwrap().to_rfc3339(),
+            "2024-01-01T00:00:00+00:00"
+        );
+        assert!(!meta.creation_time_is_local);
+        let location = meta.location.unwrap();
+        assert_eq!((location.latitude, location.longitude), (48.8584, 2.2945));
+        assert!(meta.content_identifier.is_none());
+    }
+
+    #[test]
+    fn test_frame_rate_overflow_is_unknown() {
+        let stts = |entries: &[(u32, u32)]| {
+            let mut body = vec![0; 4];
+            body.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+            for (count, delta) in entries {
+                body.extend_from_slice(&count.to_be_bytes());
+                body.extend_from_slice(&delta.to_be_bytes());
+            }
+            body
+        };
+        assert_eq!(frame_rate(&stts(&[(90, 1000)]), 30_000), Some(30.0));
+        let huge = [(u32::MAX, u32::MAX); 3];
+        assert_eq!(frame_rate(&stts(&huge), 30_000), None);
+        assert_eq!(frame_rate(&stts(&[]), 30_000), None);
+    }
+
+    #[test]
+    fn test_missing_moov_is_an_error() {
+        let file = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        assert!(matches!(
+            VideoMetadata::from_bytes(&file),
+            Err(VideoMetadataError::MissingHeader)
+        ));
+    }
+
+    #[test]
+    fn test_truncated_boxes_do_not_panic() {
+        let mut file = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        file.extend_from_slice(&1000u32.to_be_bytes());
+        file.extend_from_slice(b"moov");
+        file.extend_from_slice(&[0; 12]);
+        assert!(VideoMetadata::from_bytes(&file).is_err());
+    }
+}
@@ -0,0 +1,330 @@
+//! Matroska / WebM (EBML) metadata parser.
+//!
+//! Reads the segment `Info` and `Tracks` elements; parsing stops at the first
+//! `Cluster`, which is where the media data begins.
+
+use chrono::{DateTime, Utc};
+
+use crate::video::types::VideoFormat;
+use crate::video::{VideoMetadata, VideoMetadataError};
+
+const EBML: u32 = 0x1A45_DFA3;
+const DOC_TYPE: u32 = 0x4282;
+const SEGMENT: u32 = 0x1853_8067;
+const INFO: u32 = 0x1549_A966;
+const TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const DURATION: u32 = 0x4489;
+const DATE_UTC: u32 = 0x4461;
+const TRACKS: u32 = 0x1654_AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const DEFAULT_DURATION: u32 = 0x23_E383;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const CLUSTER: u32 = 0x1F43_B675;
+
+const TRACK_TYPE_VIDEO: u64 = 1;
+
+/// Nanoseconds per timestamp tick unless `TimestampScale` says otherwise.
+const DEFAULT_TIMESTAMP_SCALE: u64 = 1_000_000;
+
+/// Seconds from the Unix epoch to the Matroska epoch (2001-01-01).
+const MATROSKA_EPOCH_OFFSET: i64 = 978_307_200;
+
+/// Returns `true` if `data` starts with an EBML header.
+pub fn is_matroska(data: &[u8]) -> bool {
+    data.starts_with(&EBML.to_be_bytes())
+}
+
+pub fn parse(data: &[u8]) -> Result<VideoMetadata, VideoMetadataError> {
+    let mut top = elements(data);
+    let header = top
+        .next()
+        .filter(|(id, _)| *id == EBML)
+        .map(|(_, body)| body)
+        .ok_or(VideoMetadataError::UnknownContainer)?;
+    let segment = top
+        .find(|(id, _)| *id == SEGMENT)
+        .map(|(_, body)| body)
+        .ok_or(VideoMetadataError::MissingHeader)?;
+
+    let container = match find(header, DOC_TYPE) {
+        Some(b"webm") => VideoFormat::Webm,
+        _ => VideoFormat::Mkv,
+    };
+    let mut meta = VideoMetadata {
+        container: Some(container),
+        ..Default::default()
+    };
+
+    for (id, body) in elements(segment) {
+        match id {
+            INFO => parse_info(body, &mut meta),
+            TRACKS => {
+                for (_, entry) in elements(body).filter(|(id, _)| *id == TRACK_ENTRY) {
+                    if parse_video_track(entry, &mut meta) {
+                        break;
+                    }
+                }
+            }
+            CLUSTER => break,
+            _ => {}
+        }
+    }
+
+    Ok(meta)
+}
+
+fn parse_info(info: &[u8], meta: &mut VideoMetadata) {
+    let scale = find(info, TIMESTAMP_SCALE)
+        .and_then(read_uint)
+        .filter(|&s| s > 0)
+        .unwrap_or(DEFAULT_TIMESTAMP_SCALE);
+
+    meta.duration_ms = find(info, DURATION)
+        .and_then(read_float)
+        .filter(|d| d.is_finite() && *d >= 0.0)
+        .map(|d| (d * scale as f64 / 1_000_000.0).round() as u64);
+
+    meta.creation_time = find(info, DATE_UTC).and_then(read_int).and_then(|ns| {
+        let secs = ns.div_euclid(1_000_000_000) + MATROSKA_EPOCH_OFFSET;
+        let nanos = ns.rem_euclid(1_000_000_000) as u32;
+        DateTime::<Utc>::from_timestamp(secs, nanos).map(|t| t.fixed_offset())
+    });
+}
+
+/// Fills track fields from a video `TrackEntry`; returns `false` for other tracks.
+fn parse_video_track(entry: &[u8], meta: &mut VideoMetadata) -> bool {
+    if find(entry, TRACK_TYPE).and_then(read_uint) != Some(TRACK_TYPE_VIDEO) {
+        return false;
+    }
+
+    meta.codec = find(entry, CODEC_ID).map(|id| {
+        String::from_utf8_lossy(id)
+            .trim_end_matches('\0')
+            .to_string()
+    });
+    // Nanoseconds per frame
+    meta.frame_rate = find(entry, DEFAULT_DURATION)
+        .and_then(read_uint)
+        .filter(|&ns| ns > 0)
+        .map(|ns| 1_000_000_000.0 / ns as f64);
+    if let Some(video) = find(entry, VIDEO) {
+        let dimension = |id| {
+            find(video, id)
+                .and_then(read_uint)
+                .and_then(|v| u32::try_from(v).ok())
+        };
+        meta.width = dimension(PIXEL_WIDTH);
+        meta.height = dimension(PIXEL_HEIGHT);
+    }
+    true
+}
+
+// ── EBML primitives ──────────────────────────────────────────────────────────
+
+/// Iterator over sibling elements, yielding `(id, body)`.
+///
+/// Element IDs keep their length marker (as written in the specification);
+/// sizes have it stripped. An unknown size extends to the end of the parent,
+/// and a size past the end of the data is clamped to it.
+struct Elements<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = (u32, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Some((id, start, end)) = element_bounds(self.data) else {
+            self.data = &[];
+            return None;
+        };
+        let body = &self.data[start..end];
+        self.data = &self.data[end..];
+        Some((id, body))
+    }
+}
+
+/// `(id, body start, body end)` of the element at the start of `data`.
+fn element_bounds(data: &[u8]) -> Option<(u32, usize, usize)> {
+    let (id, id_len) = read_vint(data, 0, true)?;
+    if id_len > 4 {
+        return None;
+    }
+    let (size, size_len) = read_vint(data, id_len, false)?;
+    let start = id_len + size_len;
+    let unknown = size == (1u64 << (7 * size_len)) - 1;
+    let end = match usize::try_from(size) {
+        Ok(size) if !unknown => start.saturating_add(size).min(data.len()),
+        _ => data.len(),
+    };
+    Some((id as u32, start, end))
+}
+
+fn elements(data: &[u8]) -> Elements<'_> {
+    Elements { data }
+}
+
+fn find(data: &[u8], id: u32) -> Option<&[u8]> {
+    elements(data)
+        .find(|(element, _)| *element == id)
+        .map(|(_, body)| body)
+}
+
+/// Reads a variable-length integer, returning `(value, length)`.
+fn read_vint(data: &[u8], pos: usize, keep_marker: bool) -> Option<(u64, usize)> {
+    let first = *data.get(pos)?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > 8 {
+        return None;
+    }
+    let bytes = data.get(pos..pos + len)?;
+    let mut value = if keep_marker {
+        u64::from(first)
+    } else {
+        u64::from(first) & (0xFF >> len)
+    };
+    for &b in &bytes[1..] {
+        value = (value << 8) | u64::from(b);
+    }
+    Some((value, len))
+}
+
+fn read_uint(body: &[u8]) -> Option<u64> {
+    if body.len() > 8 {
+        return None;
+    }
+    Some(body.iter().fold(0, |acc, &b| (acc << 8) | u64::from(b)))
+}
+
+fn read_int(body: &[u8]) -> Option<i64> {
+    let unsigned = read_uint(body)?;
+    if body.is_empty() {
+        return Some(0);
+    }
+    // Sign-extend from the element's width.
+    let shift = 64 - 8 * body.len() as u32;
+    Some(((unsigned << shift) as i64) >> shift)
+}
+
+fn read_float(body: &[u8]) -> Option<f64> {
+    match body.len() {
+        4 => Some(f64::from(f32::from_be_bytes(body.try_into().ok()?))),
+        8 => Some(f64::from_be_bytes(body.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(id: u32, body: &[u8]) -> Vec<u8> {
+        let id_bytes = id.to_be_bytes();
+        let skip = id_bytes.iter().take_while(|&&b| b == 0).count();
+        let mut out = id_bytes[skip..].to_vec();
+        // 8-byte size vint
+        out.push(0x01);
+        out.extend_from_slice(&(body.len() as u64).to_be_bytes()[1..]);
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn mkv(doc_type: &[u8], segment: &[u8]) -> Vec<u8> {
+        [
+            element(EBML, &element(DOC_TYPE, doc_type)),
+            element(SEGMENT, segment),
+        ]
+        .concat()
+    }
+
+    fn video_track(codec: &str) -> Vec<u8> {
+        let video = [
+            element(PIXEL_WIDTH, &3840u16.to_be_bytes()),
+            element(PIXEL_HEIGHT, &2160u16.to_be_bytes()),
+        ]
+        .concat();
+        let entry = [
+            element(TRACK_TYPE, &[1]),
+            element(CODEC_ID, codec.as_bytes()),
+            // 25 fps
+            element(DEFAULT_DURATION, &40_000_000u32.to_be_bytes()),
+            element(VIDEO, &video),
+        ]
+        .concat();
+        element(TRACK_ENTRY, &entry)
+    }
+
+    #[test]
+    fn test_parse_webm() {
+        // 2024-01-01T00:00:00Z relative to 2001-01-01
+        let date_ns = (1_704_067_200 - MATROSKA_EPOCH_OFFSET) * 1_000_000_000;
+        let info = [
+            element(TIMESTAMP_SCALE, &1_000_000u32.to_be_bytes()),
+            element(DURATION, &12_345.0f64.to_be_bytes()),
+            element(DATE_UTC, &date_ns.to_be_bytes()),
+        ]
+        .concat();
+        let audio = element(TRACK_ENTRY, &element(TRACK_TYPE, &[2]));
+        let tracks = [audio, video_track("V_VP9")].concat();
+        let segment = [
+            element(INFO, &info),
+            element(TRACKS, &tracks),
+            element(CLUSTER, &[0; 16]),
+        ]
+        .concat();
+
+        let meta = VideoMetadata::from_bytes(&mkv(b"webm", &segment)).unwrap();
+        assert_eq!(meta.container, Some(VideoFormat::Webm));
+        assert_eq!(meta.duration_ms, Some(12_345));
+        assert_eq!((meta.width, meta.height), (Some(3840), Some(2160)));
+        assert_eq!(meta.codec.as_deref(), Some("V_VP9"));
+        assert_eq!(meta.frame_rate, Some(25.0));
+        assert_eq!(
+            meta.creation_time.unwrap().to_rfc3339(),
+            "2024-01-01T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_parse_mkv_with_custom_timestamp_scale() {
+        // Duration in 10 ms ticks, stored as f32
+        let info = [
+            element(TIMESTAMP_SCALE, &10_000_000u32.to_be_bytes()),
+            element(DURATION, &150.0f32.to_be_bytes()),
+        ]
+        .concat();
+        let segment = [
+            element(INFO, &info),
+            element(TRACKS, &video_track("V_MPEG4/ISO/AVC")),
+        ]
+        .concat();
+
+        let meta = VideoMetadata::from_bytes(&mkv(b"matroska", &segment)).unwrap();
+        assert_eq!(meta.container, Some(VideoFormat::Mkv));
+        assert_eq!(meta.duration_ms, Some(1_500));
+        assert_eq!(meta.codec.as_deref(), Some("V_MPEG4/ISO/AVC"));
+        assert!(meta.creation_time.is_none());
+    }
+
+    #[test]
+    fn test_read_vint() {
+        assert_eq!(read_vint(&[0x81], 0, false), Some((1, 1)));
+        assert_eq!(read_vint(&[0x40, 0x02], 0, false), Some((2, 2)));
+        assert_eq!(
+            read_vint(&[0x1A, 0x45, 0xDF, 0xA3], 0, true),
+            Some((0x1A45_DFA3, 4))
+        );
+        assert_eq!(read_vint(&[0x00], 0, false), None);
+    }
+
+    #[test]
+    fn test_read_int_sign_extends() {
+        assert_eq!(read_int(&[0xFF]), Some(-1));
+        assert_eq!(read_int(&[0x00, 0x80]), Some(128));
+        assert_eq!(read_int(&[0x80, 0x00]), Some(-32_768));
+    }
+}
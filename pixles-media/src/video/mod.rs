@@ -1,7 +1,14 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
+use crate::metadata::geo::GpsLocation;
+use crate::video::types::VideoFormat;
+
+pub mod bmff;
+pub mod matroska;
 pub mod presets;
 pub mod types;
 
@@ -11,7 +18,63 @@ pub struct VideoFile {
     pub metadata: VideoMetadata,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Container-level metadata of a video file.
+///
+/// Fields are `None` when the container does not record them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct VideoMetadata {
-    // TODO: Add fields
+    pub container: Option<VideoFormat>,
+    pub duration_ms: Option<u64>,
+    /// Stored (pre-rotation) dimensions of the first video track
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Clockwise display rotation in degrees (0, 90, 180 or 270)
+    pub rotation: Option<u16>,
+    /// Sample entry FourCC (e.g. "avc1", "hvc1") or Matroska codec ID (e.g. "V_VP9")
+    pub codec: Option<String>,
+    pub frame_rate: Option<f64>,
+    /// Capture time. Apple's `creationdate` key keeps the local offset;
+    /// movie-header and Matroska dates are UTC (`+00:00`).
+    pub creation_time: Option<DateTime<FixedOffset>>,
+    /// Whether `creation_time` carries the capture's own UTC offset, rather
+    /// than a UTC time that says nothing about where it was taken.
+    #[serde(default)]
+    pub creation_time_is_local: bool,
+    pub location: Option<GpsLocation>,
+    /// Apple Live Photo UUID (pairs the MOV with its HEIC still)
+    pub content_identifier: Option<String>,
+}
+
+impl VideoMetadata {
+    /// Parses container metadata from the start of a video file's bytes.
+    ///
+    /// Recognises ISO-BMFF (MP4, MOV, 3GP) and Matroska/WebM. Media data is
+    /// skipped over, never decoded.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, VideoMetadataError> {
+        if bmff::is_bmff(data) {
+            bmff::parse(data)
+        } else if matroska::is_matroska(data) {
+            matroska::parse(data)
+        } else {
+            Err(VideoMetadataError::UnknownContainer)
+        }
+    }
+
+    /// Reads and parses a video file through a read-only memory map.
+    pub fn from_path(path: &Path) -> Result<Self, VideoMetadataError> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: read-only map, dropped before returning.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        Self::from_bytes(&mmap)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum VideoMetadataError {
+    #[error("Unknown video container")]
+    UnknownContainer,
+    #[error("Missing movie header")]
+    MissingHeader,
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
 }